@@ -0,0 +1,114 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write as _;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use anyhow::Context as _;
+use serde::Serialize;
+use serde_json::json;
+use tracing::warn;
+
+use crate::spider::{OptOutSignal, PolitenessSource};
+
+/// Append-only JSON Lines record of every HTTP request, host pacing change, opt-out
+/// refusal, and LLM call (`--audit-log`).
+///
+/// Each event is written and flushed as one line as soon as it happens, so the log is
+/// complete up to the last request even when the process is killed.
+#[derive(Debug)]
+pub struct AuditLog {
+    file: Mutex<File>,
+    crawl_id: Option<String>,
+}
+
+/// One line of the audit log.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AuditEvent<'a> {
+    /// A request to a crawled site. `status` is `None` when no response arrived,
+    /// including requests `robots.txt` disallowed, which are never sent.
+    Http {
+        method: &'a str,
+        url: &'a str,
+        status: Option<u16>,
+        bytes: Option<u64>,
+        /// `None` for `robots.txt` itself.
+        robots: Option<RobotsDecision>,
+        error: Option<String>,
+    },
+    /// A page not fetched, or dropped after its response, because its site opted out
+    /// of AI use (`--honor-opt-outs`).
+    OptOut { url: &'a str, signal: OptOutSignal },
+    /// The pacing applied to a crawled host, on its first request and whenever
+    /// `robots.txt` changes it.
+    Politeness {
+        host: &'a str,
+        min_interval_ms: u64,
+        interval_source: PolitenessSource,
+        max_concurrent: Option<usize>,
+        concurrency_source: Option<PolitenessSource>,
+    },
+    /// A request to the OpenAI API.
+    Llm {
+        endpoint: &'a str,
+        model: &'a str,
+        status: Option<u16>,
+        input_tokens: Option<u64>,
+        output_tokens: Option<u64>,
+        error: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RobotsDecision {
+    Allowed,
+    Disallowed,
+}
+
+impl AuditLog {
+    /// Opens `path` for appending, creating it if needed.
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("open audit log: {}", path.display()))?;
+        Ok(Self {
+            file: Mutex::new(file),
+            crawl_id: None,
+        })
+    }
+
+    /// Adds `crawl_id` to every line (`--crawl-id`).
+    pub fn with_crawl_id(mut self, crawl_id: Option<String>) -> Self {
+        self.crawl_id = crawl_id;
+        self
+    }
+
+    /// Appends `event` with a millisecond RFC 3339 timestamp (`ts`). A failed write is
+    /// logged; the crawl goes on.
+    pub fn record(&self, event: &AuditEvent<'_>) {
+        let mut line = json!({
+            "ts": humantime::format_rfc3339_millis(SystemTime::now()).to_string(),
+        });
+        if let (Some(line), Some(crawl_id)) = (line.as_object_mut(), &self.crawl_id) {
+            line.insert("crawl_id".to_owned(), crawl_id.as_str().into());
+        }
+        if let (Some(line), Ok(serde_json::Value::Object(fields))) =
+            (line.as_object_mut(), serde_json::to_value(event))
+        {
+            line.extend(fields);
+        }
+        let mut text = crate::redact::redact(&line.to_string()).into_owned();
+        text.push('\n');
+        let written = match self.file.lock() {
+            Ok(mut file) => file.write_all(text.as_bytes()).and_then(|()| file.flush()),
+            Err(_) => return,
+        };
+        if let Err(err) = written {
+            warn!("write audit log: {err}");
+        }
+    }
+}
@@ -0,0 +1,398 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::openai::SelectedLink;
+use crate::spider::{CacheValidators, ContentChange, FetchedPage, TrustTier};
+
+/// On-disk cache of fetched pages, one JSON file per URL under `<dir>/pages/`.
+#[derive(Debug, Clone)]
+pub struct PageCache {
+    dir: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedPage {
+    pub url: String,
+    pub html: String,
+    pub links: Vec<String>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// `X-Robots-Tag` of the stored response; a `304` does not repeat it.
+    #[serde(default)]
+    pub x_robots_tag: Option<String>,
+    /// Seconds since the Unix epoch.
+    pub stored_at: u64,
+}
+
+impl CachedPage {
+    pub fn validators(&self) -> Option<CacheValidators> {
+        if self.etag.is_none() && self.last_modified.is_none() {
+            return None;
+        }
+        Some(CacheValidators {
+            etag: self.etag.clone(),
+            last_modified: self.last_modified.clone(),
+        })
+    }
+
+    pub fn to_fetched_page(&self) -> FetchedPage {
+        FetchedPage {
+            html: self.html.clone(),
+            links: self
+                .links
+                .iter()
+                .filter_map(|link| Url::parse(link).ok())
+                .collect(),
+            etag: self.etag.clone(),
+            last_modified: self.last_modified.clone(),
+            x_robots_tag: self.x_robots_tag.clone(),
+            ..FetchedPage::default()
+        }
+    }
+}
+
+impl PageCache {
+    pub fn open(dir: &Path) -> anyhow::Result<Self> {
+        let pages = dir.join("pages");
+        std::fs::create_dir_all(&pages)
+            .with_context(|| format!("create cache dir: {}", pages.display()))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    pub fn get(&self, url: &Url) -> Option<CachedPage> {
+        let bytes = std::fs::read(self.page_path(url)).ok()?;
+        let entry = serde_json::from_slice::<CachedPage>(&bytes).ok()?;
+        (entry.url == url.as_str()).then_some(entry)
+    }
+
+    pub fn put(&self, url: &Url, page: &FetchedPage) -> anyhow::Result<()> {
+        let entry = CachedPage {
+            url: url.to_string(),
+            html: page.html.clone(),
+            links: page.links.iter().map(Url::to_string).collect(),
+            etag: page.etag.clone(),
+            last_modified: page.last_modified.clone(),
+            x_robots_tag: page.x_robots_tag.clone(),
+            stored_at: now_secs(),
+        };
+        let json = serde_json::to_vec(&entry).context("serialize cached page")?;
+        write_atomic(&self.page_path(url), &json)
+    }
+
+    fn page_path(&self, url: &Url) -> PathBuf {
+        self.dir
+            .join("pages")
+            .join(format!("{}.json", cache_key(url.as_str())))
+    }
+}
+
+/// How long a stored `robots.txt` stays valid; RFC 9309 asks crawlers not to keep one
+/// longer than a day.
+pub const ROBOTS_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// On-disk cache of `robots.txt` documents, one JSON file per origin under
+/// `<dir>/robots/`, valid for [`ROBOTS_TTL`].
+#[derive(Debug, Clone)]
+pub struct RobotsCache {
+    dir: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedRobots {
+    pub origin: String,
+    /// `None` when the site has no `robots.txt` (a `4xx` response).
+    pub body: Option<String>,
+    /// Seconds since the Unix epoch.
+    pub stored_at: u64,
+}
+
+impl RobotsCache {
+    pub fn open(dir: &Path) -> anyhow::Result<Self> {
+        let dir = dir.join("robots");
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("create cache dir: {}", dir.display()))?;
+        Ok(Self { dir })
+    }
+
+    /// The document stored for `origin` less than [`ROBOTS_TTL`] ago.
+    pub fn get(&self, origin: &str) -> Option<CachedRobots> {
+        let bytes = std::fs::read(self.path(origin)).ok()?;
+        let entry = serde_json::from_slice::<CachedRobots>(&bytes).ok()?;
+        let age = now_secs().saturating_sub(entry.stored_at);
+        (entry.origin == origin && age < ROBOTS_TTL.as_secs()).then_some(entry)
+    }
+
+    pub fn put(&self, origin: &str, body: Option<&str>) -> anyhow::Result<()> {
+        let entry = CachedRobots {
+            origin: origin.to_owned(),
+            body: body.map(str::to_owned),
+            stored_at: now_secs(),
+        };
+        let json = serde_json::to_vec(&entry).context("serialize cached robots.txt")?;
+        write_atomic(&self.path(origin), &json)
+    }
+
+    fn path(&self, origin: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", cache_key(origin)))
+    }
+}
+
+/// On-disk cache of link selection decisions under `<dir>/selections/`, keyed by
+/// query, page URL, the exact candidate batch, the sources collected so far, and the
+/// select model and prompts.
+#[derive(Debug, Clone)]
+pub struct SelectionCache {
+    dir: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedSelection {
+    key: String,
+    selected: Vec<CachedSelectedLink>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedSelectedLink {
+    url: String,
+    trust_tier: TrustTier,
+}
+
+impl SelectionCache {
+    pub fn open(dir: &Path) -> anyhow::Result<Self> {
+        let dir = dir.join("selections");
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("create cache dir: {}", dir.display()))?;
+        Ok(Self { dir })
+    }
+
+    /// The cache key: query, page URL, `max_select`, the serialized candidates, a hash
+    /// of the serialized `collected` sources the prompt shows, and `fingerprint`, which
+    /// names the select model and prompts
+    /// ([`OpenAiApi::select_fingerprint`](crate::openai::OpenAiApi::select_fingerprint)).
+    pub fn key(
+        query: &str,
+        page_url: &Url,
+        candidates: &str,
+        max_select: usize,
+        collected: &str,
+        fingerprint: &str,
+    ) -> String {
+        let collected = cache_key(collected);
+        format!("{fingerprint}\n{query}\n{page_url}\n{max_select}\n{collected}\n{candidates}")
+    }
+
+    pub fn get(&self, key: &str) -> Option<Vec<SelectedLink>> {
+        let bytes = std::fs::read(self.path(key)).ok()?;
+        let entry = serde_json::from_slice::<CachedSelection>(&bytes).ok()?;
+        if entry.key != key {
+            return None;
+        }
+        entry
+            .selected
+            .iter()
+            .map(|link| {
+                Some(SelectedLink {
+                    url: Url::parse(&link.url).ok()?,
+                    trust_tier: link.trust_tier,
+                })
+            })
+            .collect()
+    }
+
+    pub fn put(&self, key: &str, selected: &[SelectedLink]) -> anyhow::Result<()> {
+        let entry = CachedSelection {
+            key: key.to_owned(),
+            selected: selected
+                .iter()
+                .map(|link| CachedSelectedLink {
+                    url: link.url.to_string(),
+                    trust_tier: link.trust_tier,
+                })
+                .collect(),
+        };
+        let json = serde_json::to_vec(&entry).context("serialize cached selection")?;
+        write_atomic(&self.path(key), &json)
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", cache_key(key)))
+    }
+}
+
+/// Per-host tally of the tiers the model assigned, kept in `<dir>/reputation.json`
+/// across runs.
+///
+/// Learned tiers come from the tally as loaded, so ratings recorded during a run
+/// only take effect on later runs.
+#[derive(Debug)]
+pub struct ReputationStore {
+    path: PathBuf,
+    loaded: BTreeMap<String, TierCounts>,
+    hosts: Mutex<BTreeMap<String, TierCounts>>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TierCounts {
+    pub high: u32,
+    pub medium: u32,
+    pub low: u32,
+}
+
+impl TierCounts {
+    /// The tier the model gave in at least 80% of at least five ratings.
+    pub fn consistent_tier(&self) -> Option<TrustTier> {
+        const MIN_RATINGS: u32 = 5;
+        let total = self.high + self.medium + self.low;
+        if total < MIN_RATINGS {
+            return None;
+        }
+        [
+            (TrustTier::High, self.high),
+            (TrustTier::Medium, self.medium),
+            (TrustTier::Low, self.low),
+        ]
+        .into_iter()
+        .find(|(_, count)| count * 5 >= total * 4)
+        .map(|(tier, _)| tier)
+    }
+}
+
+impl ReputationStore {
+    /// Loads the store; a missing or unreadable file starts empty.
+    pub fn open(dir: &Path) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("create cache dir: {}", dir.display()))?;
+        let path = dir.join("reputation.json");
+        let loaded: BTreeMap<String, TierCounts> = std::fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        Ok(Self {
+            path,
+            hosts: Mutex::new(loaded.clone()),
+            loaded,
+        })
+    }
+
+    /// Counts one rating for the URL's host. `Unverified` is not a rating and is ignored.
+    pub fn record(&self, url: &Url, tier: TrustTier) {
+        let Some(host) = url.host_str() else {
+            return;
+        };
+        let Ok(mut hosts) = self.hosts.lock() else {
+            return;
+        };
+        let counts = hosts.entry(host.to_ascii_lowercase()).or_default();
+        match tier {
+            TrustTier::High => counts.high += 1,
+            TrustTier::Medium => counts.medium += 1,
+            TrustTier::Low => counts.low += 1,
+            TrustTier::Unverified => {}
+        }
+    }
+
+    /// Ratings for `host`, including the ones recorded so far in this run.
+    pub fn counts(&self, host: &str) -> Option<TierCounts> {
+        let hosts = self.hosts.lock().ok()?;
+        hosts.get(&host.to_ascii_lowercase()).copied()
+    }
+
+    /// The tier the model consistently gave `host` in earlier runs, if any.
+    pub fn learned_tier(&self, host: &str) -> Option<TrustTier> {
+        self.loaded
+            .get(&host.to_ascii_lowercase())?
+            .consistent_tier()
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let hosts = self
+            .hosts
+            .lock()
+            .map_err(|_| anyhow::anyhow!("reputation store lock poisoned"))?;
+        let json = serde_json::to_vec_pretty(&*hosts).context("serialize reputation")?;
+        write_atomic(&self.path, &json)
+    }
+}
+
+/// Hash of each source's extracted text by URL, kept in `<dir>/content.json` across
+/// runs to tell which sources changed since the last one.
+#[derive(Debug)]
+pub struct ContentStore {
+    path: PathBuf,
+    loaded: BTreeMap<String, String>,
+    hashes: Mutex<BTreeMap<String, String>>,
+}
+
+impl ContentStore {
+    /// Loads the store; a missing or unreadable file starts empty.
+    pub fn open(dir: &Path) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("create cache dir: {}", dir.display()))?;
+        let path = dir.join("content.json");
+        let loaded: BTreeMap<String, String> = std::fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        Ok(Self {
+            path,
+            hashes: Mutex::new(loaded.clone()),
+            loaded,
+        })
+    }
+
+    /// Records `content` for `key` and compares it with the content of the last run, so
+    /// a page seen twice in one run gets the same answer both times. Runs of whitespace
+    /// do not count.
+    pub fn record(&self, key: &str, content: &str) -> ContentChange {
+        let hash = cache_key(&content.split_whitespace().collect::<Vec<_>>().join(" "));
+        let change = match self.loaded.get(key) {
+            None => ContentChange::New,
+            Some(previous) if *previous == hash => ContentChange::Unchanged,
+            Some(_) => ContentChange::Changed,
+        };
+        if let Ok(mut hashes) = self.hashes.lock() {
+            hashes.insert(key.to_owned(), hash);
+        }
+        change
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let hashes = self
+            .hashes
+            .lock()
+            .map_err(|_| anyhow::anyhow!("content store lock poisoned"))?;
+        let json = serde_json::to_vec_pretty(&*hashes).context("serialize content hashes")?;
+        write_atomic(&self.path, &json)
+    }
+}
+
+/// Stable 64-bit FNV-1a hash, hex-encoded. Used for file names and content hashes.
+pub(crate) fn cache_key(text: &str) -> String {
+    let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+    for byte in text.as_bytes() {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    format!("{hash:016x}")
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn write_atomic(path: &Path, bytes: &[u8]) -> anyhow::Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, bytes).with_context(|| format!("write {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("rename {}", path.display()))
+}
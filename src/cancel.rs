@@ -0,0 +1,61 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// How often waits and in-flight requests look at the token.
+pub(crate) const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Stops a crawl from another thread.
+///
+/// Clones share one flag. The crawl checks it before every fetch and OpenAI call and
+/// while it waits on per-host intervals, retries, or an HTTP response, then returns
+/// [`CrawlError::Cancelled`](crate::error::CrawlError::Cancelled).
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Sleeps for `duration`, waking early on cancellation; `false` when cancelled.
+    pub(crate) fn sleep(&self, duration: Duration) -> bool {
+        let until = Instant::now() + duration;
+        loop {
+            if self.is_cancelled() {
+                return false;
+            }
+            let remaining = until.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return true;
+            }
+            std::thread::sleep(remaining.min(CANCEL_POLL_INTERVAL));
+        }
+    }
+}
+
+impl From<Arc<AtomicBool>> for CancellationToken {
+    /// Shares an existing flag: storing `true` in it cancels.
+    fn from(flag: Arc<AtomicBool>) -> Self {
+        Self(flag)
+    }
+}
+
+/// Sleeps for `duration`, or until `token` is cancelled; `false` when cancelled.
+pub(crate) fn sleep(token: Option<&CancellationToken>, duration: Duration) -> bool {
+    match token {
+        Some(token) => token.sleep(duration),
+        None => {
+            std::thread::sleep(duration);
+            true
+        }
+    }
+}
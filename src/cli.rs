@@ -1,26 +1,258 @@
+use std::path::PathBuf;
 use std::time::Duration;
 
-use clap::{Args, Parser, Subcommand};
+use clap::parser::ValueSource;
+use clap::{ArgAction, ArgMatches, Args, Parser, Subcommand};
+use url::Url;
 
-use crate::openai::ReasoningEffort;
+use crate::config::Profile;
+use crate::openai::{QueryIntent, ReasoningEffort, SearchContextSize};
+use crate::spider::{
+    FindingsLayout, FramePolicy, PaywallPolicy, ReportFormat, RobotsTagPolicy, SearchFailurePolicy,
+    SeedUrl, SelectionFailurePolicy, SourcesFormat, TrustMergePolicy,
+};
+
+/// Page cap of `--quick`.
+const QUICK_MAX_PAGES: usize = 5;
+/// Time cap of `--quick`.
+const QUICK_MAX_ELAPSED: Duration = Duration::from_secs(10);
 
 #[derive(Debug, Parser)]
 #[command(author, version, about)]
 pub struct Cli {
+    #[arg(
+        short,
+        long,
+        global = true,
+        action = ArgAction::Count,
+        help = "More logs on stderr (-v: debug, -vv: trace)"
+    )]
+    pub verbose: u8,
+
+    #[arg(
+        short,
+        long,
+        global = true,
+        conflicts_with = "verbose",
+        help = "No logs or progress on stderr (errors are still reported)"
+    )]
+    pub quiet: bool,
+
     #[command(subcommand)]
     pub command: Command,
 }
 
+impl Cli {
+    /// Log filter selected by `-v` / `-q`, overriding `RUST_LOG` when set.
+    pub fn log_filter(&self) -> Option<&'static str> {
+        if self.quiet {
+            return Some("off");
+        }
+        match self.verbose {
+            0 => None,
+            1 => Some("debug"),
+            _ => Some("trace"),
+        }
+    }
+}
+
 #[derive(Debug, Subcommand)]
 pub enum Command {
-    Spider(SpiderArgs),
+    Spider(Box<SpiderArgs>),
+    /// Crawl the queries of a cases file against recorded WARC fixtures and score the results
+    Eval(EvalArgs),
+    /// Fetch and extract a URL list and report throughput, latency percentiles, and memory
+    Bench(BenchArgs),
+    /// Crawl one site breadth-first and report which sections address a query
+    Site(SiteArgs),
+    /// Print a shell completion script to stdout
+    Completions(CompletionsArgs),
+    /// Print the roff man page to stdout
+    Manpage,
 }
 
 #[derive(Debug, Args)]
-pub struct SpiderArgs {
-    #[arg(long)]
+pub struct CompletionsArgs {
+    #[arg(value_enum)]
+    pub shell: clap_complete::Shell,
+}
+
+#[derive(Debug, Args)]
+pub struct EvalArgs {
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "TOML (not YAML) file of [[case]] tables: query, fixture WARC, expected URLs, and page, call, and cost limits"
+    )]
+    pub cases: PathBuf,
+
+    #[arg(
+        long,
+        env = "LLM_SPIDER_CONFIG",
+        help = "TOML config file for every case"
+    )]
+    pub config: Option<PathBuf>,
+
+    #[arg(
+        long,
+        env = "LLM_SPIDER_PROFILE",
+        requires = "config",
+        help = "Preset from [profile.<name>] in the config file for every case"
+    )]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct BenchArgs {
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "URLs to fetch, one per line (blank lines and # comments are skipped)"
+    )]
+    pub urls: PathBuf,
+
+    #[arg(
+        long,
+        default_value_t = 4,
+        value_parser = clap::value_parser!(u16).range(1..),
+        help = "Fetches in flight at once (per-host intervals are not applied)"
+    )]
+    pub concurrency: u16,
+}
+
+#[derive(Debug, Args)]
+pub struct SiteArgs {
+    #[arg(
+        long,
+        value_name = "URL",
+        help = "Start page; only pages on its host under its directory are crawled"
+    )]
+    pub root: Url,
+
+    #[arg(long, help = "What the relevance grading checks each page against")]
     pub query: String,
 
+    #[arg(long, default_value_t = 200)]
+    pub max_pages: usize,
+
+    #[arg(long, default_value_t = 10, help = "Link hops from --root")]
+    pub max_depth: usize,
+
+    #[arg(
+        long,
+        value_parser = humantime::parse_duration,
+        default_value = "10m"
+    )]
+    pub max_elapsed: Duration,
+
+    #[arg(long, default_value_t = false)]
+    pub allow_local: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = ReportFormat::Markdown,
+        help = "Write the report as markdown or as one JSON document"
+    )]
+    pub format: ReportFormat,
+
+    #[arg(
+        long,
+        env = "LLM_SPIDER_CONFIG",
+        help = "TOML config file for the OpenAI client and [hosts] pacing"
+    )]
+    pub config: Option<PathBuf>,
+
+    #[arg(
+        long,
+        env = "LLM_SPIDER_PROFILE",
+        requires = "config",
+        help = "Preset from [profile.<name>] in the config file (honor_opt_outs, allow_local, challenge_retry)"
+    )]
+    pub profile: Option<String>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Skip pages whose site opts out of AI use through /ai.txt or a TDM-Reservation header"
+    )]
+    pub honor_opt_outs: bool,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Append a JSON Lines audit record of every HTTP request and OpenAI call"
+    )]
+    pub audit_log: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "ID",
+        env = "LLM_SPIDER_CRAWL_ID",
+        value_parser = parse_crawl_id,
+        help = "Identify this run to site operators: appended to the User-Agent, sent as X-Crawl-Id, and echoed in the report"
+    )]
+    pub crawl_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct SpiderArgs {
+    #[arg(
+        long,
+        required_unless_present_any = ["compare", "batch"],
+        conflicts_with = "batch",
+        help = "What to research (default with --compare: the alternatives joined by \" vs \")"
+    )]
+    pub query: Option<String>,
+
+    #[arg(
+        long,
+        num_args = 2..,
+        value_name = "ALTERNATIVE",
+        help = "Compare alternatives: search each, tag sources by the ones they cover, and add a comparison table"
+    )]
+    pub compare: Vec<String>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "Adjust defaults for this query intent instead of classifying it"
+    )]
+    pub intent: Option<QueryIntent>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Classify the query's intent and adjust defaults to it (one extra OpenAI call)"
+    )]
+    pub classify_intent: bool,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["compare", "dry_run", "estimate", "urls_out"],
+        help = "Research every query in FILE (one per line) in one crawl sharing the page and time budget"
+    )]
+    pub batch: Option<PathBuf>,
+
+    #[arg(long, env = "LLM_SPIDER_CONFIG", help = "TOML config file")]
+    pub config: Option<PathBuf>,
+
+    #[arg(
+        long,
+        env = "LLM_SPIDER_PROFILE",
+        requires = "config",
+        help = "Preset from [profile.<name>] in the config file"
+    )]
+    pub profile: Option<String>,
+
+    #[arg(
+        long,
+        env = "OPENAI_API_KEY_FILE",
+        help = "Read the OpenAI API key from this file instead of OPENAI_API_KEY"
+    )]
+    pub api_key_file: Option<PathBuf>,
+
     #[arg(
         long,
         value_enum,
@@ -28,6 +260,43 @@ pub struct SpiderArgs {
     )]
     pub reasoning_effort: Option<ReasoningEffort>,
 
+    #[arg(
+        long,
+        env = "LLM_SPIDER_OPENAI_TIMEOUT",
+        value_parser = humantime::parse_duration,
+        help = "OpenAI request timeout (default: 20s)"
+    )]
+    pub openai_timeout: Option<Duration>,
+
+    #[arg(
+        long,
+        help = "max_output_tokens for the web search call (default: 512)"
+    )]
+    pub search_max_output_tokens: Option<u64>,
+
+    #[arg(
+        long,
+        help = "max_output_tokens for each link selection call (default: 256)"
+    )]
+    pub select_max_output_tokens: Option<u64>,
+
+    #[arg(long, help = "max_tool_calls for the web search call (default: 2)")]
+    pub search_max_tool_calls: Option<u64>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "web_search search_context_size (default: API default)"
+    )]
+    pub search_context_size: Option<SearchContextSize>,
+
+    #[arg(
+        long,
+        value_name = "CC",
+        help = "Two-letter country code sent as the web_search user location"
+    )]
+    pub search_country: Option<String>,
+
     #[arg(long, default_value_t = 4000)]
     pub max_chars: usize,
 
@@ -43,6 +312,15 @@ pub struct SpiderArgs {
     #[arg(long, default_value_t = 1)]
     pub max_depth: usize,
 
+    #[arg(long, help = "Max depth for High-trust pages (default: --max-depth)")]
+    pub max_depth_high: Option<usize>,
+
+    #[arg(long, help = "Max depth for Medium-trust pages (default: --max-depth)")]
+    pub max_depth_medium: Option<usize>,
+
+    #[arg(long, help = "Max depth for Low-trust pages (default: --max-depth)")]
+    pub max_depth_low: Option<usize>,
+
     #[arg(
         long,
         value_parser = humantime::parse_duration,
@@ -50,12 +328,556 @@ pub struct SpiderArgs {
     )]
     pub max_elapsed: Duration,
 
-    #[arg(long, default_value_t = 20)]
+    #[arg(
+        long,
+        value_parser = humantime::parse_duration,
+        help = "Time limit for the web search call (default: OpenAI request timeout)"
+    )]
+    pub search_budget: Option<Duration>,
+
+    #[arg(
+        long,
+        value_parser = humantime::parse_duration,
+        help = "Time limit for fetching pages, counted from the first fetch (replaces --max-elapsed)"
+    )]
+    pub crawl_budget: Option<Duration>,
+
+    #[arg(
+        long,
+        default_value_t = 20,
+        help = "Link candidates per selection call"
+    )]
     pub max_child_candidates: usize,
 
+    #[arg(
+        long,
+        default_value_t = 3,
+        help = "Selection calls per page when it has more than --max-child-candidates links"
+    )]
+    pub max_candidate_batches: usize,
+
     #[arg(long, default_value_t = 3)]
     pub max_children_per_page: usize,
 
+    #[arg(
+        long,
+        default_value_t = 10,
+        help = "Children selected from a link-index page, which is not counted as a source (0: treat as a regular page)"
+    )]
+    pub max_children_per_hub: usize,
+
+    #[arg(
+        long,
+        default_value_t = 0,
+        help = "Follow up to N next-page links per source and append their text (0: off)"
+    )]
+    pub max_pagination: usize,
+
+    #[arg(
+        long,
+        default_value_t = 0,
+        help = "Keep at most N sources per host; further pages are listed separately (0: no limit)"
+    )]
+    pub max_sources_per_host: usize,
+
+    #[arg(
+        long,
+        default_value_t = 10_000,
+        help = "Max queued URLs; lowest-tier entries are evicted first (0: unlimited)"
+    )]
+    pub max_frontier: usize,
+
+    #[arg(
+        long,
+        default_value_t = 100_000,
+        help = "Max remembered visited URLs; oldest are forgotten first (0: unlimited)"
+    )]
+    pub max_visited: usize,
+
+    #[arg(
+        long,
+        default_value_t = 0,
+        help = "Crawl-wide download budget in bytes; stops fetching once reached (0: unlimited)"
+    )]
+    pub max_total_bytes: u64,
+
+    #[arg(
+        long,
+        default_value_t = 3,
+        help = "Skip a host's remaining URLs after this many failed fetches in a row (0: never)"
+    )]
+    pub host_failure_limit: usize,
+
+    #[arg(
+        long = "exclusion-status",
+        value_name = "CODE",
+        value_delimiter = ',',
+        help = "Also exclude a host for the rest of the crawl on its first answer with this HTTP status, like 451 (repeatable)"
+    )]
+    pub exclusion_statuses: Vec<u16>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Skip pages whose site opts out of AI use through /ai.txt or a TDM-Reservation header"
+    )]
+    pub honor_opt_outs: bool,
+
+    #[arg(
+        long,
+        default_value_t = 4,
+        help = "Upcoming frontier URLs whose hosts are resolved in the background (0: off)"
+    )]
+    pub dns_prefetch: usize,
+
+    #[arg(
+        long,
+        env = "LLM_SPIDER_CACHE_DIR",
+        help = "Page cache directory; cached pages are revalidated with ETag/Last-Modified"
+    )]
+    pub cache_dir: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Write fetched responses to a WARC file (gzip-compressed when it ends in .gz)"
+    )]
+    pub warc: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Read `pin <url>` / `ban <host>` lines from FILE between fetches while crawling"
+    )]
+    pub control: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Append a JSON Lines audit record of every HTTP request and OpenAI call"
+    )]
+    pub audit_log: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "ID",
+        env = "LLM_SPIDER_CRAWL_ID",
+        value_parser = parse_crawl_id,
+        help = "Identify this run to site operators: appended to the User-Agent, sent as X-Crawl-Id, and echoed in every artifact"
+    )]
+    pub crawl_id: Option<String>,
+
+    #[arg(
+        long,
+        help = "Show each page's selected links on stderr and confirm or edit them on stdin before they are queued"
+    )]
+    pub interactive: bool,
+
+    #[arg(
+        long,
+        value_parser = humantime::parse_duration,
+        help = "Fetch a page once more after this delay when it answers with a bot challenge"
+    )]
+    pub challenge_retry: Option<Duration>,
+
     #[arg(long, default_value_t = false)]
     pub allow_local: bool,
+
+    #[arg(
+        long,
+        value_name = "LANG",
+        help = "Preferred hreflang variant (e.g. ja) when a page exists in several languages"
+    )]
+    pub preferred_language: Option<String>,
+
+    #[arg(
+        long = "seed-url",
+        value_name = "URL",
+        help = "Crawl this URL as a High-trust seed in addition to search hits (repeatable); \
+                append depth=N and children=N to override the limits below it"
+    )]
+    pub seed_urls: Vec<SeedUrl>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Read more --seed-url seeds from FILE, one per line (blank lines and # comments skipped)"
+    )]
+    pub seed_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = SearchFailurePolicy::Abort,
+        help = "On web search failure: abort, or continue with --seed-url seeds and report why"
+    )]
+    pub on_search_failure: SearchFailurePolicy,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = SelectionFailurePolicy::Heuristic,
+        help = "On link selection failure: pick children heuristically, skip them, or abort"
+    )]
+    pub on_selection_failure: SelectionFailurePolicy,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = PaywallPolicy::Flag,
+        help = "On paywalled pages: keep and flag them, keep only the free preview, or skip them"
+    )]
+    pub paywall_policy: PaywallPolicy,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = FramePolicy::Ignore,
+        help = "On pages whose content lives in a same-origin iframe: ignore the frame, or follow it"
+    )]
+    pub frame_policy: FramePolicy,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = RobotsTagPolicy::Respect,
+        help = "Honor noindex and nofollow from X-Robots-Tag headers and robots meta tags: respect, noindex, nofollow, or ignore"
+    )]
+    pub robots_tags: RobotsTagPolicy,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = TrustMergePolicy::ClassifierWins,
+        help = "Combine [hosts] trust tiers with the model's: classifier-wins, llm-wins, min, or max"
+    )]
+    pub trust_merge: TrustMergePolicy,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = SourcesFormat::List,
+        help = "Render the Sources section as a bullet list or a table"
+    )]
+    pub sources_format: SourcesFormat,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = FindingsLayout::Flat,
+        help = "Render Findings per source, or grouped by subtopic (one extra OpenAI call)"
+    )]
+    pub findings_layout: FindingsLayout,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Suggest follow-up queries under Next steps (one extra OpenAI call)"
+    )]
+    pub follow_ups: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "List key terms shared by several sources, with definitions (one extra OpenAI call)"
+    )]
+    pub key_terms: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Add a chronological timeline of dated facts with citations (one extra OpenAI call)"
+    )]
+    pub timeline: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Drop sources graded as not useful for the query, listed in an appendix (one extra OpenAI call)"
+    )]
+    pub grade_sources: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Write a short sourced answer under Answer (one extra OpenAI call)"
+    )]
+    pub answer: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Quick answer: at most 5 pages in 10 seconds, no link following, then --answer"
+    )]
+    pub quick: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = ReportFormat::Markdown,
+        help = "Write the report as markdown or as one JSON document"
+    )]
+    pub format: ReportFormat,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["format", "dry_run", "estimate"],
+        help = "Render the report with a Handlebars template over the --format json document"
+    )]
+    pub report_template: Option<PathBuf>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["format", "report_template", "batch"],
+        help = "Start the markdown report with YAML front matter (query, date, source count, tags, cost)"
+    )]
+    pub front_matter: bool,
+
+    #[arg(
+        long = "tag",
+        value_name = "TAG",
+        requires = "front_matter",
+        help = "Add a tag to the front matter (repeatable)"
+    )]
+    pub tags: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Write every visited and skipped URL (with a reason code) to this file, one per line"
+    )]
+    pub urls_out: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Write every link selection (candidates, picks, and which became sources) to this file as JSON Lines"
+    )]
+    pub selection_log: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        conflicts_with = "batch",
+        help = "Also write one markdown note per source and an index note wiki-linking them to DIR"
+    )]
+    pub notes_dir: Option<PathBuf>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Also copy the report to the system clipboard"
+    )]
+    pub copy: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Print the report even when it is taller than the terminal, instead of opening $PAGER"
+    )]
+    pub no_pager: bool,
+
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = humantime::parse_duration,
+        help = "Notify the desktop when a crawl that ran at least this long finishes or fails"
+    )]
+    pub notify_after: Option<Duration>,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        conflicts_with_all = ["dry_run", "estimate"],
+        help = "Keep the cache, WARC, audit log, URL list, selection log, and report of this crawl in DIR, with a manifest.json"
+    )]
+    pub workdir: Option<PathBuf>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Append a Stats section to the report"
+    )]
+    pub stats: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Reproducible reports for replayed fixtures: ignore --max-elapsed and sort links"
+    )]
+    pub deterministic: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Show a live status line on stderr while crawling"
+    )]
+    pub progress: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Run the web search, print the planned frontier and limits, and exit without fetching"
+    )]
+    pub dry_run: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with = "dry_run",
+        help = "Print worst-case OpenAI calls, tokens, and cost for the limits, and exit"
+    )]
+    pub estimate: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["dry_run", "estimate"],
+        help = "Print the plan (searches, seeds with tiers, limits, estimated cost) to stderr before fetching"
+    )]
+    pub show_plan: bool,
+
+    #[arg(
+        long,
+        value_name = "USD",
+        conflicts_with_all = ["dry_run", "estimate"],
+        help = "Show the plan and ask before fetching when the estimated cost is above this (or unknown)"
+    )]
+    pub confirm_above: Option<f64>,
+
+    #[arg(
+        short = 'y',
+        long,
+        default_value_t = false,
+        requires = "confirm_above",
+        help = "Crawl without asking when the estimate is above --confirm-above"
+    )]
+    pub yes: bool,
+}
+
+impl SpiderArgs {
+    /// Fills options that were not set on the command line or via env from `profile`.
+    ///
+    /// `matches` are the `spider` subcommand matches these args were parsed from.
+    pub fn apply_profile(&mut self, profile: &Profile, matches: &ArgMatches) {
+        let explicit = |id: &str| is_explicit(matches, id);
+
+        macro_rules! fill {
+            ($($field:ident),* $(,)?) => {$(
+                if let Some(value) = profile.$field.clone()
+                    && !explicit(stringify!($field))
+                {
+                    self.$field = value;
+                }
+            )*};
+        }
+        macro_rules! fill_optional {
+            ($($field:ident),* $(,)?) => {$(
+                if profile.$field.is_some() && !explicit(stringify!($field)) {
+                    self.$field = profile.$field.clone();
+                }
+            )*};
+        }
+
+        fill!(
+            max_chars,
+            min_sources,
+            search_limit,
+            max_pages,
+            max_depth,
+            max_elapsed,
+            max_child_candidates,
+            max_candidate_batches,
+            max_children_per_page,
+            max_children_per_hub,
+            max_pagination,
+            max_sources_per_host,
+            max_frontier,
+            max_visited,
+            max_total_bytes,
+            host_failure_limit,
+            exclusion_statuses,
+            honor_opt_outs,
+            dns_prefetch,
+            allow_local,
+            sources_format,
+            findings_layout,
+            paywall_policy,
+            frame_policy,
+            robots_tags,
+            follow_ups,
+            key_terms,
+            timeline,
+            grade_sources,
+            answer,
+            classify_intent,
+            trust_merge,
+        );
+        fill_optional!(
+            max_depth_high,
+            max_depth_medium,
+            max_depth_low,
+            search_budget,
+            crawl_budget,
+            challenge_retry,
+            preferred_language,
+            reasoning_effort,
+            intent,
+        );
+    }
+
+    /// Applies `--quick`: caps `max_pages` and the time limits, follows no links, and
+    /// turns on `answer`. Options given on the command line or via env keep their values.
+    pub fn apply_quick(&mut self, matches: &ArgMatches) {
+        let explicit = |id: &str| is_explicit(matches, id);
+        if !explicit("max_pages") {
+            self.max_pages = self.max_pages.min(QUICK_MAX_PAGES);
+        }
+        if !explicit("max_elapsed") {
+            self.max_elapsed = self.max_elapsed.min(QUICK_MAX_ELAPSED);
+        }
+        if !explicit("crawl_budget") {
+            self.crawl_budget = self
+                .crawl_budget
+                .map(|budget| budget.min(QUICK_MAX_ELAPSED));
+        }
+        if !explicit("max_depth") {
+            self.max_depth = 0;
+        }
+        for (id, depth) in [
+            ("max_depth_high", &mut self.max_depth_high),
+            ("max_depth_medium", &mut self.max_depth_medium),
+            ("max_depth_low", &mut self.max_depth_low),
+        ] {
+            if !explicit(id) {
+                *depth = None;
+            }
+        }
+        self.answer = true;
+    }
+}
+
+/// Whether option `id` of `matches` was given on the command line or via env.
+pub fn is_explicit(matches: &ArgMatches, id: &str) -> bool {
+    matches!(
+        matches.value_source(id),
+        Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+    )
+}
+
+/// A crawl id is sent in the User-Agent and a header, so it is kept to ASCII letters,
+/// digits, `.`, `_`, and `-`.
+fn parse_crawl_id(value: &str) -> Result<String, String> {
+    if value.is_empty() || value.len() > 64 {
+        return Err("expected 1 to 64 characters".to_owned());
+    }
+    if !value
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+    {
+        return Err("use only ASCII letters, digits, '.', '_', and '-'".to_owned());
+    }
+    Ok(value.to_owned())
 }
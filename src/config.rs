@@ -0,0 +1,253 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Context as _;
+use serde::Deserialize;
+
+use crate::estimate::ModelPricing;
+use crate::openai::prompts::{
+    PromptTemplates, SEARCH_PLACEHOLDERS, SELECT_PLACEHOLDERS, check_placeholders,
+};
+use crate::openai::{
+    ModelCapabilities, QueryIntent, ReasoningEffort, SearchContextSize, UserLocation,
+};
+use crate::spider::{
+    FindingsLayout, FramePolicy, HostPolicy, PaywallPolicy, RobotsTagPolicy, SourcesFormat,
+    TrustMergePolicy, UrlPatterns,
+};
+
+/// Optional TOML configuration loaded via `--config` or `LLM_SPIDER_CONFIG`.
+///
+/// ```toml
+/// [hosts."small-community.example"]
+/// min_interval = "2s"
+/// max_pages = 3
+///
+/// [profile.quick]
+/// max_pages = 5
+/// max_depth = 0
+///
+/// [pricing."gpt-5.2"]
+/// input_per_million = 1.25
+/// output_per_million = 10.0
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub hosts: BTreeMap<String, HostPolicy>,
+    /// Parameter support per model name (or name prefix).
+    #[serde(default)]
+    pub models: BTreeMap<String, ModelCapabilities>,
+    /// Model prices used by `--estimate`.
+    #[serde(default)]
+    pub pricing: BTreeMap<String, ModelPricing>,
+    #[serde(default)]
+    pub openai: OpenAiConfig,
+    #[serde(default)]
+    pub prompts: PromptsConfig,
+    /// Link candidate path patterns; unset lists keep the built-in ones.
+    #[serde(default)]
+    pub url_patterns: UrlPatterns,
+    /// Named presets selected with `--profile`.
+    #[serde(default, rename = "profile")]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// `[openai]` settings. Environment variables and CLI flags take precedence.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OpenAiConfig {
+    /// Sent as `OpenAI-Organization` (env: `OPENAI_ORG_ID`).
+    pub organization: Option<String>,
+    /// Sent as `OpenAI-Project` (env: `OPENAI_PROJECT`).
+    pub project: Option<String>,
+    /// Request timeout (default: `20s`).
+    #[serde(default, with = "humantime_serde")]
+    pub timeout: Option<Duration>,
+    /// `max_output_tokens` for the web search call (default: `512`).
+    pub search_max_output_tokens: Option<u64>,
+    /// `max_output_tokens` for each link selection call (default: `256`).
+    pub select_max_output_tokens: Option<u64>,
+    #[serde(default)]
+    pub web_search: WebSearchConfig,
+}
+
+/// `[openai.web_search]` tool settings.
+///
+/// ```toml
+/// [openai.web_search]
+/// max_tool_calls = 3
+/// search_context_size = "high"
+/// user_location = { country = "JP", timezone = "Asia/Tokyo" }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebSearchConfig {
+    /// Default: `2`.
+    pub max_tool_calls: Option<u64>,
+    pub search_context_size: Option<SearchContextSize>,
+    #[serde(default)]
+    pub user_location: UserLocation,
+}
+
+/// `[prompts]` template files replacing the built-in prompts.
+///
+/// Relative paths are resolved against the config file's directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PromptsConfig {
+    pub search_system: Option<PathBuf>,
+    /// User prompt for the web search call.
+    pub search: Option<PathBuf>,
+    pub select_system: Option<PathBuf>,
+    /// User prompt for each link selection call.
+    pub select: Option<PathBuf>,
+    /// Names the query language in selection prompts for non-Latin-script queries
+    /// (default: `true`).
+    pub select_language_hint: Option<bool>,
+}
+
+impl PromptsConfig {
+    /// Reads the configured template files and rejects unknown placeholders.
+    pub fn templates(&self) -> anyhow::Result<PromptTemplates> {
+        Ok(PromptTemplates {
+            search_system: read_template(self.search_system.as_deref(), SEARCH_PLACEHOLDERS)?,
+            search_user: read_template(self.search.as_deref(), SEARCH_PLACEHOLDERS)?,
+            select_system: read_template(self.select_system.as_deref(), SELECT_PLACEHOLDERS)?,
+            select_user: read_template(self.select.as_deref(), SELECT_PLACEHOLDERS)?,
+        })
+    }
+
+    fn resolve_relative_to(&mut self, dir: &Path) {
+        for path in [
+            &mut self.search_system,
+            &mut self.search,
+            &mut self.select_system,
+            &mut self.select,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if path.is_relative() {
+                *path = dir.join(&*path);
+            }
+        }
+    }
+}
+
+fn read_template(path: Option<&Path>, allowed: &[&str]) -> anyhow::Result<Option<String>> {
+    let Some(path) = path else {
+        return Ok(None);
+    };
+    let template = std::fs::read_to_string(path)
+        .with_context(|| format!("read prompt template: {}", path.display()))?;
+    check_placeholders(&template, allowed).with_context(|| path.display().to_string())?;
+    Ok(Some(template))
+}
+
+/// A crawl preset. Unset keys keep the CLI defaults; explicit flags override the profile.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub max_chars: Option<usize>,
+    pub min_sources: Option<usize>,
+    pub search_limit: Option<usize>,
+    pub max_pages: Option<usize>,
+    pub max_depth: Option<usize>,
+    pub max_depth_high: Option<usize>,
+    pub max_depth_medium: Option<usize>,
+    pub max_depth_low: Option<usize>,
+    #[serde(default, with = "humantime_serde")]
+    pub max_elapsed: Option<Duration>,
+    #[serde(default, with = "humantime_serde")]
+    pub search_budget: Option<Duration>,
+    #[serde(default, with = "humantime_serde")]
+    pub crawl_budget: Option<Duration>,
+    #[serde(default, with = "humantime_serde")]
+    pub challenge_retry: Option<Duration>,
+    pub max_child_candidates: Option<usize>,
+    pub max_candidate_batches: Option<usize>,
+    pub max_children_per_page: Option<usize>,
+    pub max_children_per_hub: Option<usize>,
+    pub max_pagination: Option<usize>,
+    pub max_sources_per_host: Option<usize>,
+    pub max_frontier: Option<usize>,
+    pub max_visited: Option<usize>,
+    pub max_total_bytes: Option<u64>,
+    pub host_failure_limit: Option<usize>,
+    /// Statuses excluding a host on top of `451`.
+    pub exclusion_statuses: Option<Vec<u16>>,
+    pub honor_opt_outs: Option<bool>,
+    pub dns_prefetch: Option<usize>,
+    pub allow_local: Option<bool>,
+    pub preferred_language: Option<String>,
+    pub sources_format: Option<SourcesFormat>,
+    pub findings_layout: Option<FindingsLayout>,
+    pub paywall_policy: Option<PaywallPolicy>,
+    pub frame_policy: Option<FramePolicy>,
+    pub robots_tags: Option<RobotsTagPolicy>,
+    pub follow_ups: Option<bool>,
+    pub key_terms: Option<bool>,
+    pub timeline: Option<bool>,
+    pub grade_sources: Option<bool>,
+    pub answer: Option<bool>,
+    pub classify_intent: Option<bool>,
+    pub intent: Option<QueryIntent>,
+    pub trust_merge: Option<TrustMergePolicy>,
+    pub reasoning_effort: Option<ReasoningEffort>,
+    /// Overrides `LLM_SPIDER_OPENAI_SEARCH_MODEL`.
+    pub search_model: Option<String>,
+    /// Overrides `LLM_SPIDER_OPENAI_SELECT_MODEL`.
+    pub select_model: Option<String>,
+    /// Per-host politeness merged over the top-level `[hosts]`.
+    #[serde(default)]
+    pub hosts: BTreeMap<String, HostPolicy>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("read config: {}", path.display()))?;
+        let mut config =
+            Self::parse(&text).with_context(|| format!("parse config: {}", path.display()))?;
+        if let Some(dir) = path.parent() {
+            config.prompts.resolve_relative_to(dir);
+        }
+        Ok(config)
+    }
+
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let config: Self = toml::from_str(text)?;
+        for (host, policy) in &config.hosts {
+            if policy.max_concurrent == Some(0) {
+                anyhow::bail!("hosts.{host}.max_concurrent must be at least 1");
+            }
+        }
+        for (name, profile) in &config.profiles {
+            for (host, policy) in &profile.hosts {
+                if policy.max_concurrent == Some(0) {
+                    anyhow::bail!("profile.{name}.hosts.{host}.max_concurrent must be at least 1");
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn profile(&self, name: &str) -> anyhow::Result<&Profile> {
+        self.profiles.get(name).ok_or_else(|| {
+            let known = self.profiles.keys().cloned().collect::<Vec<_>>();
+            anyhow::anyhow!("unknown profile `{name}` (defined: {})", known.join(", "))
+        })
+    }
+
+    /// Top-level `[hosts]` with the profile's host entries layered on top.
+    pub fn host_policies(&self, profile: Option<&Profile>) -> BTreeMap<String, HostPolicy> {
+        let mut hosts = self.hosts.clone();
+        if let Some(profile) = profile {
+            hosts.extend(profile.hosts.clone());
+        }
+        hosts
+    }
+}
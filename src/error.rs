@@ -0,0 +1,112 @@
+use std::time::Duration;
+
+use url::Url;
+
+use crate::spider::{OptOutSignal, SkipReason};
+
+/// Failure kinds surfaced by the crawl API.
+///
+/// [`crate::spider::crawl`] and [`crate::spider::plan_crawl`] return this type. Fetchers and
+/// the OpenAI client still use `anyhow` internally, but wrap these variants so callers (and
+/// the crawl loop) can recover them with `anyhow::Error::downcast_ref::<CrawlError>()`.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum CrawlError {
+    /// The web search call failed for a reason other than rate limiting.
+    SearchFailed(anyhow::Error),
+    /// Link selection for `page` failed.
+    SelectionFailed { page: Url, source: anyhow::Error },
+    /// OpenAI answered `429 Too Many Requests`.
+    LlmRateLimited { retry_after: Option<Duration> },
+    /// A structured LLM reply still did not match its JSON schema after one repair attempt.
+    LlmOutputInvalid { reason: String },
+    /// OpenAI answered with another non-success status.
+    LlmHttp { status: u16, body: String },
+    /// `robots.txt` disallows the URL.
+    RobotsBlocked { url: Url },
+    /// The site opted the URL out of AI use (`ai.txt`, `TDM-Reservation`).
+    OptedOut { url: Url, signal: OptOutSignal },
+    /// The page could not be fetched; `status` is set for HTTP error responses.
+    FetchFailed { url: Url, status: Option<u16> },
+    /// The resource is not text (image, archive, …).
+    NonTextual { url: Url, content_type: String },
+    /// The server answered with a bot-challenge or CAPTCHA interstitial instead of the page.
+    BotChallenge { url: Url, vendor: String },
+    /// The server redirected to `location`, which the crawl requests as a fetch of its own.
+    Redirected { location: Url },
+    /// A redirect led to `url`, which the crawl would not fetch as a link either.
+    RedirectRefused { url: Url, reason: SkipReason },
+    /// The on-disk cache could not be opened.
+    Cache(anyhow::Error),
+    /// The HTTP client or runtime could not be initialized.
+    Init(anyhow::Error),
+    /// The request's [`CancellationToken`](crate::cancel::CancellationToken) was cancelled.
+    Cancelled,
+    /// The request's [`PlanReview`](crate::spider::PlanReview) declined the plan.
+    PlanDeclined,
+}
+
+impl CrawlError {
+    /// Recovers a `CrawlError` wrapped in `err`, or builds one with `otherwise`.
+    pub(crate) fn classify(
+        err: anyhow::Error,
+        otherwise: impl FnOnce(anyhow::Error) -> Self,
+    ) -> Self {
+        match err.downcast::<Self>() {
+            Ok(typed) => typed,
+            Err(err) => otherwise(err),
+        }
+    }
+}
+
+impl std::fmt::Display for CrawlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SearchFailed(_) => write!(f, "web search failed"),
+            Self::SelectionFailed { page, .. } => write!(f, "select child links: {page}"),
+            Self::LlmRateLimited {
+                retry_after: Some(after),
+            } => write!(
+                f,
+                "openai rate limited (retry after {})",
+                humantime::format_duration(*after)
+            ),
+            Self::LlmRateLimited { retry_after: None } => write!(f, "openai rate limited"),
+            Self::LlmOutputInvalid { reason } => {
+                write!(f, "llm output does not match schema: {reason}")
+            }
+            Self::LlmHttp { status, body } => write!(f, "http status: {status}; body: {body}"),
+            Self::RobotsBlocked { .. } => write!(f, "blocked by robots.txt"),
+            Self::OptedOut { signal, .. } => write!(f, "opted out by {}", signal.name()),
+            Self::FetchFailed {
+                status: Some(status),
+                ..
+            } => write!(f, "http status: {status}"),
+            Self::FetchFailed { url, status: None } => write!(f, "fetch failed: {url}"),
+            Self::NonTextual { content_type, .. } => {
+                write!(f, "non-textual content-type: {content_type}")
+            }
+            Self::BotChallenge { vendor, .. } => write!(f, "bot challenge ({vendor})"),
+            Self::Redirected { location } => write!(f, "redirected to {location}"),
+            Self::RedirectRefused { url, reason } => {
+                write!(f, "redirect to {url} refused ({})", reason.code())
+            }
+            Self::Cache(_) => write!(f, "open page cache"),
+            Self::Init(_) => write!(f, "init spider page fetcher"),
+            Self::Cancelled => write!(f, "crawl cancelled"),
+            Self::PlanDeclined => write!(f, "crawl plan declined"),
+        }
+    }
+}
+
+impl std::error::Error for CrawlError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::SearchFailed(source)
+            | Self::SelectionFailed { source, .. }
+            | Self::Cache(source)
+            | Self::Init(source) => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use crate::openai::{
+    ANALYSIS_MAX_OUTPUT_TOKENS, ModelUsage, OpenAiModels, OutputTokenLimits, SELECT_EXCERPT_CHARS,
+};
+use crate::spider::{FindingsLayout, UserRequest};
+
+/// Rough characters-per-token ratio used for prompt sizing.
+const CHARS_PER_TOKEN: u64 = 4;
+/// Fixed prompt text around the web search query (system prompt plus framing).
+const SEARCH_PROMPT_CHARS: u64 = 600;
+/// Search result context the model reads per `web_search` tool call.
+const SEARCH_CONTEXT_TOKENS_PER_TOOL_CALL: u64 = 8_000;
+/// Fixed prompt text around link selection (system prompt, rules, framing).
+const SELECT_PROMPT_CHARS: u64 = 700;
+/// Serialized size of one `{"url", "anchor_text"}` candidate.
+const SELECT_CHARS_PER_CANDIDATE: u64 = 160;
+/// Fixed prompt text around a post-crawl analysis call.
+const ANALYSIS_PROMPT_CHARS: u64 = 600;
+/// Serialized `id`, URL and title of one source, on top of its excerpt.
+const ANALYSIS_CHARS_PER_SOURCE: u64 = 200;
+
+/// Per-model prices in USD, configured under `[pricing."<model>"]`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModelPricing {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+    /// Fee per `web_search` tool call, when the model is used for search.
+    #[serde(default)]
+    pub web_search_per_call: f64,
+}
+
+/// Worst-case OpenAI usage for one crawl, derived from the limits alone.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Estimate {
+    pub search: CallEstimate,
+    pub select: CallEstimate,
+    /// Calls besides search and link selection (intent classification, and post-crawl
+    /// subtopic grouping, follow-ups, key terms, timeline, source grading, answer); made
+    /// with the select model.
+    pub analysis: CallEstimate,
+    /// `None` when pricing is missing for a model that would be called.
+    pub cost_usd: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallEstimate {
+    pub model: String,
+    pub calls: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+pub fn estimate(
+    request: &UserRequest,
+    models: &OpenAiModels,
+    output_tokens: OutputTokenLimits,
+    max_tool_calls: u64,
+    pricing: &BTreeMap<String, ModelPricing>,
+) -> Estimate {
+    // `--compare` runs one search per alternative.
+    let search_calls = request.compare.len().max(1) as u64;
+    let search_input = (SEARCH_PROMPT_CHARS + request.query.chars().count() as u64)
+        / CHARS_PER_TOKEN
+        + SEARCH_CONTEXT_TOKENS_PER_TOOL_CALL * max_tool_calls;
+    let search = CallEstimate {
+        model: models.search.clone(),
+        calls: search_calls,
+        input_tokens: search_input * search_calls,
+        output_tokens: output_tokens.search * search_calls,
+    };
+
+    // Every fetched page except the last may trigger one selection call per candidate batch.
+    let follows_links = |max_depth: usize, max_children: usize| max_depth > 0 && max_children > 0;
+    let any_seed_follows_links = request.seed_urls.iter().any(|seed| {
+        follows_links(
+            seed.limits.max_depth.unwrap_or(request.deepest_max_depth()),
+            seed.limits
+                .max_children
+                .unwrap_or(request.max_children_per_page),
+        )
+    });
+    let select_calls = if !follows_links(request.deepest_max_depth(), request.max_children_per_page)
+        && !any_seed_follows_links
+    {
+        0
+    } else {
+        request.max_pages.saturating_sub(1) as u64 * request.max_candidate_batches.max(1) as u64
+    };
+    let select_input_per_call = (SELECT_PROMPT_CHARS
+        + request.query.chars().count() as u64
+        + SELECT_EXCERPT_CHARS as u64
+        + SELECT_CHARS_PER_CANDIDATE * request.max_child_candidates as u64)
+        / CHARS_PER_TOKEN;
+    let select = CallEstimate {
+        model: models.select.clone(),
+        calls: select_calls,
+        input_tokens: select_input_per_call * select_calls,
+        output_tokens: output_tokens.select * select_calls,
+    };
+
+    // `--compare` groups findings by alternative without a call.
+    let analysis_calls = u64::from(
+        request.compare.is_empty() && request.findings_layout == FindingsLayout::Subtopics,
+    ) + u64::from(request.follow_ups)
+        + u64::from(request.key_terms && request.max_pages >= 2)
+        + u64::from(request.timeline)
+        + u64::from(request.grade_sources)
+        + u64::from(request.answer)
+        + u64::from(request.classify_intent && request.intent.is_none());
+    let analysis_input_per_call = (ANALYSIS_PROMPT_CHARS
+        + request.query.chars().count() as u64
+        + (SELECT_EXCERPT_CHARS as u64 + ANALYSIS_CHARS_PER_SOURCE) * request.max_pages as u64)
+        / CHARS_PER_TOKEN;
+    let analysis = CallEstimate {
+        model: models.select.clone(),
+        calls: analysis_calls,
+        input_tokens: analysis_input_per_call * analysis_calls,
+        output_tokens: ANALYSIS_MAX_OUTPUT_TOKENS * analysis_calls,
+    };
+
+    let search_cost = pricing.get(&search.model).map(|price| {
+        token_cost(price, &search)
+            + price.web_search_per_call * (max_tool_calls * search_calls) as f64
+    });
+    let select_cost = if select.calls == 0 {
+        Some(0.0)
+    } else {
+        pricing
+            .get(&select.model)
+            .map(|price| token_cost(price, &select))
+    };
+    let analysis_cost = if analysis.calls == 0 {
+        Some(0.0)
+    } else {
+        pricing
+            .get(&analysis.model)
+            .map(|price| token_cost(price, &analysis))
+    };
+    let cost_usd = search_cost
+        .zip(select_cost)
+        .zip(analysis_cost)
+        .map(|((a, b), c)| a + b + c);
+
+    Estimate {
+        search,
+        select,
+        analysis,
+        cost_usd,
+    }
+}
+
+/// What the calls a client actually made cost; `None` when a model that was called has
+/// no pricing.
+pub fn usage_cost(
+    usage: &BTreeMap<String, ModelUsage>,
+    pricing: &BTreeMap<String, ModelPricing>,
+) -> Option<f64> {
+    usage
+        .iter()
+        .map(|(model, usage)| {
+            let price = pricing.get(model)?;
+            Some(
+                (usage.input_tokens as f64 * price.input_per_million
+                    + usage.output_tokens as f64 * price.output_per_million)
+                    / 1_000_000.0
+                    + price.web_search_per_call * usage.web_search_calls as f64,
+            )
+        })
+        .sum()
+}
+
+fn token_cost(price: &ModelPricing, call: &CallEstimate) -> f64 {
+    (call.input_tokens as f64 * price.input_per_million
+        + call.output_tokens as f64 * price.output_per_million)
+        / 1_000_000.0
+}
+
+pub fn compose_estimate_markdown(request: &UserRequest, estimate: &Estimate) -> String {
+    format!(
+        "# Spider Estimate\n\n{}",
+        compose_estimate_table(request, estimate)
+    )
+}
+
+/// The estimate without its title: the limits it assumes, the calls per step, and
+/// the cost.
+pub(crate) fn compose_estimate_table(request: &UserRequest, estimate: &Estimate) -> String {
+    let mut out = String::new();
+    out.push_str(&format!(
+        "Worst case for `max_pages` {}, `max_child_candidates` {} x {} batches, `max_children_per_page` {}.\n\n",
+        request.max_pages,
+        request.max_child_candidates,
+        request.max_candidate_batches,
+        request.max_children_per_page
+    ));
+    out.push_str("| Step | Model | Calls | Input tokens | Output tokens |\n");
+    out.push_str("|------|-------|-------|--------------|---------------|\n");
+    let steps = [
+        ("search", &estimate.search),
+        ("select", &estimate.select),
+        ("analysis", &estimate.analysis),
+    ];
+    for (step, call) in steps {
+        if step == "analysis" && call.calls == 0 {
+            continue;
+        }
+        out.push_str(&format!(
+            "| {step} | {} | {} | {} | {} |\n",
+            call.model, call.calls, call.input_tokens, call.output_tokens
+        ));
+    }
+    out.push('\n');
+    match estimate.cost_usd {
+        Some(cost) => out.push_str(&format!("Estimated cost: ${cost:.4}\n")),
+        None => out
+            .push_str("Estimated cost: unknown (add `[pricing.\"<model>\"]` to the config file)\n"),
+    }
+    out
+}
@@ -0,0 +1,190 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context as _, bail};
+use serde::{Deserialize, Deserializer};
+use url::Url;
+
+use crate::spider::{CrawlResult, normalize_url};
+
+/// Cases for `llm-spider eval`, read from a TOML file of `[[case]]` tables (TOML like
+/// the config file, rather than YAML).
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EvalCases {
+    #[serde(default, rename = "case")]
+    pub cases: Vec<EvalCase>,
+}
+
+/// One query crawled against a recorded web and the bounds its result must meet.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EvalCase {
+    pub name: String,
+    pub query: String,
+    /// WARC file the pages are served from (see `--warc`); relative paths are resolved
+    /// against the cases file.
+    pub fixture: PathBuf,
+    /// Extra `spider` options for this case, e.g. `["--max-depth", "2"]`.
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// URLs that must end up among the sources.
+    #[serde(default, deserialize_with = "deserialize_urls")]
+    pub expected_urls: Vec<Url>,
+    /// Most pages the crawl may fetch.
+    pub max_pages: Option<usize>,
+    /// Most OpenAI calls the crawl may make.
+    pub max_llm_calls: Option<usize>,
+    /// Most the crawl's OpenAI calls may cost, in USD, at the config file's `[pricing]`.
+    pub max_cost_usd: Option<f64>,
+}
+
+impl EvalCases {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("read eval cases: {}", path.display()))?;
+        let mut cases =
+            Self::parse(&text).with_context(|| format!("parse eval cases: {}", path.display()))?;
+        if let Some(dir) = path.parent() {
+            for case in &mut cases.cases {
+                if case.fixture.is_relative() {
+                    case.fixture = dir.join(&case.fixture);
+                }
+            }
+        }
+        Ok(cases)
+    }
+
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let cases: Self = toml::from_str(text)?;
+        if cases.cases.is_empty() {
+            bail!("no [[case]] tables");
+        }
+        let mut names = HashSet::new();
+        for case in &cases.cases {
+            if !names.insert(case.name.as_str()) {
+                bail!("duplicate case name: {}", case.name);
+            }
+        }
+        Ok(cases)
+    }
+}
+
+fn deserialize_urls<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Url>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|url| Url::parse(url).map_err(|err| serde::de::Error::custom(format!("{url}: {err}"))))
+        .collect()
+}
+
+/// How one case's crawl measured up.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvalOutcome {
+    pub name: String,
+    /// Expected URLs that are not among the sources.
+    pub missing: Vec<Url>,
+    pub expected: usize,
+    pub pages: usize,
+    pub llm_calls: usize,
+    /// What the OpenAI calls cost; `None` when a model that was called has no price.
+    pub cost_usd: Option<f64>,
+    /// The crawl failed outright; the counts are zero.
+    pub error: Option<String>,
+    pub passed: bool,
+}
+
+/// Scores a finished crawl against `case`. `cost_usd` is what its OpenAI calls cost
+/// ([`crate::estimate::usage_cost`]); an unknown cost fails a `max_cost_usd` bound.
+pub fn score(case: &EvalCase, result: &CrawlResult, cost_usd: Option<f64>) -> EvalOutcome {
+    let sources = result
+        .sources
+        .iter()
+        .map(|source| normalize_url(&source.url))
+        .collect::<HashSet<_>>();
+    let missing = case
+        .expected_urls
+        .iter()
+        .filter(|url| !sources.contains(&normalize_url(url)))
+        .cloned()
+        .collect::<Vec<_>>();
+    let pages = result.stats.pages_fetched;
+    let llm_calls = result.stats.llm_calls;
+    let passed = missing.is_empty()
+        && case.max_pages.is_none_or(|max| pages <= max)
+        && case.max_llm_calls.is_none_or(|max| llm_calls <= max)
+        && case
+            .max_cost_usd
+            .is_none_or(|max| cost_usd.is_some_and(|cost| cost <= max));
+    EvalOutcome {
+        name: case.name.clone(),
+        missing,
+        expected: case.expected_urls.len(),
+        pages,
+        llm_calls,
+        cost_usd,
+        error: None,
+        passed,
+    }
+}
+
+/// The outcome of a case whose crawl returned an error.
+pub fn failed(case: &EvalCase, err: &anyhow::Error) -> EvalOutcome {
+    EvalOutcome {
+        name: case.name.clone(),
+        missing: case.expected_urls.clone(),
+        expected: case.expected_urls.len(),
+        pages: 0,
+        llm_calls: 0,
+        cost_usd: None,
+        error: Some(format!("{err:#}")),
+        passed: false,
+    }
+}
+
+/// A summary table with one row per case, then the missing URLs and errors of the
+/// failed ones.
+pub fn compose_eval_markdown(cases: &EvalCases, outcomes: &[EvalOutcome]) -> String {
+    let bound = |value: usize, max: Option<usize>| match max {
+        Some(max) => format!("{value}/{max}"),
+        None => value.to_string(),
+    };
+    let cost = |cost: Option<f64>, max: Option<f64>| {
+        let cost = cost.map_or_else(|| "?".to_owned(), |cost| format!("${cost:.4}"));
+        match max {
+            Some(max) => format!("{cost}/${max:.4}"),
+            None => cost,
+        }
+    };
+    let mut out = String::new();
+    out.push_str("# Spider Eval\n\n");
+    out.push_str("| Case | Result | Expected URLs | Pages | LLM calls | Cost |\n");
+    out.push_str("|------|--------|---------------|-------|-----------|------|\n");
+    for (case, outcome) in cases.cases.iter().zip(outcomes) {
+        out.push_str(&format!(
+            "| {} | {} | {}/{} | {} | {} | {} |\n",
+            outcome.name,
+            if outcome.passed { "pass" } else { "FAIL" },
+            outcome.expected - outcome.missing.len(),
+            outcome.expected,
+            bound(outcome.pages, case.max_pages),
+            bound(outcome.llm_calls, case.max_llm_calls),
+            cost(outcome.cost_usd, case.max_cost_usd),
+        ));
+    }
+    let passed = outcomes.iter().filter(|outcome| outcome.passed).count();
+    out.push_str(&format!("\n{passed} of {} cases passed.\n", outcomes.len()));
+
+    for outcome in outcomes.iter().filter(|outcome| !outcome.passed) {
+        if outcome.error.is_none() && outcome.missing.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("\n## {}\n\n", outcome.name));
+        if let Some(error) = &outcome.error {
+            out.push_str(&format!("- error: {error}\n"));
+        }
+        for url in &outcome.missing {
+            out.push_str(&format!("- missing: {url}\n"));
+        }
+    }
+    out
+}
@@ -1,9 +1,19 @@
 #![forbid(unsafe_code)]
 
-extern crate spider as spider_rs;
-
+pub mod audit;
+pub mod cache;
+pub mod cancel;
 pub mod cli;
+pub mod config;
+pub mod error;
+pub mod estimate;
+pub mod eval;
 pub mod logging;
+pub mod notify;
 pub mod openai;
+pub mod output;
+pub mod redact;
+pub mod secrets;
 pub mod spider;
 pub mod trust;
+pub mod workdir;
@@ -1,14 +1,20 @@
 use anyhow::Context as _;
 
-pub fn init() -> anyhow::Result<()> {
-    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
-        .or_else(|_| tracing_subscriber::EnvFilter::try_new("info"))
-        .context("build log filter")?;
+/// Installs the stderr subscriber.
+///
+/// Precedence: `filter_override` (from `-v` / `-q`), then `RUST_LOG`, then `default_filter`.
+pub fn init(filter_override: Option<&str>, default_filter: &str) -> anyhow::Result<()> {
+    let filter = match filter_override {
+        Some(directive) => tracing_subscriber::EnvFilter::try_new(directive),
+        None => tracing_subscriber::EnvFilter::try_from_default_env()
+            .or_else(|_| tracing_subscriber::EnvFilter::try_new(default_filter)),
+    }
+    .context("build log filter")?;
 
     tracing_subscriber::fmt()
         .with_env_filter(filter)
         .with_target(false)
-        .with_writer(std::io::stderr)
+        .with_writer(crate::redact::RedactingStderr)
         .try_init()
         .map_err(|err| anyhow::anyhow!("initialize tracing subscriber: {err}"))?;
 
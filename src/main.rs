@@ -1,11 +1,11 @@
 use std::process::ExitCode;
 
 use anyhow::Context as _;
-use clap::Parser as _;
+use clap::{CommandFactory as _, FromArgMatches as _};
 
 fn main() -> ExitCode {
     if let Err(err) = try_main() {
-        eprintln!("{err:#}");
+        eprintln!("{}", llm_spider::redact::redact(&format!("{err:#}")));
         return ExitCode::FAILURE;
     }
 
@@ -13,25 +13,56 @@ fn main() -> ExitCode {
 }
 
 fn try_main() -> anyhow::Result<()> {
-    llm_spider::logging::init().context("init logging")?;
+    let matches = llm_spider::cli::Cli::command().get_matches();
+    let cli = llm_spider::cli::Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
 
-    let cli = llm_spider::cli::Cli::parse();
+    // Info logs would tear the progress line, so only warnings show by default.
+    let default_filter = match &cli.command {
+        llm_spider::cli::Command::Spider(args) if args.progress => "warn",
+        _ => "info",
+    };
+    llm_spider::logging::init(cli.log_filter(), default_filter).context("init logging")?;
     tracing::debug!(?cli, "parsed cli");
 
     match cli.command {
-        llm_spider::cli::Command::Spider(args) => {
-            let request = llm_spider::spider::UserRequest {
-                query: args.query,
-                max_chars: args.max_chars,
-                min_sources: args.min_sources,
-                search_limit: args.search_limit,
-                max_pages: args.max_pages,
-                max_depth: args.max_depth,
-                max_elapsed: args.max_elapsed,
-                max_child_candidates: args.max_child_candidates,
-                max_children_per_page: args.max_children_per_page,
-                allow_local: args.allow_local,
-            };
+        llm_spider::cli::Command::Spider(mut args) => {
+            let workdir = args
+                .workdir
+                .as_deref()
+                .map(llm_spider::workdir::Workdir::create)
+                .transpose()?;
+            if let Some(workdir) = &workdir {
+                workdir.fill_args(&mut args);
+            }
+            let report_template = args
+                .report_template
+                .as_deref()
+                .map(llm_spider::spider::ReportTemplate::load)
+                .transpose()?;
+            let mut setup =
+                prepare_spider(&mut args, matches.subcommand_matches("spider"), cli.quiet)?;
+            if args.show_plan || args.confirm_above.is_some() {
+                let models = setup.models.clone();
+                let output_tokens = setup.output_tokens;
+                let max_tool_calls = setup.web_search.max_tool_calls;
+                let pricing = setup.config.pricing.clone();
+                let mut review = llm_spider::spider::PlanConfirmation::stdio(move |request| {
+                    llm_spider::estimate::estimate(
+                        request,
+                        &models,
+                        output_tokens,
+                        max_tool_calls,
+                        &pricing,
+                    )
+                })
+                .with_assume_yes(args.yes);
+                if let Some(usd) = args.confirm_above {
+                    review = review.with_confirm_above(usd);
+                }
+                setup.request.plan_review = Some(std::sync::Arc::new(review));
+            }
+            let request = &setup.request;
+            let output = llm_spider::output::ReportOutput::new(args.copy, !args.no_pager);
 
             tracing::info!(
                 query = %request.query,
@@ -41,16 +72,493 @@ fn try_main() -> anyhow::Result<()> {
                 "spider start"
             );
 
-            let openai = llm_spider::openai::OpenAiClient::from_env().context("init openai")?;
-            let openai = match args.reasoning_effort {
-                Some(effort) => openai.with_reasoning_effort(effort),
-                None => openai,
+            if args.estimate {
+                let estimate = llm_spider::estimate::estimate(
+                    request,
+                    &setup.models,
+                    setup.output_tokens,
+                    setup.web_search.max_tool_calls,
+                    &setup.config.pricing,
+                );
+                print!(
+                    "{}",
+                    llm_spider::estimate::compose_estimate_markdown(request, &estimate)
+                );
+                return Ok(());
+            }
+
+            let openai = openai_client(&args, &setup)?;
+            let notifier = args
+                .notify_after
+                .map(llm_spider::notify::CrawlNotifier::start);
+            if let Some(path) = &args.batch {
+                let text = std::fs::read_to_string(path)
+                    .with_context(|| format!("read batch file: {}", path.display()))?;
+                let requests = llm_spider::spider::parse_batch_queries(&text)
+                    .into_iter()
+                    .map(|query| llm_spider::spider::UserRequest {
+                        query,
+                        ..request.clone()
+                    })
+                    .collect::<Vec<_>>();
+                anyhow::ensure!(
+                    !requests.is_empty(),
+                    "batch file has no queries: {}",
+                    path.display()
+                );
+                let results = llm_spider::spider::crawl_batch(&requests, &openai).context("crawl");
+                if let Some(notifier) = &notifier {
+                    notifier.finish(
+                        &format!("{} batch queries", requests.len()),
+                        results.as_deref(),
+                    );
+                }
+                let results = results?;
+                if let Some(path) = &args.selection_log {
+                    let log = requests
+                        .iter()
+                        .zip(&results)
+                        .map(|(request, result)| {
+                            llm_spider::spider::compose_selection_log(request, result)
+                        })
+                        .collect::<String>();
+                    std::fs::write(path, log)
+                        .with_context(|| format!("write selection log: {}", path.display()))?;
+                }
+                let report = match (&report_template, args.format) {
+                    (Some(template), _) => template
+                        .render(&llm_spider::spider::compose_batch_json(&requests, &results)),
+                    (None, llm_spider::spider::ReportFormat::Markdown) => {
+                        llm_spider::spider::compose_batch_markdown(&requests, &results)
+                    }
+                    (None, llm_spider::spider::ReportFormat::Json) => {
+                        let json = llm_spider::spider::compose_batch_json(&requests, &results);
+                        format!("{}\n", serde_json::to_string_pretty(&json)?)
+                    }
+                };
+                output.emit(&report)?;
+                if let Some(workdir) = &workdir {
+                    workdir.write_report(args.format, &report)?;
+                    let queries = requests
+                        .iter()
+                        .map(|request| request.query.as_str())
+                        .collect::<Vec<_>>();
+                    workdir.write_manifest(&args, &queries, &results)?;
+                }
+                return Ok(());
+            }
+            if args.dry_run {
+                let plan = llm_spider::spider::plan_crawl(request, &openai).context("plan")?;
+                output.emit(&llm_spider::spider::compose_plan_markdown(request, &plan))?;
+                return Ok(());
+            }
+            let result = llm_spider::spider::crawl(request, &openai).context("crawl");
+            if let Some(notifier) = &notifier {
+                notifier.finish(&request.query, result.as_ref().map(std::slice::from_ref));
+            }
+            let result = result?;
+            if let Some(path) = &args.urls_out {
+                std::fs::write(path, llm_spider::spider::compose_url_list(&result))
+                    .with_context(|| format!("write URL list: {}", path.display()))?;
+            }
+            if let Some(path) = &args.selection_log {
+                std::fs::write(
+                    path,
+                    llm_spider::spider::compose_selection_log(request, &result),
+                )
+                .with_context(|| format!("write selection log: {}", path.display()))?;
+            }
+            if let Some(dir) = &args.notes_dir {
+                std::fs::create_dir_all(dir)
+                    .with_context(|| format!("create notes directory: {}", dir.display()))?;
+                for note in llm_spider::spider::compose_notes(request, &result) {
+                    let path = dir.join(format!("{}.md", note.name));
+                    std::fs::write(&path, note.text)
+                        .with_context(|| format!("write note: {}", path.display()))?;
+                }
+            }
+            let report = match (&report_template, args.format) {
+                (Some(template), _) => {
+                    template.render(&llm_spider::spider::compose_json(request, &result))
+                }
+                (None, llm_spider::spider::ReportFormat::Markdown) if args.front_matter => {
+                    let cost_usd =
+                        llm_spider::estimate::usage_cost(&openai.usage(), &setup.config.pricing);
+                    let front_matter =
+                        llm_spider::spider::FrontMatter::new(args.tags.clone(), cost_usd);
+                    llm_spider::spider::compose_front_matter(request, &result, &front_matter)
+                        + &llm_spider::spider::compose_markdown(request, &result)
+                }
+                (None, llm_spider::spider::ReportFormat::Markdown) => {
+                    llm_spider::spider::compose_markdown(request, &result)
+                }
+                (None, llm_spider::spider::ReportFormat::Json) => {
+                    let json = llm_spider::spider::compose_json(request, &result);
+                    format!("{}\n", serde_json::to_string_pretty(&json)?)
+                }
             };
-            let result = llm_spider::spider::crawl(&request, &openai).context("crawl")?;
-            let markdown = llm_spider::spider::compose_markdown(&request, &result);
-            print!("{markdown}");
+            output.emit(&report)?;
+            if let Some(workdir) = &workdir {
+                workdir.write_report(args.format, &report)?;
+                workdir.write_manifest(
+                    &args,
+                    &[request.query.as_str()],
+                    std::slice::from_ref(&result),
+                )?;
+            }
+        }
+        llm_spider::cli::Command::Eval(args) => {
+            let cases = llm_spider::eval::EvalCases::load(&args.cases)?;
+            let mut outcomes = Vec::with_capacity(cases.cases.len());
+            for case in &cases.cases {
+                let mut argv = vec!["llm-spider", "spider", "--query", &case.query]
+                    .into_iter()
+                    .map(std::ffi::OsString::from)
+                    .collect::<Vec<_>>();
+                if let Some(config) = &args.config {
+                    argv.extend(["--config".into(), config.into()]);
+                }
+                if let Some(profile) = &args.profile {
+                    argv.extend(["--profile".into(), profile.into()]);
+                }
+                argv.extend(case.args.iter().map(Into::into));
+                let case_matches = llm_spider::cli::Cli::command()
+                    .try_get_matches_from(argv)
+                    .with_context(|| format!("eval case {}: parse args", case.name))?;
+                let llm_spider::cli::Command::Spider(mut spider_args) =
+                    llm_spider::cli::Cli::from_arg_matches(&case_matches)?.command
+                else {
+                    anyhow::bail!("eval case {}: expected spider options", case.name);
+                };
+                let setup = prepare_spider(
+                    &mut spider_args,
+                    case_matches.subcommand_matches("spider"),
+                    cli.quiet,
+                )?;
+                let openai = openai_client(&spider_args, &setup)?;
+                let fixture = llm_spider::spider::WarcReplay::open(&case.fixture)
+                    .with_context(|| format!("eval case {}: load fixture", case.name))?;
+                tracing::info!(case = %case.name, pages = fixture.len(), "eval case start");
+                outcomes.push(
+                    match llm_spider::spider::crawl_with_fetcher(&setup.request, &openai, &fixture)
+                    {
+                        Ok(result) => {
+                            let cost_usd = llm_spider::estimate::usage_cost(
+                                &openai.usage(),
+                                &setup.config.pricing,
+                            );
+                            llm_spider::eval::score(case, &result, cost_usd)
+                        }
+                        Err(err) => llm_spider::eval::failed(case, &err.into()),
+                    },
+                );
+            }
+            print!(
+                "{}",
+                llm_spider::eval::compose_eval_markdown(&cases, &outcomes)
+            );
+            let failed = outcomes.iter().filter(|outcome| !outcome.passed).count();
+            anyhow::ensure!(
+                failed == 0,
+                "{failed} of {} eval cases failed",
+                outcomes.len()
+            );
+        }
+        llm_spider::cli::Command::Bench(args) => {
+            let text = std::fs::read_to_string(&args.urls)
+                .with_context(|| format!("read URL list: {}", args.urls.display()))?;
+            let urls = llm_spider::spider::parse_url_list(&text)
+                .with_context(|| format!("parse URL list: {}", args.urls.display()))?;
+            anyhow::ensure!(
+                !urls.is_empty(),
+                "URL list is empty: {}",
+                args.urls.display()
+            );
+            let report =
+                llm_spider::spider::bench(&urls, usize::from(args.concurrency)).context("bench")?;
+            print!("{}", llm_spider::spider::compose_bench_markdown(&report));
+        }
+        llm_spider::cli::Command::Site(args) => {
+            // Reuse the spider setup for the OpenAI client, [hosts] pacing, profile,
+            // opt-outs, audit log, and crawl ID.
+            let mut argv = vec!["llm-spider", "spider", "--query", &args.query]
+                .into_iter()
+                .map(std::ffi::OsString::from)
+                .collect::<Vec<_>>();
+            if let Some(config) = &args.config {
+                argv.extend(["--config".into(), config.into()]);
+            }
+            if let Some(profile) = &args.profile {
+                argv.extend(["--profile".into(), profile.into()]);
+            }
+            if let Some(audit_log) = &args.audit_log {
+                argv.extend(["--audit-log".into(), audit_log.into()]);
+            }
+            if let Some(crawl_id) = &args.crawl_id {
+                argv.extend(["--crawl-id".into(), crawl_id.into()]);
+            }
+            if args.honor_opt_outs {
+                argv.push("--honor-opt-outs".into());
+            }
+            if args.allow_local {
+                argv.push("--allow-local".into());
+            }
+            let spider_matches = llm_spider::cli::Cli::command()
+                .try_get_matches_from(argv)
+                .context("site: parse args")?;
+            let llm_spider::cli::Command::Spider(mut spider_args) =
+                llm_spider::cli::Cli::from_arg_matches(&spider_matches)?.command
+            else {
+                anyhow::bail!("site: expected spider options");
+            };
+            let setup = prepare_spider(
+                &mut spider_args,
+                spider_matches.subcommand_matches("spider"),
+                cli.quiet,
+            )?;
+            let openai = openai_client(&spider_args, &setup)?;
+            let request = llm_spider::spider::SiteRequest {
+                query: args.query.clone(),
+                root: args.root.clone(),
+                max_pages: args.max_pages,
+                max_depth: args.max_depth,
+                max_elapsed: args.max_elapsed,
+                allow_local: setup.request.allow_local,
+                host_policies: setup.request.host_policies.clone(),
+                challenge_retry: setup.request.challenge_retry,
+                honor_opt_outs: setup.request.honor_opt_outs,
+                crawl_id: setup.request.crawl_id.clone(),
+                audit_log: setup.audit_log.clone(),
+            };
+            let audit = llm_spider::spider::audit_site(&request, &openai).context("site")?;
+            match args.format {
+                llm_spider::spider::ReportFormat::Markdown => {
+                    print!(
+                        "{}",
+                        llm_spider::spider::compose_site_markdown(&request, &audit)
+                    );
+                }
+                llm_spider::spider::ReportFormat::Json => {
+                    let json = llm_spider::spider::compose_site_json(&request, &audit);
+                    println!("{}", serde_json::to_string_pretty(&json)?);
+                }
+            }
+        }
+        llm_spider::cli::Command::Completions(args) => {
+            let mut cmd = llm_spider::cli::Cli::command();
+            let name = cmd.get_name().to_owned();
+            clap_complete::generate(args.shell, &mut cmd, name, &mut std::io::stdout());
+        }
+        llm_spider::cli::Command::Manpage => {
+            clap_mangen::Man::new(llm_spider::cli::Cli::command())
+                .render(&mut std::io::stdout())
+                .context("render man page")?;
         }
     }
 
     Ok(())
 }
+
+/// What `spider` options resolve to before the OpenAI client is built.
+struct SpiderSetup {
+    config: llm_spider::config::Config,
+    models: llm_spider::openai::OpenAiModels,
+    output_tokens: llm_spider::openai::OutputTokenLimits,
+    web_search: llm_spider::openai::WebSearchOptions,
+    openai_timeout: std::time::Duration,
+    audit_log: Option<std::sync::Arc<llm_spider::audit::AuditLog>>,
+    request: llm_spider::spider::UserRequest,
+}
+
+/// Config, models, and the crawl request for `spider` options, after the profile is
+/// applied to `args`.
+fn prepare_spider(
+    args: &mut llm_spider::cli::SpiderArgs,
+    spider_matches: Option<&clap::ArgMatches>,
+    quiet: bool,
+) -> anyhow::Result<SpiderSetup> {
+    let config = match &args.config {
+        Some(path) => llm_spider::config::Config::load(path).context("load config")?,
+        None => llm_spider::config::Config::default(),
+    };
+    let profile = args
+        .profile
+        .as_deref()
+        .map(|name| config.profile(name))
+        .transpose()?;
+    if let (Some(profile), Some(spider_matches)) = (profile, spider_matches) {
+        args.apply_profile(profile, spider_matches);
+    }
+    if args.quick
+        && let Some(spider_matches) = spider_matches
+    {
+        args.apply_quick(spider_matches);
+    }
+    let mut args = args.clone();
+
+    let mut models = llm_spider::openai::OpenAiModels::from_env();
+    if let Some(profile) = profile {
+        if let Some(model) = &profile.search_model {
+            models.search = model.clone();
+        }
+        if let Some(model) = &profile.select_model {
+            models.select = model.clone();
+        }
+    }
+    let defaults = llm_spider::openai::OutputTokenLimits::default();
+    let output_tokens = llm_spider::openai::OutputTokenLimits {
+        search: args
+            .search_max_output_tokens
+            .or(config.openai.search_max_output_tokens)
+            .unwrap_or(defaults.search),
+        select: args
+            .select_max_output_tokens
+            .or(config.openai.select_max_output_tokens)
+            .unwrap_or(defaults.select),
+    };
+    let openai_timeout = args
+        .openai_timeout
+        .or(config.openai.timeout)
+        .unwrap_or(llm_spider::openai::DEFAULT_TIMEOUT);
+    let web_search_config = &config.openai.web_search;
+    let mut user_location = web_search_config.user_location.clone();
+    if let Some(country) = args.search_country.take() {
+        user_location.country = Some(country);
+    }
+    let web_search = llm_spider::openai::WebSearchOptions {
+        max_tool_calls: args
+            .search_max_tool_calls
+            .or(web_search_config.max_tool_calls)
+            .unwrap_or(llm_spider::openai::WebSearchOptions::default().max_tool_calls),
+        context_size: args
+            .search_context_size
+            .or(web_search_config.search_context_size),
+        user_location,
+    };
+
+    let mut seed_urls = args.seed_urls;
+    if let Some(path) = &args.seed_file {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("read seed file: {}", path.display()))?;
+        seed_urls.extend(
+            llm_spider::spider::parse_seed_file(&text)
+                .with_context(|| format!("parse seed file: {}", path.display()))?,
+        );
+    }
+    let audit_log = args
+        .audit_log
+        .as_deref()
+        .map(llm_spider::audit::AuditLog::open)
+        .transpose()?
+        .map(|log| std::sync::Arc::new(log.with_crawl_id(args.crawl_id.clone())));
+    let request = llm_spider::spider::UserRequest {
+        query: args.query.unwrap_or_else(|| args.compare.join(" vs ")),
+        compare: args.compare,
+        intent: args.intent,
+        classify_intent: args.classify_intent,
+        pinned_max_depth: spider_matches
+            .is_some_and(|matches| llm_spider::cli::is_explicit(matches, "max_depth"))
+            || profile.is_some_and(|profile| profile.max_depth.is_some())
+            || args.quick,
+        max_chars: args.max_chars,
+        min_sources: args.min_sources,
+        search_limit: args.search_limit,
+        max_pages: args.max_pages,
+        max_depth: args.max_depth,
+        max_depth_high: args.max_depth_high,
+        max_depth_medium: args.max_depth_medium,
+        max_depth_low: args.max_depth_low,
+        max_elapsed: args.max_elapsed,
+        crawl_budget: args.crawl_budget,
+        max_child_candidates: args.max_child_candidates,
+        max_candidate_batches: args.max_candidate_batches,
+        max_children_per_page: args.max_children_per_page,
+        max_children_per_hub: args.max_children_per_hub,
+        max_pagination: args.max_pagination,
+        max_sources_per_host: args.max_sources_per_host,
+        max_frontier: args.max_frontier,
+        max_visited: args.max_visited,
+        max_total_bytes: args.max_total_bytes,
+        host_failure_limit: args.host_failure_limit,
+        exclusion_statuses: std::iter::once(451)
+            .chain(args.exclusion_statuses.iter().copied())
+            .collect(),
+        honor_opt_outs: args.honor_opt_outs,
+        dns_prefetch: args.dns_prefetch,
+        cache_dir: args.cache_dir,
+        warc: args.warc,
+        control_file: args.control,
+        challenge_retry: args.challenge_retry,
+        schedule: None,
+        crawl_id: args.crawl_id,
+        audit_log: audit_log.clone(),
+        selection_review: args
+            .interactive
+            .then(|| std::sync::Arc::new(llm_spider::spider::SelectionReview::stdio())),
+        plan_review: None,
+        cancellation: None,
+        host_policies: config.host_policies(profile),
+        url_patterns: config.url_patterns.clone(),
+        allow_local: args.allow_local,
+        preferred_language: args.preferred_language,
+        seed_urls,
+        on_search_failure: args.on_search_failure,
+        on_selection_failure: args.on_selection_failure,
+        paywall_policy: args.paywall_policy,
+        frame_policy: args.frame_policy,
+        robots_tags: args.robots_tags,
+        trust_merge: args.trust_merge,
+        sources_format: args.sources_format,
+        findings_layout: args.findings_layout,
+        follow_ups: args.follow_ups,
+        key_terms: args.key_terms,
+        timeline: args.timeline,
+        grade_sources: args.grade_sources,
+        answer: args.answer,
+        show_stats: args.stats,
+        deterministic: args.deterministic,
+        show_progress: args.progress && !quiet,
+    };
+    Ok(SpiderSetup {
+        config,
+        models,
+        output_tokens,
+        web_search,
+        openai_timeout,
+        audit_log,
+        request,
+    })
+}
+
+/// The OpenAI client configured by `spider` options and their [`SpiderSetup`].
+fn openai_client(
+    args: &llm_spider::cli::SpiderArgs,
+    setup: &SpiderSetup,
+) -> anyhow::Result<llm_spider::openai::OpenAiClient> {
+    let api_key = llm_spider::secrets::resolve_openai_api_key(args.api_key_file.as_deref())
+        .context("resolve openai api key")?;
+    let openai = llm_spider::openai::OpenAiClient::from_env_with_api_key(api_key)
+        .context("init openai")?
+        .with_models(setup.models.clone())
+        .with_default_organization(setup.config.openai.organization.clone())
+        .with_default_project(setup.config.openai.project.clone())
+        .with_output_token_limits(setup.output_tokens)
+        .with_model_capabilities(setup.config.models.clone())
+        .with_web_search(setup.web_search.clone())?
+        .with_prompt_templates(setup.config.prompts.templates().context("load prompts")?)
+        .with_select_language_hint(setup.config.prompts.select_language_hint.unwrap_or(true))
+        .with_timeout(setup.openai_timeout)?;
+    let openai = match args.reasoning_effort {
+        Some(effort) => openai.with_reasoning_effort(effort),
+        None => openai,
+    };
+    let openai = match args.search_budget {
+        Some(budget) => openai.with_search_timeout(budget),
+        None => openai,
+    };
+    let openai = match setup.audit_log.clone() {
+        Some(audit_log) => openai.with_audit_log(audit_log),
+        None => openai,
+    };
+    Ok(openai)
+}
@@ -0,0 +1,105 @@
+use std::io::{IsTerminal as _, Write as _};
+use std::time::{Duration, Instant};
+
+use crate::spider::CrawlResult;
+
+/// Application name desktop notifications are sent under.
+pub const APP_NAME: &str = "llm-spider";
+
+/// A message for the end of a long crawl.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub summary: String,
+    pub body: String,
+}
+
+/// Tells the desktop when a crawl that ran for at least `after` finishes or fails
+/// (`--notify-after`).
+///
+/// Builds with the `notify` feature show a desktop notification; other builds, and
+/// failed notifications, ring the terminal bell on stderr.
+#[derive(Debug, Clone)]
+pub struct CrawlNotifier {
+    after: Duration,
+    started: Instant,
+}
+
+impl CrawlNotifier {
+    /// Starts the clock.
+    pub fn start(after: Duration) -> Self {
+        Self {
+            after,
+            started: Instant::now(),
+        }
+    }
+
+    /// The notification for `subject` (a query, or a batch), or `None` when the crawl
+    /// ended before the threshold.
+    pub fn notification(
+        &self,
+        subject: &str,
+        outcome: Result<&[CrawlResult], &anyhow::Error>,
+    ) -> Option<Notification> {
+        let elapsed = self.started.elapsed();
+        if elapsed < self.after {
+            return None;
+        }
+        let elapsed = humantime::format_duration(Duration::from_secs(elapsed.as_secs()));
+        Some(match outcome {
+            Ok(results) => {
+                let sources = results.iter().map(|r| r.sources.len()).sum::<usize>();
+                let pages = results.iter().map(|r| r.stats.pages_fetched).sum::<usize>();
+                Notification {
+                    summary: format!("Crawl finished: {subject}"),
+                    body: format!("{sources} sources from {pages} pages in {elapsed}"),
+                }
+            }
+            Err(err) => Notification {
+                summary: format!("Crawl failed: {subject}"),
+                body: format!(
+                    "after {elapsed}: {}",
+                    crate::redact::redact(&format!("{err:#}"))
+                ),
+            },
+        })
+    }
+
+    /// Sends the notification for the outcome, if it is due; never fails the run.
+    pub fn finish(&self, subject: &str, outcome: Result<&[CrawlResult], &anyhow::Error>) {
+        let Some(notification) = self.notification(subject, outcome) else {
+            return;
+        };
+        if let Err(err) = show(&notification) {
+            tracing::warn!("desktop notification failed: {err:#}");
+            ring_bell();
+        }
+    }
+}
+
+#[cfg(feature = "notify")]
+fn show(notification: &Notification) -> anyhow::Result<()> {
+    use anyhow::Context as _;
+
+    notify_rust::Notification::new()
+        .appname(APP_NAME)
+        .summary(&notification.summary)
+        .body(&notification.body)
+        .show()
+        .context("show desktop notification")?;
+    Ok(())
+}
+
+#[cfg(not(feature = "notify"))]
+fn show(notification: &Notification) -> anyhow::Result<()> {
+    tracing::info!(body = %notification.body, "{}", notification.summary);
+    ring_bell();
+    Ok(())
+}
+
+fn ring_bell() {
+    let mut stderr = std::io::stderr();
+    if stderr.is_terminal() {
+        let _ = stderr.write_all(b"\x07");
+        let _ = stderr.flush();
+    }
+}
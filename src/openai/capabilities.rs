@@ -0,0 +1,138 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
+
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tracing::warn;
+
+use super::ReasoningEffort;
+
+/// Optional request parameters a model accepts, configured under `[models."<name>"]`.
+///
+/// Unset fields fall back to the built-in defaults for the model name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModelCapabilities {
+    /// Accepts `reasoning.effort`.
+    pub reasoning: Option<bool>,
+    /// Accepts `temperature`.
+    pub temperature: Option<bool>,
+}
+
+/// Request parameters that may be dropped when the API rejects them.
+const STRIPPABLE_PARAMS: &[&str] = &["temperature", "reasoning", "max_tool_calls", "include"];
+
+/// Resolves per-model parameters and remembers the ones the API rejected.
+#[derive(Debug, Clone, Default)]
+pub(super) struct CapabilityTable {
+    configured: BTreeMap<String, ModelCapabilities>,
+    rejected: Arc<Mutex<HashMap<String, BTreeSet<String>>>>,
+}
+
+impl CapabilityTable {
+    pub(super) fn new(configured: BTreeMap<String, ModelCapabilities>) -> Self {
+        Self {
+            configured,
+            rejected: Arc::default(),
+        }
+    }
+
+    /// An exact key wins; otherwise the longest key that prefixes `model`.
+    fn configured_for(&self, model: &str) -> ModelCapabilities {
+        if let Some(caps) = self.configured.get(model) {
+            return *caps;
+        }
+        self.configured
+            .iter()
+            .filter(|(key, _)| model.starts_with(key.as_str()))
+            .max_by_key(|(key, _)| key.len())
+            .map(|(_, caps)| *caps)
+            .unwrap_or_default()
+    }
+
+    /// Adds `temperature` / `reasoning` as supported by `model`, then drops parameters
+    /// the API already rejected for it.
+    pub(super) fn apply(&self, model: &str, request: &mut Value, effort: ReasoningEffort) {
+        let configured = self.configured_for(model);
+        let reasoning = configured
+            .reasoning
+            .unwrap_or_else(|| builtin_supports_reasoning(model));
+        let temperature = configured.temperature.unwrap_or(!reasoning);
+        if temperature {
+            request["temperature"] = json!(0);
+        }
+        if reasoning {
+            request["reasoning"] = json!({ "effort": effort.as_str() });
+        }
+
+        let Ok(rejected) = self.rejected.lock() else {
+            return;
+        };
+        if let (Some(params), Some(object)) = (rejected.get(model), request.as_object_mut()) {
+            for param in params {
+                object.remove(param);
+            }
+        }
+    }
+
+    /// If `body` is a parameter error for a strippable parameter present in `request`,
+    /// records it for `model` and returns its name.
+    pub(super) fn learn_rejection(
+        &self,
+        model: &str,
+        request: &Value,
+        body: &str,
+    ) -> Option<String> {
+        let param = rejected_param(body)?;
+        request.get(&param)?;
+        warn!(
+            model,
+            param, "model rejected parameter; retrying without it"
+        );
+        if let Ok(mut rejected) = self.rejected.lock() {
+            rejected
+                .entry(model.to_owned())
+                .or_default()
+                .insert(param.clone());
+        }
+        Some(param)
+    }
+}
+
+/// Names the top-level parameter an OpenAI `400` error complains about.
+fn rejected_param(body: &str) -> Option<String> {
+    let error = serde_json::from_str::<Value>(body)
+        .ok()?
+        .get("error")?
+        .clone();
+    let from_param = error
+        .get("param")
+        .and_then(Value::as_str)
+        .map(|param| param.split('.').next().unwrap_or(param).to_owned());
+    let from_message = || {
+        let message = error.get("message")?.as_str()?;
+        STRIPPABLE_PARAMS
+            .iter()
+            .find(|param| {
+                message.contains(&format!("'{param}'")) || message.contains(&format!("'{param}."))
+            })
+            .map(|param| (*param).to_owned())
+    };
+    from_param
+        .or_else(from_message)
+        .filter(|param| STRIPPABLE_PARAMS.contains(&param.as_str()))
+}
+
+/// Built-in default for models without a configured entry: `gpt-5*` and `o<digit>*`
+/// are reasoning models; everything else takes `temperature`.
+fn builtin_supports_reasoning(model: &str) -> bool {
+    let model = model.trim();
+    if model.starts_with("gpt-5") {
+        return true;
+    }
+    let mut chars = model.chars();
+    if chars.next() != Some('o') {
+        return false;
+    }
+    matches!(chars.next(), Some(c) if c.is_ascii_digit())
+}
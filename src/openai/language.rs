@@ -0,0 +1,36 @@
+/// Writing systems that identify a language on their own, checked in order: kana before
+/// Han, since Japanese mixes both.
+const SCRIPTS: &[(&str, &[(char, char)])] = &[
+    (
+        "Japanese",
+        &[('\u{3040}', '\u{30ff}'), ('\u{31f0}', '\u{31ff}')],
+    ),
+    (
+        "Korean",
+        &[('\u{1100}', '\u{11ff}'), ('\u{ac00}', '\u{d7af}')],
+    ),
+    (
+        "Chinese",
+        &[('\u{4e00}', '\u{9fff}'), ('\u{3400}', '\u{4dbf}')],
+    ),
+    ("Arabic", &[('\u{0600}', '\u{06ff}')]),
+    ("Hebrew", &[('\u{0590}', '\u{05ff}')]),
+    ("Greek", &[('\u{0370}', '\u{03ff}')]),
+    ("Thai", &[('\u{0e00}', '\u{0e7f}')]),
+    ("Hindi", &[('\u{0900}', '\u{097f}')]),
+];
+
+/// The language of `text` when its script gives it away, e.g. `Japanese` for a query
+/// with kana. Latin-script text gives `None`: it may be English or not, and the
+/// selection prompt treats it as English.
+pub(super) fn detect_language(text: &str) -> Option<&'static str> {
+    SCRIPTS.iter().find_map(|(language, ranges)| {
+        text.chars()
+            .any(|c| {
+                ranges
+                    .iter()
+                    .any(|(start, end)| (*start..=*end).contains(&c))
+            })
+            .then_some(*language)
+    })
+}
@@ -0,0 +1,147 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use reqwest::header::HeaderMap;
+use tracing::debug;
+
+/// Below this share of the request limit, calls are spread over the rest of the window.
+const PACE_BELOW_FRACTION: f64 = 0.1;
+
+/// Paces Responses API calls by the `x-ratelimit-*` headers of earlier replies.
+///
+/// With no requests left the next call waits for the request window to reset; with
+/// less than a tenth left, calls are spread evenly over the rest of the window. A call
+/// whose estimated tokens exceed the tokens left waits for the token window to reset.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Pacer {
+    /// Shared by clones of the client, which draw on the same account limits.
+    state: Arc<Mutex<Option<Budget>>>,
+}
+
+/// What the last reply said is left, adjusted for calls sent since.
+#[derive(Debug, Clone, Copy)]
+struct Budget {
+    limit_requests: Option<u64>,
+    remaining_requests: Option<u64>,
+    requests_reset_at: Option<Instant>,
+    remaining_tokens: Option<u64>,
+    tokens_reset_at: Option<Instant>,
+}
+
+impl Pacer {
+    /// How long to wait before sending a call of about `estimated_tokens`, charging
+    /// the call to the budget.
+    pub(super) fn reserve(&self, estimated_tokens: u64) -> Duration {
+        let Ok(mut state) = self.state.lock() else {
+            return Duration::ZERO;
+        };
+        let Some(budget) = state.as_mut() else {
+            return Duration::ZERO;
+        };
+        let now = Instant::now();
+        let until = |reset_at: Option<Instant>| {
+            reset_at.map_or(Duration::ZERO, |at| at.saturating_duration_since(now))
+        };
+
+        let mut wait = Duration::ZERO;
+        if let Some(remaining) = budget.remaining_requests {
+            let reset_in = until(budget.requests_reset_at);
+            if remaining == 0 {
+                wait = wait.max(reset_in);
+            } else if budget
+                .limit_requests
+                .is_some_and(|limit| (remaining as f64) < limit as f64 * PACE_BELOW_FRACTION)
+            {
+                wait = wait.max(reset_in / u32::try_from(remaining).unwrap_or(u32::MAX));
+            }
+            budget.remaining_requests = Some(remaining.saturating_sub(1));
+        }
+        if let Some(remaining) = budget.remaining_tokens {
+            if estimated_tokens > remaining {
+                wait = wait.max(until(budget.tokens_reset_at));
+            }
+            budget.remaining_tokens = Some(remaining.saturating_sub(estimated_tokens));
+        }
+        if !wait.is_zero() {
+            debug!(
+                "pacing openai call by {} (rate limit headers)",
+                humantime::format_duration(wait)
+            );
+        }
+        wait
+    }
+
+    /// How long until the later of the windows seen in the headers resets; `None` when
+    /// neither reset is still ahead.
+    pub(super) fn reset_in(&self) -> Option<Duration> {
+        let state = self.state.lock().ok()?;
+        let budget = state.as_ref()?;
+        let now = Instant::now();
+        [budget.requests_reset_at, budget.tokens_reset_at]
+            .into_iter()
+            .flatten()
+            .map(|at| at.saturating_duration_since(now))
+            .max()
+            .filter(|wait| !wait.is_zero())
+    }
+
+    /// Takes the budget from a reply's headers; replies without them leave it as is.
+    pub(super) fn observe(&self, headers: &HeaderMap) {
+        let number = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse::<u64>().ok())
+        };
+        let now = Instant::now();
+        let reset_at = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .and_then(parse_reset)
+                .map(|after| now + after)
+        };
+        let budget = Budget {
+            limit_requests: number("x-ratelimit-limit-requests"),
+            remaining_requests: number("x-ratelimit-remaining-requests"),
+            requests_reset_at: reset_at("x-ratelimit-reset-requests"),
+            remaining_tokens: number("x-ratelimit-remaining-tokens"),
+            tokens_reset_at: reset_at("x-ratelimit-reset-tokens"),
+        };
+        if budget.remaining_requests.is_none() && budget.remaining_tokens.is_none() {
+            return;
+        }
+        if let Ok(mut state) = self.state.lock() {
+            *state = Some(budget);
+        }
+    }
+}
+
+/// Parses reset durations such as `20ms`, `1.5s`, or `6m0s`.
+fn parse_reset(text: &str) -> Option<Duration> {
+    let mut total = 0.0;
+    let mut rest = text.trim();
+    if rest.is_empty() {
+        return None;
+    }
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        let value = rest[..digits].parse::<f64>().ok()?;
+        rest = &rest[digits..];
+        let unit = rest
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(rest.len());
+        let seconds = match &rest[..unit] {
+            "h" => 3600.0,
+            "m" => 60.0,
+            "s" => 1.0,
+            "ms" => 0.001,
+            _ => return None,
+        };
+        total += value * seconds;
+        rest = &rest[unit..];
+    }
+    Duration::try_from_secs_f64(total).ok()
+}
@@ -0,0 +1,197 @@
+use std::io::{IsTerminal as _, Write as _};
+use std::process::{Command, Stdio};
+
+use anyhow::Context as _;
+
+/// Terminal height assumed when it cannot be read.
+const DEFAULT_SCREEN_ROWS: usize = 24;
+
+/// Pager used when `PAGER` is unset: quits by itself when the text fits, keeps colors,
+/// and leaves the text on screen.
+const DEFAULT_PAGER: &str = "less -FRX";
+
+/// Where a finished report goes: stdout, or a pager when stdout is a terminal and the
+/// report is taller than it, and optionally the system clipboard (`--copy`, `--no-pager`).
+#[derive(Debug, Clone)]
+pub struct ReportOutput {
+    copy: bool,
+    pager: bool,
+    clipboard_command: Option<Vec<String>>,
+    pager_command: Option<Vec<String>>,
+}
+
+impl ReportOutput {
+    pub fn new(copy: bool, pager: bool) -> Self {
+        Self {
+            copy,
+            pager,
+            clipboard_command: None,
+            pager_command: None,
+        }
+    }
+
+    /// Copies with this command (fed the report on stdin) instead of the first
+    /// clipboard tool of the platform that is installed.
+    pub fn with_clipboard_command(mut self, command: Vec<String>) -> Self {
+        self.clipboard_command = Some(command);
+        self
+    }
+
+    /// Pages with this command instead of `PAGER` (default `less -FRX`).
+    pub fn with_pager_command(mut self, command: Vec<String>) -> Self {
+        self.pager_command = Some(command);
+        self
+    }
+
+    /// Writes `report` out. A failed copy is logged, not returned, since the report
+    /// has been printed by then.
+    pub fn emit(&self, report: &str) -> anyhow::Result<()> {
+        let paged = self.pager
+            && std::io::stdout().is_terminal()
+            && report.lines().count() >= screen_rows()
+            && self.page(report)?;
+        if !paged {
+            let mut stdout = std::io::stdout().lock();
+            stdout
+                .write_all(report.as_bytes())
+                .context("write report")?;
+            stdout.flush().context("write report")?;
+        }
+        if self.copy {
+            match self.copy_to_clipboard(report) {
+                Ok(program) => tracing::info!(%program, "report copied to the clipboard"),
+                Err(err) => tracing::warn!("copy to clipboard failed: {err:#}"),
+            }
+        }
+        Ok(())
+    }
+
+    /// `false` when there is no pager to run, so the report still needs printing.
+    fn page(&self, report: &str) -> anyhow::Result<bool> {
+        let command = match &self.pager_command {
+            Some(command) => command.clone(),
+            None => std::env::var("PAGER")
+                .unwrap_or_else(|_| DEFAULT_PAGER.to_owned())
+                .split_whitespace()
+                .map(str::to_owned)
+                .collect(),
+        };
+        let Some((program, args)) = command.split_first() else {
+            return Ok(false);
+        };
+        let mut child = match Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(err) => {
+                tracing::debug!(%program, "pager unavailable: {err}");
+                return Ok(false);
+            }
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            // Quitting the pager early closes the pipe; that is not an error.
+            match stdin.write_all(report.as_bytes()) {
+                Err(err) if err.kind() != std::io::ErrorKind::BrokenPipe => {
+                    return Err(err).with_context(|| format!("write to pager: {program}"));
+                }
+                _ => {}
+            }
+        }
+        child
+            .wait()
+            .with_context(|| format!("wait for pager: {program}"))?;
+        Ok(true)
+    }
+
+    /// The program that took the text.
+    fn copy_to_clipboard(&self, text: &str) -> anyhow::Result<String> {
+        let candidates = match &self.clipboard_command {
+            Some(command) => vec![command.clone()],
+            None => clipboard_commands(),
+        };
+        for command in &candidates {
+            let Some((program, args)) = command.split_first() else {
+                continue;
+            };
+            let mut child = match Command::new(program)
+                .args(args)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::null())
+                .spawn()
+            {
+                Ok(child) => child,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err).with_context(|| format!("run {program}")),
+            };
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin
+                    .write_all(text.as_bytes())
+                    .with_context(|| format!("write to {program}"))?;
+            }
+            let status = child
+                .wait()
+                .with_context(|| format!("wait for {program}"))?;
+            anyhow::ensure!(status.success(), "{program} exited with {status}");
+            return Ok(program.clone());
+        }
+        let tried = candidates
+            .iter()
+            .filter_map(|command| command.first().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(", ");
+        anyhow::bail!("no clipboard command found (tried {tried})")
+    }
+}
+
+/// Clipboard tools of the platform, in the order they are tried.
+fn clipboard_commands() -> Vec<Vec<String>> {
+    let commands: &[&[&str]] = if cfg!(target_os = "macos") {
+        &[&["pbcopy"]]
+    } else if cfg!(windows) {
+        &[&["clip"]]
+    } else if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        &[
+            &["wl-copy"],
+            &["xclip", "-selection", "clipboard"],
+            &["xsel", "--clipboard", "--input"],
+        ]
+    } else {
+        &[
+            &["xclip", "-selection", "clipboard"],
+            &["xsel", "--clipboard", "--input"],
+            &["wl-copy"],
+        ]
+    };
+    commands
+        .iter()
+        .map(|command| command.iter().map(|&arg| arg.to_owned()).collect())
+        .collect()
+}
+
+/// `LINES`, else what `stty size` reports for the controlling terminal.
+fn screen_rows() -> usize {
+    let from_env = std::env::var("LINES")
+        .ok()
+        .and_then(|lines| lines.trim().parse().ok());
+    let from_stty = || {
+        let tty = std::fs::File::open("/dev/tty").ok()?;
+        let output = Command::new("stty")
+            .arg("size")
+            .stdin(tty)
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        String::from_utf8(output.stdout)
+            .ok()?
+            .split_whitespace()
+            .next()?
+            .parse()
+            .ok()
+    };
+    from_env
+        .or_else(from_stty)
+        .filter(|&rows| rows > 0)
+        .unwrap_or(DEFAULT_SCREEN_ROWS)
+}
@@ -0,0 +1,86 @@
+use std::path::Path;
+
+use anyhow::Context as _;
+
+/// Keychain service name used when the `keychain` feature is enabled.
+pub const KEYCHAIN_SERVICE: &str = "llm-spider";
+/// Keychain account holding the OpenAI API key.
+pub const KEYCHAIN_ACCOUNT: &str = "openai";
+
+/// Resolves the OpenAI API key.
+///
+/// Order: `api_key_file` (`--api-key-file` / `OPENAI_API_KEY_FILE`), then `OPENAI_API_KEY`,
+/// then the OS keychain when built with the `keychain` feature.
+///
+/// The resolved key is registered with [`crate::redact`] so it never reaches logs or errors.
+pub fn resolve_openai_api_key(api_key_file: Option<&Path>) -> anyhow::Result<String> {
+    let key = lookup_openai_api_key(api_key_file)?;
+    crate::redact::register_secret(&key);
+    Ok(key)
+}
+
+fn lookup_openai_api_key(api_key_file: Option<&Path>) -> anyhow::Result<String> {
+    if let Some(path) = api_key_file {
+        return read_api_key_file(path);
+    }
+    if let Ok(key) = std::env::var("OPENAI_API_KEY")
+        && !key.trim().is_empty()
+    {
+        return Ok(key);
+    }
+    if let Some(key) = keychain_api_key()? {
+        return Ok(key);
+    }
+    anyhow::bail!(
+        "OPENAI_API_KEY is not set (or use --api-key-file / OPENAI_API_KEY_FILE{})",
+        if cfg!(feature = "keychain") {
+            " / the OS keychain"
+        } else {
+            ""
+        }
+    )
+}
+
+/// Reads a key file, ignoring surrounding whitespace such as a trailing newline.
+pub fn read_api_key_file(path: &Path) -> anyhow::Result<String> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("read api key file: {}", path.display()))?;
+    let key = text.trim();
+    if key.is_empty() {
+        anyhow::bail!("api key file is empty: {}", path.display());
+    }
+    warn_if_shared(path);
+    Ok(key.to_owned())
+}
+
+#[cfg(unix)]
+fn warn_if_shared(path: &Path) {
+    use std::os::unix::fs::PermissionsExt as _;
+
+    if let Ok(meta) = std::fs::metadata(path)
+        && meta.permissions().mode() & 0o077 != 0
+    {
+        tracing::warn!(
+            path = %path.display(),
+            "api key file is readable by other users; consider chmod 600"
+        );
+    }
+}
+
+#[cfg(not(unix))]
+fn warn_if_shared(_path: &Path) {}
+
+#[cfg(feature = "keychain")]
+fn keychain_api_key() -> anyhow::Result<Option<String>> {
+    let entry = keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_ACCOUNT).context("open keychain")?;
+    match entry.get_password() {
+        Ok(key) => Ok(Some(key)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(err) => Err(err).context("read api key from keychain"),
+    }
+}
+
+#[cfg(not(feature = "keychain"))]
+fn keychain_api_key() -> anyhow::Result<Option<String>> {
+    Ok(None)
+}
@@ -0,0 +1,33 @@
+use serde_json::Value;
+
+use super::{CrawlResult, UserRequest, compose_json, compose_markdown};
+
+/// Queries of a `--batch` file: one per line, skipping blank lines and `#` comments.
+pub fn parse_batch_queries(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_owned)
+        .collect()
+}
+
+/// The report of each query in batch order, separated by a rule.
+pub fn compose_batch_markdown(requests: &[UserRequest], results: &[CrawlResult]) -> String {
+    requests
+        .iter()
+        .zip(results)
+        .map(|(request, result)| compose_markdown(request, result))
+        .collect::<Vec<_>>()
+        .join("\n---\n\n")
+}
+
+/// A JSON array with the report of each query in batch order.
+pub fn compose_batch_json(requests: &[UserRequest], results: &[CrawlResult]) -> Value {
+    Value::Array(
+        requests
+            .iter()
+            .zip(results)
+            .map(|(request, result)| compose_json(request, result))
+            .collect(),
+    )
+}
@@ -0,0 +1,245 @@
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use anyhow::Context as _;
+use url::Url;
+
+use super::fetch::{MAX_REDIRECTS, SpiderPageFetcher};
+use super::{CrawlError, PageFetcher, extract_page, readable_content};
+
+/// Fetch and extraction measurements over a URL list (`llm-spider bench`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchReport {
+    pub urls: usize,
+    pub concurrency: usize,
+    pub fetched: usize,
+    pub fetch_failed: usize,
+    pub extract_failed: usize,
+    /// Decompressed body bytes of the successful fetches.
+    pub bytes: u64,
+    pub elapsed: Duration,
+    /// Every fetch attempt, failed ones included.
+    pub fetch: Latency,
+    /// Text and readable-content extraction of each fetched page.
+    pub extract: Latency,
+    /// Peak resident memory of the process; `None` where the OS does not report it.
+    pub peak_rss_bytes: Option<u64>,
+}
+
+/// Latency percentiles of one phase; all zero when the phase never ran.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Latency {
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl Latency {
+    fn from_samples(mut samples: Vec<Duration>) -> Self {
+        samples.sort();
+        // Nearest rank: the smallest sample at or above the percentile.
+        let rank = |percent: usize| {
+            samples
+                .get((samples.len() * percent).div_ceil(100).saturating_sub(1))
+                .copied()
+                .unwrap_or_default()
+        };
+        Self {
+            p50: rank(50),
+            p90: rank(90),
+            p99: rank(99),
+            max: samples.last().copied().unwrap_or_default(),
+        }
+    }
+}
+
+/// URLs of a bench list: one per line, skipping blank lines and `#` comments.
+pub fn parse_url_list(text: &str) -> anyhow::Result<Vec<Url>> {
+    text.lines()
+        .enumerate()
+        .map(|(index, line)| (index, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(index, line)| {
+            Url::parse(line).with_context(|| format!("URL list line {}: {line}", index + 1))
+        })
+        .collect()
+}
+
+/// Fetches and extracts `urls` with the crawler's HTTP client, `concurrency` at a time.
+///
+/// `robots.txt` is honored, but per-host intervals are not: point it at hosts you run.
+pub fn bench(urls: &[Url], concurrency: usize) -> Result<BenchReport, CrawlError> {
+    let fetcher = SpiderPageFetcher::new(None, None, None, None).map_err(CrawlError::Init)?;
+    Ok(bench_with_fetcher(urls, concurrency, &fetcher))
+}
+
+pub fn bench_with_fetcher(
+    urls: &[Url],
+    concurrency: usize,
+    fetcher: &(dyn PageFetcher + Sync),
+) -> BenchReport {
+    let concurrency = concurrency.clamp(1, urls.len().max(1));
+    let next = AtomicUsize::new(0);
+    let samples = Mutex::new(Samples::default());
+    let started_at = Instant::now();
+    std::thread::scope(|scope| {
+        for _ in 0..concurrency {
+            scope.spawn(|| {
+                while let Some(url) = urls.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let sample = measure(fetcher, url);
+                    samples
+                        .lock()
+                        .unwrap_or_else(|err| err.into_inner())
+                        .push(sample);
+                }
+            });
+        }
+    });
+    let elapsed = started_at.elapsed();
+    let samples = samples.into_inner().unwrap_or_else(|err| err.into_inner());
+
+    BenchReport {
+        urls: urls.len(),
+        concurrency,
+        fetched: samples.extract.len(),
+        fetch_failed: samples.fetch_failed,
+        extract_failed: samples.extract_failed,
+        bytes: samples.bytes,
+        elapsed,
+        fetch: Latency::from_samples(samples.fetch),
+        extract: Latency::from_samples(samples.extract),
+        peak_rss_bytes: peak_rss_bytes(),
+    }
+}
+
+#[derive(Default)]
+struct Samples {
+    fetch: Vec<Duration>,
+    extract: Vec<Duration>,
+    fetch_failed: usize,
+    extract_failed: usize,
+    bytes: u64,
+}
+
+struct Sample {
+    fetch: Duration,
+    /// `None` when the fetch failed.
+    extract: Option<(Duration, bool)>,
+    bytes: u64,
+}
+
+impl Samples {
+    fn push(&mut self, sample: Sample) {
+        self.fetch.push(sample.fetch);
+        self.bytes += sample.bytes;
+        match sample.extract {
+            Some((duration, ok)) => {
+                self.extract.push(duration);
+                if !ok {
+                    self.extract_failed += 1;
+                }
+            }
+            None => self.fetch_failed += 1,
+        }
+    }
+}
+
+fn measure(fetcher: &(dyn PageFetcher + Sync), url: &Url) -> Sample {
+    let fetch_started = Instant::now();
+    let mut target = url.clone();
+    let mut page = fetcher.fetch(&target);
+    for _ in 0..MAX_REDIRECTS {
+        let Some(CrawlError::Redirected { location }) = page
+            .as_ref()
+            .err()
+            .and_then(|err| err.downcast_ref::<CrawlError>())
+        else {
+            break;
+        };
+        target = location.clone();
+        page = fetcher.fetch(&target);
+    }
+    let fetch = fetch_started.elapsed();
+    let page = match page {
+        Ok(page) => page,
+        Err(err) => {
+            tracing::warn!(url = %url, "bench fetch failed: {err:#}");
+            return Sample {
+                fetch,
+                extract: None,
+                bytes: 0,
+            };
+        }
+    };
+
+    let extract_started = Instant::now();
+    let ok = match extract_page(url, &page.html) {
+        Ok(extracted) => {
+            readable_content(url, &page.html, extracted.text);
+            true
+        }
+        Err(err) => {
+            tracing::warn!(url = %url, "bench extract failed: {err:#}");
+            false
+        }
+    };
+    Sample {
+        fetch,
+        extract: Some((extract_started.elapsed(), ok)),
+        bytes: page.bytes,
+    }
+}
+
+/// `VmHWM` from `/proc/self/status` on Linux.
+fn peak_rss_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let kib = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kib * 1024)
+}
+
+pub fn compose_bench_markdown(report: &BenchReport) -> String {
+    let secs = report.elapsed.as_secs_f64();
+    let per_sec = |value: f64| if secs > 0.0 { value / secs } else { 0.0 };
+    let ms = |duration: Duration| format!("{:.1}", duration.as_secs_f64() * 1000.0);
+
+    let mut out = String::new();
+    out.push_str("# Spider Bench\n\n");
+    out.push_str(&format!(
+        "{} URLs at concurrency {} in {secs:.2}s: {} fetched, {} fetch failures, {} extract failures.\n\n",
+        report.urls, report.concurrency, report.fetched, report.fetch_failed, report.extract_failed
+    ));
+    out.push_str(&format!(
+        "Throughput: {:.1} pages/s, {:.2} MiB/s\n\n",
+        per_sec(report.fetched as f64),
+        per_sec(report.bytes as f64 / (1024.0 * 1024.0))
+    ));
+    out.push_str("| Phase | p50 ms | p90 ms | p99 ms | max ms |\n");
+    out.push_str("|-------|--------|--------|--------|--------|\n");
+    for (phase, latency) in [("fetch", report.fetch), ("extract", report.extract)] {
+        out.push_str(&format!(
+            "| {phase} | {} | {} | {} | {} |\n",
+            ms(latency.p50),
+            ms(latency.p90),
+            ms(latency.p99),
+            ms(latency.max)
+        ));
+    }
+    out.push('\n');
+    match report.peak_rss_bytes {
+        Some(bytes) => out.push_str(&format!(
+            "Peak memory (RSS): {:.1} MiB\n",
+            bytes as f64 / (1024.0 * 1024.0)
+        )),
+        None => out.push_str("Peak memory (RSS): unknown on this platform\n"),
+    }
+    out
+}
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+
+use url::Url;
+
+use super::CrawlError;
+
+/// Stops fetching from a host after `limit` failed fetches in a row, or after one
+/// answer with an exclusion status.
+///
+/// Two kinds of failure count, each in a row of its own: the host being down (no
+/// response, e.g. connection errors and timeouts, or a `5xx`) and the host refusing us
+/// (`403` or `429`). Any other answer, including a `404`, resets both counts. A tripped
+/// host stays skipped for the rest of the crawl.
+///
+/// An exclusion status (such as `451 Unavailable For Legal Reasons`) trips the host on
+/// the first answer, even when `limit` is `0`.
+pub(super) struct HostBreaker {
+    limit: usize,
+    exclusion_statuses: Vec<u16>,
+    hosts: HashMap<String, HostFailures>,
+    refused: Vec<RefusedHost>,
+    excluded: Vec<ExcludedHost>,
+}
+
+/// Why a host is no longer fetched from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum HostTrip {
+    Down,
+    Refused,
+    Excluded,
+}
+
+/// A host that kept answering `403` or `429` and was skipped for the rest of the crawl.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefusedHost {
+    pub host: String,
+    /// The status of the last refusal.
+    pub status: u16,
+    pub refusals: usize,
+}
+
+/// A host that answered an exclusion status (e.g. `451`) and was skipped for the rest of
+/// the crawl.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExcludedHost {
+    pub host: String,
+    pub status: u16,
+    /// The URL that answered it.
+    pub url: Url,
+}
+
+#[derive(Debug, Default)]
+struct HostFailures {
+    down: usize,
+    refused: usize,
+    tripped: Option<HostTrip>,
+}
+
+impl HostBreaker {
+    /// `limit` `0` disables the breaker, but not `exclusion_statuses`.
+    pub(super) fn new(limit: usize, exclusion_statuses: &[u16]) -> Self {
+        Self {
+            limit,
+            exclusion_statuses: exclusion_statuses.to_vec(),
+            hosts: HashMap::new(),
+            refused: Vec::new(),
+            excluded: Vec::new(),
+        }
+    }
+
+    pub(super) fn tripped(&self, url: &Url) -> Option<HostTrip> {
+        host_key(url)
+            .and_then(|host| self.hosts.get(&host))
+            .and_then(|failures| failures.tripped)
+    }
+
+    pub(super) fn record_success(&mut self, url: &Url) {
+        if let Some(failures) = host_key(url).and_then(|host| self.hosts.get_mut(&host)) {
+            failures.down = 0;
+            failures.refused = 0;
+        }
+    }
+
+    /// Counts a failed fetch; returns the trip when this failure trips the breaker.
+    pub(super) fn record_failure(&mut self, url: &Url, err: &anyhow::Error) -> Option<HostTrip> {
+        let Some(kind) = self.failure_kind(err) else {
+            self.record_success(url);
+            return None;
+        };
+        let host = host_key(url).filter(|_| self.limit > 0 || kind == HostTrip::Excluded)?;
+        let failures = self.hosts.entry(host.clone()).or_default();
+        let count = match kind {
+            HostTrip::Down => {
+                failures.refused = 0;
+                failures.down += 1;
+                failures.down
+            }
+            HostTrip::Refused => {
+                failures.down = 0;
+                failures.refused += 1;
+                failures.refused
+            }
+            // The first answer is enough.
+            HostTrip::Excluded => self.limit.max(1),
+        };
+        if failures.tripped.is_some() || count < self.limit {
+            return None;
+        }
+        failures.tripped = Some(kind);
+        if let Some(CrawlError::FetchFailed {
+            status: Some(status),
+            ..
+        }) = err.downcast_ref::<CrawlError>()
+        {
+            match kind {
+                HostTrip::Refused => self.refused.push(RefusedHost {
+                    host,
+                    status: *status,
+                    refusals: count,
+                }),
+                HostTrip::Excluded => self.excluded.push(ExcludedHost {
+                    host,
+                    status: *status,
+                    url: url.clone(),
+                }),
+                HostTrip::Down => {}
+            }
+        }
+        Some(kind)
+    }
+
+    /// Hosts tripped by refusals, in the order they tripped.
+    pub(super) fn refused_hosts(&self) -> &[RefusedHost] {
+        &self.refused
+    }
+
+    /// Hosts tripped by an exclusion status, in the order they tripped.
+    pub(super) fn excluded_hosts(&self) -> &[ExcludedHost] {
+        &self.excluded
+    }
+
+    fn failure_kind(&self, err: &anyhow::Error) -> Option<HostTrip> {
+        match err.downcast_ref::<CrawlError>() {
+            Some(CrawlError::FetchFailed {
+                status: Some(status),
+                ..
+            }) if self.exclusion_statuses.contains(status) => Some(HostTrip::Excluded),
+            Some(CrawlError::FetchFailed {
+                status: Some(403 | 429),
+                ..
+            }) => Some(HostTrip::Refused),
+            Some(CrawlError::FetchFailed { status, .. }) => status
+                .is_none_or(|status| status >= 500)
+                .then_some(HostTrip::Down),
+            Some(_) => None,
+            None => Some(HostTrip::Down),
+        }
+    }
+}
+
+fn host_key(url: &Url) -> Option<String> {
+    url.host_str().map(str::to_ascii_lowercase)
+}
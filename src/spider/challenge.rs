@@ -0,0 +1,45 @@
+use reqwest::header::HeaderMap;
+
+/// Body markers of bot-challenge interstitials, by vendor. Each marker only appears on
+/// the challenge page itself, not on regular pages served through the same CDN.
+const BODY_MARKERS: &[(&str, &[&str])] = &[
+    (
+        "cloudflare",
+        &[
+            "cf_chl_opt",
+            "cf-browser-verification",
+            "<title>just a moment...</title>",
+            "attention required! | cloudflare",
+        ],
+    ),
+    ("akamai", &["errors.edgesuite.net", "_abck_challenge"]),
+    ("datadome", &["captcha-delivery.com"]),
+    ("perimeterx", &["px-captcha", "_pxcaptcha"]),
+    ("sucuri", &["sucuri website firewall - access denied"]),
+    (
+        "captcha",
+        &[
+            "<title>are you a robot",
+            "<title>verify you are human",
+            "<title>checking your browser",
+        ],
+    ),
+];
+
+/// The vendor whose challenge page `html` is, if any.
+pub(super) fn detect_challenge(html: &str) -> Option<&'static str> {
+    let html = html.to_lowercase();
+    BODY_MARKERS
+        .iter()
+        .find(|(_, markers)| markers.iter().any(|marker| html.contains(marker)))
+        .map(|(vendor, _)| *vendor)
+}
+
+/// Cloudflare marks challenge responses with `cf-mitigated: challenge`, whatever the body.
+pub(super) fn detect_challenge_header(headers: &HeaderMap) -> Option<&'static str> {
+    headers
+        .get("cf-mitigated")
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.eq_ignore_ascii_case("challenge"))
+        .then_some("cloudflare")
+}
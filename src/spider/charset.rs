@@ -0,0 +1,73 @@
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+
+const META_SNIFF_BYTES: usize = 2048;
+
+/// Decodes a fetched HTML body into UTF-8.
+///
+/// The charset is taken from the `Content-Type` header, then a byte order mark,
+/// then a `<meta>` declaration near the top of the document. Unlabeled bodies are
+/// treated as UTF-8 when valid and windows-1252 otherwise.
+pub fn decode_html(bytes: &[u8], content_type: Option<&str>) -> String {
+    let encoding = content_type
+        .and_then(charset_from_content_type)
+        .or_else(|| Encoding::for_bom(bytes).map(|(encoding, _)| encoding))
+        .or_else(|| charset_from_meta(bytes))
+        .unwrap_or_else(|| {
+            if std::str::from_utf8(bytes).is_ok() {
+                UTF_8
+            } else {
+                WINDOWS_1252
+            }
+        });
+
+    let (decoded, _encoding, _had_errors) = encoding.decode(bytes);
+    decoded.into_owned()
+}
+
+fn charset_from_content_type(content_type: &str) -> Option<&'static Encoding> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        if !name.trim().eq_ignore_ascii_case("charset") {
+            return None;
+        }
+        Encoding::for_label(value.trim().trim_matches(['"', '\'']).as_bytes())
+    })
+}
+
+fn charset_from_meta(bytes: &[u8]) -> Option<&'static Encoding> {
+    let head = &bytes[..bytes.len().min(META_SNIFF_BYTES)];
+    let head = head.to_ascii_lowercase();
+
+    let mut offset = 0usize;
+    while let Some(rel) = find(&head[offset..], b"<meta") {
+        let start = offset + rel;
+        let end = find(&head[start..], b">").map_or(head.len(), |rel| start + rel);
+        let tag = &head[start..end];
+        offset = end;
+
+        let Some(pos) = find(tag, b"charset") else {
+            continue;
+        };
+        let value = tag[pos + b"charset".len()..]
+            .iter()
+            .skip_while(|b| b.is_ascii_whitespace() || **b == b'=' || **b == b'"' || **b == b'\'')
+            .take_while(|b| !b.is_ascii_whitespace() && !matches!(**b, b'"' | b'\'' | b';' | b'/'))
+            .copied()
+            .collect::<Vec<_>>();
+        if let Some(encoding) = Encoding::for_label(&value) {
+            // A UTF-16 label in an ASCII-compatible meta tag can't be right; follow the HTML spec.
+            if encoding == encoding_rs::UTF_16LE || encoding == encoding_rs::UTF_16BE {
+                return Some(UTF_8);
+            }
+            return Some(encoding);
+        }
+    }
+
+    None
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
@@ -0,0 +1,139 @@
+use std::collections::BTreeMap;
+
+use super::{Source, Subtopic, TrustTier, escape_md_table_cell};
+
+/// The collected sources that cover one alternative of `--compare`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlternativeCoverage {
+    pub alternative: String,
+    /// Indices into [`super::CrawlResult::sources`]; a source may cover several alternatives.
+    pub sources: Vec<usize>,
+}
+
+/// Tags each source with the alternatives its title, URL, or content mentions as a
+/// whole word (case-insensitive).
+pub(super) fn cover_alternatives(
+    alternatives: &[String],
+    sources: &[Source],
+) -> Vec<AlternativeCoverage> {
+    let texts = sources
+        .iter()
+        .map(|source| {
+            format!(
+                "{}\n{}\n{}",
+                source.title.as_deref().unwrap_or_default(),
+                source.url,
+                source.content
+            )
+            .to_lowercase()
+        })
+        .collect::<Vec<_>>();
+    alternatives
+        .iter()
+        .map(|alternative| {
+            let needle = alternative.trim().to_lowercase();
+            AlternativeCoverage {
+                alternative: alternative.clone(),
+                sources: texts
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, text)| count_mentions(text, &needle) > 0)
+                    .map(|(id, _)| id)
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+/// Findings groups for comparison mode: one per alternative for sources covering only
+/// it, then one per combination of alternatives covered together, then `General`.
+pub(super) fn group_by_alternative(
+    coverage: &[AlternativeCoverage],
+    source_count: usize,
+) -> Vec<Subtopic> {
+    let mut covered_by = vec![Vec::new(); source_count];
+    for (index, entry) in coverage.iter().enumerate() {
+        for &id in &entry.sources {
+            if let Some(alternatives) = covered_by.get_mut(id) {
+                alternatives.push(index);
+            }
+        }
+    }
+    let mut groups = BTreeMap::<(usize, Vec<usize>), Vec<usize>>::new();
+    for (id, alternatives) in covered_by.into_iter().enumerate() {
+        // Uncovered sources sort last.
+        let rank = if alternatives.is_empty() {
+            usize::MAX
+        } else {
+            alternatives.len()
+        };
+        groups.entry((rank, alternatives)).or_default().push(id);
+    }
+    groups
+        .into_iter()
+        .map(|((_, alternatives), sources)| Subtopic {
+            title: if alternatives.is_empty() {
+                "General".to_owned()
+            } else {
+                alternatives
+                    .iter()
+                    .map(|&index| coverage[index].alternative.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" + ")
+            },
+            sources,
+        })
+        .collect()
+}
+
+/// The `## Comparison` table: sources per alternative by trust tier, and how many of
+/// them also cover another alternative.
+pub(super) fn compose_comparison(coverage: &[AlternativeCoverage], sources: &[Source]) -> String {
+    let mut out = String::from("## Comparison\n\n");
+    out.push_str("| Alternative | Sources | High | Medium | Low | Unverified | Shared |\n");
+    out.push_str("|-------------|---------|------|--------|-----|------------|--------|\n");
+    for entry in coverage {
+        let tiers = TrustTier::ALL.map(|tier| {
+            entry
+                .sources
+                .iter()
+                .filter(|&&id| sources.get(id).is_some_and(|s| s.trust_tier == tier))
+                .count()
+        });
+        let shared = entry
+            .sources
+            .iter()
+            .filter(|id| {
+                coverage
+                    .iter()
+                    .filter(|other| other.sources.contains(id))
+                    .count()
+                    > 1
+            })
+            .count();
+        out.push_str(&format!(
+            "| {} | {} | {} | {} | {} | {} | {shared} |\n",
+            escape_md_table_cell(&entry.alternative),
+            entry.sources.len(),
+            tiers[0],
+            tiers[1],
+            tiers[2],
+            tiers[3],
+        ));
+    }
+    out
+}
+
+/// Occurrences of `needle` in `text` with no letter or digit directly around them.
+pub(super) fn count_mentions(text: &str, needle: &str) -> usize {
+    if needle.is_empty() {
+        return 0;
+    }
+    text.match_indices(needle)
+        .filter(|(start, _)| {
+            let before = text[..*start].chars().next_back();
+            let after = text[start + needle.len()..].chars().next();
+            !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+        })
+        .count()
+}
@@ -0,0 +1,70 @@
+use url::Url;
+
+/// What a URL's file extension says about its body before anything is downloaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum UrlKind {
+    /// No extension or a typical server-rendered page extension.
+    Html,
+    /// Images, media, archives, executables, and other documents we can't extract.
+    Binary,
+    /// An extension we don't recognize; worth a `HEAD` before downloading.
+    Unknown,
+}
+
+const HTML_EXTENSIONS: &[&str] = &[
+    "html", "htm", "xhtml", "shtml", "php", "asp", "aspx", "jsp", "cgi", "txt", "md",
+];
+
+const BINARY_EXTENSIONS: &[&str] = &[
+    // images
+    "png", "jpg", "jpeg", "gif", "webp", "avif", "bmp", "ico", "svg", "tif", "tiff", "heic",
+    // audio / video
+    "mp3", "wav", "ogg", "flac", "m4a", "mp4", "m4v", "mov", "avi", "mkv", "webm", "wmv",
+    // archives / packages
+    "zip", "gz", "tgz", "bz2", "xz", "7z", "rar", "tar", "zst", "jar", "war", "deb", "rpm",
+    // executables / disk images
+    "exe", "msi", "dmg", "pkg", "apk", "ipa", "bin", "iso", "img", "so", "dll",
+    // office / binary documents
+    "pdf", "doc", "docx", "xls", "xlsx", "ppt", "pptx", "odt", "ods", "odp", "epub",
+    // fonts / assets
+    "woff", "woff2", "ttf", "otf", "eot", "css", "js", "mjs", "wasm", "map",
+];
+
+pub(super) fn classify_url(url: &Url) -> UrlKind {
+    let Some(segment) = url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+    else {
+        return UrlKind::Html;
+    };
+    let Some((_, ext)) = segment.rsplit_once('.') else {
+        return UrlKind::Html;
+    };
+    let ext = ext.to_ascii_lowercase();
+    if HTML_EXTENSIONS.contains(&ext.as_str()) {
+        UrlKind::Html
+    } else if BINARY_EXTENSIONS.contains(&ext.as_str()) {
+        UrlKind::Binary
+    } else {
+        UrlKind::Unknown
+    }
+}
+
+/// Whether a `Content-Type` value is something the extractor can read.
+pub(super) fn is_textual_content_type(content_type: &str) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+    mime.is_empty()
+        || mime.starts_with("text/")
+        || matches!(
+            mime.as_str(),
+            "application/xhtml+xml"
+                | "application/xml"
+                | "application/rss+xml"
+                | "application/atom+xml"
+        )
+}
@@ -0,0 +1,95 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use tracing::warn;
+use url::Url;
+
+/// One line of a `--control` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum ControlCommand {
+    /// `pin <url>`: crawl the URL next.
+    Pin(Url),
+    /// `ban <host>`: skip the host and its subdomains from now on.
+    Ban(String),
+}
+
+/// A file the user edits while the crawl runs, read again whenever it changes.
+///
+/// Each distinct line is applied once, so lines can be appended or the whole file
+/// rewritten. Blank lines and `#` comments are ignored.
+pub(super) struct ControlFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    seen: HashSet<String>,
+}
+
+impl ControlFile {
+    pub(super) fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_owned(),
+            modified: None,
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Commands added since the last call. A missing or unreadable file gives none.
+    pub(super) fn poll(&mut self) -> Vec<ControlCommand> {
+        let Ok(modified) = std::fs::metadata(&self.path).and_then(|meta| meta.modified()) else {
+            return Vec::new();
+        };
+        if self.modified == Some(modified) {
+            return Vec::new();
+        }
+        self.modified = Some(modified);
+        let text = match std::fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(err) => {
+                warn!(path = %self.path.display(), "read control file: {err}");
+                return Vec::new();
+            }
+        };
+
+        let mut commands = Vec::new();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') || !self.seen.insert(line.to_owned()) {
+                continue;
+            }
+            match parse_command(line) {
+                Some(command) => commands.push(command),
+                None => warn!(
+                    path = %self.path.display(),
+                    line,
+                    "ignoring control line (expected `pin <url>` or `ban <host>`)"
+                ),
+            }
+        }
+        commands
+    }
+}
+
+fn parse_command(line: &str) -> Option<ControlCommand> {
+    let (verb, arg) = line.split_once(char::is_whitespace)?;
+    let arg = arg.trim();
+    match verb {
+        "pin" => Url::parse(arg).ok().map(ControlCommand::Pin),
+        "ban" => {
+            let host = arg.trim_start_matches('.').to_ascii_lowercase();
+            (!host.is_empty() && !host.contains(['/', ' '])).then_some(ControlCommand::Ban(host))
+        }
+        _ => None,
+    }
+}
+
+/// Whether `url`'s host is `banned` or a subdomain of a banned host.
+pub(super) fn is_banned(url: &Url, banned: &HashSet<String>) -> bool {
+    let Some(host) = url.host_str().map(str::to_ascii_lowercase) else {
+        return false;
+    };
+    banned.iter().any(|ban| {
+        host == *ban
+            || host
+                .strip_suffix(ban.as_str())
+                .is_some_and(|prefix| prefix.ends_with('.'))
+    })
+}
@@ -0,0 +1,710 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Context as _;
+use reqwest::header::{
+    CONTENT_LENGTH, CONTENT_TYPE, ETAG, HeaderMap, HeaderValue, IF_MODIFIED_SINCE, IF_NONE_MATCH,
+    LAST_MODIFIED, LOCATION,
+};
+use scraper::{Html, Selector};
+use serde::Serialize;
+use url::Url;
+
+use super::challenge::{detect_challenge, detect_challenge_header};
+use super::content_type::{UrlKind, classify_url, is_textual_content_type};
+use super::opt_out::{OptOutSignal, ai_txt_allows, tdm_reserved};
+use super::{
+    CRAWL_ID_HEADER, CrawlError, DEFAULT_REQUEST_TIMEOUT, MAX_RESPONSE_BYTES, RobotsDirectives,
+    USER_AGENT, decode_html, is_allowed_by_robots, parse_robots_directives, user_agent,
+};
+use crate::audit::{AuditEvent, AuditLog, RobotsDecision};
+use crate::cache::RobotsCache;
+use crate::cancel::{CANCEL_POLL_INTERVAL, CancellationToken};
+
+#[derive(Debug, Clone, Default)]
+pub struct FetchedPage {
+    pub html: String,
+    pub links: Vec<Url>,
+    /// Minimum interval requested by `robots.txt` (`Crawl-delay` / `Request-rate`).
+    pub robots_delay: Duration,
+    /// Per-host concurrency requested by `robots.txt`, if any.
+    pub robots_max_concurrent: Option<usize>,
+    /// Decompressed body bytes for this fetch.
+    pub bytes: u64,
+    /// `ETag` response header, kept for conditional requests.
+    pub etag: Option<String>,
+    /// `Last-Modified` response header, kept for conditional requests.
+    pub last_modified: Option<String>,
+    /// `Content-Type` response header as sent.
+    pub content_type: Option<String>,
+    /// HTTP status of the final response; `None` when the fetcher does not report it.
+    pub status: Option<u16>,
+    /// URL of the final response after redirects.
+    pub final_url: Option<Url>,
+    /// `Content-Length` response header (the encoded size for compressed bodies).
+    pub content_length: Option<u64>,
+    /// `Server-Timing` response headers, joined with `, `.
+    pub server_timing: Option<String>,
+    /// `X-Robots-Tag` response headers, joined with `, `.
+    pub x_robots_tag: Option<String>,
+}
+
+/// Validators sent as `If-None-Match` / `If-Modified-Since`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CacheValidators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Revalidated {
+    /// The server answered `304 Not Modified`; the cached body is still current.
+    NotModified {
+        robots_delay: Duration,
+        robots_max_concurrent: Option<usize>,
+    },
+    Modified(Box<FetchedPage>),
+}
+
+pub trait PageFetcher {
+    fn fetch(&self, url: &Url) -> anyhow::Result<FetchedPage>;
+
+    /// Conditional fetch against a cached copy. Fetchers without HTTP-level control
+    /// fall back to a full fetch.
+    fn revalidate(&self, url: &Url, _validators: &CacheValidators) -> anyhow::Result<Revalidated> {
+        self.fetch(url)
+            .map(|page| Revalidated::Modified(Box::new(page)))
+    }
+
+    /// Hint that `urls` are about to be fetched. Fetchers may resolve their hosts
+    /// ahead of time; the default does nothing.
+    fn prefetch_dns(&self, _urls: &[Url]) {}
+
+    /// Connection reuse so far; `None` for fetchers without a connection pool.
+    fn connection_stats(&self) -> Option<ConnectionStats> {
+        None
+    }
+
+    /// `HEAD`, `robots.txt`, and `ai.txt` requests sent so far, by host. They go out
+    /// within a page's turn instead of being paced on their own; none by default.
+    fn unpaced_requests(&self) -> BTreeMap<String, usize> {
+        BTreeMap::new()
+    }
+}
+
+/// HTTP requests sent through the shared client and the connections opened for them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ConnectionStats {
+    pub requests: u64,
+    pub connections_opened: u64,
+}
+
+impl ConnectionStats {
+    /// Requests served over an already open connection.
+    pub fn reused(&self) -> u64 {
+        self.requests.saturating_sub(self.connections_opened)
+    }
+}
+
+pub(super) struct SpiderPageFetcher {
+    runtime: tokio::runtime::Runtime,
+    /// One pooled client for every page, `HEAD` and `robots.txt` request, so repeated
+    /// fetches from a host reuse its connection (multiplexed when the server speaks HTTP/2).
+    client: reqwest::Client,
+    requests: AtomicU64,
+    connections_opened: Arc<AtomicU64>,
+    /// `HEAD`, `robots.txt`, and `ai.txt` requests by host ([`PageFetcher::unpaced_requests`]).
+    unpaced_requests: Mutex<BTreeMap<String, usize>>,
+    robots_by_origin: Mutex<HashMap<String, RobotsTxt>>,
+    /// Keeps `robots.txt` documents across runs; `None` without a cache directory.
+    robots_cache: Option<RobotsCache>,
+    /// Whether `ai.txt` and `TDM-Reservation` opt-outs refuse pages.
+    honor_opt_outs: bool,
+    /// Each origin's `ai.txt` for this run; `None` when it has none or did not answer.
+    ai_txt_by_origin: Mutex<HashMap<String, Option<Arc<str>>>>,
+    /// Hosts whose DNS lookup was already started by `prefetch_dns`.
+    prefetched_hosts: Mutex<HashSet<String>>,
+    audit: Option<Arc<AuditLog>>,
+    /// Abandons requests in flight once cancelled.
+    cancellation: Option<CancellationToken>,
+}
+
+/// One site's `robots.txt`, parsed for our user agent.
+#[derive(Debug, Clone)]
+struct RobotsTxt {
+    /// `None` when the site has none or did not answer, which allows everything.
+    body: Option<Arc<str>>,
+    directives: RobotsDirectives,
+}
+
+impl RobotsTxt {
+    fn new(body: Option<String>) -> Self {
+        let directives = body
+            .as_deref()
+            .map(|body| parse_robots_directives(body, USER_AGENT))
+            .unwrap_or_default();
+        Self {
+            body: body.map(Arc::from),
+            directives,
+        }
+    }
+
+    fn allows(&self, url: &Url) -> bool {
+        let mut path = url.path().to_owned();
+        if let Some(query) = url.query() {
+            path.push('?');
+            path.push_str(query);
+        }
+        self.body
+            .as_deref()
+            .is_none_or(|body| is_allowed_by_robots(body, USER_AGENT, &path))
+    }
+}
+
+impl SpiderPageFetcher {
+    pub(super) fn new(
+        audit: Option<Arc<AuditLog>>,
+        robots_cache: Option<RobotsCache>,
+        cancellation: Option<CancellationToken>,
+        crawl_id: Option<&str>,
+    ) -> anyhow::Result<Self> {
+        let runtime = tokio::runtime::Runtime::new().context("build tokio runtime for spider")?;
+        let connections_opened = Arc::new(AtomicU64::new(0));
+        let mut headers = HeaderMap::new();
+        if let Some(crawl_id) = crawl_id {
+            headers.insert(
+                CRAWL_ID_HEADER,
+                HeaderValue::from_str(crawl_id).context("crawl id is not a valid header value")?,
+            );
+        }
+        let client = reqwest::Client::builder()
+            .user_agent(user_agent(crawl_id))
+            .default_headers(headers)
+            .timeout(DEFAULT_REQUEST_TIMEOUT)
+            .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+            .pool_max_idle_per_host(POOL_MAX_IDLE_PER_HOST)
+            .http2_adaptive_window(true)
+            .gzip(true)
+            .brotli(true)
+            .connector_layer(CountConnections(connections_opened.clone()))
+            // Each hop is its own fetch, so it answers to robots.txt and host pacing.
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .context("build http client for spider")?;
+        Ok(Self {
+            runtime,
+            client,
+            requests: AtomicU64::new(0),
+            connections_opened,
+            unpaced_requests: Mutex::new(BTreeMap::new()),
+            robots_by_origin: Mutex::new(HashMap::new()),
+            robots_cache,
+            honor_opt_outs: false,
+            ai_txt_by_origin: Mutex::new(HashMap::new()),
+            prefetched_hosts: Mutex::new(HashSet::new()),
+            audit,
+            cancellation,
+        })
+    }
+
+    /// Refuses pages whose site opted out through `ai.txt` or `TDM-Reservation`.
+    pub(super) fn with_opt_outs(mut self, honor: bool) -> Self {
+        self.honor_opt_outs = honor;
+        self
+    }
+
+    /// Runs `future` to completion, or until the cancellation token fires.
+    fn block_on<F: Future>(&self, future: F) -> Result<F::Output, CrawlError> {
+        let Some(cancellation) = &self.cancellation else {
+            return Ok(self.runtime.block_on(future));
+        };
+        self.runtime.block_on(async {
+            let mut future = std::pin::pin!(future);
+            loop {
+                if cancellation.is_cancelled() {
+                    return Err(CrawlError::Cancelled);
+                }
+                let polled = tokio::time::timeout(CANCEL_POLL_INTERVAL, future.as_mut()).await;
+                if let Ok(output) = polled {
+                    return Ok(output);
+                }
+            }
+        })
+    }
+
+    fn audit(&self, event: AuditEvent<'_>) {
+        if let Some(audit) = &self.audit {
+            audit.record(&event);
+        }
+    }
+
+    /// The `robots.txt` of `url`'s origin: as seen earlier in this run, as stored in the
+    /// disk cache within its TTL, or fetched now. Only answers from the site (`2xx`, or
+    /// `4xx` for none) are stored, so an unreachable `robots.txt` is asked for again on
+    /// the next run.
+    fn robots_txt(&self, url: &Url) -> Result<RobotsTxt, CrawlError> {
+        let origin = url.origin().ascii_serialization();
+        if let Some(cached) = self
+            .robots_by_origin
+            .lock()
+            .ok()
+            .and_then(|cache| cache.get(&origin).cloned())
+        {
+            return Ok(cached);
+        }
+
+        let robots = match self
+            .robots_cache
+            .as_ref()
+            .and_then(|cache| cache.get(&origin))
+        {
+            Some(stored) => RobotsTxt::new(stored.body),
+            None => {
+                let answer = match url.join("/robots.txt") {
+                    Ok(robots_url) => self.fetch_site_file(&robots_url)?,
+                    Err(_) => None,
+                };
+                if let (Some(cache), Some(body)) = (&self.robots_cache, &answer)
+                    && let Err(err) = cache.put(&origin, body.as_deref())
+                {
+                    tracing::warn!("robots.txt cache write failed: {err:#}");
+                }
+                RobotsTxt::new(answer.flatten())
+            }
+        };
+
+        if let Ok(mut cache) = self.robots_by_origin.lock() {
+            cache.insert(origin, robots.clone());
+        }
+        Ok(robots)
+    }
+
+    /// The `ai.txt` of `url`'s origin, fetched once per run.
+    fn ai_txt(&self, url: &Url) -> Result<Option<Arc<str>>, CrawlError> {
+        let origin = url.origin().ascii_serialization();
+        if let Some(cached) = self
+            .ai_txt_by_origin
+            .lock()
+            .ok()
+            .and_then(|cache| cache.get(&origin).cloned())
+        {
+            return Ok(cached);
+        }
+        let body = match url.join("/ai.txt") {
+            Ok(ai_txt_url) => self.fetch_site_file(&ai_txt_url)?.flatten(),
+            Err(_) => None,
+        }
+        .map(Arc::from);
+        if let Ok(mut cache) = self.ai_txt_by_origin.lock() {
+            cache.insert(origin, body.clone());
+        }
+        Ok(body)
+    }
+
+    /// `GET`s a site-wide file such as `robots.txt`: `Some(Some(body))` on `2xx`,
+    /// `Some(None)` when the site has none (`4xx`), and `None` when it did not answer
+    /// (network error, `5xx`).
+    fn fetch_site_file(&self, file_url: &Url) -> Result<Option<Option<String>>, CrawlError> {
+        self.count_unpaced(file_url);
+        let fetched = self.block_on(async {
+            let resp = self.send(self.client.get(file_url.as_str())).await?;
+            let status = resp.status();
+            let body = if status.is_success() {
+                Some(resp.text().await?)
+            } else {
+                None
+            };
+            reqwest::Result::Ok((status, body))
+        })?;
+        self.audit(AuditEvent::Http {
+            method: "GET",
+            url: file_url.as_str(),
+            status: fetched.as_ref().ok().map(|(status, _)| status.as_u16()),
+            bytes: fetched
+                .as_ref()
+                .ok()
+                .and_then(|(_, body)| body.as_ref())
+                .map(|body| body.len() as u64),
+            robots: None,
+            error: fetched.as_ref().err().map(ToString::to_string),
+        });
+        Ok(match fetched {
+            Ok((status, body)) if status.is_success() => Some(body),
+            Ok((status, _)) if status.is_client_error() => Some(None),
+            _ => None,
+        })
+    }
+
+    /// Checks `url` against its site's `robots.txt` and returns the site's pacing.
+    fn prepare(&self, url: &Url) -> anyhow::Result<RobotsDirectives> {
+        let robots = self.robots_txt(url)?;
+        if !robots.allows(url) {
+            self.audit(AuditEvent::Http {
+                method: "GET",
+                url: url.as_str(),
+                status: None,
+                bytes: None,
+                robots: Some(RobotsDecision::Disallowed),
+                error: None,
+            });
+            return Err(CrawlError::RobotsBlocked { url: url.clone() }.into());
+        }
+        if self.honor_opt_outs
+            && let Some(ai_txt) = self.ai_txt(url)?
+            && !ai_txt_allows(&ai_txt, url)
+        {
+            return Err(self.opted_out(url, OptOutSignal::AiTxt).into());
+        }
+        Ok(robots.directives)
+    }
+
+    fn opted_out(&self, url: &Url, signal: OptOutSignal) -> CrawlError {
+        self.audit(AuditEvent::OptOut {
+            url: url.as_str(),
+            signal,
+        });
+        CrawlError::OptedOut {
+            url: url.clone(),
+            signal,
+        }
+    }
+
+    /// Rejects non-textual resources before downloading the body.
+    ///
+    /// URLs with an unrecognized extension get a `HEAD` first; servers that reject
+    /// `HEAD` fall through to the normal `GET`.
+    fn check_content_type(&self, url: &Url) -> anyhow::Result<()> {
+        if classify_url(url) != UrlKind::Unknown {
+            return Ok(());
+        }
+        self.count_unpaced(url);
+        let head = self.block_on(async { self.send(self.client.head(url.as_str())).await })?;
+        self.audit(AuditEvent::Http {
+            method: "HEAD",
+            url: url.as_str(),
+            status: head.as_ref().ok().map(|resp| resp.status().as_u16()),
+            bytes: None,
+            robots: Some(RobotsDecision::Allowed),
+            error: head.as_ref().err().map(ToString::to_string),
+        });
+        let content_type = head
+            .ok()
+            .filter(|resp| resp.status().is_success())
+            .and_then(|resp| header_str(resp.headers(), CONTENT_TYPE).map(str::to_owned));
+        match content_type {
+            Some(content_type) if !is_textual_content_type(&content_type) => {
+                Err(CrawlError::NonTextual {
+                    url: url.clone(),
+                    content_type,
+                }
+                .into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn fetch_with_spider(&self, url: &Url) -> anyhow::Result<FetchedPage> {
+        // robots.txt and ai.txt come first: a refused URL gets no `HEAD` either.
+        let directives = self.prepare(url)?;
+        self.check_content_type(url)?;
+        match self.get_prepared(url, None, directives)? {
+            Revalidated::Modified(page) => Ok(*page),
+            Revalidated::NotModified { .. } => Err(CrawlError::FetchFailed {
+                url: url.clone(),
+                status: Some(reqwest::StatusCode::NOT_MODIFIED.as_u16()),
+            }
+            .into()),
+        }
+    }
+
+    fn count_unpaced(&self, url: &Url) {
+        let Some(host) = url.host_str().filter(|host| !host.is_empty()) else {
+            return;
+        };
+        if let Ok(mut counts) = self.unpaced_requests.lock() {
+            *counts.entry(host.to_ascii_lowercase()).or_default() += 1;
+        }
+    }
+
+    async fn send(&self, req: reqwest::RequestBuilder) -> reqwest::Result<reqwest::Response> {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        req.send().await
+    }
+
+    /// `GET`s `url`, conditionally when `validators` are given. The body is read
+    /// decompressed and capped at `MAX_RESPONSE_BYTES`.
+    fn get_page(
+        &self,
+        url: &Url,
+        validators: Option<&CacheValidators>,
+    ) -> anyhow::Result<Revalidated> {
+        let directives = self.prepare(url)?;
+        self.get_prepared(url, validators, directives)
+    }
+
+    /// [`Self::get_page`] for a URL that already passed [`Self::prepare`].
+    fn get_prepared(
+        &self,
+        url: &Url,
+        validators: Option<&CacheValidators>,
+        directives: RobotsDirectives,
+    ) -> anyhow::Result<Revalidated> {
+        let response = self.block_on(async {
+            let mut req = self.client.get(url.as_str());
+            if let Some(etag) = validators.and_then(|v| v.etag.as_ref()) {
+                req = req.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = validators.and_then(|v| v.last_modified.as_ref()) {
+                req = req.header(IF_MODIFIED_SINCE, last_modified);
+            }
+            let resp = self.send(req).await.context("send request")?;
+            let status = resp.status();
+            let final_url = resp.url().clone();
+            let headers = resp.headers().clone();
+            let textual = header_str(&headers, CONTENT_TYPE).is_none_or(is_textual_content_type);
+            if status == reqwest::StatusCode::FORBIDDEN
+                || status == reqwest::StatusCode::SERVICE_UNAVAILABLE
+            {
+                // Challenge interstitials come with these statuses; the body tells them apart.
+                let body = read_body_limited(resp, MAX_CHALLENGE_BYTES)
+                    .await
+                    .unwrap_or_default();
+                return anyhow::Ok((status, final_url, headers, body));
+            }
+            if status == reqwest::StatusCode::NOT_MODIFIED || !status.is_success() || !textual {
+                return anyhow::Ok((status, final_url, headers, Vec::new()));
+            }
+            let body = read_body_limited(resp, MAX_RESPONSE_BYTES).await?;
+            anyhow::Ok((status, final_url, headers, body))
+        })?;
+        self.audit(AuditEvent::Http {
+            method: "GET",
+            url: url.as_str(),
+            status: response.as_ref().ok().map(|(status, ..)| status.as_u16()),
+            bytes: response.as_ref().ok().map(|(.., body)| body.len() as u64),
+            robots: Some(RobotsDecision::Allowed),
+            error: response.as_ref().err().map(|err| format!("{err:#}")),
+        });
+        let (status, final_url, headers, body) = response?;
+        if status.is_redirection()
+            && status != reqwest::StatusCode::NOT_MODIFIED
+            && let Some(location) =
+                header_str(&headers, LOCATION).and_then(|location| final_url.join(location).ok())
+        {
+            return Err(CrawlError::Redirected { location }.into());
+        }
+        if self.honor_opt_outs && tdm_reserved(&headers) {
+            return Err(self.opted_out(url, OptOutSignal::TdmReservation).into());
+        }
+
+        if status == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(Revalidated::NotModified {
+                robots_delay: directives.min_interval(),
+                robots_max_concurrent: directives.max_concurrent(),
+            });
+        }
+        if !status.is_success() {
+            let vendor = detect_challenge_header(&headers)
+                .or_else(|| detect_challenge(&String::from_utf8_lossy(&body)));
+            if let Some(vendor) = vendor {
+                return Err(CrawlError::BotChallenge {
+                    url: url.clone(),
+                    vendor: vendor.to_owned(),
+                }
+                .into());
+            }
+            return Err(CrawlError::FetchFailed {
+                url: url.clone(),
+                status: Some(status.as_u16()),
+            }
+            .into());
+        }
+        if let Some(content_type) = header_str(&headers, CONTENT_TYPE)
+            && !is_textual_content_type(content_type)
+        {
+            return Err(CrawlError::NonTextual {
+                url: url.clone(),
+                content_type: content_type.to_owned(),
+            }
+            .into());
+        }
+
+        let html = decode_html(&body, header_str(&headers, CONTENT_TYPE));
+        let links = extract_links(&final_url, &html);
+        Ok(Revalidated::Modified(Box::new(FetchedPage {
+            html,
+            links,
+            bytes: body.len() as u64,
+            robots_delay: directives.min_interval(),
+            robots_max_concurrent: directives.max_concurrent(),
+            etag: header_str(&headers, ETAG).map(str::to_owned),
+            last_modified: header_str(&headers, LAST_MODIFIED).map(str::to_owned),
+            content_type: header_str(&headers, CONTENT_TYPE).map(str::to_owned),
+            status: Some(status.as_u16()),
+            final_url: Some(final_url),
+            content_length: header_str(&headers, CONTENT_LENGTH).and_then(|len| len.parse().ok()),
+            server_timing: joined_header(&headers, "server-timing"),
+            x_robots_tag: joined_header(&headers, "x-robots-tag"),
+        })))
+    }
+}
+
+impl PageFetcher for SpiderPageFetcher {
+    fn fetch(&self, url: &Url) -> anyhow::Result<FetchedPage> {
+        self.fetch_with_spider(url)
+    }
+
+    fn revalidate(&self, url: &Url, validators: &CacheValidators) -> anyhow::Result<Revalidated> {
+        self.get_page(url, Some(validators))
+    }
+
+    fn connection_stats(&self) -> Option<ConnectionStats> {
+        Some(ConnectionStats {
+            requests: self.requests.load(Ordering::Relaxed),
+            connections_opened: self.connections_opened.load(Ordering::Relaxed),
+        })
+    }
+
+    fn unpaced_requests(&self) -> BTreeMap<String, usize> {
+        self.unpaced_requests
+            .lock()
+            .map(|counts| counts.clone())
+            .unwrap_or_default()
+    }
+
+    /// Starts a background lookup for each new host. The results are discarded: the
+    /// point is to warm the system resolver cache before the fetch needs it.
+    fn prefetch_dns(&self, urls: &[Url]) {
+        let Ok(mut prefetched) = self.prefetched_hosts.lock() else {
+            return;
+        };
+        for url in urls {
+            let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+                continue;
+            };
+            if url
+                .host()
+                .is_some_and(|host| !matches!(host, url::Host::Domain(_)))
+            {
+                continue;
+            }
+            if !prefetched.insert(host.to_owned()) {
+                continue;
+            }
+            let host = host.to_owned();
+            self.runtime.spawn_blocking(move || {
+                let result = std::net::ToSocketAddrs::to_socket_addrs(&(host.as_str(), port));
+                tracing::debug!(host, ok = result.is_ok(), "dns prefetch");
+            });
+        }
+    }
+}
+
+/// Body bytes read from a `403`/`503` response to recognize a bot challenge.
+const MAX_CHALLENGE_BYTES: usize = 256 * 1024;
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+const POOL_MAX_IDLE_PER_HOST: usize = 8;
+/// HTTP redirects followed from one URL, as reqwest does by default.
+pub(super) const MAX_REDIRECTS: usize = 10;
+
+/// Connector layer counting new connections; every call to the connector opens one.
+#[derive(Clone)]
+struct CountConnections(Arc<AtomicU64>);
+
+impl<S> tower_layer::Layer<S> for CountConnections {
+    type Service = CountingConnector<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CountingConnector {
+            inner,
+            opened: self.0.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct CountingConnector<S> {
+    inner: S,
+    opened: Arc<AtomicU64>,
+}
+
+impl<S, R> tower_service::Service<R> for CountingConnector<S>
+where
+    S: tower_service::Service<R>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        self.opened.fetch_add(1, Ordering::Relaxed);
+        self.inner.call(request)
+    }
+}
+
+async fn read_body_limited(
+    mut resp: reqwest::Response,
+    max_bytes: usize,
+) -> anyhow::Result<Vec<u8>> {
+    // `content_length` is the encoded size and is unknown for compressed bodies, so
+    // the limit is enforced again on the decompressed chunks.
+    if resp
+        .content_length()
+        .is_some_and(|len| len > max_bytes as u64)
+    {
+        anyhow::bail!("response exceeds {max_bytes} bytes");
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = resp.chunk().await.context("read response body")? {
+        if body.len() + chunk.len() > max_bytes {
+            anyhow::bail!("response exceeds {max_bytes} bytes");
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+fn header_str(headers: &HeaderMap, name: reqwest::header::HeaderName) -> Option<&str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+/// Every value of a repeatable header, joined with `, `.
+fn joined_header(headers: &HeaderMap, name: &str) -> Option<String> {
+    let values = headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .collect::<Vec<_>>();
+    (!values.is_empty()).then(|| values.join(", "))
+}
+
+/// Collects absolute http(s) links from `a[href]`, sorted and deduplicated.
+pub(super) fn extract_links(base_url: &Url, html: &str) -> Vec<Url> {
+    let doc = Html::parse_document(html);
+    let Ok(selector) = Selector::parse("a[href]") else {
+        return Vec::new();
+    };
+
+    let mut links = doc
+        .select(&selector)
+        .filter_map(|node| node.value().attr("href"))
+        .filter_map(|href| base_url.join(href).ok())
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+        .map(|mut url| {
+            url.set_fragment(None);
+            url
+        })
+        .collect::<Vec<_>>();
+    links.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+    links.dedup_by(|a, b| a.as_str() == b.as_str());
+    links
+}
@@ -0,0 +1,28 @@
+use scraper::{Html, Selector};
+use url::Url;
+
+use super::extract_page;
+
+/// A page whose extracted text has fewer visible characters than this is a frame shell.
+const FRAME_SHELL_MAX_CHARS: usize = 50;
+
+/// The first same-origin `<iframe>` / `<frame>` source of a page whose own content is
+/// effectively empty, as in legacy documentation viewers that load every topic into a
+/// frame. Pages with content of their own, and frames on other origins, give `None`.
+pub(super) fn frame_target(page_url: &Url, html: &str) -> Option<Url> {
+    let text = extract_page(page_url, html).ok()?.text;
+    if text.chars().filter(|c| !c.is_whitespace()).count() >= FRAME_SHELL_MAX_CHARS {
+        return None;
+    }
+
+    let doc = Html::parse_document(html);
+    let selector = Selector::parse("iframe[src], frame[src]").ok()?;
+    doc.select(&selector)
+        .filter_map(|node| node.value().attr("src"))
+        .filter_map(|src| page_url.join(src.trim()).ok())
+        .map(|mut target| {
+            target.set_fragment(None);
+            target
+        })
+        .find(|target| target.origin() == page_url.origin() && target != page_url)
+}
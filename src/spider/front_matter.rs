@@ -0,0 +1,56 @@
+use std::time::SystemTime;
+
+use super::{CrawlResult, UserRequest};
+
+/// Metadata for the YAML front matter of the markdown report (`--front-matter`) that
+/// the crawl result does not carry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrontMatter {
+    /// `YYYY-MM-DD`.
+    pub date: String,
+    pub tags: Vec<String>,
+    /// What the OpenAI calls cost; `None` leaves `cost_usd` out.
+    pub cost_usd: Option<f64>,
+}
+
+impl FrontMatter {
+    /// Front matter dated today (UTC).
+    pub fn new(tags: Vec<String>, cost_usd: Option<f64>) -> Self {
+        let now = humantime::format_rfc3339_seconds(SystemTime::now()).to_string();
+        Self {
+            date: now[..10].to_owned(),
+            tags,
+            cost_usd,
+        }
+    }
+}
+
+/// A `---` delimited YAML block to put before the markdown report.
+pub fn compose_front_matter(
+    request: &UserRequest,
+    result: &CrawlResult,
+    front_matter: &FrontMatter,
+) -> String {
+    let mut out = String::from("---\n");
+    out.push_str(&format!("query: {}\n", yaml_string(&request.query)));
+    out.push_str(&format!("date: {}\n", front_matter.date));
+    out.push_str(&format!("sources: {}\n", result.sources.len()));
+    if front_matter.tags.is_empty() {
+        out.push_str("tags: []\n");
+    } else {
+        out.push_str("tags:\n");
+        for tag in &front_matter.tags {
+            out.push_str(&format!("  - {}\n", yaml_string(tag)));
+        }
+    }
+    if let Some(cost) = front_matter.cost_usd {
+        out.push_str(&format!("cost_usd: {cost:.4}\n"));
+    }
+    out.push_str("---\n\n");
+    out
+}
+
+/// `text` as a double-quoted YAML scalar, which JSON string syntax is a subset of.
+pub(super) fn yaml_string(text: &str) -> String {
+    serde_json::Value::from(text).to_string()
+}
@@ -0,0 +1,256 @@
+use std::collections::{HashSet, VecDeque};
+
+use url::Url;
+
+use super::{SeedLimits, normalize_url};
+use crate::trust::TrustTier;
+
+/// Per-entry bookkeeping overhead used for memory estimates (queue slot plus `Url` indices).
+const FRONTIER_ENTRY_OVERHEAD_BYTES: usize = std::mem::size_of::<FrontierEntry>();
+const VISITED_ENTRY_OVERHEAD_BYTES: usize = 2 * std::mem::size_of::<String>();
+
+/// A queued URL.
+#[derive(Debug, Clone)]
+pub(super) struct FrontierEntry {
+    pub(super) url: Url,
+    pub(super) depth: usize,
+    pub(super) tier: TrustTier,
+    pub(super) limits: SeedLimits,
+    /// Position among the seeds: `0` for `--seed-url` seeds, then the search rank
+    /// (`1` for the top hit). `None` for pages discovered while crawling.
+    pub(super) rank: Option<usize>,
+}
+
+/// Tiered crawl queue. High is drained before Medium, Medium before Low.
+///
+/// Within a tier, ranked entries come first in rank order, so the top search hit is
+/// crawled before the tenth; entries of equal rank, and unranked ones, keep push order.
+///
+/// When `max_len` is non-zero the queue is bounded: pushing into a full frontier evicts
+/// the newest entry of the lowest non-empty tier, or drops the pushed entry when it is
+/// not more important than anything already queued.
+///
+/// Pinned entries come before every tier and are never evicted.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Frontier {
+    pinned: VecDeque<FrontierEntry>,
+    high: VecDeque<FrontierEntry>,
+    medium: VecDeque<FrontierEntry>,
+    low: VecDeque<FrontierEntry>,
+    unverified: VecDeque<FrontierEntry>,
+    /// Canonical keys of the queued URLs.
+    keys: HashSet<String>,
+    max_len: usize,
+    bytes: usize,
+    peak_len: usize,
+    peak_bytes: usize,
+    evicted: usize,
+}
+
+impl Frontier {
+    pub(super) fn new(max_len: usize) -> Self {
+        Self {
+            max_len,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the URL of the queued entry the push evicted, if any, or `Err` with the
+    /// pushed URL when the entry was dropped because the frontier is full.
+    pub(super) fn push(&mut self, entry: FrontierEntry) -> Result<Option<Url>, Url> {
+        let mut evicted_url = None;
+        if self.max_len > 0 && self.len() >= self.max_len {
+            let Some(lowest) = self.lowest_tier() else {
+                return Err(entry.url);
+            };
+            if entry.tier >= lowest {
+                self.evicted += 1;
+                return Err(entry.url);
+            }
+            if let Some(evicted) = self.queue_mut(lowest).pop_back() {
+                self.bytes -= entry_bytes(&evicted.url);
+                self.keys.remove(&normalize_url(&evicted.url));
+                self.evicted += 1;
+                evicted_url = Some(evicted.url);
+            }
+        }
+
+        self.bytes += entry_bytes(&entry.url);
+        self.keys.insert(normalize_url(&entry.url));
+        let queue = self.queue_mut(entry.tier);
+        let rank = rank_key(&entry);
+        let at = queue.partition_point(|queued| rank_key(queued) <= rank);
+        queue.insert(at, entry);
+        self.peak_len = self.peak_len.max(self.len());
+        self.peak_bytes = self.peak_bytes.max(self.bytes);
+        Ok(evicted_url)
+    }
+
+    pub(super) fn pop(&mut self) -> Option<FrontierEntry> {
+        if let Some(entry) = self.pinned.pop_front() {
+            self.bytes -= entry_bytes(&entry.url);
+            self.keys.remove(&normalize_url(&entry.url));
+            return Some(entry);
+        }
+        for tier in TrustTier::ALL {
+            if let Some(entry) = self.queue_mut(tier).pop_front() {
+                self.bytes -= entry_bytes(&entry.url);
+                self.keys.remove(&normalize_url(&entry.url));
+                return Some(entry);
+            }
+        }
+        None
+    }
+
+    /// Moves the queued entry with `url`'s canonical form ahead of everything else, after
+    /// earlier pins. Returns `false` when no such entry is queued.
+    pub(super) fn pin(&mut self, url: &Url) -> bool {
+        let key = normalize_url(url);
+        for tier in TrustTier::ALL {
+            let queue = self.queue_mut(tier);
+            if let Some(at) = queue
+                .iter()
+                .position(|entry| normalize_url(&entry.url) == key)
+                && let Some(entry) = queue.remove(at)
+            {
+                self.pinned.push_back(entry);
+                return true;
+            }
+        }
+        self.pinned
+            .iter()
+            .any(|entry| normalize_url(&entry.url) == key)
+    }
+
+    /// Whether a URL with the same canonical form is already queued.
+    pub(super) fn contains(&self, url: &Url) -> bool {
+        self.keys.contains(&normalize_url(url))
+    }
+
+    /// The next `n` URLs in pop order, without removing them.
+    pub(super) fn peek(&self, n: usize) -> impl Iterator<Item = &Url> {
+        self.pinned
+            .iter()
+            .chain(&self.high)
+            .chain(&self.medium)
+            .chain(&self.low)
+            .chain(&self.unverified)
+            .map(|entry| &entry.url)
+            .take(n)
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.pinned.len()
+            + self.high.len()
+            + self.medium.len()
+            + self.low.len()
+            + self.unverified.len()
+    }
+
+    pub(super) fn peak_len(&self) -> usize {
+        self.peak_len
+    }
+
+    pub(super) fn peak_bytes(&self) -> usize {
+        self.peak_bytes
+    }
+
+    pub(super) fn evicted(&self) -> usize {
+        self.evicted
+    }
+
+    fn lowest_tier(&self) -> Option<TrustTier> {
+        TrustTier::ALL
+            .into_iter()
+            .rev()
+            .find(|tier| !self.queue(*tier).is_empty())
+    }
+
+    fn queue(&self, tier: TrustTier) -> &VecDeque<FrontierEntry> {
+        match tier {
+            TrustTier::High => &self.high,
+            TrustTier::Medium => &self.medium,
+            TrustTier::Low => &self.low,
+            TrustTier::Unverified => &self.unverified,
+        }
+    }
+
+    fn queue_mut(&mut self, tier: TrustTier) -> &mut VecDeque<FrontierEntry> {
+        match tier {
+            TrustTier::High => &mut self.high,
+            TrustTier::Medium => &mut self.medium,
+            TrustTier::Low => &mut self.low,
+            TrustTier::Unverified => &mut self.unverified,
+        }
+    }
+}
+
+/// Unranked entries sort after every ranked one.
+fn rank_key(entry: &FrontierEntry) -> usize {
+    entry.rank.unwrap_or(usize::MAX)
+}
+
+fn entry_bytes(url: &Url) -> usize {
+    url.as_str().len() + FRONTIER_ENTRY_OVERHEAD_BYTES
+}
+
+/// Set of normalized URLs already popped from the frontier.
+///
+/// When `max_len` is non-zero the oldest keys are forgotten first; a forgotten URL may be
+/// fetched again if it is rediscovered.
+#[derive(Debug, Default)]
+pub(super) struct VisitedSet {
+    keys: HashSet<String>,
+    order: VecDeque<String>,
+    max_len: usize,
+    bytes: usize,
+    evicted: usize,
+}
+
+impl VisitedSet {
+    pub(super) fn new(max_len: usize) -> Self {
+        Self {
+            max_len,
+            ..Self::default()
+        }
+    }
+
+    /// Returns `true` when `key` was not present.
+    pub(super) fn insert(&mut self, key: String) -> bool {
+        if self.keys.contains(&key) {
+            return false;
+        }
+        if self.max_len > 0
+            && self.keys.len() >= self.max_len
+            && let Some(oldest) = self.order.pop_front()
+        {
+            self.bytes -= visited_bytes(&oldest);
+            self.keys.remove(&oldest);
+            self.evicted += 1;
+        }
+        self.bytes += visited_bytes(&key);
+        self.order.push_back(key.clone());
+        self.keys.insert(key);
+        true
+    }
+
+    pub(super) fn contains(&self, key: &str) -> bool {
+        self.keys.contains(key)
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub(super) fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    pub(super) fn evicted(&self) -> usize {
+        self.evicted
+    }
+}
+
+fn visited_bytes(key: &str) -> usize {
+    2 * key.len() + VISITED_ENTRY_OVERHEAD_BYTES
+}
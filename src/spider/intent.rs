@@ -0,0 +1,89 @@
+use std::time::SystemTime;
+
+use tracing::{info, warn};
+
+use super::{UserRequest, escape_md_inline};
+use crate::openai::{OpenAiApi, QueryIntent};
+
+/// Host prefixes of documentation sites, boosted for [`QueryIntent::ApiReference`].
+const DOCS_HOST_PREFIXES: &[&str] = &["docs.", "developer.", "developers.", "api.", "reference."];
+
+/// How a request's intent was settled before its crawl.
+pub(super) struct IntentOutcome {
+    pub(super) intent: Option<QueryIntent>,
+    /// An LLM call was made to classify the query.
+    pub(super) classified: bool,
+    /// Why the query has no intent despite `classify_intent`, for `## Notes`.
+    pub(super) note: Option<String>,
+}
+
+/// `request` with the defaults its intent adjusts. The intent is `request.intent`, or
+/// else classified by one LLM call when `classify_intent` is set; a failed call adds a
+/// note and keeps the defaults.
+pub(super) fn resolve(
+    request: &UserRequest,
+    openai: &dyn OpenAiApi,
+) -> (UserRequest, IntentOutcome) {
+    let mut outcome = IntentOutcome {
+        intent: request.intent,
+        classified: false,
+        note: None,
+    };
+    if outcome.intent.is_none() && request.classify_intent {
+        outcome.classified = true;
+        match openai.classify_intent(&request.query) {
+            Ok(intent) => {
+                info!(intent = intent.as_str(), "query intent classified");
+                outcome.intent = Some(intent);
+            }
+            Err(err) => {
+                warn!("intent classification failed; keeping the defaults: {err:#}");
+                outcome.note = Some(format!(
+                    "クエリの意図の分類に失敗したため、既定の設定でクロールした: {}",
+                    escape_md_inline(&format!("{err:#}"))
+                ));
+            }
+        }
+    }
+
+    let mut request = request.clone();
+    if let Some(intent) = outcome.intent {
+        adjust(&mut request, intent, current_year());
+    }
+    (request, outcome)
+}
+
+/// Applies the defaults for `intent`:
+///
+/// - `News` boosts links whose path holds `year` or the year before (`/2025/`).
+/// - `ApiReference` boosts links to documentation hosts (`docs.`, `developer.`, ...).
+/// - `Troubleshooting` crawls one level deeper, unless `max_depth` is pinned.
+/// - `HowTo` and `Comparison` keep the defaults.
+fn adjust(request: &mut UserRequest, intent: QueryIntent, year: i32) {
+    let patterns = &mut request.url_patterns;
+    match intent {
+        QueryIntent::News => {
+            patterns
+                .boost
+                .extend([year, year - 1].map(|year| format!("/{year}/")));
+        }
+        QueryIntent::ApiReference => {
+            patterns
+                .boost_hosts
+                .extend(DOCS_HOST_PREFIXES.iter().map(|prefix| (*prefix).to_owned()));
+        }
+        QueryIntent::Troubleshooting => {
+            if !request.pinned_max_depth {
+                request.max_depth += 1;
+            }
+        }
+        QueryIntent::HowTo | QueryIntent::Comparison => {}
+    }
+}
+
+fn current_year() -> i32 {
+    let now = humantime::format_rfc3339_seconds(SystemTime::now()).to_string();
+    now.get(..4)
+        .and_then(|year| year.parse().ok())
+        .unwrap_or_default()
+}
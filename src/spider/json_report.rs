@@ -0,0 +1,151 @@
+use serde_json::{Value, json};
+use url::Url;
+
+use super::{CrawlResult, Source, UserRequest, is_permissive};
+
+/// The crawl result as one JSON document (`--format json`).
+///
+/// Unlike the markdown report, every source is included regardless of `max_chars`.
+pub fn compose_json(request: &UserRequest, result: &CrawlResult) -> Value {
+    let analysis = result.analysis_sources();
+    json!({
+        "query": request.query,
+        "crawl_id": request.crawl_id,
+        "intent": result.intent,
+        "sources": result.sources.iter().map(source_json).collect::<Vec<_>>(),
+        "overflow_sources": result
+            .overflow_sources
+            .iter()
+            .map(source_json)
+            .collect::<Vec<_>>(),
+        "dropped_sources": result
+            .dropped_sources
+            .iter()
+            .map(|dropped| {
+                let mut source = source_json(&dropped.source);
+                source["reason"] = json!(dropped.reason);
+                source
+            })
+            .collect::<Vec<_>>(),
+        "min_sources_met": result.sources.len() >= request.min_sources,
+        "notes": result.notes,
+        "failures": result
+            .failures
+            .iter()
+            .map(|failure| json!({
+                "url": failure.url.as_str(),
+                "stage": format!("{:?}", failure.stage),
+                "message": failure.message,
+            }))
+            .collect::<Vec<_>>(),
+        "refused_hosts": result
+            .refused_hosts
+            .iter()
+            .map(|refused| json!({
+                "host": refused.host,
+                "status": refused.status,
+                "refusals": refused.refusals,
+            }))
+            .collect::<Vec<_>>(),
+        "excluded_hosts": result
+            .excluded_hosts
+            .iter()
+            .map(|excluded| json!({
+                "host": excluded.host,
+                "status": excluded.status,
+                "url": excluded.url.as_str(),
+            }))
+            .collect::<Vec<_>>(),
+        "stats": result.stats,
+        "comparison": result
+            .comparison
+            .iter()
+            .map(|entry| json!({
+                "alternative": entry.alternative,
+                "sources": entry
+                    .sources
+                    .iter()
+                    .filter_map(|&id| result.sources.get(id))
+                    .map(|source| source.url.as_str())
+                    .collect::<Vec<_>>(),
+            }))
+            .collect::<Vec<_>>(),
+        "follow_ups": result.follow_ups,
+        "key_terms": result
+            .key_terms
+            .iter()
+            .map(|term| json!({
+                "term": term.term,
+                "definition": term.definition,
+                "sources": term
+                    .counts
+                    .iter()
+                    .filter_map(|&(id, count)| {
+                        let source = analysis.get(id)?;
+                        Some(json!({ "url": source.url.as_str(), "mentions": count }))
+                    })
+                    .collect::<Vec<_>>(),
+            }))
+            .collect::<Vec<_>>(),
+        "answer": result.answer.as_ref().map(|answer| json!({
+            "paragraphs": answer.paragraphs,
+            "sources": answer
+                .sources
+                .iter()
+                .filter_map(|&id| analysis.get(id))
+                .map(|source| source.url.as_str())
+                .collect::<Vec<_>>(),
+        })),
+        "timeline": result
+            .timeline
+            .iter()
+            .map(|event| json!({
+                "date": event.date,
+                "event": event.event,
+                "sources": event
+                    .sources
+                    .iter()
+                    .filter_map(|&id| analysis.get(id))
+                    .map(|source| source.url.as_str())
+                    .collect::<Vec<_>>(),
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn source_json(source: &Source) -> Value {
+    json!({
+        "url": source.url.as_str(),
+        "trust_tier": source.trust_tier,
+        "title": source.title,
+        "published": source.published,
+        "license": source.license,
+        "license_permissive": source.license.as_deref().is_some_and(is_permissive),
+        "paywalled": source.paywalled,
+        "change": source.change,
+        "depth": source.depth,
+        "parent": source.parent.as_ref().map(Url::as_str),
+        "fetch_order": source.fetch_order,
+        "http": {
+            "status": source.http.status,
+            "final_url": source.http.final_url.as_ref().map(Url::as_str),
+            "content_type": source.http.content_type,
+            "content_length": source.http.content_length,
+            "bytes": source.http.bytes,
+            "server_timing": source.http.server_timing,
+            "fetch_ms": source.http.fetch_duration.as_millis(),
+        },
+        "continuation_urls": source
+            .continuation_urls
+            .iter()
+            .map(Url::as_str)
+            .collect::<Vec<_>>(),
+        "outline": source
+            .outline
+            .iter()
+            .map(|heading| json!({ "level": heading.level, "text": heading.text }))
+            .collect::<Vec<_>>(),
+        "excerpt": source.excerpt,
+        "content": source.content,
+    })
+}
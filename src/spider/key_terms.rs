@@ -0,0 +1,75 @@
+use super::compare::count_mentions;
+use super::{Source, escape_md_table_cell};
+use crate::openai::GlossaryEntry;
+
+/// A key term that appears in more than one collected source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyTerm {
+    pub term: String,
+    pub definition: String,
+    /// `(source index, mentions)` for each source that mentions the term, in source order.
+    pub counts: Vec<(usize, usize)>,
+}
+
+/// Counts each glossary term in the sources' titles and text (whole words, ignoring
+/// case) and keeps the terms found in at least two sources, most widespread first.
+pub(super) fn count_key_terms(entries: Vec<GlossaryEntry>, sources: &[Source]) -> Vec<KeyTerm> {
+    let texts = sources
+        .iter()
+        .map(|source| {
+            format!(
+                "{}\n{}",
+                source.title.as_deref().unwrap_or_default(),
+                source.content
+            )
+            .to_lowercase()
+        })
+        .collect::<Vec<_>>();
+    let mut terms = entries
+        .into_iter()
+        .map(|entry| {
+            let needle = entry.term.to_lowercase();
+            let counts = texts
+                .iter()
+                .enumerate()
+                .map(|(id, text)| (id, count_mentions(text, &needle)))
+                .filter(|(_, count)| *count > 0)
+                .collect::<Vec<_>>();
+            KeyTerm {
+                term: entry.term,
+                definition: entry.definition,
+                counts,
+            }
+        })
+        .filter(|term| term.counts.len() >= 2)
+        .collect::<Vec<_>>();
+    terms.sort_by_key(|term| {
+        let total = term.counts.iter().map(|(_, count)| count).sum::<usize>();
+        std::cmp::Reverse((term.counts.len(), total))
+    });
+    terms
+}
+
+/// The `## Key terms` table. Sources are referred to by their fetch order (`#n`).
+pub(super) fn compose_key_terms(terms: &[KeyTerm], sources: &[&Source]) -> String {
+    let mut out = String::from("## Key terms\n\n");
+    out.push_str("| Term | Meaning | Sources (mentions) |\n");
+    out.push_str("|------|---------|--------------------|\n");
+    for term in terms {
+        let counts = term
+            .counts
+            .iter()
+            .filter_map(|&(id, count)| {
+                let source = sources.get(id)?;
+                Some(format!("#{} ({count})", source.fetch_order))
+            })
+            .collect::<Vec<_>>();
+        out.push_str(&format!(
+            "| {} | {} | {} |\n",
+            escape_md_table_cell(&term.term),
+            escape_md_table_cell(&term.definition),
+            counts.join(", ")
+        ));
+    }
+    out
+}
@@ -0,0 +1,206 @@
+use scraper::{Html, Selector};
+use url::Url;
+
+/// SPDX identifiers whose terms allow reuse with attribution at most.
+const PERMISSIVE: &[&str] = &[
+    "0BSD",
+    "Apache-2.0",
+    "BSD-2-Clause",
+    "BSD-3-Clause",
+    "CC-BY-2.0",
+    "CC-BY-2.5",
+    "CC-BY-3.0",
+    "CC-BY-4.0",
+    "CC0-1.0",
+    "ISC",
+    "MIT",
+    "MIT-0",
+    "Unlicense",
+    "Zlib",
+];
+
+/// Further identifiers recognized in `<meta name="license">` and license URLs.
+const RESTRICTIVE: &[&str] = &[
+    "AGPL-3.0-or-later",
+    "CC-BY-NC-4.0",
+    "CC-BY-NC-ND-4.0",
+    "CC-BY-NC-SA-4.0",
+    "CC-BY-ND-4.0",
+    "CC-BY-SA-4.0",
+    "GFDL-1.3-or-later",
+    "GPL-2.0-or-later",
+    "GPL-3.0-or-later",
+    "LGPL-2.1-or-later",
+    "LGPL-3.0-or-later",
+    "MPL-2.0",
+];
+
+const SPDX_MARKER: &str = "SPDX-License-Identifier:";
+
+/// `<meta name>` values that carry a license.
+const LICENSE_META_NAMES: &[&str] = &["license", "dcterms.license", "dc.license"];
+
+/// Finds the license a page declares, as an SPDX identifier or expression.
+///
+/// Signals in order: `rel="license"` links, license `<meta>` tags, an
+/// `SPDX-License-Identifier:` line in the visible text, Creative Commons badge images,
+/// and license links in the footer. Unrecognized licenses are ignored.
+pub(super) fn detect_license(doc: &Html) -> anyhow::Result<Option<String>> {
+    let rel_license = parse_selector("link[rel~=license][href], a[rel~=license][href]")?;
+    let meta = parse_selector("meta[name][content]")?;
+    let badges = parse_selector("img[src]")?;
+    let footer_links = parse_selector("footer a[href], [role=contentinfo] a[href]")?;
+
+    let from_rel = doc
+        .select(&rel_license)
+        .find_map(|node| license_from_href(node.value().attr("href")?));
+    let from_meta = || {
+        doc.select(&meta).find_map(|node| {
+            let name = node.value().attr("name")?;
+            if !LICENSE_META_NAMES
+                .iter()
+                .any(|known| name.eq_ignore_ascii_case(known))
+            {
+                return None;
+            }
+            let content = node.value().attr("content")?.trim();
+            license_from_href(content).or_else(|| license_from_id(content))
+        })
+    };
+    let from_spdx = || {
+        doc.root_element().text().find_map(|text| {
+            let (_, rest) = text.split_once(SPDX_MARKER)?;
+            parse_spdx_expression(rest.lines().next().unwrap_or_default())
+        })
+    };
+    let from_badge = || {
+        doc.select(&badges)
+            .find_map(|node| license_from_href(node.value().attr("src")?))
+    };
+    let from_footer = || {
+        doc.select(&footer_links)
+            .find_map(|node| license_from_href(node.value().attr("href")?))
+    };
+
+    Ok(from_rel
+        .or_else(from_meta)
+        .or_else(from_spdx)
+        .or_else(from_badge)
+        .or_else(from_footer))
+}
+
+/// Whether an SPDX expression allows permissive reuse: some `OR` alternative whose
+/// `AND` parts are all permissive. `WITH` exceptions and parentheses are ignored.
+pub fn is_permissive(expression: &str) -> bool {
+    expression.split(" OR ").any(|alternative| {
+        alternative.split(" AND ").all(|part| {
+            let id = part.split(" WITH ").next().unwrap_or_default();
+            let id = id.trim_matches(|c: char| c == '(' || c == ')' || c.is_whitespace());
+            PERMISSIVE
+                .iter()
+                .any(|known| id.eq_ignore_ascii_case(known))
+        })
+    })
+}
+
+fn parse_selector(selector: &str) -> anyhow::Result<Selector> {
+    Selector::parse(selector).map_err(|err| anyhow::anyhow!("parse selector {selector}: {err:?}"))
+}
+
+/// Leading `id (AND|OR|WITH id)*` run of `text`, e.g. `MIT OR Apache-2.0`.
+fn parse_spdx_expression(text: &str) -> Option<String> {
+    let mut parts = Vec::new();
+    for token in text.split_whitespace() {
+        let bare = token.trim_matches(|c| c == '(' || c == ')');
+        let ok = if parts.len() % 2 == 0 {
+            !bare.is_empty()
+                && bare
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+'))
+        } else {
+            matches!(bare, "AND" | "OR" | "WITH")
+        };
+        if !ok {
+            break;
+        }
+        parts.push(token);
+    }
+    if parts.len() % 2 == 0 {
+        parts.pop();
+    }
+    (!parts.is_empty()).then(|| parts.join(" "))
+}
+
+/// A known identifier, spelled the SPDX way.
+fn license_from_id(text: &str) -> Option<String> {
+    PERMISSIVE
+        .iter()
+        .chain(RESTRICTIVE)
+        .find(|known| text.eq_ignore_ascii_case(known))
+        .map(|known| (*known).to_owned())
+}
+
+/// The license behind a well-known license URL or badge image (Creative Commons,
+/// opensource.org, apache.org, gnu.org, spdx.org).
+fn license_from_href(href: &str) -> Option<String> {
+    let url = Url::parse(href)
+        .or_else(|_| Url::parse(&format!("https:{href}")))
+        .ok()?;
+    let host = url.host_str()?.to_ascii_lowercase();
+    let segments = url
+        .path_segments()?
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>();
+    let strip_extension = |name: &str| {
+        let name = name.to_ascii_lowercase();
+        [".en.html", ".html", ".htm", ".txt", ".php", ".json"]
+            .iter()
+            .find_map(|ext| name.strip_suffix(ext).map(str::to_owned))
+            .unwrap_or(name)
+    };
+
+    if host.ends_with("creativecommons.org") || host.ends_with("licensebuttons.net") {
+        return match segments.as_slice() {
+            ["licenses" | "l", kind, version, ..] => {
+                let kind = kind.to_ascii_lowercase();
+                let known_kind = ["by", "by-sa", "by-nd", "by-nc", "by-nc-sa", "by-nc-nd"]
+                    .contains(&kind.as_str());
+                let known_version = version
+                    .split('.')
+                    .all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()));
+                (known_kind && known_version)
+                    .then(|| format!("CC-{}-{version}", kind.to_ascii_uppercase()))
+            }
+            ["publicdomain" | "p", "zero", "1.0", ..] => Some("CC0-1.0".to_owned()),
+            _ => None,
+        };
+    }
+    match (host.trim_start_matches("www."), segments.as_slice()) {
+        ("opensource.org", ["licenses" | "license", name]) => {
+            license_from_id(&strip_extension(name))
+        }
+        ("apache.org", ["licenses", name]) if strip_extension(name) == "license-2.0" => {
+            Some("Apache-2.0".to_owned())
+        }
+        ("gnu.org", ["licenses", name]) => {
+            let name = strip_extension(name);
+            let (family, version) = name.split_once('-')?;
+            let family = match family {
+                "gpl" => "GPL",
+                "lgpl" => "LGPL",
+                "agpl" => "AGPL",
+                "fdl" => "GFDL",
+                _ => return None,
+            };
+            license_from_id(&format!("{family}-{version}-or-later"))
+        }
+        ("spdx.org", ["licenses", name]) => {
+            let name = name
+                .strip_suffix(".html")
+                .or_else(|| name.strip_suffix(".json"))
+                .unwrap_or(name);
+            parse_spdx_expression(name).filter(|id| !id.contains(' '))
+        }
+        _ => None,
+    }
+}
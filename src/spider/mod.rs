@@ -1,39 +1,689 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use anyhow::Context as _;
-use scraper::{ElementRef, Html, Selector};
+use clap::ValueEnum;
+use scraper::{ElementRef, Html, Node, Selector};
+use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
-use tracing::warn;
+use tracing::{debug, info, warn};
+use unicode_normalization::UnicodeNormalization as _;
 use url::Url;
 
+mod batch;
+mod bench;
+mod breaker;
+mod challenge;
+mod charset;
+mod compare;
+mod content_type;
+mod control;
+mod fetch;
+mod frames;
+mod front_matter;
+mod frontier;
+mod intent;
+mod json_report;
+mod key_terms;
+mod license;
+mod notes;
+mod opt_out;
+mod pagination;
+mod paywall;
+mod plan;
+mod plan_review;
+mod politeness;
+mod progress;
+mod refresh;
+mod relevance;
+mod report_template;
+mod review;
+mod robots;
+mod robots_tags;
+mod schedule;
+mod seed;
+mod selection_log;
+mod site;
+mod url_list;
+mod warc;
+
+pub use self::batch::{compose_batch_json, compose_batch_markdown, parse_batch_queries};
+pub use self::bench::{
+    BenchReport, Latency, bench, bench_with_fetcher, compose_bench_markdown, parse_url_list,
+};
+pub use self::breaker::{ExcludedHost, RefusedHost};
+use self::breaker::{HostBreaker, HostTrip};
+pub use self::charset::decode_html;
+pub use self::compare::AlternativeCoverage;
+use self::content_type::{UrlKind, classify_url};
+use self::control::{ControlCommand, ControlFile, is_banned};
+pub use self::fetch::{CacheValidators, ConnectionStats, FetchedPage, PageFetcher, Revalidated};
+use self::fetch::{MAX_REDIRECTS, SpiderPageFetcher};
+use self::frames::frame_target;
+pub use self::front_matter::{FrontMatter, compose_front_matter};
+use self::frontier::{Frontier, FrontierEntry, VisitedSet};
+pub use self::json_report::compose_json;
+pub use self::key_terms::KeyTerm;
+pub use self::license::is_permissive;
+pub use self::notes::{Note, compose_notes};
+pub use self::opt_out::{OptOutSignal, ai_txt_allows};
+use self::paywall::Paywall;
+use self::plan::plan_frontier;
+pub use self::plan::{CrawlPlan, PlannedSeed, compose_plan_markdown, plan_crawl};
+pub use self::plan_review::{PlanConfirmation, PlanReview};
+pub use self::politeness::{HostPoliteness, PolitenessSource};
+use self::politeness::{Politeness, lookup_host_policy};
+use self::progress::{Progress, ProgressSnapshot};
+use self::refresh::{MAX_META_REFRESH_HOPS, meta_refresh_target};
+pub use self::relevance::UrlPatterns;
+pub use self::report_template::ReportTemplate;
+pub use self::review::SelectionReview;
+pub use self::robots::{RobotsDirectives, is_allowed_by_robots, parse_robots_directives};
+pub use self::robots_tags::RobotsTags;
+pub use self::schedule::{FixedIntervalSchedule, SchedulePolicy};
+pub use self::seed::{SeedLimits, SeedUrl, parse_seed_file};
+pub use self::selection_log::{SelectionRecord, compose_selection_log};
+pub use self::site::{
+    SectionCoverage, SiteAudit, SitePage, SiteRequest, SiteStats, audit_site,
+    audit_site_with_fetcher, compose_site_json, compose_site_markdown,
+};
+pub use self::url_list::{SkipReason, SkippedUrl, compose_url_list};
+pub use self::warc::WarcReplay;
+use self::warc::WarcWriter;
+use crate::audit::AuditLog;
+pub use crate::cancel::CancellationToken;
+pub use crate::error::CrawlError;
+pub use crate::openai::{Answer, QueryIntent, SourceGrade, Subtopic, TimelineEvent};
 pub use crate::trust::TrustTier;
 
 #[derive(Debug, Clone)]
 pub struct UserRequest {
     pub query: String,
+    /// Alternatives compared side by side (`--compare`), each searched separately;
+    /// empty disables comparison mode.
+    pub compare: Vec<String>,
+    /// What the query is after; adjusts some defaults before the crawl (see
+    /// [`QueryIntent`]). `None` keeps them unless `classify_intent` is set.
+    pub intent: Option<QueryIntent>,
+    /// Classify the query with one LLM call when `intent` is `None`.
+    pub classify_intent: bool,
+    /// `max_depth` was set by a flag or profile, so the intent leaves it as is.
+    pub pinned_max_depth: bool,
     pub max_chars: usize,
     pub min_sources: usize,
     pub search_limit: usize,
     pub max_pages: usize,
     pub max_depth: usize,
+    pub max_depth_high: Option<usize>,
+    pub max_depth_medium: Option<usize>,
+    pub max_depth_low: Option<usize>,
     pub max_elapsed: Duration,
+    /// Time limit for the fetch phase, counted from the first fetch. When set it
+    /// replaces `max_elapsed`, so a slow web search does not eat into crawling.
+    pub crawl_budget: Option<Duration>,
+    /// Candidates per selection call.
     pub max_child_candidates: usize,
+    /// Selection calls per page; candidates beyond `max_child_candidates * max_candidate_batches`
+    /// are never considered.
+    pub max_candidate_batches: usize,
     pub max_children_per_page: usize,
+    /// Children selected from a hub page (mostly links); `0` treats hubs as regular pages.
+    pub max_children_per_hub: usize,
+    /// Pagination pages (`rel="next"` or a "next page" link) appended to each source;
+    /// `0` disables pagination following.
+    pub max_pagination: usize,
+    /// Sources kept per host; further pages from the host go to
+    /// [`CrawlResult::overflow_sources`]. `0` sets no limit.
+    pub max_sources_per_host: usize,
+    pub max_frontier: usize,
+    pub max_visited: usize,
+    /// Crawl-wide download budget in bytes; `0` disables it.
+    pub max_total_bytes: u64,
+    /// Failed fetches in a row (no response or `5xx`, or `403` / `429`) after which a
+    /// host's remaining URLs are skipped; `0` disables it.
+    pub host_failure_limit: usize,
+    /// HTTP statuses after which a host is not requested again for the rest of the
+    /// crawl, on the first answer (takedown signals such as `451`); empty disables it.
+    pub exclusion_statuses: Vec<u16>,
+    /// Refuse pages whose site opted out of AI use through `/ai.txt` or a
+    /// `TDM-Reservation: 1` response header; each refusal goes to the audit log.
+    pub honor_opt_outs: bool,
+    /// Upcoming frontier URLs whose hosts are resolved ahead of the fetch; `0` disables it.
+    pub dns_prefetch: usize,
+    /// Directory for the on-disk page cache; `None` disables caching.
+    pub cache_dir: Option<PathBuf>,
+    /// WARC file that receives every fetched response; a `.gz` suffix compresses it.
+    pub warc: Option<PathBuf>,
+    /// File of `pin <url>` / `ban <host>` lines, read again between fetches whenever it
+    /// changes; `None` disables it.
+    pub control_file: Option<PathBuf>,
+    /// Delay before fetching a page once more after a bot challenge; `None` gives up
+    /// on the first challenge.
+    pub challenge_retry: Option<Duration>,
+    /// Per-host intervals, concurrency, and challenge retries; `None` uses
+    /// [`FixedIntervalSchedule`] over `host_policies` and `challenge_retry`.
+    pub schedule: Option<Arc<dyn SchedulePolicy>>,
+    /// Run identifier (`--crawl-id`) appended to the User-Agent, sent as `X-Crawl-Id`,
+    /// and echoed in the reports, the WARC file, and the selection log.
+    pub crawl_id: Option<String>,
+    /// Receives one line per HTTP request to a crawled site (the OpenAI client logs its
+    /// calls through its own handle).
+    pub audit_log: Option<Arc<AuditLog>>,
+    /// Asks for confirmation of each page's link selection before its picks are queued;
+    /// `None` queues the picks as they are.
+    pub selection_review: Option<Arc<SelectionReview>>,
+    /// Shown the plan of the crawl after the web search, before the first fetch, and
+    /// may stop it there; `None` crawls without asking. The first request's review
+    /// covers a batch.
+    pub plan_review: Option<Arc<dyn PlanReview>>,
+    /// Aborts the crawl with [`CrawlError::Cancelled`] once cancelled, from any thread;
+    /// `None` runs until a budget is spent.
+    pub cancellation: Option<CancellationToken>,
+    pub host_policies: BTreeMap<String, HostPolicy>,
+    /// URL path patterns that boost or penalize link candidates before selection.
+    pub url_patterns: UrlPatterns,
     pub allow_local: bool,
+    /// Language (`hreflang`, e.g. `ja`) to prefer when a page has language variants.
+    /// `None` keeps whichever variant is fetched first.
+    pub preferred_language: Option<String>,
+    /// URLs queued as depth-0 `High` seeds alongside the search hits, each with optional
+    /// limits for the pages crawled from it.
+    pub seed_urls: Vec<SeedUrl>,
+    pub on_search_failure: SearchFailurePolicy,
+    pub on_selection_failure: SelectionFailurePolicy,
+    pub paywall_policy: PaywallPolicy,
+    pub frame_policy: FramePolicy,
+    pub robots_tags: RobotsTagPolicy,
+    /// How `[hosts]` trust tiers combine with the model's tiers.
+    pub trust_merge: TrustMergePolicy,
+    pub sources_format: SourcesFormat,
+    pub findings_layout: FindingsLayout,
+    /// Ask the LLM for follow-up queries after the crawl (one extra call).
+    pub follow_ups: bool,
+    /// Extract key terms shared by several sources after the crawl (one extra call).
+    pub key_terms: bool,
+    /// Build a dated timeline from the sources after the crawl (one extra call).
+    pub timeline: bool,
+    /// Drop sources the LLM grades as not useful for the query after the crawl (one extra
+    /// call).
+    pub grade_sources: bool,
+    /// Write a short answer from the sources after the crawl (one extra call).
+    pub answer: bool,
+    pub show_stats: bool,
+    /// Make the report depend only on the fetched pages and OpenAI responses: ignore
+    /// `max_elapsed` and `crawl_budget` and sort fetcher-provided links.
+    pub deterministic: bool,
+    /// Draw a live status line on stderr while crawling (terminals only).
+    pub show_progress: bool,
+}
+
+/// Politeness overrides for one host (and its subdomains).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HostPolicy {
+    /// Minimum interval between requests; replaces the built-in default.
+    /// `robots.txt` crawl-delay still applies when it is longer.
+    #[serde(default, with = "humantime_serde")]
+    pub min_interval: Option<Duration>,
+    /// Maximum in-flight requests. Pages are fetched one at a time today, so this only
+    /// bounds future parallel fetching.
+    pub max_concurrent: Option<usize>,
+    /// Maximum pages fetched from the host during one crawl.
+    pub max_pages: Option<usize>,
+    /// Tier for pages on the host, combined with the model's tier by `trust_merge`.
+    pub trust_tier: Option<TrustTier>,
+}
+
+/// How a host rule's tier and the model's tier for a URL combine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TrustMergePolicy {
+    /// The `[hosts]` rule decides; the model's tier applies to hosts without one.
+    #[default]
+    #[value(name = "classifier-wins")]
+    ClassifierWins,
+    /// The model decides; host rules are ignored for ordering.
+    #[value(name = "llm-wins")]
+    LlmWins,
+    /// The less trusted of the two.
+    #[value(name = "min")]
+    Min,
+    /// The more trusted of the two.
+    #[value(name = "max")]
+    Max,
+}
+
+impl UserRequest {
+    /// Most specific policy for `host`: an exact key wins over a parent-domain key.
+    pub fn host_policy(&self, host: &str) -> Option<&HostPolicy> {
+        lookup_host_policy(&self.host_policies, host)
+    }
+
+    /// Tier from the most specific `[hosts]` rule that sets one, or else the tier the
+    /// model consistently gave the host in earlier runs.
+    pub fn classify_trust_tier(
+        &self,
+        url: &Url,
+        reputation: Option<&crate::cache::ReputationStore>,
+    ) -> Option<TrustTier> {
+        let host = url.host_str()?.trim_end_matches('.').to_ascii_lowercase();
+        let mut domain = host.as_str();
+        loop {
+            if let Some(tier) = self
+                .host_policies
+                .get(domain)
+                .and_then(|policy| policy.trust_tier)
+            {
+                return Some(tier);
+            }
+            let Some((_, parent)) = domain.split_once('.') else {
+                break;
+            };
+            domain = parent;
+        }
+        reputation?.learned_tier(&host)
+    }
+
+    /// Combines the model's `llm_tier` for `url` with the host's classified tier per
+    /// `trust_merge`.
+    pub fn merge_trust_tier(
+        &self,
+        url: &Url,
+        llm_tier: TrustTier,
+        reputation: Option<&crate::cache::ReputationStore>,
+    ) -> TrustTier {
+        let Some(classified) = self.classify_trust_tier(url, reputation) else {
+            return llm_tier;
+        };
+        match self.trust_merge {
+            TrustMergePolicy::ClassifierWins => classified,
+            TrustMergePolicy::LlmWins => llm_tier,
+            // `Ord` runs from High to Low, so the less trusted tier is the larger one.
+            TrustMergePolicy::Min => classified.max(llm_tier),
+            TrustMergePolicy::Max => classified.min(llm_tier),
+        }
+    }
+
+    /// Maximum link depth for pages of `tier`; falls back to `max_depth` when unset.
+    pub fn max_depth_for(&self, tier: TrustTier) -> usize {
+        let per_tier = match tier {
+            TrustTier::High => self.max_depth_high,
+            TrustTier::Medium => self.max_depth_medium,
+            TrustTier::Low | TrustTier::Unverified => self.max_depth_low,
+        };
+        per_tier.unwrap_or(self.max_depth)
+    }
+
+    pub(crate) fn deepest_max_depth(&self) -> usize {
+        TrustTier::ALL
+            .into_iter()
+            .map(|tier| self.max_depth_for(tier))
+            .max()
+            .unwrap_or(self.max_depth)
+    }
+
+    /// The limits of the `seed_urls` entry with `url`'s canonical form, if any.
+    pub(crate) fn seed_limits(&self, url: &Url) -> SeedLimits {
+        let key = normalize_url(url);
+        self.seed_urls
+            .iter()
+            .find(|seed| normalize_url(&seed.url) == key)
+            .map(|seed| seed.limits)
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn max_considered_candidates(&self) -> usize {
+        self.max_child_candidates
+            .saturating_mul(self.max_candidate_batches.max(1))
+    }
+}
+
+/// What to do when the web search call fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchFailurePolicy {
+    /// Return [`CrawlError::SearchFailed`] (or the underlying typed error).
+    #[default]
+    #[value(name = "abort")]
+    Abort,
+    /// Crawl the seed URLs only and explain the failure in the report.
+    #[value(name = "continue")]
+    Continue,
+}
+
+/// What to do when link selection fails for a page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SelectionFailurePolicy {
+    /// Pick children heuristically (same host, with anchor text) and keep crawling.
+    #[default]
+    #[value(name = "heuristic")]
+    Heuristic,
+    /// Enqueue no children from that page and keep crawling.
+    #[value(name = "skip")]
+    Skip,
+    /// Return [`CrawlError::SelectionFailed`].
+    #[value(name = "abort")]
+    Abort,
+}
+
+/// What to do with a page that hides its article behind a paywall.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PaywallPolicy {
+    /// Keep the page as extracted and mark the source as paywalled.
+    #[default]
+    #[value(name = "flag")]
+    Flag,
+    /// Keep only the text outside the gated parts and mark the source as paywalled.
+    #[value(name = "preview")]
+    Preview,
+    /// Drop the page; its links are not followed.
+    #[value(name = "skip")]
+    Skip,
+}
+
+/// Which `noindex` / `nofollow` directives of `X-Robots-Tag` headers and robots `<meta>`
+/// tags the crawl honors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RobotsTagPolicy {
+    /// `noindex` pages are not kept as sources and `nofollow` pages' links are not followed.
+    #[default]
+    #[value(name = "respect")]
+    Respect,
+    /// Only `noindex` is honored.
+    #[value(name = "noindex")]
+    Noindex,
+    /// Only `nofollow` is honored.
+    #[value(name = "nofollow")]
+    Nofollow,
+    /// Neither is honored.
+    #[value(name = "ignore")]
+    Ignore,
+}
+
+/// What to do with a page whose content lives in a same-origin `<iframe>` or `<frame>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FramePolicy {
+    /// Keep the page as extracted, frames left out.
+    #[default]
+    #[value(name = "ignore")]
+    Ignore,
+    /// Fetch the frame source in place of a page with no content of its own.
+    #[value(name = "follow")]
+    Follow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourcesFormat {
+    #[default]
+    #[value(name = "list")]
+    List,
+    #[value(name = "table")]
+    Table,
+}
+
+/// How the Findings section orders sources.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FindingsLayout {
+    /// One section per source, in collection order.
+    #[default]
+    #[value(name = "flat")]
+    Flat,
+    /// Sources grouped under subtopics chosen by the LLM after the crawl.
+    #[value(name = "subtopics")]
+    Subtopics,
+}
+
+/// Output of the `spider` command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    #[value(name = "markdown")]
+    Markdown,
+    /// One JSON document with every source, notes, failures, and stats.
+    #[value(name = "json")]
+    Json,
 }
 
 #[derive(Debug, Clone)]
 pub struct Source {
     pub url: Url,
     pub trust_tier: TrustTier,
+    pub title: Option<String>,
+    pub published: Option<String>,
+    /// SPDX identifier or expression of the license the page declares.
+    pub license: Option<String>,
+    /// The page showed paywall markers; `content` may be a teaser or free preview.
+    pub paywalled: bool,
+    /// How `content` compares with the last run; `None` without a cache directory.
+    pub change: Option<ContentChange>,
+    pub depth: usize,
+    /// Minimum interval applied to this source's host (default, config, or `robots.txt`).
+    pub politeness_delay: Duration,
+    pub outline: Vec<Heading>,
     pub excerpt: String,
+    /// Extracted text of this page followed by any pages reached through pagination.
     pub content: String,
+    /// Pagination pages whose text was appended to `content`, in order.
+    pub continuation_urls: Vec<Url>,
+    /// The page this one was selected from; `None` for seeds and search hits.
+    pub parent: Option<Url>,
+    /// 1-based position among all successful fetches of the crawl.
+    pub fetch_order: usize,
+    pub http: HttpMetadata,
 }
 
-#[derive(Debug)]
+/// A source's content compared with the run before, by hash of its extracted text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentChange {
+    /// Not collected in any earlier run with this cache directory.
+    New,
+    Changed,
+    Unchanged,
+}
+
+impl ContentChange {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::New => "new",
+            Self::Changed => "changed",
+            Self::Unchanged => "unchanged",
+        }
+    }
+}
+
+/// How the page behind a [`Source`] was fetched (its first page, not pagination).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HttpMetadata {
+    /// `304` when a cached copy was revalidated; `None` when the fetcher does not report it.
+    pub status: Option<u16>,
+    /// URL after redirects.
+    pub final_url: Option<Url>,
+    pub content_type: Option<String>,
+    /// `Content-Length` header as sent (the encoded size for compressed bodies).
+    pub content_length: Option<u64>,
+    /// Decompressed body bytes downloaded; zero for a cached `304`.
+    pub bytes: u64,
+    /// `Server-Timing` header as sent.
+    pub server_timing: Option<String>,
+    /// Wall time of the fetch, including a bot-challenge retry.
+    pub fetch_duration: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heading {
+    pub level: u8,
+    pub text: String,
+}
+
+#[derive(Debug, Default)]
 pub struct CrawlResult {
+    /// Intent the crawl's defaults were adjusted for, given or classified.
+    pub intent: Option<QueryIntent>,
     pub sources: Vec<Source>,
+    /// Pages that would have been sources but whose host already had
+    /// `max_sources_per_host` of them, in fetch order. Not part of the findings, and
+    /// not counted toward `max_pages`, but graded and analyzed after `sources`: see
+    /// [`CrawlResult::analysis_sources`].
+    pub overflow_sources: Vec<Source>,
+    /// Sources removed by `grade_sources`, in fetch order, rendered in the
+    /// `## Dropped sources` appendix.
+    pub dropped_sources: Vec<DroppedSource>,
+    pub stats: CrawlStats,
+    /// Diagnoses rendered under `## Notes` (search failures, missing seeds).
+    pub notes: Vec<String>,
+    /// Per-page failures the crawl recovered from, in the order they happened.
+    pub failures: Vec<CrawlFailure>,
+    /// Hosts skipped after answering `403` / `429` `host_failure_limit` times in a row.
+    pub refused_hosts: Vec<RefusedHost>,
+    /// Hosts skipped after answering one of `exclusion_statuses`.
+    pub excluded_hosts: Vec<ExcludedHost>,
+    /// Successfully fetched URLs in fetch order, pagination pages included.
+    pub fetched: Vec<Url>,
+    /// URLs the crawl came across but did not fetch or keep, in the order they were skipped.
+    pub skipped: Vec<SkippedUrl>,
+    /// Link selections in the order they were made, for `--selection-log`.
+    pub selections: Vec<SelectionRecord>,
+    /// Subtopic groups for [`FindingsLayout::Subtopics`]; `sources` are indices into
+    /// [`CrawlResult::sources`] and every source is in exactly one group. Empty renders
+    /// the flat layout.
+    pub subtopics: Vec<Subtopic>,
+    /// Per-alternative coverage in `--compare` mode, in `compare` order.
+    pub comparison: Vec<AlternativeCoverage>,
+    /// Follow-up queries suggested after the crawl (`follow_ups`), rendered under `## Next steps`.
+    pub follow_ups: Vec<String>,
+    /// Terms found in at least two sources (`key_terms`), rendered under `## Key terms`;
+    /// `counts` index [`CrawlResult::analysis_sources`].
+    pub key_terms: Vec<KeyTerm>,
+    /// Dated events in chronological order (`timeline`); `sources` index
+    /// [`CrawlResult::analysis_sources`].
+    pub timeline: Vec<TimelineEvent>,
+    /// Answer drawn from the sources (`answer`), rendered under `## Answer`; its
+    /// `sources` index [`CrawlResult::analysis_sources`].
+    pub answer: Option<Answer>,
+}
+
+impl CrawlResult {
+    /// The post-crawl analysis input: `sources`, then `overflow_sources`. The source
+    /// indices of `key_terms`, `timeline`, and `answer` point into it.
+    pub fn analysis_sources(&self) -> Vec<&Source> {
+        self.sources.iter().chain(&self.overflow_sources).collect()
+    }
+}
+
+/// A collected source the LLM graded as not useful for the query.
+#[derive(Debug, Clone)]
+pub struct DroppedSource {
+    pub source: Source,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct CrawlFailure {
+    pub url: Url,
+    pub stage: FailureStage,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureStage {
+    Fetch,
+    Extract,
+    Selection,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CrawlStats {
+    /// Successful fetches, including cache revalidations.
+    pub pages_fetched: usize,
+    /// Body bytes downloaded (a `304 Not Modified` counts as zero).
+    pub bytes_downloaded: u64,
+    pub bytes_by_host: BTreeMap<String, u64>,
+    /// Set when `max_total_bytes` stopped the crawl.
+    pub download_budget_exhausted: bool,
+    /// OpenAI requests issued (web search plus link selection).
+    pub llm_calls: usize,
+    pub frontier_peak_len: usize,
+    pub frontier_peak_bytes: usize,
+    pub frontier_evicted: usize,
+    pub visited_len: usize,
+    pub visited_bytes: usize,
+    pub visited_evicted: usize,
+    /// Pages served from the page cache after a `304 Not Modified`.
+    pub cache_not_modified: usize,
+    /// Selection batches answered from the selection cache instead of the LLM.
+    pub selection_cache_hits: usize,
+    /// Link-index pages expanded without being counted as sources.
+    pub hub_pages: usize,
+    /// Pagination pages appended to their source (also counted in `pages_fetched`).
+    pub pagination_pages: usize,
+    /// `hreflang` variants skipped because another language of the page was collected.
+    pub language_variants_skipped: usize,
+    /// Pages with paywall markers, whatever `paywall_policy` did with them.
+    pub paywalled_pages: usize,
+    /// Pages not kept as sources because of an honored `noindex`.
+    pub noindex_pages: usize,
+    /// Pages whose links were not followed because of an honored `nofollow`.
+    pub nofollow_pages: usize,
+    /// Pages another query of the batch had already fetched (not counted in
+    /// `pages_fetched`).
+    pub shared_fetches: usize,
+    /// Links dropped before selection or queueing because a URL with the same
+    /// canonical form was already visited or queued.
+    pub duplicate_links_skipped: usize,
+    /// Connection reuse reported by the fetcher, when it pools connections.
+    pub connections: Option<ConnectionStats>,
+    /// Pacing applied to each requested host, crawl-wide in a batch.
+    pub host_politeness: Vec<HostPoliteness>,
+}
+
+impl CrawlStats {
+    fn record_fetch(&mut self, url: &Url, bytes: u64, not_modified: bool) {
+        self.pages_fetched += 1;
+        self.cache_not_modified += usize::from(not_modified);
+        self.bytes_downloaded += bytes;
+        if let Some(host) = url.host_str() {
+            *self
+                .bytes_by_host
+                .entry(host.to_ascii_lowercase())
+                .or_default() += bytes;
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ExtractedPage {
+    title: Option<String>,
+    published: Option<String>,
+    license: Option<String>,
+    paywall: Option<Paywall>,
+    /// Robots `<meta>` directives (the `X-Robots-Tag` header is not part of the HTML).
+    robots_tags: RobotsTags,
+    outline: Vec<Heading>,
+    excerpt: String,
+    text: String,
+    anchor_text_by_url: HashMap<String, String>,
+    /// `hreflang` language variants of this page.
+    alternates: Vec<(String, Url)>,
+    /// Links inside the content root.
+    link_count: usize,
+    /// Share of the content root's visible characters that sit inside links.
+    link_density: f64,
+}
+
+impl ExtractedPage {
+    fn is_hub(&self) -> bool {
+        self.link_count >= HUB_MIN_LINKS && self.link_density >= HUB_MIN_LINK_DENSITY
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -45,45 +695,60 @@ struct LinkCandidate {
 const MIN_HOST_INTERVAL: Duration = Duration::from_millis(150);
 const MAX_EXCERPT_RAW_BYTES: usize = 32 * 1024;
 const MAX_EXCERPT_CHARS: usize = 600;
+const HUB_MIN_LINKS: usize = 15;
+const HUB_MIN_LINK_DENSITY: f64 = 0.5;
+const NON_CONTENT_SELECTOR: &str = "script, style, noscript, template, svg, iframe";
+const MAX_TEXT_BYTES: usize = 64 * 1024;
+const MAX_TABLE_ROWS: usize = 50;
+const MAX_TABLE_COLUMNS: usize = 12;
+const MAX_TABLE_CELL_CHARS: usize = 200;
+const MAX_OUTLINE_HEADINGS: usize = 24;
+const MAX_HEADING_CHARS: usize = 120;
+const MAX_SUBTOPICS: usize = 8;
+const MAX_FOLLOW_UPS: usize = 5;
+const MAX_ANSWER_PARAGRAPHS: usize = 2;
+/// Most recent sources listed to link selection as already collected.
+const MAX_COLLECTED_IN_SELECTION: usize = 30;
+/// Headings per collected source given to link selection as its topics.
+const MAX_COLLECTED_TOPICS: usize = 5;
+const MAX_KEY_TERMS: usize = 12;
+const MAX_TIMELINE_EVENTS: usize = 20;
 const MAX_RESPONSE_BYTES: usize = 1024 * 1024;
 const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
 const USER_AGENT: &str = "llm-spider/0.1 (respectful; contact: unknown)";
-
-#[derive(Default)]
-struct Frontier {
-    high: VecDeque<(Url, usize)>,
-    medium: VecDeque<(Url, usize)>,
-    low: VecDeque<(Url, usize)>,
-}
-
-impl Frontier {
-    fn push(&mut self, url: Url, depth: usize, tier: TrustTier) {
-        match tier {
-            TrustTier::High => self.high.push_back((url, depth)),
-            TrustTier::Medium => self.medium.push_back((url, depth)),
-            TrustTier::Low => self.low.push_back((url, depth)),
-        }
-    }
-
-    fn pop(&mut self) -> Option<(Url, usize, TrustTier)> {
-        if let Some((url, depth)) = self.high.pop_front() {
-            return Some((url, depth, TrustTier::High));
-        }
-        if let Some((url, depth)) = self.medium.pop_front() {
-            return Some((url, depth, TrustTier::Medium));
-        }
-        if let Some((url, depth)) = self.low.pop_front() {
-            return Some((url, depth, TrustTier::Low));
-        }
-        None
+/// Request header carrying the run's `--crawl-id`.
+const CRAWL_ID_HEADER: &str = "x-crawl-id";
+
+/// `USER_AGENT` with the crawl id inside its comment, so the product token `robots.txt`
+/// groups match stays the same.
+fn user_agent(crawl_id: Option<&str>) -> String {
+    match crawl_id {
+        Some(id) => format!(
+            "{}; crawl-id: {id})",
+            USER_AGENT.strip_suffix(')').unwrap_or(USER_AGENT)
+        ),
+        None => USER_AGENT.to_owned(),
     }
 }
 
 pub fn crawl(
     request: &UserRequest,
     openai: &dyn crate::openai::OpenAiApi,
-) -> anyhow::Result<CrawlResult> {
-    let fetcher = SpiderPageFetcher::new().context("init spider page fetcher")?;
+) -> Result<CrawlResult, CrawlError> {
+    let robots_cache = request
+        .cache_dir
+        .as_deref()
+        .map(crate::cache::RobotsCache::open)
+        .transpose()
+        .map_err(CrawlError::Cache)?;
+    let fetcher = SpiderPageFetcher::new(
+        request.audit_log.clone(),
+        robots_cache,
+        request.cancellation.clone(),
+        request.crawl_id.as_deref(),
+    )
+    .map_err(CrawlError::Init)?
+    .with_opt_outs(request.honor_opt_outs);
     crawl_with_fetcher(request, openai, &fetcher)
 }
 
@@ -91,118 +756,788 @@ pub fn crawl_with_fetcher(
     request: &UserRequest,
     openai: &dyn crate::openai::OpenAiApi,
     fetcher: &dyn PageFetcher,
-) -> anyhow::Result<CrawlResult> {
-    let started_at = Instant::now();
-    let hits = openai
-        .web_search(&request.query, request.search_limit)
-        .context("web search")?;
+) -> Result<CrawlResult, CrawlError> {
+    let mut results = crawl_batch_with_fetcher(std::slice::from_ref(request), openai, fetcher)?;
+    Ok(results.remove(0))
+}
 
-    let mut frontier = Frontier::default();
-    for hit in hits {
-        frontier.push(hit.url, 0usize, hit.trust_tier);
+/// Crawls for every request of a batch (`--batch`) in one interleaved run.
+pub fn crawl_batch(
+    requests: &[UserRequest],
+    openai: &dyn crate::openai::OpenAiApi,
+) -> Result<Vec<CrawlResult>, CrawlError> {
+    let Some(request) = requests.first() else {
+        return Ok(Vec::new());
+    };
+    let robots_cache = request
+        .cache_dir
+        .as_deref()
+        .map(crate::cache::RobotsCache::open)
+        .transpose()
+        .map_err(CrawlError::Cache)?;
+    let fetcher = SpiderPageFetcher::new(
+        request.audit_log.clone(),
+        robots_cache,
+        request.cancellation.clone(),
+        request.crawl_id.as_deref(),
+    )
+    .map_err(CrawlError::Init)?
+    .with_opt_outs(request.honor_opt_outs);
+    crawl_batch_with_fetcher(requests, openai, &fetcher)
+}
+
+/// Crawls for every request at once, one result per request.
+///
+/// Each query keeps its own frontier, selection, and report. The budgets, politeness,
+/// caches, and archive of the first request cover the whole batch: `max_pages` counts
+/// the pages fetched for any query. The query with the fewest sources goes next, so the
+/// budget splits evenly until a query runs out of links. A page one query fetched is
+/// reused by the others without a second request.
+pub fn crawl_batch_with_fetcher(
+    requests: &[UserRequest],
+    openai: &dyn crate::openai::OpenAiApi,
+    fetcher: &dyn PageFetcher,
+) -> Result<Vec<CrawlResult>, CrawlError> {
+    let Some(request) = requests.first() else {
+        return Ok(Vec::new());
+    };
+    let started_at = Instant::now();
+    let progress = Progress::new(request, started_at);
+
+    // The intent adjusts a request's defaults, so it is settled before its search.
+    let mut adjusted = Vec::with_capacity(requests.len());
+    let mut intents = Vec::with_capacity(requests.len());
+    for request in requests {
+        check_cancelled(request)?;
+        let (request, outcome) = intent::resolve(request, openai);
+        adjusted.push(request);
+        intents.push(outcome);
+    }
+    let requests = adjusted.as_slice();
+    let request = &requests[0];
+
+    let reputation = request
+        .cache_dir
+        .as_deref()
+        .map(crate::cache::ReputationStore::open)
+        .transpose()
+        .map_err(CrawlError::Cache)?;
+    let content_store = request
+        .cache_dir
+        .as_deref()
+        .map(crate::cache::ContentStore::open)
+        .transpose()
+        .map_err(CrawlError::Cache)?;
+    let mut lanes = Vec::with_capacity(requests.len());
+    for (request, intent) in requests.iter().zip(intents) {
+        let mut stats = CrawlStats::default();
+        stats.llm_calls += usize::from(intent.classified);
+        let mut notes = intent.note.into_iter().collect::<Vec<_>>();
+        check_cancelled(request)?;
+        stats.llm_calls += search_queries(request).len();
+        let seeds = collect_seeds(request, openai, &mut notes)?;
+
+        let mut frontier = Frontier::new(request.max_frontier);
+        let mut skipped = Vec::new();
+        let mut seed_titles = HashMap::<String, String>::new();
+        for Seed { hit: seed, rank } in seeds {
+            if let Some(title) = seed.title {
+                seed_titles.insert(normalize_url(&seed.url), title);
+            }
+            let trust_tier =
+                request.merge_trust_tier(&seed.url, seed.trust_tier, reputation.as_ref());
+            if let Some(reputation) = &reputation
+                && !request
+                    .seed_urls
+                    .iter()
+                    .any(|seed_url| seed_url.url == seed.url)
+            {
+                reputation.record(&seed.url, seed.trust_tier);
+            }
+            push_frontier(
+                &mut frontier,
+                FrontierEntry {
+                    limits: request.seed_limits(&seed.url),
+                    url: seed.url,
+                    depth: 0,
+                    tier: trust_tier,
+                    rank: Some(rank),
+                },
+                &mut skipped,
+            );
+        }
+        let selector = LinkSelector {
+            request,
+            openai,
+            cache: request
+                .cache_dir
+                .as_deref()
+                .map(crate::cache::SelectionCache::open)
+                .transpose()
+                .map_err(CrawlError::Cache)?,
+            reputation: reputation.as_ref(),
+        };
+        lanes.push(Lane {
+            request,
+            intent: intent.intent,
+            selector,
+            frontier,
+            visited: VisitedSet::new(request.max_visited),
+            discovered_from: HashMap::new(),
+            seed_titles,
+            language_variants: HashSet::new(),
+            sources: Vec::new(),
+            overflow_sources: Vec::new(),
+            stats,
+            notes,
+            failures: Vec::new(),
+            fetched: Vec::new(),
+            skipped,
+            selections: Vec::new(),
+            done: false,
+        });
     }
+    if let Some(review) = &request.plan_review {
+        let plans = lanes
+            .iter()
+            .map(|lane| {
+                plan_frontier(
+                    lane.request,
+                    lane.frontier.clone(),
+                    |url| lane.seed_titles.get(&normalize_url(url)).cloned(),
+                    lane.notes.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        if !review.review(requests, &plans) {
+            return Err(CrawlError::PlanDeclined);
+        }
+        check_cancelled(request)?;
+    }
+    let crawl_started_at = Instant::now();
+
+    let default_schedule;
+    let schedule: &dyn SchedulePolicy = match &request.schedule {
+        Some(schedule) => schedule.as_ref(),
+        None => {
+            default_schedule =
+                FixedIntervalSchedule::new(request.host_policies.clone(), request.challenge_retry);
+            &default_schedule
+        }
+    };
+    let mut politeness = Politeness::new(
+        &request.host_policies,
+        schedule,
+        request.cancellation.as_ref(),
+        request.audit_log.as_deref(),
+    );
+    let mut breaker = HostBreaker::new(request.host_failure_limit, &request.exclusion_statuses);
+    let page_cache = request
+        .cache_dir
+        .as_deref()
+        .map(crate::cache::PageCache::open)
+        .transpose()
+        .map_err(CrawlError::Cache)?;
+    let mut warc = request
+        .warc
+        .as_deref()
+        .map(|path| WarcWriter::create(path, request.crawl_id.as_deref()))
+        .transpose()
+        .map_err(CrawlError::Init)?;
+    // Fetch outcomes by normalized URL, for the other queries of a batch. A single
+    // query never fetches a URL twice, so it keeps none.
+    let mut shared_fetches = (lanes.len() > 1).then(HashMap::<String, SharedFetch>::new);
+    let mut control = request.control_file.as_deref().map(ControlFile::new);
+    let mut banned = HashSet::<String>::new();
+    // Sources fetched for any query; reused ones are free.
+    let mut collected = 0;
+
+    while collected < request.max_pages {
+        check_cancelled(request)?;
+        if let Some(control) = &mut control {
+            for command in control.poll() {
+                apply_control(command, &mut lanes, &mut banned);
+            }
+        }
 
-    let mut visited = HashSet::<String>::new();
-    let mut sources = Vec::<Source>::new();
-    let mut last_request_by_host = HashMap::<String, Instant>::new();
-    let mut min_interval_by_host = HashMap::<String, Duration>::new();
+        // Deterministic runs must not stop at a wall-clock dependent point.
+        let over_budget = match request.crawl_budget {
+            Some(budget) => crawl_started_at.elapsed() > budget,
+            None => started_at.elapsed() > request.max_elapsed,
+        };
+        if !request.deterministic && over_budget {
+            break;
+        }
 
-    while sources.len() < request.max_pages {
-        if started_at.elapsed() > request.max_elapsed {
+        let bytes_downloaded = lanes
+            .iter()
+            .map(|lane| lane.stats.bytes_downloaded)
+            .sum::<u64>();
+        if request.max_total_bytes > 0 && bytes_downloaded >= request.max_total_bytes {
+            info!(
+                bytes_downloaded,
+                max_total_bytes = request.max_total_bytes,
+                "download budget exhausted; stopping"
+            );
+            for lane in &mut lanes {
+                lane.stats.download_budget_exhausted = true;
+            }
             break;
         }
 
-        let Some((url, depth, trust_tier)) = frontier.pop() else {
+        let Some(lane) = lanes
+            .iter_mut()
+            .filter(|lane| !lane.done)
+            .min_by_key(|lane| lane.sources.len())
+        else {
             break;
         };
+        // The byte budget covers the whole batch, as checked above.
+        let max_total_bytes = request.max_total_bytes;
+        let request = lane.request;
+        let Lane {
+            selector,
+            frontier,
+            visited,
+            discovered_from,
+            seed_titles,
+            language_variants,
+            sources,
+            overflow_sources,
+            stats,
+            notes,
+            failures,
+            fetched,
+            skipped,
+            selections,
+            done,
+            ..
+        } = lane;
+        let other_lanes_bytes = bytes_downloaded - stats.bytes_downloaded;
+        let Some(FrontierEntry {
+            url,
+            depth,
+            tier: trust_tier,
+            limits,
+            rank,
+        }) = frontier.pop()
+        else {
+            *done = true;
+            continue;
+        };
+        if request.dns_prefetch > 0 {
+            let upcoming = frontier
+                .peek(request.dns_prefetch)
+                .cloned()
+                .collect::<Vec<_>>();
+            if !upcoming.is_empty() {
+                fetcher.prefetch_dns(&upcoming);
+            }
+        }
 
         let normalized = normalize_url(&url);
-        if !visited.insert(normalized) {
+        let parent = discovered_from.remove(&normalized);
+        if !visited.insert(normalized.clone()) {
             continue;
         }
-
-        if !is_allowed(&url, request.allow_local) {
+        let shared = shared_fetches
+            .as_ref()
+            .and_then(|fetches| fetches.get(&normalized))
+            .cloned();
+
+        let skip_reason = if language_variants.contains(&normalize_url(&url)) {
+            debug!(url = %url, "language variant of a collected page; skipping");
+            stats.language_variants_skipped += 1;
+            Some(SkipReason::LanguageVariant)
+        } else {
+            fetch_skip_reason(
+                &url,
+                request.allow_local,
+                &banned,
+                &breaker,
+                &politeness,
+                shared.is_some(),
+            )
+        };
+        if let Some(reason) = skip_reason {
+            skipped.push(SkippedUrl { url, reason });
             continue;
         }
+        progress.update(&ProgressSnapshot {
+            pages: collected,
+            frontier_len: frontier.len(),
+            llm_calls: stats.llm_calls,
+            current: Some(&url),
+        });
 
-        if let Some(host) = url
-            .host_str()
-            .map(str::to_ascii_lowercase)
-            .filter(|host| !host.is_empty())
-        {
-            let min_interval = min_interval_by_host
-                .get(&host)
-                .copied()
-                .unwrap_or(MIN_HOST_INTERVAL);
-            if let Some(last) = last_request_by_host.get(&host) {
-                let elapsed = last.elapsed();
-                if elapsed < min_interval {
-                    std::thread::sleep(min_interval - elapsed);
+        let reused = shared.is_some();
+        let outcome = match shared {
+            Some(outcome) => {
+                debug!(url = %url, "fetched for another query; reusing");
+                stats.shared_fetches += 1;
+                outcome
+            }
+            None => {
+                politeness.wait_turn(&url);
+                check_cancelled(request)?;
+                let fetch_started = Instant::now();
+                let fetched_page = fetch_page(
+                    fetcher,
+                    page_cache.as_ref(),
+                    &url,
+                    schedule,
+                    request.cancellation.as_ref(),
+                    &mut |target| {
+                        admit_redirect(target, request, &banned, &breaker, &mut politeness)
+                    },
+                );
+                let outcome = match fetched_page {
+                    Ok((scraped, not_modified)) => {
+                        stats.record_fetch(&url, scraped.bytes, not_modified);
+                        archive_fetch(warc.as_mut(), &url, &scraped, not_modified);
+                        breaker.record_success(&url);
+                        Ok((scraped, fetch_started.elapsed()))
+                    }
+                    Err(err) => {
+                        if let Some(CrawlError::Cancelled) = err.downcast_ref::<CrawlError>() {
+                            return Err(CrawlError::Cancelled);
+                        }
+                        if let Some(trip) = breaker.record_failure(&url, &err) {
+                            report_trip(&url, trip, request.host_failure_limit, &err, notes);
+                        }
+                        match err.downcast_ref::<CrawlError>() {
+                            Some(
+                                CrawlError::RobotsBlocked { .. }
+                                | CrawlError::OptedOut { .. }
+                                | CrawlError::NonTextual { .. },
+                            ) => {
+                                debug!(url = %url, "skipping: {err:#}");
+                            }
+                            _ => warn!(url = %url, "spider fetch failed; skipping: {err:#}"),
+                        }
+                        Err((SkipReason::for_fetch_error(&err), format!("{err:#}")))
+                    }
+                };
+                if let Some(fetches) = shared_fetches.as_mut() {
+                    fetches.insert(normalized, outcome.clone());
+                }
+                outcome
+            }
+        };
+        let (mut scraped, fetch_duration) = match outcome {
+            Ok(fetch) => {
+                fetched.push(url.clone());
+                fetch
+            }
+            Err((reason, message)) => {
+                skipped.push(SkippedUrl {
+                    url: url.clone(),
+                    reason,
+                });
+                failures.push(CrawlFailure {
+                    url: url.clone(),
+                    stage: FailureStage::Fetch,
+                    message,
+                });
+                continue;
+            }
+        };
+
+        // Follow `<meta http-equiv="refresh">` interstitials like HTTP redirects, and
+        // with `FramePolicy::Follow` frame shells into their frame: the source keeps
+        // `url` and records the target as its final URL.
+        let mut refresh_hops = 0;
+        let mut refreshed_to_visited = false;
+        while let Some(target) = {
+            let base = scraped.final_url.as_ref().unwrap_or(&url);
+            meta_refresh_target(base, &scraped.html).or_else(|| {
+                (request.frame_policy == FramePolicy::Follow)
+                    .then(|| frame_target(base, &scraped.html))
+                    .flatten()
+            })
+        } {
+            if refresh_hops == MAX_META_REFRESH_HOPS {
+                debug!(url = %url, "too many meta refresh or frame hops; keeping the last page");
+                break;
+            }
+            refresh_hops += 1;
+            if max_total_bytes > 0 && other_lanes_bytes + stats.bytes_downloaded >= max_total_bytes
+            {
+                debug!(url = %url, target = %target, "download budget exhausted; keeping the page");
+                break;
+            }
+            if let Some(reason) = fetch_skip_reason(
+                &target,
+                request.allow_local,
+                &banned,
+                &breaker,
+                &politeness,
+                false,
+            ) {
+                skipped.push(SkippedUrl {
+                    url: target,
+                    reason,
+                });
+                break;
+            }
+            if !visited.insert(normalize_url(&target)) {
+                debug!(url = %url, target = %target, "redirect to a visited page; dropping");
+                refreshed_to_visited = true;
+                break;
+            }
+            debug!(url = %url, target = %target, "following meta refresh or frame");
+            politeness.wait_turn(&target);
+            check_cancelled(request)?;
+            let followed = fetch_page(
+                fetcher,
+                page_cache.as_ref(),
+                &target,
+                schedule,
+                request.cancellation.as_ref(),
+                &mut |target| admit_redirect(target, request, &banned, &breaker, &mut politeness),
+            );
+            match followed {
+                Ok((next, not_modified)) => {
+                    stats.record_fetch(&target, next.bytes, not_modified);
+                    archive_fetch(warc.as_mut(), &target, &next, not_modified);
+                    breaker.record_success(&target);
+                    fetched.push(target.clone());
+                    scraped = FetchedPage {
+                        final_url: Some(next.final_url.clone().unwrap_or(target)),
+                        ..next
+                    };
+                }
+                Err(err) => {
+                    if let Some(CrawlError::Cancelled) = err.downcast_ref::<CrawlError>() {
+                        return Err(CrawlError::Cancelled);
+                    }
+                    if let Some(trip) = breaker.record_failure(&target, &err) {
+                        report_trip(&target, trip, request.host_failure_limit, &err, notes);
+                    }
+                    warn!(url = %target, "meta refresh or frame fetch failed: {err:#}");
+                    skipped.push(SkippedUrl {
+                        url: target.clone(),
+                        reason: SkipReason::for_fetch_error(&err),
+                    });
+                    failures.push(CrawlFailure {
+                        url: target,
+                        stage: FailureStage::Fetch,
+                        message: format!("{err:#}"),
+                    });
+                    break;
                 }
             }
-            last_request_by_host.insert(host, Instant::now());
         }
+        if refreshed_to_visited {
+            continue;
+        }
+
+        let fetch_order = stats.pages_fetched + stats.shared_fetches;
+        let http = HttpMetadata {
+            status: scraped.status,
+            final_url: scraped.final_url.clone(),
+            content_type: scraped.content_type.clone(),
+            content_length: scraped.content_length,
+            bytes: scraped.bytes,
+            server_timing: scraped.server_timing.clone(),
+            fetch_duration,
+        };
+        politeness.record_robots(&url, scraped.robots_delay, scraped.robots_max_concurrent);
 
-        let scraped = match fetcher.fetch(&url) {
-            Ok(scraped) => scraped,
+        let page = match extract_page(&url, &scraped.html) {
+            Ok(ok) => ok,
             Err(err) => {
-                warn!(url = %url, "spider fetch failed; skipping: {err:#}");
+                warn!(url = %url, "extract failed; skipping: {err:#}");
+                skipped.push(SkippedUrl {
+                    url: url.clone(),
+                    reason: SkipReason::ExtractFailed,
+                });
+                failures.push(CrawlFailure {
+                    url: url.clone(),
+                    stage: FailureStage::Extract,
+                    message: format!("{err:#}"),
+                });
                 continue;
             }
         };
 
-        if let Some(host) = url
-            .host_str()
-            .map(str::to_ascii_lowercase)
-            .filter(|host| !host.is_empty())
-        {
-            let current = min_interval_by_host.get(&host).copied().unwrap_or_default();
-            let updated = current.max(scraped.robots_delay).max(MIN_HOST_INTERVAL);
-            min_interval_by_host.insert(host, updated);
-        };
-
-        let (excerpt, anchor_text_by_url) =
-            match extract_excerpt_and_anchor_map(&url, &scraped.html) {
-                Ok(ok) => ok,
-                Err(err) => {
-                    warn!(url = %url, "extract failed; skipping: {err:#}");
-                    continue;
+        // Language variants of one page share a single source slot.
+        if !page.alternates.is_empty() {
+            let page_key = normalize_url(&url);
+            let preferred = request.preferred_language.as_deref().and_then(|preferred| {
+                page.alternates
+                    .iter()
+                    .map(|(lang, alternate)| (lang, alternate, normalize_url(alternate)))
+                    .find(|(lang, _, key)| {
+                        language_matches(lang, preferred)
+                            && *key != page_key
+                            && !visited.contains(key)
+                    })
+            });
+            let switch_to = preferred.and_then(|(_, alternate, key)| {
+                push_frontier(
+                    frontier,
+                    FrontierEntry {
+                        url: alternate.clone(),
+                        depth,
+                        tier: trust_tier,
+                        limits,
+                        rank,
+                    },
+                    skipped,
+                )
+                .then_some(key)
+            });
+            if let (Some(key), Some(parent)) = (&switch_to, &parent) {
+                discovered_from.insert(key.clone(), parent.clone());
+            }
+            for (_, alternate) in &page.alternates {
+                let key = normalize_url(alternate);
+                if key != page_key && Some(&key) != switch_to.as_ref() {
+                    language_variants.insert(key);
                 }
-            };
+            }
+            if switch_to.is_some() {
+                debug!(url = %url, "switching to the preferred language variant");
+                stats.language_variants_skipped += 1;
+                continue;
+            }
+        }
 
-        let content = extract_readable_content(&scraped.html)
-            .filter(|md| !md.trim().is_empty())
-            .unwrap_or_else(|| {
-                warn!(url = %url, "readability extraction failed; falling back to excerpt");
-                excerpt.clone()
-            });
+        let robots_tags = scraped
+            .x_robots_tag
+            .as_deref()
+            .map(RobotsTags::parse_header)
+            .unwrap_or_default()
+            .union(page.robots_tags)
+            .honored(request.robots_tags);
+        if robots_tags.nofollow {
+            stats.nofollow_pages += 1;
+        }
 
-        sources.push(Source {
-            url: url.clone(),
-            trust_tier,
+        // Link indexes are for finding sources, not sources themselves.
+        let hub = request.max_children_per_hub > 0 && page.is_hub();
+        let paywall = page.paywall.clone().filter(|_| !hub);
+        if paywall.is_some() {
+            stats.paywalled_pages += 1;
+            if request.paywall_policy == PaywallPolicy::Skip {
+                debug!(url = %url, "paywalled page; skipping");
+                skipped.push(SkippedUrl {
+                    url: url.clone(),
+                    reason: SkipReason::Paywalled,
+                });
+                continue;
+            }
+        }
+        let ExtractedPage {
+            title,
+            published,
+            license,
+            outline,
             excerpt,
-            content,
-        });
+            text,
+            anchor_text_by_url,
+            ..
+        } = page;
+        let title = title.or_else(|| seed_titles.remove(&normalize_url(&url)));
+        let page_excerpt = excerpt.clone();
+        let mut links = scraped.links;
+        if hub {
+            debug!(url = %url, "hub page; expanding without counting it as a source");
+            stats.hub_pages += 1;
+        } else if robots_tags.noindex {
+            debug!(url = %url, "noindex page; not keeping it as a source");
+            stats.noindex_pages += 1;
+            skipped.push(SkippedUrl {
+                url: url.clone(),
+                reason: SkipReason::Noindex,
+            });
+        } else {
+            // With `preview`, the source is built from the page minus its gated parts.
+            let preview = paywall
+                .as_ref()
+                .filter(|_| request.paywall_policy == PaywallPolicy::Preview)
+                .map(|paywall| paywall.strip_gated(&scraped.html));
+            let (excerpt, text) = match &preview {
+                Some(preview) => extract_page(&url, preview)
+                    .map(|page| (page.excerpt, page.text))
+                    .unwrap_or_default(),
+                None => (excerpt, text),
+            };
+            let fallback = if text.is_empty() {
+                excerpt.clone()
+            } else {
+                text
+            };
+            let mut content =
+                readable_content(&url, preview.as_deref().unwrap_or(&scraped.html), fallback);
+
+            let mut continuation_urls = Vec::new();
+            let mut page_url = url.clone();
+            let mut html = scraped.html;
+            // Following a `rel="next"` link is following a link.
+            while !robots_tags.nofollow && continuation_urls.len() < request.max_pagination {
+                if max_total_bytes > 0
+                    && other_lanes_bytes + stats.bytes_downloaded >= max_total_bytes
+                {
+                    break;
+                }
+                let Some(next) = pagination::find_next_page(&page_url, &html) else {
+                    break;
+                };
+                let next_key = normalize_url(&next);
+                if visited.contains(&next_key) {
+                    break;
+                }
+                if let Some(reason) = fetch_skip_reason(
+                    &next,
+                    request.allow_local,
+                    &banned,
+                    &breaker,
+                    &politeness,
+                    false,
+                ) {
+                    skipped.push(SkippedUrl { url: next, reason });
+                    break;
+                }
+                visited.insert(next_key);
+                politeness.wait_turn(&next);
+                check_cancelled(request)?;
+                let next_fetch = fetch_page(
+                    fetcher,
+                    page_cache.as_ref(),
+                    &next,
+                    schedule,
+                    request.cancellation.as_ref(),
+                    &mut |target| {
+                        admit_redirect(target, request, &banned, &breaker, &mut politeness)
+                    },
+                );
+                let next_page = match next_fetch {
+                    Ok((next_page, not_modified)) => {
+                        breaker.record_success(&next);
+                        stats.record_fetch(&next, next_page.bytes, not_modified);
+                        archive_fetch(warc.as_mut(), &next, &next_page, not_modified);
+                        fetched.push(next.clone());
+                        next_page
+                    }
+                    Err(err) => {
+                        if let Some(CrawlError::Cancelled) = err.downcast_ref::<CrawlError>() {
+                            return Err(CrawlError::Cancelled);
+                        }
+                        if let Some(trip) = breaker.record_failure(&next, &err) {
+                            report_trip(&next, trip, request.host_failure_limit, &err, notes);
+                        }
+                        warn!(url = %next, "pagination fetch failed; stopping chain: {err:#}");
+                        skipped.push(SkippedUrl {
+                            url: next.clone(),
+                            reason: SkipReason::for_fetch_error(&err),
+                        });
+                        failures.push(CrawlFailure {
+                            url: next,
+                            stage: FailureStage::Fetch,
+                            message: format!("{err:#}"),
+                        });
+                        break;
+                    }
+                };
+                debug!(url = %next, source = %url, "following pagination");
+                stats.pagination_pages += 1;
+                let fallback = extract_page(&next, &next_page.html)
+                    .map(|page| page.text)
+                    .unwrap_or_default();
+                let next_content = readable_content(&next, &next_page.html, fallback);
+                if !next_content.trim().is_empty() {
+                    content.push_str("\n\n");
+                    content.push_str(&next_content);
+                }
+                links.extend(next_page.links);
+                continuation_urls.push(next.clone());
+                page_url = next;
+                html = next_page.html;
+            }
 
-        if sources.len() >= request.max_pages {
+            let change = content_store
+                .as_ref()
+                .map(|store| store.record(&normalize_url(&url), &content));
+            let source = Source {
+                url: url.clone(),
+                trust_tier,
+                title,
+                published,
+                license,
+                paywalled: paywall.is_some(),
+                change,
+                depth,
+                politeness_delay: politeness.min_interval_for(&url),
+                outline,
+                excerpt,
+                content,
+                continuation_urls,
+                parent,
+                fetch_order,
+                http,
+            };
+            if request.max_sources_per_host > 0
+                && sources
+                    .iter()
+                    .filter(|kept| same_host(&kept.url, &url))
+                    .count()
+                    >= request.max_sources_per_host
+            {
+                debug!(url = %url, "host has max_sources_per_host sources; keeping as overflow");
+                overflow_sources.push(source);
+            } else {
+                sources.push(source);
+                collected += usize::from(!reused);
+            }
+        }
+
+        if collected >= request.max_pages {
             break;
         }
 
-        if depth >= request.max_depth {
+        if robots_tags.nofollow {
+            debug!(url = %url, "nofollow page; not following its links");
+            continue;
+        }
+        if depth
+            >= limits
+                .max_depth
+                .unwrap_or_else(|| request.deepest_max_depth())
+        {
             continue;
         }
 
         let mut candidates = Vec::new();
         let mut candidate_seen = HashSet::<String>::new();
-        for link_url in scraped.links {
+        if request.deterministic {
+            // Fetchers may return links in any order; sort before the candidate cap applies.
+            links.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+            links.dedup_by(|a, b| a.as_str() == b.as_str());
+        }
+        for link_url in links {
             if !is_allowed(&link_url, request.allow_local) {
+                skipped.push(SkippedUrl {
+                    url: link_url,
+                    reason: SkipReason::NotAllowed,
+                });
+                continue;
+            }
+            if classify_url(&link_url) == UrlKind::Binary {
+                skipped.push(SkippedUrl {
+                    url: link_url,
+                    reason: SkipReason::Binary,
+                });
                 continue;
             }
             let key = normalize_url(&link_url);
-            if visited.contains(&key) {
+            if visited.contains(&key) || frontier.contains(&link_url) {
+                stats.duplicate_links_skipped += 1;
                 continue;
             }
             if !candidate_seen.insert(key) {
+                stats.duplicate_links_skipped += 1;
                 continue;
             }
 
@@ -214,47 +1549,1113 @@ pub fn crawl_with_fetcher(
                 url: link_url,
                 anchor_text,
             });
-            if candidates.len() >= request.max_child_candidates {
-                break;
+        }
+        let mut unpicked = candidates
+            .iter()
+            .map(|candidate| candidate.url.clone())
+            .collect::<Vec<_>>();
+        relevance::keep_most_relevant(
+            &mut candidates,
+            &request.query,
+            &url,
+            trust_tier,
+            &request.url_patterns,
+            request.max_considered_candidates(),
+        );
+
+        let max_children = limits.max_children.unwrap_or(if hub {
+            request.max_children_per_hub
+        } else {
+            request.max_children_per_page
+        });
+        let selected = if max_children == 0 || candidates.is_empty() {
+            Vec::new()
+        } else {
+            check_cancelled(request)?;
+            let failures_before = failures.len();
+            let collected = collected_digests(sources);
+            let page = SelectionPage {
+                url: &url,
+                excerpt: &page_excerpt,
+                collected: &collected,
+            };
+            let picked = selector.select(&page, &candidates, max_children, stats, failures)?;
+            let selected = match &request.selection_review {
+                Some(review) => review.review(&url, &candidates, picked.clone()),
+                None => picked.clone(),
+            };
+            selections.push(SelectionRecord {
+                page: url.clone(),
+                depth,
+                candidates: candidates
+                    .iter()
+                    .map(|candidate| (candidate.url.clone(), candidate.anchor_text.clone()))
+                    .collect(),
+                selected: picked,
+                followed: selected.iter().map(|link| link.url.clone()).collect(),
+                selection_failed: failures.len() > failures_before,
+            });
+            selected
+        };
+        unpicked.retain(|candidate| !selected.iter().any(|link| link.url == *candidate));
+        skipped.extend(unpicked.into_iter().map(|url| SkippedUrl {
+            url,
+            reason: SkipReason::NotSelected,
+        }));
+
+        for selected_link in selected {
+            let child_url = selected_link.url;
+            if !is_allowed(&child_url, request.allow_local) {
+                skipped.push(SkippedUrl {
+                    url: child_url,
+                    reason: SkipReason::NotAllowed,
+                });
+                continue;
+            }
+            let child_tier =
+                request.merge_trust_tier(&child_url, selected_link.trust_tier, reputation.as_ref());
+            if depth + 1
+                > limits
+                    .max_depth
+                    .unwrap_or_else(|| request.max_depth_for(child_tier))
+            {
+                skipped.push(SkippedUrl {
+                    url: child_url,
+                    reason: SkipReason::OverDepth,
+                });
+                continue;
+            }
+            let child_key = normalize_url(&child_url);
+            if visited.contains(&child_key) || frontier.contains(&child_url) {
+                stats.duplicate_links_skipped += 1;
+                continue;
+            }
+            if push_frontier(
+                frontier,
+                FrontierEntry {
+                    url: child_url,
+                    depth: depth + 1,
+                    tier: child_tier,
+                    limits,
+                    rank: None,
+                },
+                skipped,
+            ) {
+                discovered_from
+                    .entry(child_key)
+                    .or_insert_with(|| url.clone());
             }
         }
+    }
 
-        if request.max_children_per_page == 0 || candidates.is_empty() {
-            continue;
+    progress.finish();
+    if let Some(warc) = warc
+        && let Err(err) = warc.finish()
+    {
+        warn!("WARC write failed: {err:#}");
+    }
+    if let Some(reputation) = &reputation
+        && let Err(err) = reputation.save()
+    {
+        warn!("reputation store write failed: {err:#}");
+    }
+    if let Some(content_store) = &content_store
+        && let Err(err) = content_store.save()
+    {
+        warn!("content store write failed: {err:#}");
+    }
+    check_cancelled(request)?;
+    let connections = fetcher.connection_stats();
+    let host_politeness = politeness.host_politeness(&fetcher.unpaced_requests());
+    let results = lanes
+        .into_iter()
+        .map(|lane| finish_lane(lane, openai, connections, &breaker, &host_politeness))
+        .collect();
+    // The analysis calls of `finish_lane` give up once cancelled.
+    check_cancelled(request)?;
+    Ok(results)
+}
+
+/// `Err(CrawlError::Cancelled)` once the request's cancellation token fired.
+fn check_cancelled(request: &UserRequest) -> Result<(), CrawlError> {
+    match &request.cancellation {
+        Some(token) if token.is_cancelled() => Err(CrawlError::Cancelled),
+        _ => Ok(()),
+    }
+}
+
+/// Logs once that `url`'s host tripped the breaker. A host that is down gets a note;
+/// refusing hosts are listed with a suggestion in the failure report instead.
+fn report_trip(
+    url: &Url,
+    trip: HostTrip,
+    limit: usize,
+    err: &anyhow::Error,
+    notes: &mut Vec<String>,
+) {
+    let host = url.host_str().unwrap_or_default();
+    match trip {
+        HostTrip::Down => {
+            warn!(
+                host,
+                limit, "host keeps failing; skipping its remaining URLs: {err:#}"
+            );
+            notes.push(format!(
+                "{host} への取得が {limit} 回続けて失敗したため、このホストの残りの URL をスキップした（最後のエラー: {err:#}）"
+            ));
+        }
+        HostTrip::Refused => {
+            warn!(
+                host,
+                limit, "host keeps refusing us; backing off for this run: {err:#}"
+            );
+        }
+        HostTrip::Excluded => {
+            warn!(
+                host,
+                "host sent a takedown signal; excluding it for this run: {err:#}"
+            );
+        }
+    }
+}
+
+/// One query's share of a crawl.
+struct Lane<'a> {
+    request: &'a UserRequest,
+    intent: Option<QueryIntent>,
+    selector: LinkSelector<'a>,
+    frontier: Frontier,
+    visited: VisitedSet,
+    /// Child URL -> the page it was first selected from; entries leave when popped.
+    discovered_from: HashMap<String, Url>,
+    /// Search titles fill in for pages that carry no title of their own.
+    seed_titles: HashMap<String, String>,
+    language_variants: HashSet<String>,
+    sources: Vec<Source>,
+    overflow_sources: Vec<Source>,
+    stats: CrawlStats,
+    notes: Vec<String>,
+    failures: Vec<CrawlFailure>,
+    fetched: Vec<Url>,
+    skipped: Vec<SkippedUrl>,
+    selections: Vec<SelectionRecord>,
+    /// The frontier ran dry.
+    done: bool,
+}
+
+/// Queues `entry`, recording whichever URL a full frontier drops (the pushed one or
+/// the one it evicted) as skipped. Returns `false` when `entry` itself was dropped.
+fn push_frontier(
+    frontier: &mut Frontier,
+    entry: FrontierEntry,
+    skipped: &mut Vec<SkippedUrl>,
+) -> bool {
+    let (queued, dropped) = match frontier.push(entry) {
+        Ok(evicted) => (true, evicted),
+        Err(url) => (false, Some(url)),
+    };
+    if let Some(url) = dropped {
+        debug!(url = %url, "frontier full; dropped URL");
+        skipped.push(SkippedUrl {
+            url,
+            reason: SkipReason::FrontierFull,
+        });
+    }
+    queued
+}
+
+/// Applies one `--control` command to every query of the crawl.
+fn apply_control(command: ControlCommand, lanes: &mut [Lane<'_>], banned: &mut HashSet<String>) {
+    match command {
+        ControlCommand::Ban(host) => {
+            info!(host, "banning host (control file)");
+            banned.insert(host);
+        }
+        ControlCommand::Pin(url) => {
+            let mut pinned = false;
+            for lane in lanes.iter_mut() {
+                if lane.frontier.pin(&url) {
+                    lane.done = false;
+                    pinned = true;
+                }
+            }
+            // Not queued anywhere yet: the first query crawls it as a seed.
+            if !pinned && let Some(lane) = lanes.first_mut() {
+                if lane.visited.contains(&normalize_url(&url)) {
+                    warn!(url = %url, "pinned URL was already crawled; ignoring");
+                    return;
+                }
+                push_frontier(
+                    &mut lane.frontier,
+                    FrontierEntry {
+                        limits: lane.request.seed_limits(&url),
+                        url: url.clone(),
+                        depth: 0,
+                        tier: TrustTier::High,
+                        rank: Some(0),
+                    },
+                    &mut lane.skipped,
+                );
+                pinned = lane.frontier.pin(&url);
+                lane.done = false;
+            }
+            if pinned {
+                info!(url = %url, "pinning URL to the front of the frontier (control file)");
+            }
         }
+    }
+}
 
-        candidates.sort_by(|a, b| a.url.as_str().cmp(b.url.as_str()));
+/// A fetched page and how long it took, or why the fetch failed.
+type SharedFetch = Result<(FetchedPage, Duration), (SkipReason, String)>;
 
-        let candidate_values = candidates
-            .iter()
-            .map(|c| {
-                json!({
-                    "url": c.url.as_str(),
-                    "anchor_text": c.anchor_text,
-                })
+/// Runs the post-crawl analysis of one query and assembles its result.
+fn finish_lane(
+    lane: Lane<'_>,
+    openai: &dyn crate::openai::OpenAiApi,
+    connections: Option<ConnectionStats>,
+    breaker: &HostBreaker,
+    host_politeness: &[HostPoliteness],
+) -> CrawlResult {
+    let Lane {
+        request,
+        intent,
+        frontier,
+        visited,
+        sources,
+        overflow_sources,
+        mut stats,
+        mut notes,
+        failures,
+        fetched,
+        mut skipped,
+        selections,
+        ..
+    } = lane;
+    skipped.extend(frontier.peek(frontier.len()).map(|url| SkippedUrl {
+        url: url.clone(),
+        reason: SkipReason::NotReached,
+    }));
+
+    // Graded first so the analysis below only sees the sources the report keeps.
+    let (sources, overflow_sources, dropped_sources) =
+        if request.grade_sources && !sources.is_empty() {
+            stats.llm_calls += 1;
+            grade_sources(request, openai, sources, overflow_sources, &mut notes)
+        } else {
+            (sources, overflow_sources, Vec::new())
+        };
+    // Pages held back by `--max-sources-per-host` still inform the synthesis; their
+    // indices follow the sources'.
+    let analysis: Cow<'_, [Source]> = if overflow_sources.is_empty() {
+        Cow::Borrowed(&sources)
+    } else {
+        Cow::Owned(sources.iter().chain(&overflow_sources).cloned().collect())
+    };
+    let comparison = compare::cover_alternatives(&request.compare, &sources);
+    let subtopics = if !request.compare.is_empty() {
+        compare::group_by_alternative(&comparison, sources.len())
+    } else if request.findings_layout == FindingsLayout::Subtopics && !sources.is_empty() {
+        stats.llm_calls += 1;
+        group_by_subtopic(request, openai, &sources, &mut notes)
+    } else {
+        Vec::new()
+    };
+    let follow_ups = if request.follow_ups && !sources.is_empty() {
+        stats.llm_calls += 1;
+        suggest_follow_ups(request, openai, &source_digests(&*analysis), &mut notes)
+    } else {
+        Vec::new()
+    };
+    let key_terms = if request.key_terms && analysis.len() >= 2 {
+        stats.llm_calls += 1;
+        extract_key_terms(request, openai, &analysis, &mut notes)
+    } else {
+        Vec::new()
+    };
+    let timeline = if request.timeline && !sources.is_empty() {
+        stats.llm_calls += 1;
+        extract_timeline(request, openai, &analysis, &mut notes)
+    } else {
+        Vec::new()
+    };
+    let answer = if request.answer && !sources.is_empty() {
+        stats.llm_calls += 1;
+        answer_query(request, openai, &analysis, &mut notes)
+    } else {
+        None
+    };
+
+    stats.frontier_peak_len = frontier.peak_len();
+    stats.frontier_peak_bytes = frontier.peak_bytes();
+    stats.frontier_evicted = frontier.evicted();
+    stats.visited_len = visited.len();
+    stats.visited_bytes = visited.bytes();
+    stats.visited_evicted = visited.evicted();
+    stats.connections = connections;
+    stats.host_politeness = host_politeness.to_vec();
+    info!(
+        frontier_peak_len = stats.frontier_peak_len,
+        frontier_peak_bytes = stats.frontier_peak_bytes,
+        frontier_evicted = stats.frontier_evicted,
+        visited_len = stats.visited_len,
+        visited_bytes = stats.visited_bytes,
+        visited_evicted = stats.visited_evicted,
+        "crawl memory"
+    );
+    info!(
+        pages_fetched = stats.pages_fetched,
+        bytes_downloaded = stats.bytes_downloaded,
+        llm_calls = stats.llm_calls,
+        "crawl downloads"
+    );
+
+    CrawlResult {
+        intent,
+        sources,
+        overflow_sources,
+        dropped_sources,
+        stats,
+        notes,
+        failures,
+        refused_hosts: breaker.refused_hosts().to_vec(),
+        excluded_hosts: breaker.excluded_hosts().to_vec(),
+        fetched,
+        skipped,
+        selections,
+        subtopics,
+        comparison,
+        follow_ups,
+        key_terms,
+        timeline,
+        answer,
+    }
+}
+
+/// Asks the LLM for a short answer from `sources`; a failed call adds a note and
+/// answers nothing.
+fn answer_query(
+    request: &UserRequest,
+    openai: &dyn crate::openai::OpenAiApi,
+    sources: &[Source],
+    notes: &mut Vec<String>,
+) -> Option<Answer> {
+    match openai.answer_query(
+        &request.query,
+        &source_digests(sources),
+        MAX_ANSWER_PARAGRAPHS,
+    ) {
+        Ok(answer) => Some(answer),
+        Err(err) => {
+            warn!("answer synthesis failed: {err:#}");
+            notes.push(format!(
+                "回答の作成に失敗した: {}",
+                escape_md_inline(&format!("{err:#}"))
+            ));
+            None
+        }
+    }
+}
+
+/// Asks the LLM to grade `sources` and `overflow` (offered after them) and splits off
+/// the ones it would drop. Pages the model does not grade are kept; a failed call adds
+/// a note and keeps them all.
+fn grade_sources(
+    request: &UserRequest,
+    openai: &dyn crate::openai::OpenAiApi,
+    sources: Vec<Source>,
+    overflow: Vec<Source>,
+    notes: &mut Vec<String>,
+) -> (Vec<Source>, Vec<Source>, Vec<DroppedSource>) {
+    let digests = source_digests(sources.iter().chain(&overflow));
+    let grades = match openai.grade_sources(&request.query, &digests) {
+        Ok(grades) => grades,
+        Err(err) => {
+            warn!("source grading failed; keeping every source: {err:#}");
+            notes.push(format!(
+                "ソースの有用性の判定に失敗したため、すべてのソースを残した: {}",
+                escape_md_inline(&format!("{err:#}"))
+            ));
+            return (sources, overflow, Vec::new());
+        }
+    };
+    let mut drop_reasons = grades
+        .into_iter()
+        .filter(|grade| !grade.keep)
+        .map(|grade| (grade.source, grade.reason))
+        .collect::<HashMap<_, _>>();
+
+    let mut dropped = Vec::new();
+    let mut split = |pages: Vec<Source>, offset: usize| {
+        let mut kept = Vec::with_capacity(pages.len());
+        for (id, source) in pages.into_iter().enumerate() {
+            match drop_reasons.remove(&(offset + id)) {
+                Some(reason) => {
+                    debug!(url = %source.url, reason = %reason, "source graded as not useful; dropping");
+                    dropped.push(DroppedSource { source, reason });
+                }
+                None => kept.push(source),
+            }
+        }
+        kept
+    };
+    let offset = sources.len();
+    let kept = split(sources, 0);
+    let kept_overflow = split(overflow, offset);
+    (kept, kept_overflow, dropped)
+}
+
+fn same_host(a: &Url, b: &Url) -> bool {
+    match (a.host_str(), b.host_str()) {
+        (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+        _ => false,
+    }
+}
+
+/// `sources` as passed to post-crawl analysis calls: `id` (the index), URL, title,
+/// excerpt, and `published` when known.
+fn source_digests<'a>(sources: impl IntoIterator<Item = &'a Source>) -> Vec<Value> {
+    sources
+        .into_iter()
+        .enumerate()
+        .map(|(id, source)| {
+            let mut digest = json!({
+                "id": id,
+                "url": source.url.as_str(),
+                "title": source.title.as_deref().unwrap_or_default(),
+                "excerpt": source.excerpt,
+            });
+            if let Some(published) = &source.published {
+                digest["published"] = json!(published);
+            }
+            digest
+        })
+        .collect()
+}
+
+/// Asks the LLM for dated facts, sorted by date; a failed call adds a note and
+/// extracts none.
+fn extract_timeline(
+    request: &UserRequest,
+    openai: &dyn crate::openai::OpenAiApi,
+    sources: &[Source],
+    notes: &mut Vec<String>,
+) -> Vec<TimelineEvent> {
+    match openai.extract_timeline(
+        &request.query,
+        &source_digests(sources),
+        MAX_TIMELINE_EVENTS,
+    ) {
+        Ok(events) => events
+            .into_iter()
+            .filter_map(|mut event| {
+                event.sources.retain(|&id| id < sources.len());
+                (!event.sources.is_empty()).then_some(event)
             })
-            .collect::<Vec<Value>>();
+            .collect(),
+        Err(err) => {
+            warn!("timeline extraction failed: {err:#}");
+            notes.push(format!(
+                "年表の作成に失敗した: {}",
+                escape_md_inline(&format!("{err:#}"))
+            ));
+            Vec::new()
+        }
+    }
+}
+
+/// The latest sources as `{url, title, topics}` objects for link selection, where
+/// `topics` joins the page's first headings.
+fn collected_digests(sources: &[Source]) -> Vec<Value> {
+    let recent = &sources[sources.len().saturating_sub(MAX_COLLECTED_IN_SELECTION)..];
+    recent
+        .iter()
+        .map(|source| {
+            let mut digest = json!({ "url": source.url.as_str() });
+            if let Some(title) = &source.title {
+                digest["title"] = json!(title);
+            }
+            let topics = source
+                .outline
+                .iter()
+                .take(MAX_COLLECTED_TOPICS)
+                .map(|heading| heading.text.as_str())
+                .collect::<Vec<_>>();
+            if !topics.is_empty() {
+                digest["topics"] = json!(topics.join("; "));
+            }
+            digest
+        })
+        .collect()
+}
+
+/// Asks the LLM for follow-up queries; a failed call adds a note and suggests none.
+fn suggest_follow_ups(
+    request: &UserRequest,
+    openai: &dyn crate::openai::OpenAiApi,
+    digests: &[Value],
+    notes: &mut Vec<String>,
+) -> Vec<String> {
+    match openai.suggest_follow_ups(&request.query, digests, MAX_FOLLOW_UPS) {
+        Ok(queries) => queries,
+        Err(err) => {
+            warn!("follow-up suggestion failed: {err:#}");
+            notes.push(format!(
+                "次の検索クエリの提案に失敗した: {}",
+                escape_md_inline(&format!("{err:#}"))
+            ));
+            Vec::new()
+        }
+    }
+}
 
-        let selected = openai
-            .select_child_links(
+/// Asks the LLM for a glossary and keeps the terms that appear in several sources;
+/// a failed call adds a note and extracts none.
+fn extract_key_terms(
+    request: &UserRequest,
+    openai: &dyn crate::openai::OpenAiApi,
+    sources: &[Source],
+    notes: &mut Vec<String>,
+) -> Vec<KeyTerm> {
+    match openai.extract_key_terms(&request.query, &source_digests(sources), MAX_KEY_TERMS) {
+        Ok(entries) => key_terms::count_key_terms(entries, sources),
+        Err(err) => {
+            warn!("key term extraction failed: {err:#}");
+            notes.push(format!(
+                "キーワードの抽出に失敗した: {}",
+                escape_md_inline(&format!("{err:#}"))
+            ));
+            Vec::new()
+        }
+    }
+}
+
+/// Asks the LLM to group `sources` by subtopic. Sources the model leaves out go to a
+/// trailing "Other" group; a failed call adds a note and keeps the flat layout.
+fn group_by_subtopic(
+    request: &UserRequest,
+    openai: &dyn crate::openai::OpenAiApi,
+    sources: &[Source],
+    notes: &mut Vec<String>,
+) -> Vec<Subtopic> {
+    let groups =
+        match openai.cluster_sources(&request.query, &source_digests(sources), MAX_SUBTOPICS) {
+            Ok(groups) => groups,
+            Err(err) => {
+                warn!("subtopic clustering failed; keeping the flat layout: {err:#}");
+                notes.push(format!(
+                    "サブトピックへの分類に失敗したため、Findings をソースごとに並べた: {}",
+                    escape_md_inline(&format!("{err:#}"))
+                ));
+                return Vec::new();
+            }
+        };
+
+    let mut assigned = vec![false; sources.len()];
+    let mut subtopics = Vec::new();
+    for group in groups {
+        let ids = group
+            .sources
+            .into_iter()
+            .filter(|&id| id < sources.len() && !std::mem::replace(&mut assigned[id], true))
+            .collect::<Vec<_>>();
+        if !ids.is_empty() {
+            subtopics.push(Subtopic {
+                title: group.title,
+                sources: ids,
+            });
+        }
+    }
+    let rest = (0..sources.len())
+        .filter(|&id| !assigned[id])
+        .collect::<Vec<_>>();
+    if !subtopics.is_empty() && !rest.is_empty() {
+        subtopics.push(Subtopic {
+            title: "Other".to_owned(),
+            sources: rest,
+        });
+    }
+    subtopics
+}
+
+/// Link selection for one crawl: the LLM plus the optional decision cache.
+struct LinkSelector<'a> {
+    request: &'a UserRequest,
+    openai: &'a dyn crate::openai::OpenAiApi,
+    cache: Option<crate::cache::SelectionCache>,
+    /// Receives the tiers of fresh model picks.
+    reputation: Option<&'a crate::cache::ReputationStore>,
+}
+
+/// The page whose links are selected, as the selection prompt describes it.
+struct SelectionPage<'a> {
+    url: &'a Url,
+    excerpt: &'a str,
+    /// The lane's sources so far (`collected_digests`).
+    collected: &'a [Value],
+}
+
+impl LinkSelector<'_> {
+    /// Runs link selection over `candidates` in batches of `max_child_candidates` and keeps
+    /// the best `max_children` picks: higher tiers first, then each batch's own order.
+    ///
+    /// A failed batch follows `on_selection_failure` without discarding the other batches.
+    /// Successful decisions are cached; heuristic fallbacks are not.
+    fn select(
+        &self,
+        page: &SelectionPage<'_>,
+        candidates: &[LinkCandidate],
+        max_children: usize,
+        stats: &mut CrawlStats,
+        failures: &mut Vec<CrawlFailure>,
+    ) -> Result<Vec<crate::openai::SelectedLink>, CrawlError> {
+        let SelectionPage {
+            url,
+            excerpt,
+            collected,
+        } = *page;
+        let request = self.request;
+        // The prompt shows what was collected, so a decision only holds for the same set.
+        let collected_key = Value::from(collected.to_vec()).to_string();
+        let mut ranked = Vec::new();
+        for batch in candidates.chunks(request.max_child_candidates.max(1)) {
+            let mut batch = batch.to_vec();
+            batch.sort_by(|a, b| a.url.as_str().cmp(b.url.as_str()));
+            let candidate_values = batch
+                .iter()
+                .map(|c| {
+                    let mut value = json!({
+                        "url": c.url.as_str(),
+                        "anchor_text": c.anchor_text,
+                    });
+                    if let Some(hint) = request.url_patterns.hint(&c.url) {
+                        value["path_hint"] = json!(hint.as_str());
+                    }
+                    value
+                })
+                .collect::<Vec<Value>>();
+
+            let cache_key = crate::cache::SelectionCache::key(
                 &request.query,
-                &url,
-                sources.last().map(|s| s.excerpt.as_str()).unwrap_or(""),
+                url,
+                &Value::from(candidate_values.clone()).to_string(),
+                max_children,
+                &collected_key,
+                &self.openai.select_fingerprint(),
+            );
+            if let Some(selected) = self.cache.as_ref().and_then(|cache| cache.get(&cache_key)) {
+                debug!(url = %url, "selection cache hit");
+                stats.selection_cache_hits += 1;
+                ranked.extend(selected.into_iter().enumerate());
+                continue;
+            }
+
+            stats.llm_calls += 1;
+            let selected = match self.openai.select_child_links(
+                &request.query,
+                url,
+                excerpt,
                 &candidate_values,
-                request.max_children_per_page,
-            )
-            .with_context(|| format!("select child links: {url}"))?;
+                collected,
+                max_children,
+            ) {
+                Ok(selected) => {
+                    if let Some(reputation) = self.reputation {
+                        for link in &selected {
+                            reputation.record(&link.url, link.trust_tier);
+                        }
+                    }
+                    if let Some(cache) = &self.cache
+                        && let Err(err) = cache.put(&cache_key, &selected)
+                    {
+                        warn!(url = %url, "selection cache write failed: {err:#}");
+                    }
+                    selected
+                }
+                Err(err) => {
+                    let err = CrawlError::classify(err, |source| CrawlError::SelectionFailed {
+                        page: url.clone(),
+                        source,
+                    });
+                    if request.on_selection_failure == SelectionFailurePolicy::Abort {
+                        return Err(err);
+                    }
+                    let message = format!("{:#}", anyhow::Error::from(err));
+                    warn!(
+                        url = %url,
+                        policy = ?request.on_selection_failure,
+                        "link selection failed: {message}"
+                    );
+                    failures.push(CrawlFailure {
+                        url: url.clone(),
+                        stage: FailureStage::Selection,
+                        message,
+                    });
+                    match request.on_selection_failure {
+                        SelectionFailurePolicy::Heuristic => {
+                            heuristic_child_links(url, &batch, &request.url_patterns, max_children)
+                        }
+                        _ => Vec::new(),
+                    }
+                }
+            };
+            ranked.extend(selected.into_iter().enumerate());
+        }
+
+        // Stable sort: equal keys keep batch order.
+        ranked.sort_by_key(|(position, link)| (link.trust_tier, *position));
+        let mut seen = HashSet::new();
+        Ok(ranked
+            .into_iter()
+            .map(|(_, link)| link)
+            .filter(|link| seen.insert(normalize_url(&link.url)))
+            .take(max_children)
+            .collect())
+    }
+}
+
+/// Readability output for `html`, or `fallback` (plain extracted text) when it yields nothing.
+fn readable_content(url: &Url, html: &str, fallback: String) -> String {
+    extract_readable_content(html)
+        .filter(|md| !md.trim().is_empty())
+        .unwrap_or_else(|| {
+            warn!(url = %url, "readability extraction failed; falling back to extracted text");
+            fallback
+        })
+}
+
+/// Fallback when link selection fails: `url_patterns` boosts first and penalties last,
+/// then same-host candidates with anchor text first, otherwise in candidate order. Picks
+/// are `Unverified` trust since nothing vetted them.
+fn heuristic_child_links(
+    page_url: &Url,
+    candidates: &[LinkCandidate],
+    patterns: &UrlPatterns,
+    max_select: usize,
+) -> Vec<crate::openai::SelectedLink> {
+    let rank = |candidate: &LinkCandidate| {
+        let hint = match patterns.hint(&candidate.url) {
+            Some(relevance::PathHint::Prefer) => 0,
+            None => 1,
+            Some(relevance::PathHint::Avoid) => 2,
+        };
+        let same_host = candidate.url.host_str() == page_url.host_str();
+        let has_anchor = !candidate.anchor_text.trim().is_empty();
+        (hint, !same_host, !has_anchor)
+    };
+    let mut ranked = candidates.iter().collect::<Vec<_>>();
+    ranked.sort_by_key(|candidate| rank(candidate));
+    ranked
+        .into_iter()
+        .take(max_select)
+        .map(|candidate| crate::openai::SelectedLink {
+            url: candidate.url.clone(),
+            trust_tier: TrustTier::Unverified,
+        })
+        .collect()
+}
+
+/// A URL the crawl starts from.
+struct Seed {
+    hit: crate::openai::SearchHit,
+    /// `0` for `--seed-url` seeds; otherwise the best position (`1` for the top hit) the
+    /// URL reached in any search.
+    rank: usize,
+}
+
+/// Seed URLs followed by search hits. Applies `on_search_failure` and records a
+/// diagnosis in `notes` when the crawl has nothing to start from.
+fn collect_seeds(
+    request: &UserRequest,
+    openai: &dyn crate::openai::OpenAiApi,
+    notes: &mut Vec<String>,
+) -> Result<Vec<Seed>, CrawlError> {
+    let mut seeds = request
+        .seed_urls
+        .iter()
+        .map(|seed| Seed {
+            hit: crate::openai::SearchHit {
+                url: seed.url.clone(),
+                title: None,
+                trust_tier: TrustTier::High,
+            },
+            rank: 0,
+        })
+        .collect::<Vec<_>>();
+
+    let queries = search_queries(request);
+    for query in &queries {
+        // Comparison mode runs several searches; name the one a note is about.
+        let which = if queries.len() > 1 {
+            format!("（{}）", escape_md_inline(query))
+        } else {
+            String::new()
+        };
+        match openai.web_search(query, request.search_limit) {
+            Ok(hits) => {
+                if hits.is_empty() {
+                    warn!(query = %query, "web search returned no hits");
+                    notes.push(format!("web search の結果が 0 件だった{which}。"));
+                }
+                for (index, hit) in hits.into_iter().enumerate() {
+                    merge_seed(&mut seeds, hit, index + 1);
+                }
+            }
+            Err(err) => {
+                let err = CrawlError::classify(err, CrawlError::SearchFailed);
+                if request.on_search_failure == SearchFailurePolicy::Abort {
+                    return Err(err);
+                }
+                let reason = format!("{:#}", anyhow::Error::from(err));
+                warn!(query = %query, "web search failed; continuing with seed URLs: {reason}");
+                notes.push(format!(
+                    "web search が失敗した{which}: {}",
+                    escape_md_inline(&reason)
+                ));
+            }
+        }
+    }
+
+    if seeds.is_empty() {
+        notes.push(
+            "no seeds: 検索結果もシード URL もないため、クロールできなかった。`--seed-url` を指定する。"
+                .to_owned(),
+        );
+    }
+    Ok(seeds)
+}
+
+/// The web searches to run: the query itself, or one per `--compare` alternative.
+fn search_queries(request: &UserRequest) -> Vec<String> {
+    if request.compare.is_empty() {
+        return vec![request.query.clone()];
+    }
+    request
+        .compare
+        .iter()
+        .map(|alternative| format!("{alternative}: {}", request.query))
+        .collect()
+}
+
+/// Adds `hit` at search position `rank` to `seeds`, folding it into an earlier seed
+/// with the same canonical URL. A merged seed keeps the least trusted tier, the first
+/// known title, and the best rank.
+fn merge_seed(seeds: &mut Vec<Seed>, hit: crate::openai::SearchHit, rank: usize) {
+    let key = normalize_url(&hit.url);
+    match seeds
+        .iter_mut()
+        .find(|seed| normalize_url(&seed.hit.url) == key)
+    {
+        Some(seed) => {
+            seed.hit.trust_tier = seed.hit.trust_tier.max(hit.trust_tier);
+            if seed.hit.title.is_none() {
+                seed.hit.title = hit.title;
+            }
+            seed.rank = seed.rank.min(rank);
+        }
+        None => seeds.push(Seed { hit, rank }),
+    }
+}
+
+/// Fetches `url`, revalidating a cached copy when one with validators exists.
+/// Returns the page and whether it was served from the cache.
+fn fetch_with_cache(
+    fetcher: &dyn PageFetcher,
+    cache: Option<&crate::cache::PageCache>,
+    url: &Url,
+) -> anyhow::Result<(FetchedPage, bool)> {
+    let Some(cache) = cache else {
+        let page = fetcher.fetch(url)?;
+        reject_challenge(url, &page)?;
+        return Ok((page, false));
+    };
+
+    let cached = cache.get(url);
+    let validators = cached.as_ref().and_then(|entry| entry.validators());
+    let page = match (cached, validators) {
+        (Some(entry), Some(validators)) => match fetcher.revalidate(url, &validators)? {
+            Revalidated::NotModified {
+                robots_delay,
+                robots_max_concurrent,
+            } => {
+                debug!(url = %url, "page cache hit (not modified)");
+                let mut page = entry.to_fetched_page();
+                page.status = Some(304);
+                page.robots_delay = robots_delay;
+                page.robots_max_concurrent = robots_max_concurrent;
+                return Ok((page, true));
+            }
+            Revalidated::Modified(page) => *page,
+        },
+        _ => fetcher.fetch(url)?,
+    };
+    reject_challenge(url, &page)?;
+
+    if let Err(err) = cache.put(url, &page) {
+        warn!(url = %url, "page cache write failed: {err:#}");
+    }
+    Ok((page, false))
+}
+
+/// Fails with [`CrawlError::BotChallenge`] when a successful response is a challenge
+/// interstitial, so it is neither cached nor extracted.
+fn reject_challenge(url: &Url, page: &FetchedPage) -> anyhow::Result<()> {
+    match challenge::detect_challenge(&page.html) {
+        Some(vendor) => Err(CrawlError::BotChallenge {
+            url: url.clone(),
+            vendor: vendor.to_owned(),
+        }
+        .into()),
+        None => Ok(()),
+    }
+}
+
+/// Why `url` must not be fetched now: a disallowed address, a banned host, a binary
+/// resource, a host tripped by the breaker, or a host at its page cap. The breaker and
+/// cap do not apply to a fetch `reused` from another query, which makes no request.
+fn fetch_skip_reason(
+    url: &Url,
+    allow_local: bool,
+    banned: &HashSet<String>,
+    breaker: &HostBreaker,
+    politeness: &Politeness<'_>,
+    reused: bool,
+) -> Option<SkipReason> {
+    if !is_allowed(url, allow_local) {
+        Some(SkipReason::NotAllowed)
+    } else if is_banned(url, banned) {
+        debug!(url = %url, "host banned through the control file; skipping");
+        Some(SkipReason::Banned)
+    } else if classify_url(url) == UrlKind::Binary {
+        debug!(url = %url, "non-textual resource; skipping");
+        Some(SkipReason::Binary)
+    } else if let Some(trip) = breaker.tripped(url).filter(|_| !reused) {
+        debug!(url = %url, ?trip, "host tripped the breaker; skipping");
+        Some(match trip {
+            HostTrip::Down => SkipReason::HostDown,
+            HostTrip::Refused => SkipReason::HostRefused,
+            HostTrip::Excluded => SkipReason::HostExcluded,
+        })
+    } else if !reused && !politeness.admit(url) {
+        debug!(url = %url, "host page cap reached; skipping");
+        Some(SkipReason::HostCap)
+    } else {
+        None
+    }
+}
+
+/// Vets an HTTP redirect target like a frontier URL, then waits for its host's turn.
+fn admit_redirect(
+    target: &Url,
+    request: &UserRequest,
+    banned: &HashSet<String>,
+    breaker: &HostBreaker,
+    politeness: &mut Politeness<'_>,
+) -> anyhow::Result<()> {
+    if let Some(reason) = fetch_skip_reason(
+        target,
+        request.allow_local,
+        banned,
+        breaker,
+        politeness,
+        false,
+    ) {
+        return Err(CrawlError::RedirectRefused {
+            url: target.clone(),
+            reason,
+        }
+        .into());
+    }
+    politeness.wait_turn(target);
+    check_cancelled(request)?;
+    Ok(())
+}
+
+/// [`fetch_answer`], following HTTP redirects one hop at a time. `before_hop` gets each
+/// target before it is requested and refuses it with an error; the page keeps the last
+/// target as its final URL.
+fn fetch_page(
+    fetcher: &dyn PageFetcher,
+    cache: Option<&crate::cache::PageCache>,
+    url: &Url,
+    schedule: &dyn SchedulePolicy,
+    cancellation: Option<&CancellationToken>,
+    before_hop: &mut dyn FnMut(&Url) -> anyhow::Result<()>,
+) -> anyhow::Result<(FetchedPage, bool)> {
+    let mut current = url.clone();
+    for _ in 0..=MAX_REDIRECTS {
+        let result = fetch_answer(fetcher, cache, &current, schedule, cancellation);
+        let location = result
+            .as_ref()
+            .err()
+            .and_then(|err| err.downcast_ref::<CrawlError>())
+            .and_then(|err| match err {
+                CrawlError::Redirected { location } => Some(location.clone()),
+                _ => None,
+            });
+        let Some(location) = location else {
+            return result.map(|(mut page, not_modified)| {
+                if current != *url {
+                    page.final_url = Some(current);
+                }
+                (page, not_modified)
+            });
+        };
+        debug!(url = %current, location = %location, "following redirect");
+        before_hop(&location)?;
+        current = location;
+    }
+    Err(anyhow::Error::new(CrawlError::FetchFailed {
+        url: url.clone(),
+        status: None,
+    })
+    .context(format!("more than {MAX_REDIRECTS} redirects")))
+}
 
-        for selected_link in selected {
-            let child_url = selected_link.url;
-            if !is_allowed(&child_url, request.allow_local) {
-                continue;
-            }
-            frontier.push(child_url, depth + 1, selected_link.trust_tier);
+/// [`fetch_with_cache`], retried once after `retry_after` when the first answer is a
+/// bot challenge.
+fn fetch_answer(
+    fetcher: &dyn PageFetcher,
+    cache: Option<&crate::cache::PageCache>,
+    url: &Url,
+    schedule: &dyn SchedulePolicy,
+    cancellation: Option<&CancellationToken>,
+) -> anyhow::Result<(FetchedPage, bool)> {
+    let mut attempt = 0;
+    loop {
+        let result = fetch_with_cache(fetcher, cache, url);
+        let challenged = result
+            .as_ref()
+            .err()
+            .and_then(|err| err.downcast_ref::<CrawlError>())
+            .is_some_and(|err| matches!(err, CrawlError::BotChallenge { .. }));
+        if !challenged {
+            return result;
+        }
+        attempt += 1;
+        let Some(delay) = schedule.retry_delay(url, attempt) else {
+            return result;
+        };
+        debug!(url = %url, attempt, "bot challenge; retrying in {}", humantime::format_duration(delay));
+        if !crate::cancel::sleep(cancellation, delay) {
+            return Err(CrawlError::Cancelled.into());
         }
     }
+}
 
-    Ok(CrawlResult { sources })
+/// Appends one fetch to the WARC file, if any. Write errors are logged, not fatal.
+fn archive_fetch(warc: Option<&mut WarcWriter>, url: &Url, page: &FetchedPage, not_modified: bool) {
+    let Some(warc) = warc else {
+        return;
+    };
+    let written = if not_modified {
+        warc.write_not_modified(url, page)
+    } else {
+        warc.write_response(url, page)
+    };
+    if let Err(err) = written {
+        warn!(url = %url, "WARC write failed: {err:#}");
+    }
 }
 
 pub fn compose_markdown(request: &UserRequest, result: &CrawlResult) -> String {
@@ -264,21 +2665,91 @@ pub fn compose_markdown(request: &UserRequest, result: &CrawlResult) -> String {
     out.push_str("- ");
     out.push_str(&escape_md_inline(&request.query));
     out.push('\n');
+    if let Some(crawl_id) = &request.crawl_id {
+        out.push_str(&format!("- Crawl ID: `{crawl_id}`\n"));
+    }
+    if let Some(intent) = result.intent {
+        out.push_str(&format!("- Intent: {}\n", intent.as_str()));
+    }
     out.push('\n');
 
+    if let Some(answer) = &result.answer {
+        out.push_str(&compose_answer(answer, &result.analysis_sources()));
+        out.push('\n');
+    }
+
+    if !result.comparison.is_empty() {
+        out.push_str(&compare::compose_comparison(
+            &result.comparison,
+            &result.sources,
+        ));
+        out.push('\n');
+    }
+
+    if !result.key_terms.is_empty() {
+        out.push_str(&key_terms::compose_key_terms(
+            &result.key_terms,
+            &result.analysis_sources(),
+        ));
+        out.push('\n');
+    }
+
+    if !result.timeline.is_empty() {
+        out.push_str(&compose_timeline(
+            &result.timeline,
+            &result.analysis_sources(),
+        ));
+        out.push('\n');
+    }
+
     out.push_str("## Findings\n\n");
-    let mut included_count = 0usize;
+    let mut included = Vec::<&Source>::new();
 
     if result.sources.is_empty() {
         out.push_str("- No sources collected.\n");
     } else {
-        for source in &result.sources {
+        for (subtopic, source) in findings_order(result) {
             let mut page_block = String::new();
-            page_block.push_str(&format!("### [{:?}] {}\n\n", source.trust_tier, source.url,));
+            let source_heading = match subtopic {
+                Some(Some(title)) => {
+                    page_block.push_str(&format!("### {}\n\n", escape_md_inline(title)));
+                    "####"
+                }
+                Some(None) => "####",
+                None => "###",
+            };
+            let mut marks = Vec::new();
+            if source.paywalled {
+                marks.push("paywalled");
+            }
+            if let Some(change) = source.change {
+                marks.push(change.as_str());
+            }
+            let marks = if marks.is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", marks.join(", "))
+            };
+            page_block.push_str(&format!(
+                "{source_heading} [{:?}] {}{marks}\n\n",
+                source.trust_tier, source.url,
+            ));
+            if !source.continuation_urls.is_empty() {
+                let urls = source
+                    .continuation_urls
+                    .iter()
+                    .map(Url::as_str)
+                    .collect::<Vec<_>>();
+                page_block.push_str(&format!("Continued on: {}\n\n", urls.join(", ")));
+            }
+            if !source.outline.is_empty() {
+                page_block.push_str(&compose_outline(&source.outline));
+                page_block.push('\n');
+            }
             page_block.push_str(&source.content);
             page_block.push_str("\n\n");
 
-            if request.max_chars > 0 && included_count > 0 {
+            if request.max_chars > 0 && !included.is_empty() {
                 let new_total = out.chars().count() + page_block.chars().count();
                 if new_total > request.max_chars {
                     break;
@@ -286,31 +2757,369 @@ pub fn compose_markdown(request: &UserRequest, result: &CrawlResult) -> String {
             }
 
             out.push_str(&page_block);
-            included_count += 1;
+            included.push(source);
         }
     }
     out.push('\n');
 
     out.push_str("## Sources\n\n");
-    for source in result.sources.iter().take(included_count) {
-        out.push_str("- ");
-        out.push_str(&format!("[{:?}] {}", source.trust_tier, source.url));
+    match request.sources_format {
+        SourcesFormat::List => {
+            for source in &included {
+                out.push_str("- ");
+                out.push_str(&format!(
+                    "[{:?}] {} ({})",
+                    source.trust_tier,
+                    source.url,
+                    crawl_path(source)
+                ));
+                if let Some(license) = &source.license {
+                    out.push_str(&format!(" — {}", escape_md_inline(license)));
+                }
+                out.push('\n');
+            }
+        }
+        SourcesFormat::Table => {
+            if !included.is_empty() {
+                out.push_str(&compose_sources_table(&included));
+            }
+        }
+    }
+
+    if !result.overflow_sources.is_empty() {
+        out.push('\n');
+        out.push_str("## More from the same hosts\n\n");
+        out.push_str(&format!(
+            "Pages over the limit of {} sources per host, left out of the findings:\n\n",
+            request.max_sources_per_host
+        ));
+        for source in &result.overflow_sources {
+            out.push_str(&format!("- [{:?}] {}", source.trust_tier, source.url));
+            if let Some(title) = &source.title {
+                out.push_str(&format!(" — {}", escape_md_inline(title)));
+            }
+            out.push('\n');
+        }
+    }
+
+    if !result.dropped_sources.is_empty() {
+        out.push('\n');
+        out.push_str("## Dropped sources\n\n");
+        out.push_str("Collected but graded as not useful for the query:\n\n");
+        for dropped in &result.dropped_sources {
+            let source = &dropped.source;
+            out.push_str(&format!("- [{:?}] {}", source.trust_tier, source.url));
+            if let Some(title) = &source.title {
+                out.push_str(&format!(" — {}", escape_md_inline(title)));
+            }
+            if !dropped.reason.is_empty() {
+                out.push_str(&format!(": {}", escape_md_inline(&dropped.reason)));
+            }
+            out.push('\n');
+        }
+    }
+
+    if !result.follow_ups.is_empty() {
         out.push('\n');
+        out.push_str("## Next steps\n\n");
+        for query in &result.follow_ups {
+            out.push_str("- ");
+            out.push_str(&escape_md_inline(query));
+            out.push('\n');
+        }
     }
 
-    if included_count < request.min_sources {
+    if included.len() < request.min_sources || !result.notes.is_empty() {
         out.push('\n');
         out.push_str("## Notes\n\n");
-        out.push_str("- `min_sources` を満たせなかった。\n");
-        out.push_str("- 収集制約（`max_pages` / `max_depth` / `max_elapsed`）を見直す。\n");
+        for note in &result.notes {
+            out.push_str("- ");
+            out.push_str(note);
+            out.push('\n');
+        }
+        if included.len() < request.min_sources {
+            out.push_str("- `min_sources` を満たせなかった。\n");
+            out.push_str("- 収集制約（`max_pages` / `max_depth` / `max_elapsed`）を見直す。\n");
+        }
+    }
+
+    if !result.failures.is_empty()
+        || !result.refused_hosts.is_empty()
+        || !result.excluded_hosts.is_empty()
+    {
+        out.push('\n');
+        out.push_str(&compose_failures(
+            &result.failures,
+            &result.refused_hosts,
+            &result.excluded_hosts,
+        ));
     }
 
+    if request.show_stats {
+        out.push('\n');
+        out.push_str(&compose_stats(&result.stats));
+    }
+
+    out
+}
+
+/// Sources in Findings order. With subtopics, each source comes with `Some(title)` when
+/// it opens its subtopic and `Some(None)` otherwise; the flat layout yields `None`.
+fn findings_order(result: &CrawlResult) -> Vec<(Option<Option<&str>>, &Source)> {
+    if result.subtopics.is_empty() {
+        return result.sources.iter().map(|source| (None, source)).collect();
+    }
+    result
+        .subtopics
+        .iter()
+        .flat_map(|subtopic| {
+            subtopic
+                .sources
+                .iter()
+                .filter_map(|&id| result.sources.get(id))
+                .enumerate()
+                .map(|(position, source)| {
+                    let title = (position == 0).then_some(subtopic.title.as_str());
+                    (Some(title), source)
+                })
+        })
+        .collect()
+}
+
+/// `## Answer`: the answer's paragraphs, then the sources it cites by fetch order with links.
+fn compose_answer(answer: &Answer, sources: &[&Source]) -> String {
+    let mut out = String::from("## Answer\n\n");
+    for paragraph in &answer.paragraphs {
+        out.push_str(&escape_md_inline(&normalize_text(paragraph)));
+        out.push_str("\n\n");
+    }
+    let citations = answer
+        .sources
+        .iter()
+        .filter_map(|&id| sources.get(id))
+        .map(|source| format!("[#{}]({})", source.fetch_order, source.url))
+        .collect::<Vec<_>>();
+    out.push_str(&format!("Sources: {}\n", citations.join(", ")));
+    out
+}
+
+/// `## Timeline`: one bullet per event, citing sources by fetch order with links.
+fn compose_timeline(events: &[TimelineEvent], sources: &[&Source]) -> String {
+    let mut out = String::from("## Timeline\n\n");
+    for event in events {
+        let citations = event
+            .sources
+            .iter()
+            .filter_map(|&id| sources.get(id))
+            .map(|source| format!("[#{}]({})", source.fetch_order, source.url))
+            .collect::<Vec<_>>();
+        out.push_str(&format!(
+            "- {} — {} ({})\n",
+            event.date,
+            escape_md_inline(&normalize_text(&event.event)),
+            citations.join(", ")
+        ));
+    }
+    out
+}
+
+fn compose_failures(
+    failures: &[CrawlFailure],
+    refused_hosts: &[RefusedHost],
+    excluded_hosts: &[ExcludedHost],
+) -> String {
+    let mut out = String::from("## Failures\n\n");
+    for failure in failures {
+        out.push_str(&format!(
+            "- [{:?}] {}: {}\n",
+            failure.stage,
+            failure.url,
+            escape_md_inline(&failure.message)
+        ));
+    }
+    if !refused_hosts.is_empty() {
+        out.push_str(
+            "\nHosts that kept refusing requests were skipped for the rest of the crawl:\n\n",
+        );
+        for refused in refused_hosts {
+            out.push_str(&format!(
+                "- {host}: answered {status} to {refusals} requests in a row. \
+                 To stop requesting it, add `[hosts.\"{host}\"]` with `max_pages = 0` to the config file, \
+                 or raise its `min_interval`.\n",
+                host = refused.host,
+                status = refused.status,
+                refusals = refused.refusals,
+            ));
+        }
+    }
+    if !excluded_hosts.is_empty() {
+        out.push_str("\nHosts excluded for the rest of the crawl after a takedown signal:\n\n");
+        for excluded in excluded_hosts {
+            out.push_str(&format!(
+                "- {}: answered {} at {}\n",
+                excluded.host, excluded.status, excluded.url
+            ));
+        }
+    }
+    out
+}
+
+fn compose_stats(stats: &CrawlStats) -> String {
+    let mut out = String::from("## Stats\n\n");
+    out.push_str(&format!("- Pages fetched: {}\n", stats.pages_fetched));
+    out.push_str(&format!("- LLM calls: {}\n", stats.llm_calls));
+    out.push_str(&format!(
+        "- Hub pages (not counted as sources): {}\n",
+        stats.hub_pages
+    ));
+    out.push_str(&format!(
+        "- Pagination pages appended: {}\n",
+        stats.pagination_pages
+    ));
+    out.push_str(&format!(
+        "- Language variants skipped: {}\n",
+        stats.language_variants_skipped
+    ));
+    out.push_str(&format!("- Paywalled pages: {}\n", stats.paywalled_pages));
+    out.push_str(&format!(
+        "- Pages excluded by noindex: {}\n",
+        stats.noindex_pages
+    ));
+    out.push_str(&format!(
+        "- Pages not followed by nofollow: {}\n",
+        stats.nofollow_pages
+    ));
+    out.push_str(&format!(
+        "- Duplicate links skipped: {}\n",
+        stats.duplicate_links_skipped
+    ));
+    out.push_str(&format!(
+        "- Pages reused from cache: {}\n",
+        stats.cache_not_modified
+    ));
+    out.push_str(&format!(
+        "- Selections reused from cache: {}\n",
+        stats.selection_cache_hits
+    ));
+    if stats.shared_fetches > 0 {
+        out.push_str(&format!(
+            "- Pages shared with other queries: {}\n",
+            stats.shared_fetches
+        ));
+    }
+    out.push_str(&format!("- Bytes downloaded: {}", stats.bytes_downloaded));
+    if stats.download_budget_exhausted {
+        out.push_str(" (download budget exhausted)");
+    }
+    out.push('\n');
+    for (host, bytes) in &stats.bytes_by_host {
+        out.push_str(&format!("  - {host}: {bytes}\n"));
+    }
+    if !stats.host_politeness.is_empty() {
+        out.push_str("- Host politeness:\n");
+        for host in &stats.host_politeness {
+            out.push_str(&format!("  - {}\n", host.describe()));
+        }
+    }
+    if let Some(connections) = &stats.connections {
+        out.push_str(&format!(
+            "- Connections opened: {} for {} requests ({} reused)\n",
+            connections.connections_opened,
+            connections.requests,
+            connections.reused()
+        ));
+    }
+    out.push_str(&format!(
+        "- Frontier peak: {} URLs (~{} bytes, {} evicted)\n",
+        stats.frontier_peak_len, stats.frontier_peak_bytes, stats.frontier_evicted
+    ));
+    out.push_str(&format!(
+        "- Visited: {} URLs (~{} bytes, {} forgotten)\n",
+        stats.visited_len, stats.visited_bytes, stats.visited_evicted
+    ));
+    out
+}
+
+fn compose_outline(outline: &[Heading]) -> String {
+    let min_level = outline.iter().map(|h| h.level).min().unwrap_or(1);
+    let mut out = String::from("Outline:\n\n");
+    for heading in outline {
+        let indent = usize::from(heading.level - min_level) * 2;
+        out.push_str(&" ".repeat(indent));
+        out.push_str("- ");
+        out.push_str(&escape_md_inline(&heading.text));
+        out.push('\n');
+    }
+    out
+}
+
+fn compose_sources_table(sources: &[&Source]) -> String {
+    let mut out = String::new();
+    out.push_str("| # | Tier | Title | URL | Date | License | Depth | Via |\n");
+    out.push_str("|---|------|-------|-----|------|---------|-------|-----|\n");
+    for source in sources {
+        out.push_str(&format!(
+            "| {} | {:?} | {} | {} | {} | {} | {} | {} |\n",
+            source.fetch_order,
+            source.trust_tier,
+            escape_md_table_cell(source.title.as_deref().unwrap_or("")),
+            escape_md_table_cell(source.url.as_str()),
+            escape_md_table_cell(source.published.as_deref().unwrap_or("")),
+            escape_md_table_cell(source.license.as_deref().unwrap_or("")),
+            source.depth,
+            escape_md_table_cell(source.parent.as_ref().map(Url::as_str).unwrap_or("")),
+        ));
+    }
     out
 }
 
-fn normalize_url(url: &Url) -> String {
+/// `#3, depth 1 via <parent>` or `#1, seed`.
+fn crawl_path(source: &Source) -> String {
+    match &source.parent {
+        Some(parent) => format!(
+            "#{}, depth {} via {}",
+            source.fetch_order, source.depth, parent
+        ),
+        None => format!("#{}, seed", source.fetch_order),
+    }
+}
+
+/// Query parameters that only track the referrer and never change the page.
+const TRACKING_PARAMS: &[&str] = &["fbclid", "gclid", "mc_cid", "mc_eid", "msclkid"];
+
+/// Canonical key used for visited and queued checks: drops the fragment, tracking
+/// parameters, an empty query, `index.html`-style file names and a trailing slash.
+/// Scheme, host and the remaining query are kept as they are.
+pub(crate) fn normalize_url(url: &Url) -> String {
     let mut normalized = url.clone();
     normalized.set_fragment(None);
+
+    if normalized.query().is_some() {
+        let kept = normalized
+            .query_pairs()
+            .filter(|(name, _)| {
+                !name.starts_with("utm_") && !TRACKING_PARAMS.contains(&name.as_ref())
+            })
+            .map(|(name, value)| (name.into_owned(), value.into_owned()))
+            .collect::<Vec<_>>();
+        if kept.is_empty() {
+            normalized.set_query(None);
+        } else {
+            normalized.query_pairs_mut().clear().extend_pairs(kept);
+        }
+    }
+
+    let path = normalized.path();
+    let trimmed = ["index.html", "index.htm", "index.php"]
+        .iter()
+        .find_map(|index| path.strip_suffix(index).filter(|rest| rest.ends_with('/')))
+        .unwrap_or(path);
+    let trimmed = match trimmed.strip_suffix('/') {
+        Some(rest) if !rest.is_empty() => rest,
+        _ => trimmed,
+    }
+    .to_owned();
+    normalized.set_path(&trimmed);
     normalized.to_string()
 }
 
@@ -345,116 +3154,41 @@ fn is_local_ipv4(ip: std::net::Ipv4Addr) -> bool {
         || ip.is_unspecified()
 }
 
-#[derive(Debug, Clone)]
-pub struct FetchedPage {
-    pub html: String,
-    pub links: Vec<Url>,
-    pub robots_delay: Duration,
-}
-
-pub trait PageFetcher {
-    fn fetch(&self, url: &Url) -> anyhow::Result<FetchedPage>;
-}
-
-struct SpiderPageFetcher {
-    runtime: crate::spider_rs::tokio::runtime::Runtime,
-}
-
-impl SpiderPageFetcher {
-    fn new() -> anyhow::Result<Self> {
-        let runtime = crate::spider_rs::tokio::runtime::Runtime::new()
-            .context("build tokio runtime for spider")?;
-        Ok(Self { runtime })
-    }
-
-    fn fetch_with_spider(&self, url: &Url) -> anyhow::Result<FetchedPage> {
-        let runtime = &self.runtime;
-
-        let mut website = crate::spider_rs::website::Website::new(url.as_str());
-        website
-            .with_respect_robots_txt(true)
-            .with_user_agent(Some(USER_AGENT))
-            .with_request_timeout(Some(DEFAULT_REQUEST_TIMEOUT))
-            .with_max_bytes_allowed(Some(MAX_RESPONSE_BYTES as u64))
-            .with_external_domains(Some(std::iter::once("*".to_owned())))
-            .with_limit(1);
-
-        let (client, control) = runtime.block_on(async { website.setup().await });
-
-        let robots_delay = website.get_delay();
-
-        if !website.is_allowed_robots(url.as_str()) {
-            anyhow::bail!("blocked by robots.txt");
-        }
-
-        let mut page = runtime.block_on(async {
-            crate::spider_rs::page::Page::new_page(url.as_str(), &client).await
-        });
-
-        if let Some((_state, join)) = control {
-            join.abort();
-        }
-
-        if !page.status_code.is_success() {
-            anyhow::bail!("http status: {}", page.status_code);
-        }
-
-        page.set_external(website.configuration.external_domains_caseless.clone());
-
-        let selectors = crate::spider_rs::page::get_page_selectors(url.as_str(), true, true);
-        let base = Some(Box::new(url.clone()));
-        let links = runtime.block_on(async { page.links(&selectors, &base).await });
-
-        let mut out_links = Vec::<Url>::new();
-        for link in links {
-            let Ok(mut parsed) = Url::parse(link.as_ref()) else {
-                continue;
-            };
-            parsed.set_fragment(None);
-            if matches!(parsed.scheme(), "http" | "https") {
-                out_links.push(parsed);
-            }
-        }
-        out_links.sort_by(|a, b| a.as_str().cmp(b.as_str()));
-        out_links.dedup_by(|a, b| a.as_str() == b.as_str());
-
-        Ok(FetchedPage {
-            html: page.get_html(),
-            links: out_links,
-            robots_delay,
-        })
-    }
-}
-
-impl PageFetcher for SpiderPageFetcher {
-    fn fetch(&self, url: &Url) -> anyhow::Result<FetchedPage> {
-        self.fetch_with_spider(url)
-    }
-}
-
-fn extract_excerpt_and_anchor_map(
-    base_url: &Url,
-    html: &str,
-) -> anyhow::Result<(String, HashMap<String, String>)> {
-    let cleaned_html = strip_tag_blocks(html, "noscript");
-    let doc = Html::parse_document(&cleaned_html);
+fn extract_page(base_url: &Url, html: &str) -> anyhow::Result<ExtractedPage> {
+    let mut doc = Html::parse_document(html);
+    let paywall = paywall::detect_paywall(&doc)?;
+    let robots_tags = RobotsTags::from_meta(&doc);
+    remove_non_content_nodes(&mut doc)?;
 
     let content_root = select_content_root(&doc)?;
     let link_selector = Selector::parse("a[href]")
         .map_err(|err| anyhow::anyhow!("parse selector a[href]: {err:?}"))?;
 
-    let mut raw_text = String::new();
-    for piece in content_root.text() {
-        raw_text.push_str(piece);
-        raw_text.push(' ');
-        if raw_text.len() >= MAX_EXCERPT_RAW_BYTES {
-            break;
+    // Tables keep their cell boundaries in the excerpt. The raw text covers a page
+    // whose first block alone is over `MAX_TEXT_BYTES`.
+    let text = extract_text_with_tables(content_root);
+    let excerpt = if text.is_empty() {
+        let mut raw_text = String::new();
+        for piece in content_root.text() {
+            raw_text.push_str(piece);
+            raw_text.push(' ');
+            if raw_text.len() >= MAX_EXCERPT_RAW_BYTES {
+                break;
+            }
         }
-    }
-    let excerpt = truncate_chars(&normalize_text(&raw_text), MAX_EXCERPT_CHARS);
+        truncate_chars(&normalize_text(&raw_text), MAX_EXCERPT_CHARS)
+    } else {
+        truncate_chars(&normalize_text(&text), MAX_EXCERPT_CHARS)
+    };
 
+    let visible_chars = |text: &str| text.chars().filter(|c| !c.is_whitespace()).count();
+    let text_chars = content_root.text().map(visible_chars).sum::<usize>();
+    let mut link_count = 0;
+    let mut link_chars = 0;
     let mut anchor_text_by_url = HashMap::<String, String>::new();
     for node in content_root.select(&link_selector) {
+        link_count += 1;
+        link_chars += node.text().map(visible_chars).sum::<usize>();
         let Some(href) = node.value().attr("href") else {
             continue;
         };
@@ -473,33 +3207,264 @@ fn extract_excerpt_and_anchor_map(
         }
     }
 
-    Ok((excerpt, anchor_text_by_url))
+    Ok(ExtractedPage {
+        title: extract_title(&doc)?,
+        alternates: extract_hreflang_alternates(&doc, base_url)?,
+        published: extract_published_date(&doc)?,
+        paywall,
+        robots_tags,
+        license: license::detect_license(&doc)?,
+        outline: extract_outline(content_root)?,
+        excerpt,
+        text,
+        anchor_text_by_url,
+        link_count,
+        link_density: if text_chars == 0 {
+            0.0
+        } else {
+            link_chars as f64 / text_chars as f64
+        },
+    })
+}
+
+fn extract_text_with_tables(content_root: ElementRef<'_>) -> String {
+    let mut blocks = Vec::<String>::new();
+    let mut pending = String::new();
+    collect_text_blocks(content_root, &mut blocks, &mut pending);
+    flush_text_block(&mut blocks, &mut pending);
+
+    let mut out = String::new();
+    for block in blocks {
+        if out.len() + block.len() > MAX_TEXT_BYTES {
+            break;
+        }
+        if !out.is_empty() {
+            out.push_str("\n\n");
+        }
+        out.push_str(&block);
+    }
+    out
+}
+
+fn collect_text_blocks(element: ElementRef<'_>, blocks: &mut Vec<String>, pending: &mut String) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => {
+                pending.push_str(text);
+                pending.push(' ');
+            }
+            Node::Element(_) => {
+                let Some(child) = ElementRef::wrap(child) else {
+                    continue;
+                };
+                if child.value().name() == "table"
+                    && let Some(table) = render_markdown_table(child)
+                {
+                    flush_text_block(blocks, pending);
+                    blocks.push(table);
+                    continue;
+                }
+                collect_text_blocks(child, blocks, pending);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn flush_text_block(blocks: &mut Vec<String>, pending: &mut String) {
+    let text = normalize_text(pending);
+    if !text.is_empty() {
+        blocks.push(text);
+    }
+    pending.clear();
 }
 
-fn strip_tag_blocks(html: &str, tag_name: &str) -> String {
-    let tag_lc = tag_name.to_ascii_lowercase();
-    let open_pat = format!("<{tag_lc}");
-    let close_pat = format!("</{tag_lc}>");
+/// Renders a `<table>` as a Markdown table, or `None` when it is too wide to be useful.
+fn render_markdown_table(table: ElementRef<'_>) -> Option<String> {
+    let mut rows = Vec::<Vec<String>>::new();
+    collect_table_rows(table, &mut rows);
+    if rows.is_empty() {
+        return None;
+    }
+
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    if columns == 0 || columns > MAX_TABLE_COLUMNS {
+        return None;
+    }
+
+    let mut out = String::new();
+    for (idx, row) in rows.iter().take(MAX_TABLE_ROWS + 1).enumerate() {
+        out.push('|');
+        for col in 0..columns {
+            out.push(' ');
+            out.push_str(row.get(col).map(String::as_str).unwrap_or(""));
+            out.push_str(" |");
+        }
+        out.push('\n');
+        if idx == 0 {
+            out.push('|');
+            out.push_str(&"---|".repeat(columns));
+            out.push('\n');
+        }
+    }
+    out.pop();
+    Some(out)
+}
 
-    let lower = html.to_ascii_lowercase();
-    let mut out = String::with_capacity(html.len());
-    let mut idx = 0usize;
+fn collect_table_rows(element: ElementRef<'_>, rows: &mut Vec<Vec<String>>) {
+    for child in element.children().filter_map(ElementRef::wrap) {
+        match child.value().name() {
+            "thead" | "tbody" | "tfoot" => collect_table_rows(child, rows),
+            "tr" => {
+                let cells = child
+                    .children()
+                    .filter_map(ElementRef::wrap)
+                    .filter(|cell| matches!(cell.value().name(), "td" | "th"))
+                    .map(|cell| {
+                        let text = cell.text().collect::<Vec<_>>().join(" ");
+                        escape_md_table_cell(&truncate_chars(
+                            &normalize_text(&text),
+                            MAX_TABLE_CELL_CHARS,
+                        ))
+                    })
+                    .collect::<Vec<_>>();
+                if !cells.is_empty() {
+                    rows.push(cells);
+                }
+            }
+            _ => {}
+        }
+    }
+}
 
-    while let Some(open_rel) = lower[idx..].find(&open_pat) {
-        let open_idx = idx + open_rel;
-        out.push_str(&html[idx..open_idx]);
+fn extract_outline(content_root: ElementRef<'_>) -> anyhow::Result<Vec<Heading>> {
+    let heading_selector = Selector::parse("h1, h2, h3")
+        .map_err(|err| anyhow::anyhow!("parse selector h1, h2, h3: {err:?}"))?;
 
-        let search_from = open_idx + open_pat.len();
-        let Some(close_rel) = lower[search_from..].find(&close_pat) else {
-            idx = html.len();
+    let mut outline = Vec::new();
+    for node in content_root.select(&heading_selector) {
+        let level = match node.value().name() {
+            "h1" => 1,
+            "h2" => 2,
+            _ => 3,
+        };
+        let text = node.text().collect::<Vec<_>>().join(" ");
+        let text = truncate_chars(&normalize_text(&text), MAX_HEADING_CHARS);
+        if text.is_empty() {
+            continue;
+        }
+        outline.push(Heading { level, text });
+        if outline.len() >= MAX_OUTLINE_HEADINGS {
             break;
+        }
+    }
+
+    Ok(outline)
+}
+
+/// `<link rel="alternate" hreflang="…">` variants, lowercased language tag first.
+fn extract_hreflang_alternates(doc: &Html, base_url: &Url) -> anyhow::Result<Vec<(String, Url)>> {
+    let selector = Selector::parse("link[rel][hreflang][href]")
+        .map_err(|err| anyhow::anyhow!("parse selector hreflang: {err:?}"))?;
+    Ok(doc
+        .select(&selector)
+        .filter(|node| {
+            node.value().attr("rel").is_some_and(|rel| {
+                rel.split_ascii_whitespace()
+                    .any(|r| r.eq_ignore_ascii_case("alternate"))
+            })
+        })
+        .filter_map(|node| {
+            let lang = node.value().attr("hreflang")?.trim().to_ascii_lowercase();
+            let mut url = base_url.join(node.value().attr("href")?).ok()?;
+            url.set_fragment(None);
+            matches!(url.scheme(), "http" | "https").then_some((lang, url))
+        })
+        .collect())
+}
+
+/// Whether an `hreflang` tag such as `ja-jp` matches a preferred language such as `ja`.
+fn language_matches(hreflang: &str, preferred: &str) -> bool {
+    let preferred = preferred.trim().to_ascii_lowercase();
+    hreflang == preferred
+        || hreflang
+            .strip_prefix(preferred.as_str())
+            .is_some_and(|rest| rest.starts_with('-'))
+}
+
+fn extract_title(doc: &Html) -> anyhow::Result<Option<String>> {
+    let og_selector = Selector::parse(r#"meta[property="og:title"]"#)
+        .map_err(|err| anyhow::anyhow!("parse selector og:title: {err:?}"))?;
+    let title_selector =
+        Selector::parse("title").map_err(|err| anyhow::anyhow!("parse selector title: {err:?}"))?;
+
+    let title = doc
+        .select(&title_selector)
+        .next()
+        .map(|node| node.text().collect::<String>())
+        .or_else(|| {
+            doc.select(&og_selector)
+                .next()
+                .and_then(|node| node.value().attr("content"))
+                .map(str::to_owned)
+        })
+        .map(|title| truncate_chars(&normalize_text(&title), 200))
+        .filter(|title| !title.is_empty());
+
+    Ok(title)
+}
+
+fn extract_published_date(doc: &Html) -> anyhow::Result<Option<String>> {
+    const CANDIDATES: [(&str, &str); 5] = [
+        (r#"meta[property="article:published_time"]"#, "content"),
+        (r#"meta[itemprop="datePublished"]"#, "content"),
+        (r#"meta[name="date"]"#, "content"),
+        (r#"meta[name="dc.date"]"#, "content"),
+        ("time[datetime]", "datetime"),
+    ];
+
+    for (selector, attr) in CANDIDATES {
+        let selector = Selector::parse(selector)
+            .map_err(|err| anyhow::anyhow!("parse selector {selector}: {err:?}"))?;
+        let Some(value) = doc
+            .select(&selector)
+            .next()
+            .and_then(|node| node.value().attr(attr))
+        else {
+            continue;
         };
-        let close_end = search_from + close_rel + close_pat.len();
-        idx = close_end;
+        if let Some(date) = parse_iso_date_prefix(value) {
+            return Ok(Some(date));
+        }
     }
 
-    out.push_str(&html[idx..]);
-    out
+    Ok(None)
+}
+
+fn parse_iso_date_prefix(value: &str) -> Option<String> {
+    let date = value.trim().get(..10)?;
+    let bytes = date.as_bytes();
+    let is_date = bytes.iter().enumerate().all(|(i, b)| match i {
+        4 | 7 => *b == b'-',
+        _ => b.is_ascii_digit(),
+    });
+    is_date.then(|| date.to_owned())
+}
+
+/// Detaches elements that never carry readable text so they don't leak into excerpts or links.
+fn remove_non_content_nodes(doc: &mut Html) -> anyhow::Result<()> {
+    let selector = Selector::parse(NON_CONTENT_SELECTOR)
+        .map_err(|err| anyhow::anyhow!("parse selector {NON_CONTENT_SELECTOR}: {err:?}"))?;
+    let ids = doc
+        .select(&selector)
+        .map(|node| node.id())
+        .collect::<Vec<_>>();
+    for id in ids {
+        if let Some(mut node) = doc.tree.get_mut(id) {
+            node.detach();
+        }
+    }
+    Ok(())
 }
 
 fn select_content_root<'a>(doc: &'a Html) -> anyhow::Result<ElementRef<'a>> {
@@ -514,8 +3479,56 @@ fn select_content_root<'a>(doc: &'a Html) -> anyhow::Result<ElementRef<'a>> {
     anyhow::bail!("missing content root");
 }
 
+/// Cleans extracted text: decodes stray numeric entities, applies NFKC, drops invisible
+/// format characters, and collapses whitespace.
 fn normalize_text(text: &str) -> String {
-    text.split_whitespace().collect::<Vec<_>>().join(" ")
+    let decoded = decode_numeric_entities(text);
+    let cleaned = decoded
+        .nfkc()
+        .filter(|c| !is_invisible_format_char(*c))
+        .collect::<String>();
+    cleaned.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn is_invisible_format_char(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00AD}' | '\u{200B}'..='\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2060}'..='\u{2064}' | '\u{FEFF}'
+    )
+}
+
+/// Decodes `&#NNN;` / `&#xHH;` sequences that survive HTML parsing (for example double-escaped markup).
+fn decode_numeric_entities(text: &str) -> Cow<'_, str> {
+    if !text.contains("&#") {
+        return Cow::Borrowed(text);
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("&#") {
+        out.push_str(&rest[..start]);
+        let tail = &rest[start + 2..];
+        let decoded = tail.find(';').filter(|end| *end <= 8).and_then(|end| {
+            let digits = &tail[..end];
+            let code = match digits.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => digits.parse::<u32>().ok()?,
+            };
+            char::from_u32(code).map(|c| (c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &tail[end + 1..];
+            }
+            None => {
+                out.push_str("&#");
+                rest = tail;
+            }
+        }
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
 }
 
 fn truncate_chars(text: &str, max_chars: usize) -> String {
@@ -533,14 +3546,57 @@ fn extract_readable_content(html: &str) -> Option<String> {
         return None;
     }
 
-    let md = htmd::convert(&content_html).ok()?;
+    let (content_html, tables) = take_tables(&content_html);
+    let mut md = htmd::convert(&content_html).ok()?;
     if md.trim().is_empty() {
         return None;
     }
+    for (index, table) in tables.iter().enumerate() {
+        md = md.replacen(&table_placeholder(index), table, 1);
+    }
 
     Some(md)
 }
 
+/// `content_html` with each top-level `<table>` that renders as Markdown swapped for a
+/// placeholder paragraph, and the rendered tables in placeholder order.
+fn take_tables(content_html: &str) -> (String, Vec<String>) {
+    let fragment = Html::parse_fragment(content_html);
+    let Ok(selector) = Selector::parse("table") else {
+        return (content_html.to_owned(), Vec::new());
+    };
+    let mut html = fragment.root_element().inner_html();
+    let mut tables = Vec::new();
+    for table in fragment.select(&selector) {
+        let nested = table
+            .ancestors()
+            .filter_map(ElementRef::wrap)
+            .any(|ancestor| ancestor.value().name() == "table");
+        if nested {
+            continue;
+        }
+        let Some(markdown) = render_markdown_table(table) else {
+            continue;
+        };
+        let outer = table.html();
+        if let Some(start) = html.find(&outer) {
+            let placeholder = format!("<p>{}</p>", table_placeholder(tables.len()));
+            html.replace_range(start..start + outer.len(), &placeholder);
+            tables.push(markdown);
+        }
+    }
+    (html, tables)
+}
+
+/// Letters and digits only, so the Markdown conversion leaves it as is.
+fn table_placeholder(index: usize) -> String {
+    format!("llmspidertable{index}end")
+}
+
+fn escape_md_table_cell(text: &str) -> String {
+    normalize_text(text).replace('|', "\\|")
+}
+
 fn escape_md_inline(text: &str) -> String {
     text.replace('\\', "\\\\")
         .replace('[', "\\[")
@@ -0,0 +1,194 @@
+use std::collections::HashSet;
+
+use url::Url;
+
+use super::front_matter::yaml_string;
+use super::{
+    CrawlResult, Source, UserRequest, compose_outline, escape_md_inline, findings_order,
+    normalize_text,
+};
+
+/// Longest note name, in characters, before `.md`.
+const MAX_NOTE_NAME_CHARS: usize = 100;
+
+/// One markdown file of a note export (`--notes-dir`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Note {
+    /// Note name; the file is `<name>.md` and wiki links read `[[<name>]]`.
+    pub name: String,
+    pub text: String,
+}
+
+/// An index note named after the query, wiki-linking one note per source in Findings
+/// order; the index comes first.
+///
+/// Each source note has YAML front matter, its title and URL, the excerpt, highlights
+/// (timeline events and key terms it backs), and its outline.
+pub fn compose_notes(request: &UserRequest, result: &CrawlResult) -> Vec<Note> {
+    let index_name = note_name(&request.query).unwrap_or_else(|| "Research".to_owned());
+    let mut taken = HashSet::from([index_name.to_lowercase()]);
+    let ordered = findings_order(result)
+        .into_iter()
+        .map(|(subtopic, source)| {
+            let id = result
+                .sources
+                .iter()
+                .position(|candidate| std::ptr::eq(candidate, source))
+                .unwrap_or_default();
+            let base = source
+                .title
+                .as_deref()
+                .and_then(note_name)
+                .unwrap_or_else(|| url_note_name(&source.url));
+            let mut name = base.clone();
+            let mut suffix = 2;
+            // Names are unique ignoring case, for case-insensitive file systems.
+            while !taken.insert(name.to_lowercase()) {
+                name = format!("{base} {suffix}");
+                suffix += 1;
+            }
+            (subtopic, id, source, name)
+        })
+        .collect::<Vec<_>>();
+
+    let mut index = String::from("---\n");
+    index.push_str(&format!("query: {}\n", yaml_string(&request.query)));
+    index.push_str(&format!("sources: {}\n", result.sources.len()));
+    index.push_str("---\n\n");
+    index.push_str(&format!("# {}\n\n", escape_md_inline(&request.query)));
+    if ordered.is_empty() {
+        index.push_str("- No sources collected.\n");
+    }
+    for (subtopic, _, source, name) in &ordered {
+        if let Some(Some(title)) = subtopic {
+            index.push_str(&format!("\n## {}\n\n", escape_md_inline(title)));
+        }
+        index.push_str(&format!(
+            "- [[{name}]] — [{:?}] {}\n",
+            source.trust_tier, source.url
+        ));
+    }
+    if !result.follow_ups.is_empty() {
+        index.push_str("\n## Next steps\n\n");
+        for query in &result.follow_ups {
+            index.push_str(&format!("- {}\n", escape_md_inline(query)));
+        }
+    }
+
+    let mut notes = vec![Note {
+        name: index_name.clone(),
+        text: index,
+    }];
+    for (_, id, source, name) in ordered {
+        notes.push(Note {
+            text: compose_source_note(request, result, id, source, &index_name),
+            name,
+        });
+    }
+    notes
+}
+
+fn compose_source_note(
+    request: &UserRequest,
+    result: &CrawlResult,
+    id: usize,
+    source: &Source,
+    index_name: &str,
+) -> String {
+    let mut out = String::from("---\n");
+    out.push_str(&format!("url: {}\n", yaml_string(source.url.as_str())));
+    out.push_str(&format!("trust_tier: {:?}\n", source.trust_tier));
+    if let Some(published) = &source.published {
+        out.push_str(&format!("published: {}\n", yaml_string(published)));
+    }
+    out.push_str(&format!("query: {}\n", yaml_string(&request.query)));
+    out.push_str("---\n\n");
+    let title = source
+        .title
+        .as_deref()
+        .map(normalize_text)
+        .unwrap_or_else(|| source.url.to_string());
+    out.push_str(&format!("# {}\n\n", escape_md_inline(&title)));
+    out.push_str(&format!("<{}>\n\n", source.url));
+    out.push_str(&format!("Research: [[{index_name}]]\n\n"));
+
+    if !source.excerpt.is_empty() {
+        out.push_str("## Excerpt\n\n");
+        out.push_str(&source.excerpt);
+        out.push_str("\n\n");
+    }
+
+    let events = result
+        .timeline
+        .iter()
+        .filter(|event| event.sources.contains(&id))
+        .map(|event| {
+            format!(
+                "- {} — {}\n",
+                event.date,
+                escape_md_inline(&normalize_text(&event.event))
+            )
+        });
+    let terms = result
+        .key_terms
+        .iter()
+        .filter(|term| term.counts.iter().any(|&(source, _)| source == id))
+        .map(|term| {
+            format!(
+                "- **{}**: {}\n",
+                escape_md_inline(&term.term),
+                escape_md_inline(&normalize_text(&term.definition))
+            )
+        });
+    let highlights = events.chain(terms).collect::<String>();
+    if !highlights.is_empty() {
+        out.push_str("## Highlights\n\n");
+        out.push_str(&highlights);
+        out.push('\n');
+    }
+
+    if !source.outline.is_empty() {
+        out.push_str("## Outline\n\n");
+        out.push_str(
+            compose_outline(&source.outline)
+                .strip_prefix("Outline:\n\n")
+                .unwrap_or_default(),
+        );
+        out.push('\n');
+    }
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    out
+}
+
+/// `text` as a note name: characters that break file names or wiki links become
+/// spaces, whitespace collapses, and long names are cut. `None` when nothing is left.
+fn note_name(text: &str) -> Option<String> {
+    let cleaned = normalize_text(text)
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' | '#' | '^' | '[' | ']' => ' ',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .collect::<String>();
+    let name = cleaned
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(MAX_NOTE_NAME_CHARS)
+        .collect::<String>();
+    let name = name.trim_end().trim_start_matches('.').trim();
+    (!name.is_empty()).then(|| name.to_owned())
+}
+
+/// Host and path, for sources without a usable title.
+fn url_note_name(url: &Url) -> String {
+    note_name(&format!(
+        "{} {}",
+        url.host_str().unwrap_or_default(),
+        url.path()
+    ))
+    .unwrap_or_else(|| "Untitled".to_owned())
+}
@@ -0,0 +1,47 @@
+use reqwest::header::HeaderMap;
+use serde::Serialize;
+use url::Url;
+
+use super::{USER_AGENT, is_allowed_by_robots};
+
+/// Response header of the TDM Reservation Protocol (TDMRep); `1` reserves text and data
+/// mining rights.
+const TDM_RESERVATION: &str = "tdm-reservation";
+
+/// Machine-readable signal by which a site reserves its content from AI use and text and
+/// data mining (`--honor-opt-outs`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum OptOutSignal {
+    /// `/ai.txt` disallows the page for every agent or `llm-spider`.
+    #[serde(rename = "ai.txt")]
+    AiTxt,
+    /// The page was served with `TDM-Reservation: 1`.
+    #[serde(rename = "tdm-reservation")]
+    TdmReservation,
+}
+
+impl OptOutSignal {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::AiTxt => "ai.txt",
+            Self::TdmReservation => "tdm-reservation",
+        }
+    }
+}
+
+/// Whether `ai_txt` (the `robots.txt` syntax of `/ai.txt`) lets `url` be used.
+///
+/// Rules are matched as in `robots.txt`, so extension patterns such as
+/// `Disallow: *.html` cover every page ending in `.html`.
+pub fn ai_txt_allows(ai_txt: &str, url: &Url) -> bool {
+    is_allowed_by_robots(ai_txt, USER_AGENT, url.path())
+}
+
+/// Whether `headers` reserve TDM rights (`TDM-Reservation: 1`).
+pub(super) fn tdm_reserved(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(TDM_RESERVATION)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .any(|value| value.trim() == "1")
+}
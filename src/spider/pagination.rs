@@ -0,0 +1,50 @@
+use scraper::{Html, Selector};
+use url::Url;
+
+/// Anchor texts that mean "next page" when a site doesn't mark it with `rel="next"`.
+const NEXT_ANCHOR_TEXTS: &[&str] = &[
+    "next",
+    "next page",
+    "next »",
+    "next ›",
+    "next →",
+    "older posts",
+    "次へ",
+    "次のページ",
+    "次へ »",
+];
+
+/// Finds the next page of a paginated document: `rel="next"` on `<link>` or `<a>`
+/// first, then an anchor whose whole text reads "next page".
+///
+/// Only same-host `http(s)` URLs other than `page_url` itself are returned.
+pub(super) fn find_next_page(page_url: &Url, html: &str) -> Option<Url> {
+    let doc = Html::parse_document(html);
+    let rel_next = Selector::parse("link[rel][href], a[rel][href]").ok()?;
+    let anchors = Selector::parse("a[href]").ok()?;
+
+    let by_rel = doc.select(&rel_next).find(|node| {
+        node.value().attr("rel").is_some_and(|rel| {
+            rel.split_ascii_whitespace()
+                .any(|r| r.eq_ignore_ascii_case("next"))
+        })
+    });
+    let by_text = || {
+        doc.select(&anchors).find(|node| {
+            let text = node.text().collect::<Vec<_>>().join(" ");
+            let text = text
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .to_lowercase();
+            NEXT_ANCHOR_TEXTS.contains(&text.as_str())
+        })
+    };
+
+    let href = by_rel.or_else(by_text)?.value().attr("href")?;
+    let mut next = page_url.join(href).ok()?;
+    next.set_fragment(None);
+    let same_host = next.host_str() == page_url.host_str();
+    let is_http = matches!(next.scheme(), "http" | "https");
+    (same_host && is_http && next != *page_url).then_some(next)
+}
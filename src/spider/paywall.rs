@@ -0,0 +1,101 @@
+use scraper::{Html, Selector};
+use serde_json::Value;
+
+/// Containers that publishers put around gated text or the subscribe prompt.
+const MARKER_SELECTOR: &str = "[class*=paywall], [id*=paywall], [class*=regwall], \
+     [class*=subscriber-only], [class*=premium-content], [class*=locked-content]";
+
+/// Signs that a page hides its article behind a subscription.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Paywall {
+    /// CSS selectors of the gated parts: `hasPart.cssSelector` from JSON-LD plus the
+    /// marker containers.
+    gated: Vec<String>,
+}
+
+impl Paywall {
+    /// `html` with the gated parts removed, leaving the free preview.
+    pub(super) fn strip_gated(&self, html: &str) -> String {
+        let mut doc = Html::parse_document(html);
+        let ids = self
+            .gated
+            .iter()
+            .filter_map(|selector| Selector::parse(selector).ok())
+            .flat_map(|selector| {
+                doc.select(&selector)
+                    .map(|node| node.id())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        for id in ids {
+            if let Some(mut node) = doc.tree.get_mut(id) {
+                node.detach();
+            }
+        }
+        doc.html()
+    }
+}
+
+/// Looks for schema.org `isAccessibleForFree: false` in JSON-LD, a `locked`
+/// `article:content_tier`, or paywall containers. Must run before `<script>` elements
+/// are removed.
+pub(super) fn detect_paywall(doc: &Html) -> anyhow::Result<Option<Paywall>> {
+    let json_ld = parse_selector(r#"script[type="application/ld+json"]"#)?;
+    let content_tier = parse_selector(
+        r#"meta[property="article:content_tier"][content], meta[name="article:content_tier"][content]"#,
+    )?;
+    let markers = parse_selector(MARKER_SELECTOR)?;
+
+    let mut paywalled = false;
+    let mut gated = Vec::new();
+    for script in doc.select(&json_ld) {
+        let text = script.text().collect::<String>();
+        if let Ok(value) = serde_json::from_str::<Value>(&text) {
+            paywalled |= collect_gated_parts(&value, &mut gated);
+        }
+    }
+    paywalled |= doc.select(&content_tier).any(|node| {
+        node.value()
+            .attr("content")
+            .is_some_and(|tier| tier.trim().eq_ignore_ascii_case("locked"))
+    });
+    paywalled |= doc.select(&markers).next().is_some();
+
+    Ok(paywalled.then(|| {
+        gated.push(MARKER_SELECTOR.to_owned());
+        Paywall { gated }
+    }))
+}
+
+/// Walks JSON-LD (including `@graph` and nested objects) for `isAccessibleForFree:
+/// false` and pushes the `cssSelector` of gated `hasPart` entries.
+fn collect_gated_parts(value: &Value, gated: &mut Vec<String>) -> bool {
+    match value {
+        Value::Array(items) => items.iter().fold(false, |found, item| {
+            collect_gated_parts(item, gated) | found
+        }),
+        Value::Object(object) => {
+            let mut found = object.get("isAccessibleForFree").is_some_and(is_false);
+            if found && let Some(selector) = object.get("cssSelector").and_then(Value::as_str) {
+                gated.push(selector.to_owned());
+            }
+            for nested in object.values() {
+                found |= collect_gated_parts(nested, gated);
+            }
+            found
+        }
+        _ => false,
+    }
+}
+
+fn is_false(value: &Value) -> bool {
+    match value {
+        Value::Bool(flag) => !flag,
+        Value::String(text) => text.trim().eq_ignore_ascii_case("false"),
+        _ => false,
+    }
+}
+
+fn parse_selector(selector: &str) -> anyhow::Result<Selector> {
+    Selector::parse(selector).map_err(|err| anyhow::anyhow!("parse selector {selector}: {err:?}"))
+}
@@ -0,0 +1,210 @@
+use url::Url;
+
+use super::content_type::{UrlKind, classify_url};
+use super::frontier::{Frontier, FrontierEntry};
+use super::{
+    CrawlError, Seed, TrustTier, UserRequest, collect_seeds, escape_md_inline, is_allowed,
+    search_queries,
+};
+
+/// What a crawl would start from, computed without fetching any page.
+#[derive(Debug, Clone, Default)]
+pub struct CrawlPlan {
+    /// The web searches the seeds came from.
+    pub searches: Vec<String>,
+    /// Seeds in the order the frontier would pop them.
+    pub seeds: Vec<PlannedSeed>,
+    /// Same diagnoses a crawl would report (search failures, missing seeds).
+    pub notes: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PlannedSeed {
+    pub url: Url,
+    pub trust_tier: TrustTier,
+    pub title: Option<String>,
+    /// Why the crawl would skip this seed; `None` when it would be fetched.
+    pub skip_reason: Option<&'static str>,
+}
+
+/// Runs the web search and orders the seeds as the crawl would, without fetching pages.
+pub fn plan_crawl(
+    request: &UserRequest,
+    openai: &dyn crate::openai::OpenAiApi,
+) -> Result<CrawlPlan, CrawlError> {
+    let mut notes = Vec::new();
+    let hits = collect_seeds(request, openai, &mut notes)?;
+
+    let reputation = request
+        .cache_dir
+        .as_deref()
+        .map(crate::cache::ReputationStore::open)
+        .transpose()
+        .map_err(CrawlError::Cache)?;
+    let mut titles = std::collections::HashMap::new();
+    let mut frontier = Frontier::new(request.max_frontier);
+    for Seed { hit, rank } in hits {
+        titles.insert(hit.url.to_string(), hit.title);
+        let trust_tier = request.merge_trust_tier(&hit.url, hit.trust_tier, reputation.as_ref());
+        let _ = frontier.push(FrontierEntry {
+            limits: request.seed_limits(&hit.url),
+            url: hit.url,
+            depth: 0,
+            tier: trust_tier,
+            rank: Some(rank),
+        });
+    }
+
+    Ok(plan_frontier(
+        request,
+        frontier,
+        |url| titles.get(url.as_str()).cloned().flatten(),
+        notes,
+    ))
+}
+
+/// The plan of a frontier holding only seeds, popped in crawl order.
+pub(super) fn plan_frontier(
+    request: &UserRequest,
+    mut frontier: Frontier,
+    title: impl Fn(&Url) -> Option<String>,
+    notes: Vec<String>,
+) -> CrawlPlan {
+    let mut seeds = Vec::new();
+    while let Some(FrontierEntry {
+        url,
+        tier: trust_tier,
+        ..
+    }) = frontier.pop()
+    {
+        let skip_reason = if !is_allowed(&url, request.allow_local) {
+            Some("not allowed (scheme or local address)")
+        } else if classify_url(&url) == UrlKind::Binary {
+            Some("non-textual resource")
+        } else {
+            None
+        };
+        seeds.push(PlannedSeed {
+            title: title(&url),
+            url,
+            trust_tier,
+            skip_reason,
+        });
+    }
+
+    CrawlPlan {
+        searches: search_queries(request),
+        seeds,
+        notes,
+    }
+}
+
+pub fn compose_plan_markdown(request: &UserRequest, plan: &CrawlPlan) -> String {
+    format!(
+        "# Spider Plan (dry run)\n\n{}",
+        compose_plan_sections(request, plan)
+    )
+}
+
+/// The plan without its title, from `## Query` to the limits and host policies.
+pub(super) fn compose_plan_sections(request: &UserRequest, plan: &CrawlPlan) -> String {
+    let mut out = String::new();
+    out.push_str("## Query\n\n");
+    out.push_str("- ");
+    out.push_str(&escape_md_inline(&request.query));
+    out.push_str("\n\n");
+
+    if plan.searches.len() > 1 || plan.searches.first() != Some(&request.query) {
+        out.push_str("## Searches\n\n");
+        for search in &plan.searches {
+            out.push_str(&format!("- {}\n", escape_md_inline(search)));
+        }
+        out.push('\n');
+    }
+
+    out.push_str("## Frontier\n\n");
+    if plan.seeds.is_empty() {
+        out.push_str("- No seeds found.\n");
+    }
+    for seed in &plan.seeds {
+        out.push_str(&format!("- [{:?}] {}", seed.trust_tier, seed.url));
+        if let Some(title) = &seed.title {
+            out.push_str(&format!(" — {}", escape_md_inline(title)));
+        }
+        if let Some(reason) = seed.skip_reason {
+            out.push_str(&format!(" (skipped: {reason})"));
+        }
+        out.push('\n');
+    }
+
+    if !plan.notes.is_empty() {
+        out.push_str("\n## Notes\n\n");
+        for note in &plan.notes {
+            out.push_str(&format!("- {note}\n"));
+        }
+    }
+
+    out.push_str("\n## Limits\n\n");
+    out.push_str(&format!("- max_pages: {}\n", request.max_pages));
+    out.push_str(&format!(
+        "- max_depth: {} (High: {}, Medium: {}, Low: {})\n",
+        request.max_depth,
+        request.max_depth_for(TrustTier::High),
+        request.max_depth_for(TrustTier::Medium),
+        request.max_depth_for(TrustTier::Low),
+    ));
+    out.push_str(&format!(
+        "- max_elapsed: {}\n",
+        humantime::format_duration(request.max_elapsed)
+    ));
+    if let Some(budget) = request.crawl_budget {
+        out.push_str(&format!(
+            "- crawl_budget: {}\n",
+            humantime::format_duration(budget)
+        ));
+    }
+    out.push_str(&format!("- max_chars: {}\n", request.max_chars));
+    out.push_str(&format!("- min_sources: {}\n", request.min_sources));
+    out.push_str(&format!(
+        "- max_child_candidates: {} (x{} batches)\n",
+        request.max_child_candidates, request.max_candidate_batches
+    ));
+    out.push_str(&format!(
+        "- max_children_per_page: {} (hubs: {})\n",
+        request.max_children_per_page, request.max_children_per_hub
+    ));
+    out.push_str(&format!("- max_frontier: {}\n", request.max_frontier));
+    out.push_str(&format!("- max_visited: {}\n", request.max_visited));
+    out.push_str(&format!("- max_total_bytes: {}\n", request.max_total_bytes));
+    out.push_str(&format!("- allow_local: {}\n", request.allow_local));
+    out.push_str(&format!(
+        "- on_search_failure: {:?}\n",
+        request.on_search_failure
+    ));
+    match &request.cache_dir {
+        Some(dir) => out.push_str(&format!("- cache_dir: `{}`\n", dir.display())),
+        None => out.push_str("- cache_dir: (disabled)\n"),
+    }
+
+    if !request.host_policies.is_empty() {
+        out.push_str("\n## Host policies\n\n");
+        for (host, policy) in &request.host_policies {
+            let mut parts = Vec::new();
+            if let Some(interval) = policy.min_interval {
+                parts.push(format!(
+                    "min_interval {}",
+                    humantime::format_duration(interval)
+                ));
+            }
+            if let Some(max_pages) = policy.max_pages {
+                parts.push(format!("max_pages {max_pages}"));
+            }
+            if let Some(max_concurrent) = policy.max_concurrent {
+                parts.push(format!("max_concurrent {max_concurrent}"));
+            }
+            out.push_str(&format!("- `{host}`: {}\n", parts.join(", ")));
+        }
+    }
+
+    out
+}
@@ -0,0 +1,122 @@
+use std::fmt;
+use std::io::{BufRead, Write};
+use std::sync::Mutex;
+
+use tracing::warn;
+
+use super::UserRequest;
+use super::plan::{CrawlPlan, compose_plan_sections};
+use crate::estimate::{Estimate, compose_estimate_table};
+
+/// Looks at what a crawl will do once its searches are done, before the first page is
+/// fetched. Set through [`UserRequest::plan_review`](super::UserRequest::plan_review).
+pub trait PlanReview: fmt::Debug + Send + Sync {
+    /// `plans` has one plan per request of the batch, in order. `false` stops the crawl
+    /// with [`CrawlError::PlanDeclined`](super::CrawlError::PlanDeclined).
+    fn review(&self, requests: &[UserRequest], plans: &[CrawlPlan]) -> bool;
+}
+
+/// Estimates the OpenAI usage of one request.
+type Estimator = Box<dyn Fn(&UserRequest) -> Estimate + Send + Sync>;
+
+/// The built-in [`PlanReview`] (`--show-plan`, `--confirm-above`): writes each plan with
+/// its cost estimate to the output and, when the estimated cost is above the threshold
+/// or unknown, asks for `y` on the input.
+pub struct PlanConfirmation {
+    estimate: Estimator,
+    confirm_above: Option<f64>,
+    assume_yes: bool,
+    io: Mutex<(Box<dyn BufRead + Send>, Box<dyn Write + Send>)>,
+}
+
+impl fmt::Debug for PlanConfirmation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PlanConfirmation")
+            .field("confirm_above", &self.confirm_above)
+            .field("assume_yes", &self.assume_yes)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PlanConfirmation {
+    /// Writes to stderr and reads the answer from stdin.
+    pub fn stdio(estimate: impl Fn(&UserRequest) -> Estimate + Send + Sync + 'static) -> Self {
+        Self::new(
+            estimate,
+            std::io::BufReader::new(std::io::stdin()),
+            std::io::stderr(),
+        )
+    }
+
+    pub fn new(
+        estimate: impl Fn(&UserRequest) -> Estimate + Send + Sync + 'static,
+        input: impl BufRead + Send + 'static,
+        output: impl Write + Send + 'static,
+    ) -> Self {
+        Self {
+            estimate: Box::new(estimate),
+            confirm_above: None,
+            assume_yes: false,
+            io: Mutex::new((Box::new(input), Box::new(output))),
+        }
+    }
+
+    /// Asks before crawling when the estimated cost in USD is above `usd`.
+    pub fn with_confirm_above(mut self, usd: f64) -> Self {
+        self.confirm_above = Some(usd);
+        self
+    }
+
+    /// Answers the question with yes without reading the input.
+    pub fn with_assume_yes(mut self, assume_yes: bool) -> Self {
+        self.assume_yes = assume_yes;
+        self
+    }
+}
+
+impl PlanReview for PlanConfirmation {
+    /// Closed or unreadable input declines.
+    fn review(&self, requests: &[UserRequest], plans: &[CrawlPlan]) -> bool {
+        let mut io = self.io.lock().unwrap_or_else(|err| err.into_inner());
+        let (input, output) = &mut *io;
+
+        let mut cost_usd = Some(0.0);
+        let mut text = String::new();
+        for (request, plan) in requests.iter().zip(plans) {
+            let estimate = (self.estimate)(request);
+            cost_usd = cost_usd
+                .zip(estimate.cost_usd)
+                .map(|(sum, cost)| sum + cost);
+            text.push_str("# Spider Plan\n\n");
+            text.push_str(&compose_plan_sections(request, plan));
+            text.push_str("\n## Estimate\n\n");
+            text.push_str(&compose_estimate_table(request, &estimate));
+            text.push('\n');
+        }
+        let _ = write!(output, "{text}");
+        let _ = output.flush();
+
+        let Some(threshold) = self.confirm_above else {
+            return true;
+        };
+        let question = match cost_usd {
+            Some(cost) if cost <= threshold => return true,
+            Some(cost) => format!("Estimated cost ${cost:.4} is above ${threshold:.4}."),
+            None => format!("Estimated cost is unknown (threshold ${threshold:.4})."),
+        };
+        if self.assume_yes {
+            let _ = writeln!(output, "{question} Crawling as confirmed in advance.");
+            return true;
+        }
+        let _ = write!(output, "{question} Crawl anyway? [y/N] ");
+        let _ = output.flush();
+        let mut line = String::new();
+        match input.read_line(&mut line) {
+            Ok(_) => matches!(line.trim().to_lowercase().as_str(), "y" | "yes"),
+            Err(err) => {
+                warn!("read plan confirmation: {err}");
+                false
+            }
+        }
+    }
+}
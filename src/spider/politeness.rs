@@ -0,0 +1,313 @@
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tracing::debug;
+use url::Url;
+
+use super::{CancellationToken, HostPolicy, MIN_HOST_INTERVAL, SchedulePolicy};
+use crate::audit::{AuditEvent, AuditLog};
+
+/// Where a host's effective interval or concurrency limit came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolitenessSource {
+    /// The built-in minimum interval.
+    Default,
+    /// A `[hosts]` rule of the config file.
+    Config,
+    /// The host's `robots.txt` (`Crawl-delay` / `Request-rate`).
+    Robots,
+    /// A custom [`SchedulePolicy`].
+    Schedule,
+}
+
+impl PolitenessSource {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::Config => "config",
+            Self::Robots => "robots.txt",
+            Self::Schedule => "schedule",
+        }
+    }
+}
+
+/// The pacing applied to one host, as the crawl ended (`CrawlStats::host_politeness`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HostPoliteness {
+    pub host: String,
+    /// Page requests sent to the host, each paced, by every query of a batch.
+    pub requests: usize,
+    /// `HEAD`, `robots.txt`, and `ai.txt` requests sent to the host. They go out within
+    /// a page request's turn rather than being paced on their own.
+    pub unpaced_requests: usize,
+    #[serde(with = "humantime_serde")]
+    pub min_interval: Duration,
+    pub interval_source: PolitenessSource,
+    /// Requests allowed in flight at once; `None` sets no limit.
+    pub max_concurrent: Option<usize>,
+    pub concurrency_source: Option<PolitenessSource>,
+}
+
+impl HostPoliteness {
+    /// One line of the stats section, e.g. `example.com: 2s between requests (robots.txt)`.
+    pub(super) fn describe(&self) -> String {
+        let mut line = format!(
+            "{}: {} between requests ({})",
+            self.host,
+            humantime::format_duration(self.min_interval),
+            self.interval_source.label()
+        );
+        if let (Some(max), Some(source)) = (self.max_concurrent, self.concurrency_source) {
+            line.push_str(&format!(", at most {max} at a time ({})", source.label()));
+        }
+        line.push_str(&format!(", {} requests", self.requests));
+        if self.unpaced_requests > 0 {
+            line.push_str(&format!(
+                " (+{} HEAD, robots.txt, or ai.txt)",
+                self.unpaced_requests
+            ));
+        }
+        line
+    }
+}
+
+/// Per-host request pacing and page caps.
+///
+/// Every host waits at least the minimum interval the [`SchedulePolicy`] gives it,
+/// which sees any `robots.txt` crawl-delay recorded for that host. Page caps come from
+/// the configured [`HostPolicy::max_pages`].
+pub(super) struct Politeness<'a> {
+    policies: &'a BTreeMap<String, HostPolicy>,
+    schedule: &'a dyn SchedulePolicy,
+    /// Cuts the wait for a host's turn short.
+    cancellation: Option<&'a CancellationToken>,
+    /// Gets a `politeness` line whenever a host's pacing is first applied or changes.
+    audit: Option<&'a AuditLog>,
+    hosts: HashMap<String, HostState>,
+}
+
+#[derive(Debug, Default)]
+struct HostState {
+    last_request: Option<Instant>,
+    robots_delay: Duration,
+    robots_max_concurrent: Option<usize>,
+    pages: usize,
+    /// The pacing last written to the audit log.
+    announced: Option<(Duration, Option<usize>)>,
+}
+
+impl<'a> Politeness<'a> {
+    pub(super) fn new(
+        policies: &'a BTreeMap<String, HostPolicy>,
+        schedule: &'a dyn SchedulePolicy,
+        cancellation: Option<&'a CancellationToken>,
+        audit: Option<&'a AuditLog>,
+    ) -> Self {
+        Self {
+            policies,
+            schedule,
+            cancellation,
+            audit,
+            hosts: HashMap::new(),
+        }
+    }
+
+    /// Returns `false` when the host already reached its configured page cap.
+    pub(super) fn admit(&self, url: &Url) -> bool {
+        let Some(host) = host_key(url) else {
+            return true;
+        };
+        let Some(max_pages) = self.policy(&host).and_then(|policy| policy.max_pages) else {
+            return true;
+        };
+        self.hosts.get(&host).map_or(0, |state| state.pages) < max_pages
+    }
+
+    /// Sleeps until the host's minimum interval has passed, then records the request.
+    /// Returns early on cancellation, which the caller checks before fetching.
+    pub(super) fn wait_turn(&mut self, url: &Url) {
+        let Some(host) = host_key(url) else {
+            return;
+        };
+        let min_interval = self.min_interval(&host);
+        self.announce(&host);
+        let state = self.hosts.entry(host).or_default();
+        if let Some(last) = state.last_request {
+            let elapsed = last.elapsed();
+            if elapsed < min_interval {
+                crate::cancel::sleep(self.cancellation, min_interval - elapsed);
+            }
+        }
+        state.last_request = Some(Instant::now());
+        state.pages += 1;
+    }
+
+    /// Records `robots.txt` pacing for the host. Delays only ever grow within a crawl.
+    pub(super) fn record_robots(
+        &mut self,
+        url: &Url,
+        robots_delay: Duration,
+        robots_max_concurrent: Option<usize>,
+    ) {
+        let Some(host) = host_key(url) else {
+            return;
+        };
+        let state = self.hosts.entry(host.clone()).or_default();
+        let changed = robots_delay > state.robots_delay
+            || (robots_max_concurrent.is_some()
+                && robots_max_concurrent != state.robots_max_concurrent);
+        state.robots_delay = state.robots_delay.max(robots_delay);
+        state.robots_max_concurrent = match (state.robots_max_concurrent, robots_max_concurrent) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        if changed {
+            debug!(
+                host = %host,
+                min_interval = ?self.min_interval(&host),
+                max_concurrent = ?self.max_concurrent(&host),
+                "host politeness updated from robots.txt"
+            );
+        }
+    }
+
+    /// The pacing of every host requested so far, by host name. `unpaced` holds the
+    /// fetcher's other requests by host ([`super::PageFetcher::unpaced_requests`]).
+    pub(super) fn host_politeness(&self, unpaced: &BTreeMap<String, usize>) -> Vec<HostPoliteness> {
+        let mut pages = self
+            .hosts
+            .iter()
+            .filter(|(_, state)| state.pages > 0)
+            .map(|(host, state)| (host.as_str(), state.pages))
+            .collect::<BTreeMap<_, _>>();
+        for host in unpaced.keys() {
+            pages.entry(host.as_str()).or_default();
+        }
+        pages
+            .into_iter()
+            .map(|(host, requests)| {
+                let (min_interval, interval_source) = self.interval_with_source(host);
+                let (max_concurrent, concurrency_source) = self.concurrency_with_source(host);
+                HostPoliteness {
+                    host: host.to_owned(),
+                    requests,
+                    unpaced_requests: unpaced.get(host).copied().unwrap_or_default(),
+                    min_interval,
+                    interval_source,
+                    max_concurrent,
+                    concurrency_source,
+                }
+            })
+            .collect()
+    }
+
+    /// Writes the host's pacing to the audit log when it differs from the last line.
+    fn announce(&mut self, host: &str) {
+        let Some(audit) = self.audit else {
+            return;
+        };
+        let (min_interval, interval_source) = self.interval_with_source(host);
+        let (max_concurrent, concurrency_source) = self.concurrency_with_source(host);
+        let state = self.hosts.entry(host.to_owned()).or_default();
+        if state.announced == Some((min_interval, max_concurrent)) {
+            return;
+        }
+        state.announced = Some((min_interval, max_concurrent));
+        audit.record(&AuditEvent::Politeness {
+            host,
+            min_interval_ms: u64::try_from(min_interval.as_millis()).unwrap_or(u64::MAX),
+            interval_source,
+            max_concurrent,
+            concurrency_source,
+        });
+    }
+
+    /// The effective interval and the setting that decided it. A `[hosts]` rule wins a
+    /// tie with `robots.txt`, since the schedule takes the larger of the two.
+    fn interval_with_source(&self, host: &str) -> (Duration, PolitenessSource) {
+        let interval = self.min_interval(host);
+        let robots_delay = self
+            .hosts
+            .get(host)
+            .map_or(Duration::ZERO, |state| state.robots_delay);
+        let configured = self.policy(host).and_then(|policy| policy.min_interval);
+        let source = if configured == Some(interval) {
+            PolitenessSource::Config
+        } else if !robots_delay.is_zero() && robots_delay == interval {
+            PolitenessSource::Robots
+        } else if interval == MIN_HOST_INTERVAL {
+            PolitenessSource::Default
+        } else {
+            PolitenessSource::Schedule
+        };
+        (interval, source)
+    }
+
+    fn concurrency_with_source(&self, host: &str) -> (Option<usize>, Option<PolitenessSource>) {
+        let max_concurrent = self.max_concurrent(host);
+        let robots = self
+            .hosts
+            .get(host)
+            .and_then(|state| state.robots_max_concurrent);
+        let configured = self.policy(host).and_then(|policy| policy.max_concurrent);
+        let source = max_concurrent.map(|max| {
+            if configured == Some(max) {
+                PolitenessSource::Config
+            } else if robots == Some(max) {
+                PolitenessSource::Robots
+            } else {
+                PolitenessSource::Schedule
+            }
+        });
+        (max_concurrent, source)
+    }
+
+    /// Minimum interval currently applied to the URL's host.
+    pub(super) fn min_interval_for(&self, url: &Url) -> Duration {
+        host_key(url).map_or(MIN_HOST_INTERVAL, |host| self.min_interval(&host))
+    }
+
+    fn max_concurrent(&self, host: &str) -> Option<usize> {
+        let robots = self
+            .hosts
+            .get(host)
+            .and_then(|state| state.robots_max_concurrent);
+        self.schedule.max_concurrent(host, robots)
+    }
+
+    fn min_interval(&self, host: &str) -> Duration {
+        let robots_delay = self
+            .hosts
+            .get(host)
+            .map_or(Duration::ZERO, |state| state.robots_delay);
+        self.schedule.min_interval(host, robots_delay)
+    }
+
+    fn policy(&self, host: &str) -> Option<&'a HostPolicy> {
+        lookup_host_policy(self.policies, host)
+    }
+}
+
+/// Most specific policy for `host`: an exact key wins over a parent-domain key.
+pub(super) fn lookup_host_policy<'a>(
+    policies: &'a BTreeMap<String, HostPolicy>,
+    host: &str,
+) -> Option<&'a HostPolicy> {
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    let mut candidate = host.as_str();
+    loop {
+        if let Some(policy) = policies.get(candidate) {
+            return Some(policy);
+        }
+        let (_, parent) = candidate.split_once('.')?;
+        candidate = parent;
+    }
+}
+
+fn host_key(url: &Url) -> Option<String> {
+    url.host_str()
+        .map(str::to_ascii_lowercase)
+        .filter(|host| !host.is_empty())
+}
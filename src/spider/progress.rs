@@ -0,0 +1,78 @@
+use std::io::{IsTerminal as _, Write as _};
+use std::time::{Duration, Instant};
+
+use url::Url;
+
+use super::UserRequest;
+
+const MAX_URL_CHARS: usize = 60;
+
+/// Single-line crawl status redrawn in place on stderr.
+///
+/// Disabled unless requested and stderr is a terminal, so redirected output stays clean.
+pub(super) struct Progress {
+    enabled: bool,
+    started_at: Instant,
+    max_pages: usize,
+    max_elapsed: Duration,
+}
+
+pub(super) struct ProgressSnapshot<'a> {
+    pub(super) pages: usize,
+    pub(super) frontier_len: usize,
+    pub(super) llm_calls: usize,
+    pub(super) current: Option<&'a Url>,
+}
+
+impl Progress {
+    pub(super) fn new(request: &UserRequest, started_at: Instant) -> Self {
+        Self {
+            enabled: request.show_progress
+                && request.selection_review.is_none()
+                && std::io::stderr().is_terminal(),
+            started_at,
+            max_pages: request.max_pages,
+            max_elapsed: request.max_elapsed,
+        }
+    }
+
+    pub(super) fn update(&self, snapshot: &ProgressSnapshot<'_>) {
+        if !self.enabled {
+            return;
+        }
+        let mut line = format!(
+            "pages {}/{} | {:.1}s/{}s | frontier {} | llm {}",
+            snapshot.pages,
+            self.max_pages,
+            self.started_at.elapsed().as_secs_f64(),
+            self.max_elapsed.as_secs(),
+            snapshot.frontier_len,
+            snapshot.llm_calls,
+        );
+        if let Some(url) = snapshot.current {
+            line.push_str(" | ");
+            line.push_str(&truncate_url(url.as_str()));
+        }
+        let mut stderr = std::io::stderr().lock();
+        let _ = write!(stderr, "\r\x1b[2K{line}");
+        let _ = stderr.flush();
+    }
+
+    pub(super) fn finish(&self) {
+        if !self.enabled {
+            return;
+        }
+        let mut stderr = std::io::stderr().lock();
+        let _ = write!(stderr, "\r\x1b[2K");
+        let _ = stderr.flush();
+    }
+}
+
+fn truncate_url(url: &str) -> String {
+    if url.chars().count() <= MAX_URL_CHARS {
+        return url.to_owned();
+    }
+    let mut out = url.chars().take(MAX_URL_CHARS - 1).collect::<String>();
+    out.push('…');
+    out
+}
@@ -0,0 +1,47 @@
+use scraper::{Html, Selector};
+use url::Url;
+
+/// Meta refresh redirects (and followed frames) from one fetched page before giving up.
+pub(super) const MAX_META_REFRESH_HOPS: usize = 3;
+
+/// Longest refresh delay, in seconds, still treated as a redirect. Interstitials move on
+/// at once; a page that refreshes after minutes is content with a timer.
+const MAX_META_REFRESH_DELAY_SECS: f64 = 5.0;
+
+/// The target of a `<meta http-equiv="refresh" content="0; url=...">` redirect, if it
+/// points somewhere other than `page_url` within `MAX_META_REFRESH_DELAY_SECS`. A
+/// refresh without a URL only reloads the page and is ignored.
+pub(super) fn meta_refresh_target(page_url: &Url, html: &str) -> Option<Url> {
+    let doc = Html::parse_document(html);
+    let selector = Selector::parse("meta[http-equiv][content]").ok()?;
+    let content = doc
+        .select(&selector)
+        .find(|node| {
+            node.value()
+                .attr("http-equiv")
+                .is_some_and(|value| value.trim().eq_ignore_ascii_case("refresh"))
+        })?
+        .value()
+        .attr("content")?;
+
+    // `<delay>[;,] [url=]<target>`, the target optionally quoted.
+    let (delay, target) = content.split_once([';', ','])?;
+    let delay = delay.trim().parse::<f64>().ok()?;
+    if !(0.0..=MAX_META_REFRESH_DELAY_SECS).contains(&delay) {
+        return None;
+    }
+    let target = target.trim();
+    let target = target
+        .get(..3)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("url"))
+        .and_then(|_| target[3..].trim_start().strip_prefix('='))
+        .unwrap_or(target)
+        .trim();
+    let target = target.trim_matches(|c| c == '"' || c == '\'').trim();
+    if target.is_empty() {
+        return None;
+    }
+    let mut target = page_url.join(target).ok()?;
+    target.set_fragment(None);
+    (target != *page_url).then_some(target)
+}
@@ -0,0 +1,172 @@
+use serde::Deserialize;
+use url::Url;
+
+use super::LinkCandidate;
+use crate::trust::TrustTier;
+
+/// Score added to a candidate whose path matches a `boost` pattern, and taken from one
+/// matching a `penalize` pattern.
+const PATTERN_WEIGHT: isize = 2;
+
+/// Query words too common to say anything about a link.
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "for", "how", "in", "is", "of", "on", "or", "the", "to", "what",
+    "with",
+];
+
+/// URL path patterns that make a link candidate more or less worth following
+/// (`[url_patterns]`). A pattern matches when the lowercased path contains it.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UrlPatterns {
+    pub boost: Vec<String>,
+    pub penalize: Vec<String>,
+    /// Host prefixes (such as `docs.`) whose links count as matching `boost`.
+    pub boost_hosts: Vec<String>,
+}
+
+impl Default for UrlPatterns {
+    fn default() -> Self {
+        let owned = |patterns: &[&str]| patterns.iter().map(|p| (*p).to_owned()).collect();
+        Self {
+            boost: owned(&[
+                "/docs/",
+                "/doc/",
+                "/reference/",
+                "/guide/",
+                "/manual/",
+                "/api/",
+            ]),
+            penalize: owned(&[
+                "/tag/",
+                "/tags/",
+                "/category/",
+                "/categories/",
+                "/login",
+                "/signin",
+                "/signup",
+                "/register",
+            ]),
+            boost_hosts: Vec::new(),
+        }
+    }
+}
+
+/// How `UrlPatterns` judge one URL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum PathHint {
+    Prefer,
+    Avoid,
+}
+
+impl PathHint {
+    /// The `path_hint` value passed to link selection.
+    pub(super) fn as_str(self) -> &'static str {
+        match self {
+            Self::Prefer => "prefer",
+            Self::Avoid => "avoid",
+        }
+    }
+}
+
+impl UrlPatterns {
+    /// `Avoid` wins when a path matches both lists, even on a `boost_hosts` host.
+    pub(super) fn hint(&self, url: &Url) -> Option<PathHint> {
+        let path = url.path().to_lowercase();
+        let matches = |patterns: &[String]| {
+            patterns
+                .iter()
+                .any(|pattern| !pattern.is_empty() && path.contains(&pattern.to_lowercase()))
+        };
+        let host = url.host_str().unwrap_or_default().to_lowercase();
+        let boosted_host = self
+            .boost_hosts
+            .iter()
+            .any(|prefix| !prefix.is_empty() && host.starts_with(&prefix.to_lowercase()));
+        if matches(&self.penalize) {
+            Some(PathHint::Avoid)
+        } else if boosted_host || matches(&self.boost) {
+            Some(PathHint::Prefer)
+        } else {
+            None
+        }
+    }
+}
+
+/// Orders `candidates` by relevance and keeps the first `max`.
+///
+/// A candidate scores its provisional tier (same-host links inherit the page's tier,
+/// other hosts rank one tier lower) plus the number of query terms found in its
+/// anchor text or URL path, raised or lowered when its path matches `patterns`.
+/// Equal scores keep document order.
+pub(super) fn keep_most_relevant(
+    candidates: &mut Vec<LinkCandidate>,
+    query: &str,
+    page_url: &Url,
+    page_tier: TrustTier,
+    patterns: &UrlPatterns,
+    max: usize,
+) {
+    let terms = query_terms(query);
+    let mut scored = std::mem::take(candidates)
+        .into_iter()
+        .map(|candidate| {
+            let score = score(&candidate, &terms, page_url, page_tier, patterns);
+            (score, candidate)
+        })
+        .collect::<Vec<_>>();
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    candidates.extend(scored.into_iter().take(max).map(|(_, candidate)| candidate));
+}
+
+fn score(
+    candidate: &LinkCandidate,
+    terms: &[String],
+    page_url: &Url,
+    page_tier: TrustTier,
+    patterns: &UrlPatterns,
+) -> isize {
+    let tier = if candidate.url.host_str() == page_url.host_str() {
+        page_tier
+    } else {
+        match page_tier {
+            TrustTier::High => TrustTier::Medium,
+            TrustTier::Medium | TrustTier::Low => TrustTier::Low,
+            TrustTier::Unverified => TrustTier::Unverified,
+        }
+    };
+    let tier_score = match tier {
+        TrustTier::High => 3,
+        TrustTier::Medium => 2,
+        TrustTier::Low => 1,
+        TrustTier::Unverified => 0,
+    };
+
+    let haystack = format!(
+        "{} {}",
+        candidate.anchor_text.to_lowercase(),
+        candidate.url.path().to_lowercase()
+    );
+    let overlap = terms
+        .iter()
+        .filter(|term| haystack.contains(term.as_str()))
+        .count();
+    let pattern_score = match patterns.hint(&candidate.url) {
+        Some(PathHint::Prefer) => PATTERN_WEIGHT,
+        Some(PathHint::Avoid) => -PATTERN_WEIGHT,
+        None => 0,
+    };
+    tier_score + overlap as isize + pattern_score
+}
+
+fn query_terms(query: &str) -> Vec<String> {
+    let mut terms = query
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| term.chars().count() >= 2 && !STOP_WORDS.contains(term))
+        .map(str::to_owned)
+        .collect::<Vec<_>>();
+    terms.sort();
+    terms.dedup();
+    terms
+}
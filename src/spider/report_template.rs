@@ -0,0 +1,424 @@
+use std::borrow::Cow;
+use std::path::Path;
+
+use anyhow::{Context as _, bail};
+use serde_json::Value;
+
+/// A report layout in a subset of Handlebars (`--report-template`), rendered over the
+/// document `--format json` would print.
+///
+/// Supported: `{{path.to.value}}` (also `{{{…}}}`; nothing is HTML-escaped),
+/// `{{#each}}` over arrays and objects with `this`, `@index`, `@first`, `@last`, and
+/// `@key`, `{{#if}}` / `{{#unless}}` / `{{#with}}` with `{{else}}`, `../` and `@root`
+/// paths, `.length` of arrays, `{{! comments }}`, and `~` whitespace control. Block tags alone on a line
+/// leave no blank line behind. Missing values render as nothing.
+#[derive(Debug, Clone)]
+pub struct ReportTemplate {
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Text(String),
+    Value(PathExpr),
+    Block {
+        helper: Helper,
+        path: PathExpr,
+        body: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Helper {
+    Each,
+    If,
+    Unless,
+    With,
+}
+
+impl Helper {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "each" => Some(Self::Each),
+            "if" => Some(Self::If),
+            "unless" => Some(Self::Unless),
+            "with" => Some(Self::With),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Each => "each",
+            Self::If => "if",
+            Self::Unless => "unless",
+            Self::With => "with",
+        }
+    }
+}
+
+/// `a.b`, `../a`, `@root.a`, `this`, or `@index`.
+#[derive(Debug, Clone)]
+struct PathExpr {
+    up: usize,
+    root: bool,
+    segments: Vec<String>,
+}
+
+impl PathExpr {
+    fn parse(text: &str) -> anyhow::Result<Self> {
+        if text.is_empty() || text.contains(char::is_whitespace) {
+            bail!("unsupported expression: {text:?}");
+        }
+        let mut rest = text;
+        let mut up = 0;
+        while let Some(stripped) = rest.strip_prefix("../") {
+            up += 1;
+            rest = stripped;
+        }
+        let root = match rest.strip_prefix("@root") {
+            Some(stripped) if stripped.is_empty() || stripped.starts_with('.') => {
+                rest = stripped;
+                true
+            }
+            _ => false,
+        };
+        let segments = rest
+            .split('.')
+            .filter(|segment| !segment.is_empty() && *segment != "this")
+            .map(str::to_owned)
+            .collect();
+        Ok(Self { up, root, segments })
+    }
+}
+
+/// A block whose closing tag has not been read yet.
+struct OpenBlock {
+    helper: Helper,
+    path: PathExpr,
+    body: Vec<Node>,
+    /// `Some` after `{{else}}`.
+    otherwise: Option<Vec<Node>>,
+    line: usize,
+}
+
+impl ReportTemplate {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("read report template: {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("parse report template: {}", path.display()))
+    }
+
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let mut nodes = Vec::new();
+        let mut stack = Vec::<OpenBlock>::new();
+        for (token, line) in tokenize(text)? {
+            let node = match token {
+                Token::Text(text) => Node::Text(text),
+                Token::Value(expr) => {
+                    Node::Value(PathExpr::parse(&expr).with_context(|| format!("line {line}"))?)
+                }
+                Token::Open(name, expr) => {
+                    let Some(helper) = Helper::parse(&name) else {
+                        bail!("line {line}: unsupported helper: #{name}");
+                    };
+                    stack.push(OpenBlock {
+                        helper,
+                        path: PathExpr::parse(&expr).with_context(|| format!("line {line}"))?,
+                        body: Vec::new(),
+                        otherwise: None,
+                        line,
+                    });
+                    continue;
+                }
+                Token::Else => {
+                    match stack.last_mut() {
+                        Some(block) if block.otherwise.is_none() => {
+                            block.otherwise = Some(Vec::new());
+                        }
+                        Some(block) => {
+                            bail!("line {line}: second else in #{}", block.helper.name())
+                        }
+                        None => bail!("line {line}: else outside a block"),
+                    }
+                    continue;
+                }
+                Token::Close(name) => {
+                    let Some(block) = stack.pop() else {
+                        bail!("line {line}: /{name} without an open block");
+                    };
+                    if block.helper.name() != name {
+                        bail!("line {line}: /{name} closes #{}", block.helper.name());
+                    }
+                    Node::Block {
+                        helper: block.helper,
+                        path: block.path,
+                        body: block.body,
+                        otherwise: block.otherwise.unwrap_or_default(),
+                    }
+                }
+            };
+            match stack.last_mut() {
+                Some(block) => block
+                    .otherwise
+                    .as_mut()
+                    .unwrap_or(&mut block.body)
+                    .push(node),
+                None => nodes.push(node),
+            }
+        }
+        if let Some(block) = stack.pop() {
+            bail!(
+                "line {}: #{} is never closed",
+                block.line,
+                block.helper.name()
+            );
+        }
+        Ok(Self { nodes })
+    }
+
+    pub fn render(&self, data: &Value) -> String {
+        let mut out = String::new();
+        let mut frames = vec![Frame {
+            value: data,
+            key: None,
+            index: None,
+        }];
+        render_nodes(&self.nodes, &mut frames, &mut out);
+        out
+    }
+}
+
+enum Token {
+    Text(String),
+    Value(String),
+    Open(String, String),
+    Else,
+    Close(String),
+}
+
+/// Splits `text` into text and tags with their line numbers, applying `~` and
+/// standalone-line trimming.
+fn tokenize(text: &str) -> anyhow::Result<Vec<(Token, usize)>> {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+    let mut line = 1;
+    let mut at_line_start = true;
+    let mut trim_start = false;
+    while let Some(start) = rest.find("{{") {
+        let mut before = &rest[..start];
+        let tag_line = line + before.matches('\n').count();
+        let opened = &rest[start + 2..];
+        let (close, opened) = if let Some(opened) = opened.strip_prefix('{') {
+            ("}}}", opened)
+        } else if opened.starts_with("!--") || opened.starts_with("~!--") {
+            ("--}}", opened)
+        } else {
+            ("}}", opened)
+        };
+        let Some(end) = opened.find(close) else {
+            bail!("line {tag_line}: unclosed tag");
+        };
+        let mut inner = &opened[..end];
+        let mut after = &opened[end + close.len()..];
+        line = tag_line + inner.matches('\n').count();
+
+        if let Some(stripped) = inner.strip_prefix('~') {
+            inner = stripped;
+            before = before.trim_end();
+        }
+        let trim_after = match inner.strip_suffix('~') {
+            Some(stripped) => {
+                inner = stripped;
+                true
+            }
+            None => false,
+        };
+        let inner = inner.trim();
+        let token = if inner.starts_with('!') {
+            None
+        } else if let Some(open) = inner.strip_prefix('#') {
+            let (name, expr) = open
+                .split_once(char::is_whitespace)
+                .unwrap_or((open, "this"));
+            Some(Token::Open(name.to_owned(), expr.trim().to_owned()))
+        } else if let Some(name) = inner.strip_prefix('/') {
+            Some(Token::Close(name.trim().to_owned()))
+        } else if inner == "else" {
+            Some(Token::Else)
+        } else if inner.starts_with('>') {
+            bail!("line {tag_line}: partials are not supported");
+        } else {
+            Some(Token::Value(inner.to_owned()))
+        };
+
+        // A block or comment tag alone on its line takes the whole line with it.
+        let indent = before
+            .rfind('\n')
+            .map_or(before, |newline| &before[newline + 1..]);
+        let line_end = after.find('\n');
+        let standalone = !matches!(token, Some(Token::Value(_)))
+            && (at_line_start || before.contains('\n'))
+            && indent.trim().is_empty()
+            && after[..line_end.unwrap_or(after.len())].trim().is_empty();
+        at_line_start = false;
+        if standalone {
+            before = &before[..before.len() - indent.len()];
+            after = match line_end {
+                Some(newline) => {
+                    line += 1;
+                    at_line_start = true;
+                    &after[newline + 1..]
+                }
+                None => "",
+            };
+        }
+
+        push_text(&mut tokens, before, trim_start, tag_line);
+        if let Some(token) = token {
+            tokens.push((token, tag_line));
+        }
+        trim_start = trim_after;
+        rest = after;
+    }
+    push_text(&mut tokens, rest, trim_start, line);
+    Ok(tokens)
+}
+
+fn push_text(tokens: &mut Vec<(Token, usize)>, text: &str, trim_start: bool, line: usize) {
+    let text = if trim_start { text.trim_start() } else { text };
+    if !text.is_empty() {
+        tokens.push((Token::Text(text.to_owned()), line));
+    }
+}
+
+/// One context while rendering; `each` iterations know their position.
+struct Frame<'a> {
+    value: &'a Value,
+    key: Option<&'a str>,
+    /// Index and length of the iterated collection.
+    index: Option<(usize, usize)>,
+}
+
+fn render_nodes<'a>(nodes: &'a [Node], frames: &mut Vec<Frame<'a>>, out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Value(path) => {
+                if let Some(value) = resolve(frames, path) {
+                    display(&value, out);
+                }
+            }
+            Node::Block {
+                helper,
+                path,
+                body,
+                otherwise,
+            } => {
+                let value = resolve(frames, path);
+                let truthy = value.as_deref().is_some_and(is_truthy);
+                match (helper, value) {
+                    (Helper::If, _) => {
+                        render_nodes(if truthy { body } else { otherwise }, frames, out)
+                    }
+                    (Helper::Unless, _) => {
+                        render_nodes(if truthy { otherwise } else { body }, frames, out)
+                    }
+                    (Helper::With, Some(Cow::Borrowed(value))) if truthy => {
+                        frames.push(Frame {
+                            value,
+                            key: None,
+                            index: None,
+                        });
+                        render_nodes(body, frames, out);
+                        frames.pop();
+                    }
+                    (Helper::Each, Some(Cow::Borrowed(value))) if truthy => {
+                        let items = match value {
+                            Value::Array(items) => items.iter().map(|item| (None, item)).collect(),
+                            Value::Object(map) => map
+                                .iter()
+                                .map(|(key, item)| (Some(key.as_str()), item))
+                                .collect(),
+                            _ => Vec::new(),
+                        };
+                        if items.is_empty() {
+                            render_nodes(otherwise, frames, out);
+                        }
+                        let len = items.len();
+                        for (index, (key, item)) in items.into_iter().enumerate() {
+                            frames.push(Frame {
+                                value: item,
+                                key,
+                                index: Some((index, len)),
+                            });
+                            render_nodes(body, frames, out);
+                            frames.pop();
+                        }
+                    }
+                    _ => render_nodes(otherwise, frames, out),
+                }
+            }
+        }
+    }
+}
+
+fn resolve<'a>(frames: &[Frame<'a>], path: &PathExpr) -> Option<Cow<'a, Value>> {
+    let frame = if path.root {
+        frames.first()?
+    } else {
+        frames.get(frames.len().checked_sub(path.up + 1)?)?
+    };
+    let mut value = match path.segments.first().map(String::as_str) {
+        Some("@index") => return Some(Cow::Owned(Value::from(frame.index?.0))),
+        Some("@first") => return Some(Cow::Owned(Value::from(frame.index?.0 == 0))),
+        Some("@last") => {
+            let (index, len) = frame.index?;
+            return Some(Cow::Owned(Value::from(index + 1 == len)));
+        }
+        Some("@key") => return Some(Cow::Owned(Value::from(frame.key?))),
+        _ => frame.value,
+    };
+    for (position, segment) in path.segments.iter().enumerate() {
+        value = match value {
+            Value::Object(map) => map.get(segment)?,
+            Value::Array(items) if segment == "length" => {
+                return (position + 1 == path.segments.len())
+                    .then(|| Cow::Owned(Value::from(items.len())));
+            }
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    Some(Cow::Borrowed(value))
+}
+
+/// Handlebars truthiness: `false`, `null`, `""`, `0`, and `[]` are false.
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(value) => *value,
+        Value::Number(number) => number.as_f64() != Some(0.0),
+        Value::String(text) => !text.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(_) => true,
+    }
+}
+
+/// Strings as they are, arrays joined with `, `, objects as JSON.
+fn display(value: &Value, out: &mut String) {
+    match value {
+        Value::Null => {}
+        Value::String(text) => out.push_str(text),
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    out.push_str(", ");
+                }
+                display(item, out);
+            }
+        }
+        other => out.push_str(&other.to_string()),
+    }
+}
@@ -0,0 +1,152 @@
+use std::fmt;
+use std::io::{BufRead, Write};
+use std::sync::Mutex;
+
+use tracing::warn;
+use url::Url;
+
+use super::LinkCandidate;
+use crate::openai::SelectedLink;
+use crate::trust::TrustTier;
+
+/// Confirmation of each page's link selection before the picks are queued (`--interactive`).
+///
+/// The candidates are listed on the output with the model's picks marked, and one line
+/// of input accepts, replaces, or clears the picks.
+pub struct SelectionReview {
+    io: Mutex<(Box<dyn BufRead + Send>, Box<dyn Write + Send>)>,
+}
+
+impl fmt::Debug for SelectionReview {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SelectionReview").finish_non_exhaustive()
+    }
+}
+
+/// One line of review input.
+enum Answer {
+    Accept,
+    Follow(Vec<usize>),
+}
+
+impl SelectionReview {
+    /// Prompts on stderr and reads answers from stdin.
+    pub fn stdio() -> Self {
+        Self::new(std::io::BufReader::new(std::io::stdin()), std::io::stderr())
+    }
+
+    pub fn new(input: impl BufRead + Send + 'static, output: impl Write + Send + 'static) -> Self {
+        Self {
+            io: Mutex::new((Box::new(input), Box::new(output))),
+        }
+    }
+
+    /// The links to follow from `page`: the model's `selected` picks unless the answer
+    /// names others. Links the user adds are `Medium` trust, since a person vetted them
+    /// but not their source. Closed or unreadable input keeps the picks.
+    pub(super) fn review(
+        &self,
+        page: &Url,
+        candidates: &[LinkCandidate],
+        selected: Vec<SelectedLink>,
+    ) -> Vec<SelectedLink> {
+        let mut io = self.io.lock().unwrap_or_else(|err| err.into_inner());
+        let (input, output) = &mut *io;
+
+        // The picks first, in selection order, then the rest of the candidates.
+        let mut listed = selected
+            .iter()
+            .map(|link| (link.url.clone(), Some(link.trust_tier)))
+            .collect::<Vec<_>>();
+        listed.extend(
+            candidates
+                .iter()
+                .filter(|candidate| !selected.iter().any(|link| link.url == candidate.url))
+                .map(|candidate| (candidate.url.clone(), None)),
+        );
+        let anchor = |url: &Url| {
+            candidates
+                .iter()
+                .find(|candidate| candidate.url == *url)
+                .map_or("", |candidate| candidate.anchor_text.as_str())
+        };
+
+        let mut prompt = format!("\nLinks from {page}:\n");
+        for (index, (url, tier)) in listed.iter().enumerate() {
+            let mark = if tier.is_some() { '*' } else { ' ' };
+            prompt.push_str(&format!("{mark} {:>2}. {url}", index + 1));
+            if let Some(tier) = tier {
+                prompt.push_str(&format!(" [{}]", tier.as_str()));
+            }
+            let anchor = anchor(url);
+            if !anchor.is_empty() {
+                prompt.push_str(&format!("  \"{anchor}\""));
+            }
+            prompt.push('\n');
+        }
+        let question = format!(
+            "Follow [Enter = the {} marked, numbers like \"1 3\", \"-\" = none]: ",
+            selected.len()
+        );
+
+        loop {
+            let _ = write!(output, "{prompt}{question}");
+            let _ = output.flush();
+            let mut line = String::new();
+            match input.read_line(&mut line) {
+                Ok(0) => return selected,
+                Ok(_) => {}
+                Err(err) => {
+                    warn!(page = %page, "read selection review: {err}");
+                    return selected;
+                }
+            }
+            match parse_answer(&line, listed.len()) {
+                Some(Answer::Accept) => return selected,
+                Some(Answer::Follow(picks)) => {
+                    return picks
+                        .into_iter()
+                        .map(|index| {
+                            let (url, tier) = &listed[index];
+                            SelectedLink {
+                                url: url.clone(),
+                                trust_tier: tier.unwrap_or(TrustTier::Medium),
+                            }
+                        })
+                        .collect();
+                }
+                None => {
+                    let _ = writeln!(
+                        output,
+                        "Expected numbers between 1 and {}, \"-\", or an empty line.",
+                        listed.len()
+                    );
+                    prompt.clear();
+                }
+            }
+        }
+    }
+}
+
+/// Zero-based picks from 1-based numbers separated by spaces or commas; `None` when a
+/// number is out of range or not a number.
+fn parse_answer(line: &str, listed: usize) -> Option<Answer> {
+    let line = line.trim();
+    if line.is_empty() {
+        return Some(Answer::Accept);
+    }
+    if line == "-" {
+        return Some(Answer::Follow(Vec::new()));
+    }
+    let mut picks = Vec::new();
+    for number in line.split([' ', ',']).filter(|number| !number.is_empty()) {
+        let index = number.parse::<usize>().ok()?.checked_sub(1)?;
+        if index >= listed {
+            return None;
+        }
+        if !picks.contains(&index) {
+            picks.push(index);
+        }
+    }
+    Some(Answer::Follow(picks))
+}
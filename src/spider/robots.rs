@@ -0,0 +1,184 @@
+use std::time::Duration;
+
+/// Pacing directives from the `robots.txt` group that applies to our user agent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RobotsDirectives {
+    /// `Crawl-delay`.
+    pub crawl_delay: Option<Duration>,
+    /// `Request-rate` as `(requests, per)`.
+    pub request_rate: Option<(u32, Duration)>,
+}
+
+impl RobotsDirectives {
+    /// Minimum interval between requests implied by `Crawl-delay` and `Request-rate`.
+    pub fn min_interval(&self) -> Duration {
+        let from_rate = self
+            .request_rate
+            .map_or(Duration::ZERO, |(requests, per)| per / requests.max(1));
+        self.crawl_delay.unwrap_or_default().max(from_rate)
+    }
+
+    /// Effective per-host concurrency: one request at a time once the site asks for pacing.
+    pub fn max_concurrent(&self) -> Option<usize> {
+        (self.crawl_delay.is_some() || self.request_rate.is_some()).then_some(1)
+    }
+}
+
+/// Parses `Crawl-delay` / `Request-rate` from the group matching `user_agent`.
+///
+/// Groups are matched by product token (the part of the user agent before `/`),
+/// case-insensitively. When no group names the token, `*` groups apply.
+pub fn parse_robots_directives(robots_txt: &str, user_agent: &str) -> RobotsDirectives {
+    let mut directives = RobotsDirectives::default();
+    for (key, value) in group_lines(robots_txt, user_agent) {
+        match key.as_str() {
+            "crawl-delay" => {
+                if let Some(delay) = parse_seconds(value) {
+                    directives.crawl_delay =
+                        Some(directives.crawl_delay.unwrap_or_default().max(delay));
+                }
+            }
+            "request-rate" => {
+                if let Some(rate) = parse_request_rate(value) {
+                    directives.request_rate = Some(rate);
+                }
+            }
+            _ => {}
+        }
+    }
+    directives
+}
+
+/// Whether `path` (with its query) may be fetched under the `Allow` / `Disallow` rules
+/// of the group matching `user_agent` (RFC 9309).
+///
+/// The longest matching rule wins, `Allow` on a tie; `*` matches any run of characters
+/// and a trailing `$` anchors the end. `/robots.txt` itself is always allowed.
+pub fn is_allowed_by_robots(robots_txt: &str, user_agent: &str, path: &str) -> bool {
+    if path == "/robots.txt" {
+        return true;
+    }
+    group_lines(robots_txt, user_agent)
+        .into_iter()
+        .filter_map(|(key, pattern)| match key.as_str() {
+            "allow" => Some((true, pattern)),
+            // An empty `Disallow` allows everything, so it is no rule at all.
+            "disallow" if !pattern.is_empty() => Some((false, pattern)),
+            _ => None,
+        })
+        .filter(|(_, pattern)| pattern_matches(pattern, path))
+        .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+        .is_none_or(|(allow, _)| allow)
+}
+
+/// `(key, value)` rule lines of the groups that name `user_agent`'s product token, or of
+/// the `*` groups when none does. Keys are lowercase; comments are stripped.
+///
+/// Groups are matched by product token (the part of the user agent before `/`),
+/// case-insensitively.
+fn group_lines<'a>(robots_txt: &'a str, user_agent: &str) -> Vec<(String, &'a str)> {
+    let token = product_token(user_agent);
+
+    let mut specific = Vec::new();
+    let mut wildcard = Vec::new();
+    let mut matched_specific = false;
+
+    let mut group_agents = Vec::<String>::new();
+    let mut in_rules = false;
+
+    for line in robots_txt.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let key = key.trim().to_ascii_lowercase();
+        let value = value.trim();
+
+        if key == "user-agent" {
+            if in_rules {
+                group_agents.clear();
+                in_rules = false;
+            }
+            group_agents.push(product_token(value).to_ascii_lowercase());
+            continue;
+        }
+
+        in_rules = true;
+        let is_specific = group_agents.contains(&token);
+        let is_wildcard = group_agents.iter().any(|agent| agent == "*");
+        if !is_specific && !is_wildcard {
+            continue;
+        }
+        matched_specific |= is_specific;
+        if is_specific {
+            specific.push((key, value));
+        } else {
+            wildcard.push((key, value));
+        }
+    }
+
+    if matched_specific { specific } else { wildcard }
+}
+
+/// RFC 9309 path pattern: a prefix match where `*` matches any run of characters and a
+/// trailing `$` requires the path to end there.
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let Some(mut rest) = path.strip_prefix(parts.next().unwrap_or_default()) else {
+        return false;
+    };
+    let parts = parts.collect::<Vec<_>>();
+    let Some((last, middle)) = parts.split_last() else {
+        return !anchored || rest.is_empty();
+    };
+    for part in middle {
+        let Some(at) = rest.find(part) else {
+            return false;
+        };
+        rest = &rest[at + part.len()..];
+    }
+    if anchored {
+        rest.ends_with(last)
+    } else {
+        rest.contains(last)
+    }
+}
+
+fn product_token(user_agent: &str) -> String {
+    user_agent
+        .trim()
+        .split(['/', ' '])
+        .next()
+        .unwrap_or("")
+        .to_ascii_lowercase()
+}
+
+fn parse_seconds(value: &str) -> Option<Duration> {
+    let seconds = value.parse::<f64>().ok()?;
+    (seconds.is_finite() && seconds >= 0.0).then(|| Duration::from_secs_f64(seconds.min(3600.0)))
+}
+
+/// Parses `n/t[s|m|h]`, ignoring an optional trailing time-of-day window.
+fn parse_request_rate(value: &str) -> Option<(u32, Duration)> {
+    let rate = value.split_whitespace().next()?;
+    let (requests, period) = rate.split_once('/')?;
+    let requests = requests.trim().parse::<u32>().ok().filter(|n| *n > 0)?;
+
+    let period = period.trim().to_ascii_lowercase();
+    let (number, unit) = match period.char_indices().find(|(_, c)| c.is_ascii_alphabetic()) {
+        Some((idx, _)) => period.split_at(idx),
+        None => (period.as_str(), "s"),
+    };
+    let number = number.parse::<u64>().ok().filter(|n| *n > 0)?;
+    let seconds = match unit {
+        "s" => number,
+        "m" => number * 60,
+        "h" => number * 3600,
+        _ => return None,
+    };
+    Some((requests, Duration::from_secs(seconds)))
+}
@@ -0,0 +1,103 @@
+use scraper::{Html, Selector};
+
+use super::RobotsTagPolicy;
+
+/// Robots token that addresses this crawler in `X-Robots-Tag` and `<meta name>`, as in
+/// `robots.txt`.
+const CRAWLER_TOKEN: &str = "llm-spider";
+
+/// `X-Robots-Tag` rules that take a value after a colon, so the colon does not name a
+/// user agent.
+const VALUED_RULES: [&str; 4] = [
+    "unavailable_after",
+    "max-snippet",
+    "max-image-preview",
+    "max-video-preview",
+];
+
+/// Indexing directives from `X-Robots-Tag` response headers and `<meta name="robots">`
+/// tags that address every crawler or `llm-spider`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RobotsTags {
+    /// `noindex` or `none`: the page must not be kept.
+    pub noindex: bool,
+    /// `nofollow` or `none`: the page's links must not be followed.
+    pub nofollow: bool,
+}
+
+impl RobotsTags {
+    /// Parses `X-Robots-Tag` values (several headers joined with `, `). A `<agent>:`
+    /// prefix scopes the rules after it to that agent; rules for other agents are
+    /// ignored.
+    pub fn parse_header(value: &str) -> Self {
+        let mut tags = Self::default();
+        let mut applies = true;
+        for token in value.split(',') {
+            let mut rule = token.trim();
+            if let Some((agent, rest)) = rule.split_once(':')
+                && !agent.contains(char::is_whitespace)
+                && !VALUED_RULES.contains(&agent.trim().to_ascii_lowercase().as_str())
+            {
+                applies = agent.trim().eq_ignore_ascii_case(CRAWLER_TOKEN);
+                rule = rest.trim();
+            }
+            if applies {
+                tags.add_rule(rule);
+            }
+        }
+        tags
+    }
+
+    /// `<meta name="robots">` and `<meta name="llm-spider">` rules of a document.
+    pub(super) fn from_meta(doc: &Html) -> Self {
+        let Ok(selector) = Selector::parse("meta[name][content]") else {
+            return Self::default();
+        };
+        let mut tags = Self::default();
+        for meta in doc.select(&selector) {
+            let name = meta.value().attr("name").unwrap_or_default().trim();
+            if !name.eq_ignore_ascii_case("robots") && !name.eq_ignore_ascii_case(CRAWLER_TOKEN) {
+                continue;
+            }
+            for rule in meta.value().attr("content").unwrap_or_default().split(',') {
+                tags.add_rule(rule);
+            }
+        }
+        tags
+    }
+
+    pub(super) fn union(self, other: Self) -> Self {
+        Self {
+            noindex: self.noindex || other.noindex,
+            nofollow: self.nofollow || other.nofollow,
+        }
+    }
+
+    /// The directives `policy` honors.
+    pub(super) fn honored(self, policy: RobotsTagPolicy) -> Self {
+        match policy {
+            RobotsTagPolicy::Respect => self,
+            RobotsTagPolicy::Noindex => Self {
+                nofollow: false,
+                ..self
+            },
+            RobotsTagPolicy::Nofollow => Self {
+                noindex: false,
+                ..self
+            },
+            RobotsTagPolicy::Ignore => Self::default(),
+        }
+    }
+
+    fn add_rule(&mut self, rule: &str) {
+        match rule.trim().to_ascii_lowercase().as_str() {
+            "noindex" => self.noindex = true,
+            "nofollow" => self.nofollow = true,
+            "none" => {
+                self.noindex = true;
+                self.nofollow = true;
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+use url::Url;
+
+use super::politeness::lookup_host_policy;
+use super::{HostPolicy, MIN_HOST_INTERVAL};
+
+/// When the crawl may send its next request to a host, consulted before every fetch.
+///
+/// [`FixedIntervalSchedule`] is the default. Embedding applications can set their own
+/// through [`UserRequest::schedule`](super::UserRequest::schedule); a policy faster than
+/// the default, or one that ignores `robots.txt`, needs the site owner's permission.
+pub trait SchedulePolicy: fmt::Debug + Send + Sync {
+    /// Minimum time between two requests to `host` (lowercase). `robots_delay` is the
+    /// interval the host's `robots.txt` asks for, zero when it sets none.
+    fn min_interval(&self, host: &str, robots_delay: Duration) -> Duration;
+
+    /// Requests to `host` allowed in flight at once; `None` sets no limit.
+    /// `robots_max_concurrent` comes from the host's `robots.txt` `Request-rate`.
+    fn max_concurrent(&self, host: &str, robots_max_concurrent: Option<usize>) -> Option<usize>;
+
+    /// How long to wait before fetching `url` again after its `attempt`-th bot
+    /// challenge in a row, counting from 1; `None` gives up on the page.
+    fn retry_delay(&self, url: &Url, attempt: usize) -> Option<Duration>;
+}
+
+/// The built-in schedule: a fixed minimum interval per host, from the `[hosts]` rules
+/// or the default, raised to any `robots.txt` crawl-delay, and at most one retry after
+/// a bot challenge.
+#[derive(Debug, Clone, Default)]
+pub struct FixedIntervalSchedule {
+    host_policies: BTreeMap<String, HostPolicy>,
+    challenge_retry: Option<Duration>,
+}
+
+impl FixedIntervalSchedule {
+    pub fn new(
+        host_policies: BTreeMap<String, HostPolicy>,
+        challenge_retry: Option<Duration>,
+    ) -> Self {
+        Self {
+            host_policies,
+            challenge_retry,
+        }
+    }
+}
+
+impl SchedulePolicy for FixedIntervalSchedule {
+    fn min_interval(&self, host: &str, robots_delay: Duration) -> Duration {
+        lookup_host_policy(&self.host_policies, host)
+            .and_then(|policy| policy.min_interval)
+            .unwrap_or(MIN_HOST_INTERVAL)
+            .max(robots_delay)
+    }
+
+    fn max_concurrent(&self, host: &str, robots_max_concurrent: Option<usize>) -> Option<usize> {
+        let configured =
+            lookup_host_policy(&self.host_policies, host).and_then(|policy| policy.max_concurrent);
+        match (configured, robots_max_concurrent) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    fn retry_delay(&self, _url: &Url, attempt: usize) -> Option<Duration> {
+        self.challenge_retry.filter(|_| attempt == 1)
+    }
+}
@@ -0,0 +1,70 @@
+use std::str::FromStr;
+
+use anyhow::{Context as _, bail};
+use url::Url;
+
+/// A `--seed-url` seed with optional limits for the pages crawled from it.
+///
+/// Written `URL [depth=N] [children=N]`, e.g. `https://docs.example.com/ depth=3 children=5`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeedUrl {
+    pub url: Url,
+    pub limits: SeedLimits,
+}
+
+/// Overrides of `max_depth` and the children-per-page caps for a seed and every page
+/// reached from it. `None` keeps the request's setting.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SeedLimits {
+    pub max_depth: Option<usize>,
+    pub max_children: Option<usize>,
+}
+
+impl From<Url> for SeedUrl {
+    fn from(url: Url) -> Self {
+        Self {
+            url,
+            limits: SeedLimits::default(),
+        }
+    }
+}
+
+impl FromStr for SeedUrl {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> anyhow::Result<Self> {
+        let mut fields = text.split_whitespace();
+        let Some(url) = fields.next() else {
+            bail!("empty seed");
+        };
+        let url = Url::parse(url).with_context(|| format!("invalid seed URL: {url}"))?;
+        let mut limits = SeedLimits::default();
+        for field in fields {
+            let Some((key, value)) = field.split_once('=') else {
+                bail!("expected key=value after the seed URL, got `{field}`");
+            };
+            let value = value
+                .parse::<usize>()
+                .with_context(|| format!("invalid seed {key}: `{value}`"))?;
+            match key {
+                "depth" => limits.max_depth = Some(value),
+                "children" => limits.max_children = Some(value),
+                _ => bail!("unknown seed option `{key}` (expected depth or children)"),
+            }
+        }
+        Ok(Self { url, limits })
+    }
+}
+
+/// Seeds of a `--seed-file`: one per line, skipping blank lines and `#` comments.
+pub fn parse_seed_file(text: &str) -> anyhow::Result<Vec<SeedUrl>> {
+    text.lines()
+        .enumerate()
+        .map(|(index, line)| (index, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(index, line)| {
+            line.parse()
+                .with_context(|| format!("seed file line {}", index + 1))
+        })
+        .collect()
+}
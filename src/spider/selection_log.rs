@@ -0,0 +1,61 @@
+use std::collections::HashSet;
+
+use serde_json::json;
+use url::Url;
+
+use super::{CrawlResult, UserRequest, normalize_url};
+use crate::openai::SelectedLink;
+
+/// One page's link selection, kept for `--selection-log`.
+#[derive(Debug, Clone)]
+pub struct SelectionRecord {
+    pub page: Url,
+    pub depth: usize,
+    /// Candidates offered to selection, with their anchor text, in ranked order.
+    pub candidates: Vec<(Url, String)>,
+    /// The picks of the model (or the selection cache, or the failure fallback).
+    pub selected: Vec<SelectedLink>,
+    /// The links queued for the crawl: `selected` unless `--interactive` changed them.
+    pub followed: Vec<Url>,
+    /// A selection call failed, so `selected` came from `on_selection_failure`.
+    pub selection_failed: bool,
+}
+
+/// One JSON line per selection of the crawl: the page, every candidate, whether the
+/// model picked it, whether it was followed, and whether it ended up as a source.
+pub fn compose_selection_log(request: &UserRequest, result: &CrawlResult) -> String {
+    let sources = result
+        .sources
+        .iter()
+        .map(|source| normalize_url(&source.url))
+        .collect::<HashSet<_>>();
+    let mut out = String::new();
+    for record in &result.selections {
+        let candidates = record
+            .candidates
+            .iter()
+            .map(|(url, anchor_text)| {
+                let selected = record.selected.iter().find(|link| link.url == *url);
+                json!({
+                    "url": url.as_str(),
+                    "anchor_text": anchor_text,
+                    "selected": selected.is_some(),
+                    "trust_tier": selected.map(|link| link.trust_tier.as_str()),
+                    "followed": record.followed.contains(url),
+                    "became_source": sources.contains(&normalize_url(url)),
+                })
+            })
+            .collect::<Vec<_>>();
+        let line = json!({
+            "query": request.query,
+            "crawl_id": request.crawl_id,
+            "page": record.page.as_str(),
+            "depth": record.depth,
+            "selection_failed": record.selection_failed,
+            "candidates": candidates,
+        });
+        out.push_str(&line.to_string());
+        out.push('\n');
+    }
+    out
+}
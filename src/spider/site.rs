@@ -0,0 +1,458 @@
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::{Value, json};
+use tracing::{debug, warn};
+use url::Url;
+
+use super::content_type::{UrlKind, classify_url};
+use super::fetch::SpiderPageFetcher;
+use super::politeness::Politeness;
+use super::{
+    CrawlError, CrawlFailure, FailureStage, FixedIntervalSchedule, HostPolicy, PageFetcher,
+    RobotsTags, SkipReason, escape_md_inline, extract_page, fetch_page, is_allowed, normalize_url,
+    same_host,
+};
+use crate::audit::AuditLog;
+
+/// Pages offered to the LLM per relevance call.
+const RELEVANCE_BATCH: usize = 25;
+
+/// A breadth-first audit of one site (`llm-spider site`).
+#[derive(Debug, Clone)]
+pub struct SiteRequest {
+    pub query: String,
+    /// Where the crawl starts. Only pages on its host under its directory are fetched.
+    pub root: Url,
+    pub max_pages: usize,
+    /// Link hops from `root`.
+    pub max_depth: usize,
+    pub max_elapsed: Duration,
+    pub allow_local: bool,
+    pub host_policies: BTreeMap<String, HostPolicy>,
+    pub challenge_retry: Option<Duration>,
+    /// Skip pages whose site opts out of AI use (`ai.txt`, `TDM-Reservation`).
+    pub honor_opt_outs: bool,
+    /// Sent with every request and echoed in the report, as for `spider`.
+    pub crawl_id: Option<String>,
+    pub audit_log: Option<Arc<AuditLog>>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SiteAudit {
+    /// Fetched pages in breadth-first order; `noindex` pages are left out.
+    pub pages: Vec<SitePage>,
+    /// Diagnoses rendered under `## Notes` (failed relevance calls).
+    pub notes: Vec<String>,
+    pub failures: Vec<CrawlFailure>,
+    pub stats: SiteStats,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SitePage {
+    pub url: Url,
+    pub title: Option<String>,
+    pub excerpt: String,
+    pub depth: usize,
+    /// The first directory below the root, e.g. `/docs/guide/` for `/docs/guide/intro`.
+    pub section: String,
+    /// `None` when the page was not graded (failed call or left out by the model).
+    pub relevant: Option<bool>,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SiteStats {
+    pub pages_fetched: usize,
+    pub bytes_downloaded: u64,
+    pub llm_calls: usize,
+    pub noindex_pages: usize,
+    /// Distinct links that leave the root's host or directory.
+    pub out_of_scope_links: usize,
+    /// In-scope URLs still queued when `max_pages` or `max_elapsed` stopped the crawl.
+    pub unvisited: usize,
+    pub elapsed_ms: u128,
+}
+
+/// Page counts of one section in the coverage table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionCoverage {
+    pub section: String,
+    pub pages: usize,
+    pub relevant: usize,
+}
+
+impl SiteAudit {
+    /// Pages and relevant pages per section, by section path.
+    pub fn coverage(&self) -> Vec<SectionCoverage> {
+        let mut sections = BTreeMap::<&str, SectionCoverage>::new();
+        for page in &self.pages {
+            let entry = sections
+                .entry(&page.section)
+                .or_insert_with(|| SectionCoverage {
+                    section: page.section.clone(),
+                    pages: 0,
+                    relevant: 0,
+                });
+            entry.pages += 1;
+            entry.relevant += usize::from(page.relevant == Some(true));
+        }
+        sections.into_values().collect()
+    }
+}
+
+/// Crawls `request.root` breadth-first and asks the LLM which pages address the query.
+///
+/// The LLM only grades relevance; which links to follow is decided by scope alone.
+pub fn audit_site(
+    request: &SiteRequest,
+    openai: &dyn crate::openai::OpenAiApi,
+) -> Result<SiteAudit, CrawlError> {
+    let fetcher = SpiderPageFetcher::new(
+        request.audit_log.clone(),
+        None,
+        None,
+        request.crawl_id.as_deref(),
+    )
+    .map_err(CrawlError::Init)?
+    .with_opt_outs(request.honor_opt_outs);
+    Ok(audit_site_with_fetcher(request, openai, &fetcher))
+}
+
+pub fn audit_site_with_fetcher(
+    request: &SiteRequest,
+    openai: &dyn crate::openai::OpenAiApi,
+    fetcher: &dyn PageFetcher,
+) -> SiteAudit {
+    let started_at = Instant::now();
+    let mut audit = crawl_site(request, fetcher, started_at);
+    grade_pages(request, openai, &mut audit);
+    audit.stats.elapsed_ms = started_at.elapsed().as_millis();
+    audit
+}
+
+fn crawl_site(request: &SiteRequest, fetcher: &dyn PageFetcher, started_at: Instant) -> SiteAudit {
+    let mut audit = SiteAudit::default();
+    let scope = scope_prefix(&request.root);
+    let schedule =
+        FixedIntervalSchedule::new(request.host_policies.clone(), request.challenge_retry);
+    let mut politeness = Politeness::new(
+        &request.host_policies,
+        &schedule,
+        None,
+        request.audit_log.as_deref(),
+    );
+    let mut seen = HashSet::from([normalize_url(&request.root)]);
+    let mut queue = VecDeque::new();
+    if is_allowed(&request.root, request.allow_local) {
+        queue.push_back((request.root.clone(), 0));
+    } else {
+        audit.notes.push(format!(
+            "ルート URL は取得対象外のため、クロールしなかった: {}",
+            request.root
+        ));
+    }
+
+    while audit.pages.len() < request.max_pages && started_at.elapsed() < request.max_elapsed {
+        let Some((url, depth)) = queue.pop_front() else {
+            break;
+        };
+        if !politeness.admit(&url) {
+            debug!(url = %url, "host page cap reached; stopping the site crawl");
+            queue.push_front((url, depth));
+            break;
+        }
+        politeness.wait_turn(&url);
+        let fetched = fetch_page(fetcher, None, &url, &schedule, None, &mut |target| {
+            let reason = if !is_allowed(target, request.allow_local) {
+                SkipReason::NotAllowed
+            } else if !politeness.admit(target) {
+                SkipReason::HostCap
+            } else {
+                politeness.wait_turn(target);
+                return Ok(());
+            };
+            Err(CrawlError::RedirectRefused {
+                url: target.clone(),
+                reason,
+            }
+            .into())
+        });
+        let page = match fetched {
+            Ok((page, _)) => page,
+            Err(err) => {
+                audit.failures.push(CrawlFailure {
+                    url,
+                    stage: FailureStage::Fetch,
+                    message: format!("{err:#}"),
+                });
+                continue;
+            }
+        };
+        audit.stats.pages_fetched += 1;
+        audit.stats.bytes_downloaded += page.bytes;
+        politeness.record_robots(&url, page.robots_delay, page.robots_max_concurrent);
+
+        let base = page.final_url.clone().unwrap_or_else(|| url.clone());
+        if !in_scope(&base, &request.root, &scope) {
+            debug!(url = %url, final_url = %base, "redirected off the site; skipping");
+            audit.stats.out_of_scope_links += 1;
+            continue;
+        }
+        let extracted = match extract_page(&base, &page.html) {
+            Ok(extracted) => extracted,
+            Err(err) => {
+                audit.failures.push(CrawlFailure {
+                    url,
+                    stage: FailureStage::Extract,
+                    message: format!("{err:#}"),
+                });
+                continue;
+            }
+        };
+        let robots_tags = page
+            .x_robots_tag
+            .as_deref()
+            .map(RobotsTags::parse_header)
+            .unwrap_or_default()
+            .union(extracted.robots_tags);
+
+        if !robots_tags.nofollow && depth < request.max_depth {
+            for link in &page.links {
+                if !is_allowed(link, request.allow_local)
+                    || classify_url(link) == UrlKind::Binary
+                    || !seen.insert(normalize_url(link))
+                {
+                    continue;
+                }
+                if in_scope(link, &request.root, &scope) {
+                    queue.push_back((link.clone(), depth + 1));
+                } else {
+                    audit.stats.out_of_scope_links += 1;
+                }
+            }
+        }
+        if robots_tags.noindex {
+            audit.stats.noindex_pages += 1;
+            continue;
+        }
+        audit.pages.push(SitePage {
+            section: section_of(&base, &scope),
+            url: base,
+            title: extracted.title,
+            excerpt: extracted.excerpt,
+            depth,
+            relevant: None,
+            reason: String::new(),
+        });
+    }
+    audit.stats.unvisited = queue.len();
+    audit
+}
+
+/// Grades the pages `RELEVANCE_BATCH` at a time; a failed call adds a note and leaves
+/// its pages ungraded.
+fn grade_pages(
+    request: &SiteRequest,
+    openai: &dyn crate::openai::OpenAiApi,
+    audit: &mut SiteAudit,
+) {
+    for batch in audit.pages.chunks_mut(RELEVANCE_BATCH) {
+        let digests = batch
+            .iter()
+            .enumerate()
+            .map(|(id, page)| {
+                json!({
+                    "id": id,
+                    "url": page.url.as_str(),
+                    "title": page.title.as_deref().unwrap_or_default(),
+                    "excerpt": page.excerpt,
+                })
+            })
+            .collect::<Vec<_>>();
+        audit.stats.llm_calls += 1;
+        match openai.grade_sources(&request.query, &digests) {
+            Ok(grades) => {
+                for grade in grades {
+                    if let Some(page) = batch.get_mut(grade.source) {
+                        page.relevant = Some(grade.keep);
+                        page.reason = grade.reason;
+                    }
+                }
+            }
+            Err(err) => {
+                warn!("site relevance grading failed: {err:#}");
+                audit.notes.push(format!(
+                    "{} ページの関連性の判定に失敗したため、未判定として扱った: {}",
+                    batch.len(),
+                    escape_md_inline(&format!("{err:#}"))
+                ));
+            }
+        }
+    }
+}
+
+/// The directory of `root`: `/docs/` for both `/docs/` and `/docs/index.html`.
+fn scope_prefix(root: &Url) -> String {
+    let path = root.path();
+    match path.rfind('/') {
+        Some(end) => path[..=end].to_owned(),
+        None => "/".to_owned(),
+    }
+}
+
+fn in_scope(url: &Url, root: &Url, scope: &str) -> bool {
+    same_host(url, root)
+        && url.port_or_known_default() == root.port_or_known_default()
+        && url.path().starts_with(scope)
+}
+
+/// Pages directly in the scope directory belong to the scope itself.
+fn section_of(url: &Url, scope: &str) -> String {
+    let rest = url.path().strip_prefix(scope).unwrap_or_default();
+    match rest.split_once('/') {
+        Some((dir, _)) if !dir.is_empty() => format!("{scope}{dir}/"),
+        _ => scope.to_owned(),
+    }
+}
+
+pub fn compose_site_markdown(request: &SiteRequest, audit: &SiteAudit) -> String {
+    let coverage = audit.coverage();
+    let relevant = coverage
+        .iter()
+        .map(|section| section.relevant)
+        .sum::<usize>();
+
+    let mut out = String::new();
+    out.push_str("# Site Audit\n\n");
+    out.push_str("## Query\n\n");
+    out.push_str(&format!("- {}\n", escape_md_inline(&request.query)));
+    out.push_str(&format!("- Root: {}\n", request.root));
+    if let Some(crawl_id) = &request.crawl_id {
+        out.push_str(&format!("- Crawl ID: `{crawl_id}`\n"));
+    }
+    out.push_str(&format!(
+        "- Pages: {} in {} sections, {relevant} relevant\n",
+        audit.pages.len(),
+        coverage.len()
+    ));
+    if audit.stats.unvisited > 0 {
+        out.push_str(&format!(
+            "- Not reached: {} queued pages (`max_pages` / `max_elapsed`)\n",
+            audit.stats.unvisited
+        ));
+    }
+    out.push('\n');
+
+    if !coverage.is_empty() {
+        out.push_str("## Coverage\n\n");
+        out.push_str("| Section | Pages | Relevant |\n");
+        out.push_str("|---------|-------|----------|\n");
+        for section in &coverage {
+            out.push_str(&format!(
+                "| {} | {} | {} |\n",
+                escape_md_inline(&section.section),
+                section.pages,
+                section.relevant
+            ));
+        }
+        out.push('\n');
+    }
+
+    if relevant > 0 {
+        out.push_str("## Relevant pages\n");
+        for section in coverage.iter().filter(|section| section.relevant > 0) {
+            out.push_str(&format!("\n### {}\n\n", escape_md_inline(&section.section)));
+            for page in audit
+                .pages
+                .iter()
+                .filter(|page| page.section == section.section && page.relevant == Some(true))
+            {
+                out.push_str(&format!("- {}", page.url));
+                if let Some(title) = &page.title {
+                    out.push_str(&format!(" — {}", escape_md_inline(title)));
+                }
+                if !page.reason.is_empty() {
+                    out.push_str(&format!(": {}", escape_md_inline(&page.reason)));
+                }
+                out.push('\n');
+            }
+        }
+        out.push('\n');
+    }
+
+    if !audit.notes.is_empty() {
+        out.push_str("## Notes\n\n");
+        for note in &audit.notes {
+            out.push_str(&format!("- {note}\n"));
+        }
+        out.push('\n');
+    }
+
+    if !audit.failures.is_empty() {
+        out.push_str("## Failures\n\n");
+        for failure in &audit.failures {
+            out.push_str(&format!(
+                "- [{:?}] {}: {}\n",
+                failure.stage,
+                failure.url,
+                escape_md_inline(&failure.message)
+            ));
+        }
+        out.push('\n');
+    }
+
+    out.push_str("## Stats\n\n");
+    out.push_str(&format!(
+        "- Pages fetched: {}\n- Bytes downloaded: {}\n- LLM calls: {}\n- Off-site links: {}\n- Elapsed: {} ms\n",
+        audit.stats.pages_fetched,
+        audit.stats.bytes_downloaded,
+        audit.stats.llm_calls,
+        audit.stats.out_of_scope_links,
+        audit.stats.elapsed_ms
+    ));
+    out
+}
+
+/// The audit as one JSON document (`--format json`).
+pub fn compose_site_json(request: &SiteRequest, audit: &SiteAudit) -> Value {
+    json!({
+        "query": request.query,
+        "root": request.root.as_str(),
+        "crawl_id": request.crawl_id,
+        "coverage": audit
+            .coverage()
+            .iter()
+            .map(|section| json!({
+                "section": section.section,
+                "pages": section.pages,
+                "relevant": section.relevant,
+            }))
+            .collect::<Vec<_>>(),
+        "pages": audit
+            .pages
+            .iter()
+            .map(|page| json!({
+                "url": page.url.as_str(),
+                "title": page.title,
+                "section": page.section,
+                "depth": page.depth,
+                "relevant": page.relevant,
+                "reason": page.reason,
+            }))
+            .collect::<Vec<_>>(),
+        "notes": audit.notes,
+        "failures": audit
+            .failures
+            .iter()
+            .map(|failure| json!({
+                "url": failure.url.as_str(),
+                "stage": format!("{:?}", failure.stage),
+                "message": failure.message,
+            }))
+            .collect::<Vec<_>>(),
+        "stats": audit.stats,
+    })
+}
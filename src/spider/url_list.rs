@@ -0,0 +1,132 @@
+use std::collections::{HashMap, HashSet};
+
+use url::Url;
+
+use super::{CrawlError, CrawlResult, normalize_url};
+
+/// Why the crawl did not fetch (or did not keep) a URL it came across.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    /// Local/private address or a non-HTTP scheme.
+    NotAllowed,
+    /// The URL path looks like a binary resource.
+    Binary,
+    /// Another language variant of the page was collected instead.
+    LanguageVariant,
+    /// The host's page cap was reached.
+    HostCap,
+    RobotsBlocked,
+    /// The host failed `host_failure_limit` fetches in a row earlier in the crawl.
+    HostDown,
+    /// The host answered `403` / `429` `host_failure_limit` times in a row earlier in the crawl.
+    HostRefused,
+    /// The host answered an exclusion status (e.g. `451`) earlier in the crawl.
+    HostExcluded,
+    /// The host was banned through the `--control` file.
+    Banned,
+    /// The site opted the page out of AI use (`ai.txt`, `TDM-Reservation`).
+    OptedOut,
+    /// The page asked not to be indexed (`X-Robots-Tag` or robots `<meta>`).
+    Noindex,
+    /// The page is paywalled and `--paywall-policy skip` drops such pages.
+    Paywalled,
+    /// The server answered with a non-textual `Content-Type`.
+    NonTextual,
+    /// A bot-challenge or CAPTCHA page was served instead.
+    BotChallenge,
+    FetchFailed,
+    ExtractFailed,
+    /// A link candidate that link selection did not pick.
+    NotSelected,
+    /// A selected link beyond the depth limit for its trust tier.
+    OverDepth,
+    /// A queued or selected URL dropped because the frontier was full.
+    FrontierFull,
+    /// Still queued when the crawl stopped.
+    NotReached,
+}
+
+impl SkipReason {
+    /// Stable reason code written by `--urls-out`.
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::NotAllowed => "not-allowed",
+            Self::Binary => "binary",
+            Self::LanguageVariant => "language-variant",
+            Self::HostCap => "host-cap",
+            Self::RobotsBlocked => "robots",
+            Self::HostDown => "host-down",
+            Self::HostRefused => "host-refused",
+            Self::HostExcluded => "host-excluded",
+            Self::Banned => "banned",
+            Self::OptedOut => "opted-out",
+            Self::Noindex => "noindex",
+            Self::Paywalled => "paywalled",
+            Self::NonTextual => "non-textual",
+            Self::BotChallenge => "bot-challenge",
+            Self::FetchFailed => "fetch-failed",
+            Self::ExtractFailed => "extract-failed",
+            Self::NotSelected => "not-selected",
+            Self::OverDepth => "over-depth",
+            Self::FrontierFull => "frontier-full",
+            Self::NotReached => "not-reached",
+        }
+    }
+
+    /// The reason for a failed fetch.
+    pub(super) fn for_fetch_error(err: &anyhow::Error) -> Self {
+        match err.downcast_ref::<CrawlError>() {
+            Some(CrawlError::RobotsBlocked { .. }) => Self::RobotsBlocked,
+            Some(CrawlError::OptedOut { .. }) => Self::OptedOut,
+            Some(CrawlError::NonTextual { .. }) => Self::NonTextual,
+            Some(CrawlError::BotChallenge { .. }) => Self::BotChallenge,
+            Some(CrawlError::RedirectRefused { reason, .. }) => *reason,
+            _ => Self::FetchFailed,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SkippedUrl {
+    pub url: Url,
+    pub reason: SkipReason,
+}
+
+/// One line per URL: `<url>\tvisited` for fetched pages, then `<url>\tskipped:<reason>`.
+///
+/// A URL appears once; a fetched URL is never listed as skipped, except a `noindex`
+/// or skipped paywalled page, which keeps its place among the fetched ones. A URL
+/// skipped several times keeps its first reason.
+pub fn compose_url_list(result: &CrawlResult) -> String {
+    let mut dropped_after_fetch = HashMap::new();
+    for skipped in &result.skipped {
+        if matches!(skipped.reason, SkipReason::Noindex | SkipReason::Paywalled) {
+            dropped_after_fetch
+                .entry(normalize_url(&skipped.url))
+                .or_insert(skipped.reason);
+        }
+    }
+    let mut seen = HashSet::new();
+    let mut out = String::new();
+    for url in &result.fetched {
+        let key = normalize_url(url);
+        if !seen.insert(key.clone()) {
+            continue;
+        }
+        if let Some(reason) = dropped_after_fetch.get(&key) {
+            out.push_str(&format!("{url}\tskipped:{}\n", reason.code()));
+        } else {
+            out.push_str(&format!("{url}\tvisited\n"));
+        }
+    }
+    for skipped in &result.skipped {
+        if seen.insert(normalize_url(&skipped.url)) {
+            out.push_str(&format!(
+                "{}\tskipped:{}\n",
+                skipped.url,
+                skipped.reason.code()
+            ));
+        }
+    }
+    out
+}
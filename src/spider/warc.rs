@@ -0,0 +1,320 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Read as _, Write as _};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context as _, bail};
+use flate2::Compression;
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use url::Url;
+
+use super::fetch::extract_links;
+use super::{CrawlError, FetchedPage, PageFetcher, decode_html, normalize_url, user_agent};
+use crate::cache::cache_key;
+
+const SERVER_NOT_MODIFIED_PROFILE: &str =
+    "http://netpreserve.org/warc/1.1/revisit/server-not-modified";
+
+/// WARC/1.1 writer for fetched responses.
+///
+/// A path ending in `.gz` gets one gzip member per record, the layout archive tools
+/// expect for random access.
+pub(super) struct WarcWriter {
+    out: BufWriter<File>,
+    gzip: bool,
+    records: u64,
+}
+
+impl WarcWriter {
+    /// Creates the file and writes the leading `warcinfo` record.
+    pub(super) fn create(path: &Path, crawl_id: Option<&str>) -> anyhow::Result<Self> {
+        let file =
+            File::create(path).with_context(|| format!("create WARC file: {}", path.display()))?;
+        let mut writer = Self {
+            out: BufWriter::new(file),
+            gzip: path.extension().is_some_and(|ext| ext == "gz"),
+            records: 0,
+        };
+        let filename = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut info = format!(
+            "software: llm-spider/{}\r\nformat: WARC File Format 1.1\r\nhttp-header-user-agent: {}\r\n",
+            env!("CARGO_PKG_VERSION"),
+            user_agent(crawl_id)
+        );
+        if let Some(crawl_id) = crawl_id {
+            info.push_str(&format!("crawl-id: {crawl_id}\r\n"));
+        }
+        writer.write_record(
+            &[
+                ("WARC-Type", "warcinfo"),
+                ("WARC-Filename", &filename),
+                ("Content-Type", "application/warc-fields"),
+            ],
+            info.as_bytes(),
+        )?;
+        Ok(writer)
+    }
+
+    /// Writes a `response` record. The body is the decoded page re-encoded as UTF-8,
+    /// so the stored `Content-Type` carries `charset=utf-8`.
+    pub(super) fn write_response(&mut self, url: &Url, page: &FetchedPage) -> anyhow::Result<()> {
+        let essence = page
+            .content_type
+            .as_deref()
+            .and_then(|value| value.split(';').next())
+            .map(str::trim)
+            .filter(|essence| !essence.is_empty())
+            .unwrap_or("text/html");
+        let mut http = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: {essence}; charset=utf-8\r\nContent-Length: {}\r\n",
+            page.html.len()
+        );
+        push_validators(&mut http, page);
+        http.push_str("\r\n");
+        let mut block = http.into_bytes();
+        block.extend_from_slice(page.html.as_bytes());
+        self.write_record(
+            &[
+                ("WARC-Type", "response"),
+                ("WARC-Target-URI", url.as_str()),
+                ("Content-Type", "application/http;msgtype=response"),
+            ],
+            &block,
+        )
+    }
+
+    /// Writes a `revisit` record for a `304 Not Modified` answer served from the page cache.
+    pub(super) fn write_not_modified(
+        &mut self,
+        url: &Url,
+        page: &FetchedPage,
+    ) -> anyhow::Result<()> {
+        let mut http = "HTTP/1.1 304 Not Modified\r\n".to_owned();
+        push_validators(&mut http, page);
+        http.push_str("\r\n");
+        self.write_record(
+            &[
+                ("WARC-Type", "revisit"),
+                ("WARC-Target-URI", url.as_str()),
+                ("WARC-Profile", SERVER_NOT_MODIFIED_PROFILE),
+                ("Content-Type", "application/http;msgtype=response"),
+            ],
+            http.as_bytes(),
+        )
+    }
+
+    pub(super) fn finish(mut self) -> anyhow::Result<()> {
+        self.out.flush().context("flush WARC file")
+    }
+
+    fn write_record(&mut self, fields: &[(&str, &str)], block: &[u8]) -> anyhow::Result<()> {
+        self.records += 1;
+        let mut record = format!(
+            "WARC/1.1\r\nWARC-Record-ID: <urn:uuid:{}>\r\nWARC-Date: {}\r\n",
+            self.record_id(fields),
+            humantime::format_rfc3339_seconds(SystemTime::now())
+        );
+        for (name, value) in fields {
+            record.push_str(&format!("{name}: {value}\r\n"));
+        }
+        record.push_str(&format!("Content-Length: {}\r\n\r\n", block.len()));
+        let mut bytes = record.into_bytes();
+        bytes.extend_from_slice(block);
+        bytes.extend_from_slice(b"\r\n\r\n");
+
+        if self.gzip {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&bytes).context("compress WARC record")?;
+            bytes = encoder.finish().context("compress WARC record")?;
+        }
+        self.out.write_all(&bytes).context("write WARC record")
+    }
+
+    /// A version-4 shaped UUID derived from the record number, clock and header fields.
+    fn record_id(&self, fields: &[(&str, &str)]) -> String {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        let seed = format!(
+            "{}\n{nanos}\n{}\n{fields:?}",
+            self.records,
+            std::process::id()
+        );
+        let hex = format!("{}{}", cache_key(&seed), cache_key(&format!("{seed}\nlow")));
+        format!(
+            "{}-{}-4{}-8{}-{}",
+            &hex[0..8],
+            &hex[8..12],
+            &hex[13..16],
+            &hex[17..20],
+            &hex[20..32]
+        )
+    }
+}
+
+/// Serves pages from a WARC file instead of the network, e.g. one written by `--warc`,
+/// so a crawl can be replayed against a recorded web.
+///
+/// Only `response` records are served, the first per URL. URLs the archive lacks
+/// answer `404`; `revisit` records carry no body and are skipped.
+#[derive(Debug)]
+pub struct WarcReplay {
+    responses: HashMap<String, ReplayedResponse>,
+}
+
+#[derive(Debug)]
+struct ReplayedResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl ReplayedResponse {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl WarcReplay {
+    /// Reads every record of `path`, gunzipping it when the name ends in `.gz`.
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let file =
+            File::open(path).with_context(|| format!("open WARC file: {}", path.display()))?;
+        let mut bytes = Vec::new();
+        if path.extension().is_some_and(|ext| ext == "gz") {
+            MultiGzDecoder::new(file).read_to_end(&mut bytes)
+        } else {
+            std::io::BufReader::new(file).read_to_end(&mut bytes)
+        }
+        .with_context(|| format!("read WARC file: {}", path.display()))?;
+        Self::parse(&bytes).with_context(|| format!("parse WARC file: {}", path.display()))
+    }
+
+    fn parse(mut rest: &[u8]) -> anyhow::Result<Self> {
+        let mut responses = HashMap::new();
+        loop {
+            while let [b'\r' | b'\n', tail @ ..] = rest {
+                rest = tail;
+            }
+            if rest.is_empty() {
+                break;
+            }
+            let Some((head, after)) = split_head(rest) else {
+                bail!("truncated WARC record header");
+            };
+            let fields = parse_fields(head);
+            let field = |name: &str| {
+                fields
+                    .iter()
+                    .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                    .map(|(_, value)| value.as_str())
+            };
+            let length = field("Content-Length")
+                .and_then(|len| len.parse::<usize>().ok())
+                .context("WARC record without a valid Content-Length")?;
+            let Some(block) = after.get(..length) else {
+                bail!("truncated WARC record block");
+            };
+            rest = &after[length..];
+
+            if field("WARC-Type") != Some("response") {
+                continue;
+            }
+            let Some(target) = field("WARC-Target-URI").and_then(|uri| Url::parse(uri).ok()) else {
+                continue;
+            };
+            let Some((http_head, body)) = split_head(block) else {
+                continue;
+            };
+            let (status_line, header_lines) =
+                http_head.split_once("\r\n").unwrap_or((http_head, ""));
+            let Some(status) = status_line
+                .split_whitespace()
+                .nth(1)
+                .and_then(|status| status.parse().ok())
+            else {
+                continue;
+            };
+            responses
+                .entry(normalize_url(&target))
+                .or_insert(ReplayedResponse {
+                    status,
+                    headers: parse_fields(header_lines),
+                    body: body.to_vec(),
+                });
+        }
+        Ok(Self { responses })
+    }
+
+    /// Archived responses.
+    pub fn len(&self) -> usize {
+        self.responses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.responses.is_empty()
+    }
+}
+
+impl PageFetcher for WarcReplay {
+    fn fetch(&self, url: &Url) -> anyhow::Result<FetchedPage> {
+        let response = self.responses.get(&normalize_url(url));
+        let status = response.map_or(404, |response| response.status);
+        let Some(response) = response.filter(|_| (200..300).contains(&status)) else {
+            return Err(CrawlError::FetchFailed {
+                url: url.clone(),
+                status: Some(status),
+            }
+            .into());
+        };
+        let content_type = response.header("Content-Type");
+        let html = decode_html(&response.body, content_type);
+        Ok(FetchedPage {
+            links: extract_links(url, &html),
+            html,
+            bytes: response.body.len() as u64,
+            etag: response.header("ETag").map(str::to_owned),
+            last_modified: response.header("Last-Modified").map(str::to_owned),
+            content_type: content_type.map(str::to_owned),
+            status: Some(response.status),
+            final_url: Some(url.clone()),
+            content_length: Some(response.body.len() as u64),
+            x_robots_tag: response.header("X-Robots-Tag").map(str::to_owned),
+            ..FetchedPage::default()
+        })
+    }
+}
+
+/// Splits a record at the blank line ending its header, which must be UTF-8.
+fn split_head(bytes: &[u8]) -> Option<(&str, &[u8])> {
+    let end = bytes.windows(4).position(|window| window == b"\r\n\r\n")?;
+    let head = std::str::from_utf8(&bytes[..end]).ok()?;
+    Some((head, &bytes[end + 4..]))
+}
+
+/// `Name: value` lines; the first line of a WARC header (`WARC/1.1`) has no colon and
+/// is dropped.
+fn parse_fields(head: &str) -> Vec<(String, String)> {
+    head.lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_owned(), value.trim().to_owned()))
+        .collect()
+}
+
+fn push_validators(http: &mut String, page: &FetchedPage) {
+    if let Some(etag) = &page.etag {
+        http.push_str(&format!("ETag: {etag}\r\n"));
+    }
+    if let Some(last_modified) = &page.last_modified {
+        http.push_str(&format!("Last-Modified: {last_modified}\r\n"));
+    }
+}
@@ -1,11 +1,21 @@
 use std::fmt;
 use std::str::FromStr;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+use serde::{Deserialize, Serialize};
+
+/// How far a page is trusted, from most to least. `Ord` follows the same order, so
+/// the less trusted of two tiers is the greater one.
+///
+/// Serializes as `"High"`, `"Medium"`, `"Low"`, or `"Unverified"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum TrustTier {
     High,
     Medium,
     Low,
+    /// Neither the model nor a host rule judged the page, for example heuristic link
+    /// picks after a selection failure. Crawled after `Low` and limited like it.
+    #[serde(alias = "Unknown")]
+    Unverified,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -20,11 +30,15 @@ impl fmt::Display for ParseTrustTierError {
 impl std::error::Error for ParseTrustTierError {}
 
 impl TrustTier {
+    /// Every tier, most trusted first.
+    pub const ALL: [Self; 4] = [Self::High, Self::Medium, Self::Low, Self::Unverified];
+
     pub fn as_str(self) -> &'static str {
         match self {
             Self::High => "High",
             Self::Medium => "Medium",
             Self::Low => "Low",
+            Self::Unverified => "Unverified",
         }
     }
 }
@@ -48,6 +62,7 @@ impl FromStr for TrustTier {
             "high" => Ok(Self::High),
             "medium" => Ok(Self::Medium),
             "low" => Ok(Self::Low),
+            "unverified" | "unknown" => Ok(Self::Unverified),
             _ => Err(ParseTrustTierError),
         }
     }
@@ -0,0 +1,145 @@
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::Context as _;
+use serde_json::{Value, json};
+
+use crate::cli::SpiderArgs;
+use crate::spider::{CrawlResult, ReportFormat};
+
+/// File name of the manifest at the root of a working directory.
+pub const MANIFEST: &str = "manifest.json";
+
+/// One directory holding every artifact of a crawl (`--workdir`), described by a
+/// manifest written when the crawl finishes.
+///
+/// Artifact options left unset point into it:
+///
+/// | Option | Path |
+/// |--------|------|
+/// | `--cache-dir` | `cache/` |
+/// | `--warc` | `archive.warc.gz` |
+/// | `--audit-log` | `audit.jsonl` |
+/// | `--urls-out` | `urls.txt` |
+/// | `--selection-log` | `selections.jsonl` |
+///
+/// The report goes to `report.md` or `report.json` as well as stdout.
+#[derive(Debug, Clone)]
+pub struct Workdir {
+    root: PathBuf,
+    started_at: SystemTime,
+}
+
+impl Workdir {
+    /// Creates `root` if needed; files of an earlier run in it are overwritten.
+    pub fn create(root: &Path) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(root)
+            .with_context(|| format!("create working directory: {}", root.display()))?;
+        Ok(Self {
+            root: root.to_owned(),
+            started_at: SystemTime::now(),
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Points the artifact options of `args` that are still unset into the directory.
+    pub fn fill_args(&self, args: &mut SpiderArgs) {
+        let fill = |path: &mut Option<PathBuf>, name: &str| {
+            if path.is_none() {
+                *path = Some(self.root.join(name));
+            }
+        };
+        fill(&mut args.cache_dir, "cache");
+        fill(&mut args.warc, "archive.warc.gz");
+        fill(&mut args.audit_log, "audit.jsonl");
+        // A batch run writes no URL list.
+        if args.batch.is_none() {
+            fill(&mut args.urls_out, "urls.txt");
+        }
+        fill(&mut args.selection_log, "selections.jsonl");
+    }
+
+    pub fn report_path(&self, format: ReportFormat) -> PathBuf {
+        self.root.join(match format {
+            ReportFormat::Markdown => "report.md",
+            ReportFormat::Json => "report.json",
+        })
+    }
+
+    pub fn write_report(&self, format: ReportFormat, report: &str) -> anyhow::Result<()> {
+        let path = self.report_path(format);
+        std::fs::write(&path, report).with_context(|| format!("write report: {}", path.display()))
+    }
+
+    /// The manifest: the queries with their counts, and every artifact `args` names
+    /// that exists, by path relative to the directory when it lies inside.
+    pub fn compose_manifest(
+        &self,
+        args: &SpiderArgs,
+        queries: &[&str],
+        results: &[CrawlResult],
+    ) -> Value {
+        let queries = queries
+            .iter()
+            .zip(results)
+            .map(|(query, result)| {
+                json!({
+                    "query": query,
+                    "sources": result.sources.len(),
+                    "pages_fetched": result.stats.pages_fetched,
+                    "llm_calls": result.stats.llm_calls,
+                })
+            })
+            .collect::<Vec<_>>();
+        let report = self.report_path(args.format);
+        let artifacts = [
+            ("report", Some(&report)),
+            ("cache", args.cache_dir.as_ref()),
+            ("warc", args.warc.as_ref()),
+            ("audit_log", args.audit_log.as_ref()),
+            ("url_list", args.urls_out.as_ref()),
+            ("selection_log", args.selection_log.as_ref()),
+            ("notes", args.notes_dir.as_ref()),
+        ]
+        .into_iter()
+        .filter_map(|(kind, path)| self.artifact(kind, path?))
+        .collect::<Vec<_>>();
+        json!({
+            "llm_spider_version": env!("CARGO_PKG_VERSION"),
+            "crawl_id": args.crawl_id,
+            "started_at": humantime::format_rfc3339_seconds(self.started_at).to_string(),
+            "finished_at": humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+            "queries": queries,
+            "artifacts": artifacts,
+        })
+    }
+
+    pub fn write_manifest(
+        &self,
+        args: &SpiderArgs,
+        queries: &[&str],
+        results: &[CrawlResult],
+    ) -> anyhow::Result<()> {
+        let manifest = self.compose_manifest(args, queries, results);
+        let path = self.root.join(MANIFEST);
+        std::fs::write(
+            &path,
+            format!("{}\n", serde_json::to_string_pretty(&manifest)?),
+        )
+        .with_context(|| format!("write manifest: {}", path.display()))
+    }
+
+    fn artifact(&self, kind: &str, path: &Path) -> Option<Value> {
+        let metadata = std::fs::metadata(path).ok()?;
+        let shown = path.strip_prefix(&self.root).unwrap_or(path);
+        Some(json!({
+            "kind": kind,
+            "path": shown.to_string_lossy(),
+            "directory": metadata.is_dir(),
+            "bytes": metadata.is_file().then_some(metadata.len()),
+        }))
+    }
+}
@@ -8,3 +8,228 @@ fn spider_help_includes_reasoning_effort_flag() {
         .success()
         .stdout(predicate::str::contains("--reasoning-effort"));
 }
+
+#[test]
+fn verbosity_flags_map_to_log_filters() {
+    use clap::Parser as _;
+    use llm_spider::cli::Cli;
+
+    let parse = |args: &[&str]| {
+        Cli::try_parse_from(["llm-spider", "spider", "--query", "q"].iter().chain(args))
+            .map(|cli| cli.log_filter())
+    };
+
+    assert_eq!(parse(&[]).unwrap(), None);
+    assert_eq!(parse(&["-v"]).unwrap(), Some("debug"));
+    assert_eq!(parse(&["-vv"]).unwrap(), Some("trace"));
+    assert_eq!(parse(&["-q"]).unwrap(), Some("off"));
+    assert!(parse(&["-q", "-v"]).is_err());
+}
+
+#[test]
+fn completions_are_generated_from_cli_definition() {
+    let mut cmd = assert_cmd::cargo::cargo_bin_cmd!("llm-spider");
+    cmd.args(["completions", "bash"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--max-pages"));
+}
+
+#[test]
+fn manpage_is_rendered_as_roff() {
+    let mut cmd = assert_cmd::cargo::cargo_bin_cmd!("llm-spider");
+    cmd.arg("manpage")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(".ie"))
+        .stdout(predicate::str::contains("llm-spider"));
+}
+
+#[test]
+fn profile_fills_only_options_not_given_explicitly() {
+    use clap::{CommandFactory as _, FromArgMatches as _};
+    use llm_spider::cli::{Cli, Command};
+
+    let profile = llm_spider::config::Profile {
+        max_pages: Some(60),
+        max_depth: Some(3),
+        max_depth_low: Some(0),
+        ..Default::default()
+    };
+    let matches = Cli::command()
+        .try_get_matches_from(["llm-spider", "spider", "--query", "q", "--max-depth", "1"])
+        .expect("parse");
+    let Command::Spider(mut args) = Cli::from_arg_matches(&matches).expect("cli").command else {
+        panic!("expected spider subcommand");
+    };
+    args.apply_profile(&profile, matches.subcommand_matches("spider").unwrap());
+
+    assert_eq!(args.max_pages, 60);
+    assert_eq!(args.max_depth, 1);
+    assert_eq!(args.max_depth_low, Some(0));
+    assert_eq!(args.max_chars, 4000);
+}
+
+#[test]
+fn quick_caps_limits_and_turns_on_the_answer_unless_given_explicitly() {
+    use clap::{CommandFactory as _, FromArgMatches as _};
+    use llm_spider::cli::{Cli, Command};
+
+    let matches = Cli::command()
+        .try_get_matches_from([
+            "llm-spider",
+            "spider",
+            "--query",
+            "q",
+            "--quick",
+            "--max-pages",
+            "8",
+            "--max-depth-high",
+            "1",
+        ])
+        .expect("parse");
+    let Command::Spider(mut args) = Cli::from_arg_matches(&matches).expect("cli").command else {
+        panic!("expected spider subcommand");
+    };
+    args.max_depth_low = Some(2);
+    args.apply_quick(matches.subcommand_matches("spider").unwrap());
+
+    assert_eq!(args.max_pages, 8);
+    assert_eq!(args.max_elapsed, std::time::Duration::from_secs(10));
+    assert_eq!(args.max_depth, 0);
+    assert_eq!(args.max_depth_high, Some(1));
+    assert_eq!(args.max_depth_low, None);
+    assert!(args.answer);
+}
+
+#[test]
+fn compare_takes_two_or_more_alternatives_and_makes_query_optional() {
+    use clap::Parser as _;
+    use llm_spider::cli::{Cli, Command};
+
+    let parse = |args: &[&str]| Cli::try_parse_from(["llm-spider", "spider"].iter().chain(args));
+
+    let Command::Spider(args) = parse(&["--compare", "tokio", "async-std"])
+        .expect("compare without query")
+        .command
+    else {
+        panic!("expected spider");
+    };
+    assert_eq!(args.compare, ["tokio", "async-std"]);
+    assert_eq!(args.query, None);
+
+    assert!(parse(&["--compare", "tokio"]).is_err());
+    assert!(parse(&[]).is_err(), "--query is required without --compare");
+}
+
+#[test]
+fn batch_replaces_query_and_excludes_compare() {
+    use clap::Parser as _;
+    use llm_spider::cli::Cli;
+
+    let parse = |args: &[&str]| Cli::try_parse_from(["llm-spider", "spider"].iter().chain(args));
+
+    assert!(parse(&["--batch", "queries.txt"]).is_ok());
+    assert!(parse(&["--batch", "queries.txt", "--query", "q"]).is_err());
+    assert!(parse(&["--batch", "queries.txt", "--compare", "a", "b"]).is_err());
+    assert!(parse(&[]).is_err());
+}
+
+#[test]
+fn crawl_id_is_limited_to_header_safe_characters() {
+    use clap::Parser as _;
+    use llm_spider::cli::{Cli, Command};
+
+    let parse = |id: &str| {
+        Cli::try_parse_from(["llm-spider", "spider", "--query", "q", "--crawl-id", id]).map(|cli| {
+            match cli.command {
+                Command::Spider(args) => args.crawl_id,
+                _ => None,
+            }
+        })
+    };
+
+    assert_eq!(
+        parse("run-2024.06_a").unwrap().as_deref(),
+        Some("run-2024.06_a")
+    );
+    assert!(parse("").is_err());
+    assert!(parse("run 1").is_err());
+    assert!(parse("run\r\nx-evil: 1").is_err());
+    assert!(parse(&"x".repeat(65)).is_err());
+}
+
+#[test]
+fn workdir_collects_unset_artifacts_and_lists_them_in_the_manifest() {
+    use clap::Parser as _;
+    use llm_spider::cli::{Cli, Command};
+    use llm_spider::workdir::Workdir;
+
+    let root = std::env::temp_dir().join(format!(
+        "llm-spider-workdir-{}-{}",
+        std::process::id(),
+        line!()
+    ));
+    let _ = std::fs::remove_dir_all(&root);
+    let cli = Cli::try_parse_from([
+        "llm-spider",
+        "spider",
+        "--query",
+        "q",
+        "--workdir",
+        root.to_str().unwrap(),
+        "--warc",
+        "/elsewhere/crawl.warc",
+    ])
+    .expect("parse");
+    let Command::Spider(mut args) = cli.command else {
+        panic!("expected spider subcommand");
+    };
+    let workdir = Workdir::create(args.workdir.as_deref().unwrap()).expect("create workdir");
+    workdir.fill_args(&mut args);
+
+    assert_eq!(args.cache_dir, Some(root.join("cache")));
+    assert_eq!(args.warc, Some("/elsewhere/crawl.warc".into()));
+    assert_eq!(args.audit_log, Some(root.join("audit.jsonl")));
+    assert_eq!(args.urls_out, Some(root.join("urls.txt")));
+    assert_eq!(args.selection_log, Some(root.join("selections.jsonl")));
+
+    std::fs::create_dir_all(root.join("cache")).unwrap();
+    std::fs::write(root.join("urls.txt"), "visited https://example.test/\n").unwrap();
+    workdir.write_report(args.format, "# Report\n").unwrap();
+    workdir
+        .write_manifest(&args, &["q"], &[Default::default()])
+        .unwrap();
+
+    let manifest: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(root.join("manifest.json")).unwrap())
+            .unwrap();
+    assert_eq!(manifest["queries"][0]["query"], "q");
+    let artifacts = manifest["artifacts"].as_array().unwrap();
+    let paths = artifacts
+        .iter()
+        .map(|artifact| artifact["path"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(paths, ["report.md", "cache", "urls.txt"]);
+    assert_eq!(artifacts[0]["bytes"], 9);
+    assert_eq!(artifacts[1]["directory"], true);
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn site_requires_a_root_url_and_a_query() {
+    use clap::Parser as _;
+    use llm_spider::cli::{Cli, Command};
+
+    let parse = |args: &[&str]| Cli::try_parse_from(["llm-spider", "site"].iter().chain(args));
+
+    let cli = parse(&["--root", "https://docs.example.com/", "--query", "q"]).unwrap();
+    let Command::Site(args) = cli.command else {
+        panic!("expected the site command");
+    };
+    assert_eq!(args.root.as_str(), "https://docs.example.com/");
+    assert_eq!(args.max_pages, 200);
+    assert!(parse(&["--root", "not a url", "--query", "q"]).is_err());
+    assert!(parse(&["--query", "q"]).is_err());
+    assert!(parse(&["--root", "https://docs.example.com/"]).is_err());
+}
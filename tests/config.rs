@@ -0,0 +1,251 @@
+use std::time::Duration;
+
+use llm_spider::config::Config;
+use llm_spider::openai::{ModelCapabilities, SearchContextSize};
+
+#[test]
+fn config_parses_host_policies() {
+    let config = Config::parse(
+        r#"
+[hosts."community.example"]
+min_interval = "2s"
+max_pages = 3
+
+[hosts."infra.internal"]
+min_interval = "0s"
+max_concurrent = 8
+trust_tier = "High"
+"#,
+    )
+    .expect("parse config");
+
+    let community = &config.hosts["community.example"];
+    assert_eq!(community.min_interval, Some(Duration::from_secs(2)));
+    assert_eq!(community.max_pages, Some(3));
+    assert_eq!(community.max_concurrent, None);
+
+    let infra = &config.hosts["infra.internal"];
+    assert_eq!(infra.min_interval, Some(Duration::ZERO));
+    assert_eq!(infra.max_concurrent, Some(8));
+    assert_eq!(infra.trust_tier, Some(llm_spider::spider::TrustTier::High));
+    assert_eq!(community.trust_tier, None);
+}
+
+#[test]
+fn config_trust_tier_round_trips_through_strings() {
+    use llm_spider::spider::TrustTier;
+
+    let config = Config::parse("[hosts.\"a.example\"]\ntrust_tier = \"Unknown\"\n").expect("parse");
+    assert_eq!(
+        config.hosts["a.example"].trust_tier,
+        Some(TrustTier::Unverified)
+    );
+    for tier in TrustTier::ALL {
+        assert_eq!(tier.as_str().parse::<TrustTier>(), Ok(tier));
+        assert_eq!(
+            serde_json::to_value(tier).unwrap(),
+            serde_json::Value::from(tier.as_str())
+        );
+    }
+}
+
+#[test]
+fn config_rejects_unknown_keys_and_zero_concurrency() {
+    assert!(Config::parse("[hosts.\"a.example\"]\nmin_intervl = \"1s\"\n").is_err());
+    assert!(Config::parse("[hosts.\"a.example\"]\nmax_concurrent = 0\n").is_err());
+}
+
+#[test]
+fn config_parses_model_pricing() {
+    let config = Config::parse(
+        r#"
+[pricing."gpt-5.2"]
+input_per_million = 1.25
+output_per_million = 10.0
+"#,
+    )
+    .expect("parse config");
+
+    let price = &config.pricing["gpt-5.2"];
+    assert_eq!(price.input_per_million, 1.25);
+    assert_eq!(price.web_search_per_call, 0.0);
+}
+
+#[test]
+fn config_parses_profiles_and_layers_profile_hosts() {
+    let config = Config::parse(
+        r#"
+[hosts."a.example"]
+max_pages = 3
+
+[profile.deep]
+max_pages = 60
+max_depth = 3
+max_elapsed = "5m"
+sources_format = "table"
+trust_merge = "llm-wins"
+reasoning_effort = "high"
+select_model = "gpt-5.2-mini"
+
+[profile.deep.hosts."a.example"]
+max_pages = 10
+"#,
+    )
+    .expect("parse config");
+
+    let deep = config.profile("deep").expect("profile");
+    assert_eq!(deep.max_pages, Some(60));
+    assert_eq!(deep.max_elapsed, Some(Duration::from_secs(300)));
+    assert_eq!(
+        deep.sources_format,
+        Some(llm_spider::spider::SourcesFormat::Table)
+    );
+    assert_eq!(
+        deep.trust_merge,
+        Some(llm_spider::spider::TrustMergePolicy::LlmWins)
+    );
+    assert_eq!(
+        deep.reasoning_effort,
+        Some(llm_spider::openai::ReasoningEffort::High)
+    );
+    assert_eq!(config.host_policies(None)["a.example"].max_pages, Some(3));
+    assert_eq!(
+        config.host_policies(Some(deep))["a.example"].max_pages,
+        Some(10)
+    );
+
+    let err = config.profile("quick").unwrap_err().to_string();
+    assert!(err.contains("unknown profile `quick`"), "{err}");
+    assert!(Config::parse("[profile.x]\nmax_pagez = 1\n").is_err());
+}
+
+#[test]
+fn config_parses_openai_org_and_project() {
+    let config = Config::parse(
+        r#"
+[openai]
+organization = "org-123"
+project = "proj_abc"
+"#,
+    )
+    .expect("parse config");
+
+    assert_eq!(config.openai.organization.as_deref(), Some("org-123"));
+    assert_eq!(config.openai.project.as_deref(), Some("proj_abc"));
+    assert!(Config::parse("[openai]\norg = \"x\"\n").is_err());
+}
+
+#[test]
+fn config_parses_openai_timeout_and_output_tokens() {
+    let config = Config::parse(
+        r#"
+[openai]
+timeout = "90s"
+select_max_output_tokens = 2048
+"#,
+    )
+    .expect("parse config");
+
+    assert_eq!(config.openai.timeout, Some(Duration::from_secs(90)));
+    assert_eq!(config.openai.search_max_output_tokens, None);
+    assert_eq!(config.openai.select_max_output_tokens, Some(2048));
+}
+
+#[test]
+fn config_parses_model_capabilities() {
+    let config = Config::parse(
+        r#"
+[models."my-proxy-reasoner"]
+reasoning = true
+temperature = false
+
+[models."local-llm"]
+temperature = true
+"#,
+    )
+    .expect("parse config");
+
+    assert_eq!(
+        config.models["my-proxy-reasoner"],
+        ModelCapabilities {
+            reasoning: Some(true),
+            temperature: Some(false),
+        }
+    );
+    assert_eq!(config.models["local-llm"].reasoning, None);
+    assert!(
+        Config::parse(
+            "[models.x]
+top_p = true
+"
+        )
+        .is_err()
+    );
+}
+
+#[test]
+fn config_parses_web_search_options() {
+    let config = Config::parse(
+        r#"
+[openai.web_search]
+max_tool_calls = 3
+search_context_size = "high"
+user_location = { country = "JP", timezone = "Asia/Tokyo" }
+"#,
+    )
+    .expect("parse config");
+
+    let web_search = &config.openai.web_search;
+    assert_eq!(web_search.max_tool_calls, Some(3));
+    assert_eq!(
+        web_search.search_context_size,
+        Some(SearchContextSize::High)
+    );
+    assert_eq!(web_search.user_location.country.as_deref(), Some("JP"));
+    assert_eq!(web_search.user_location.city, None);
+    assert!(Config::parse("[openai.web_search]\nuser_location = { locale = \"ja\" }\n").is_err());
+}
+
+#[test]
+fn config_loads_prompt_templates_relative_to_config_file() {
+    let dir = std::env::temp_dir().join(format!("llm-spider-prompts-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("prompts")).expect("mkdir");
+    std::fs::write(
+        dir.join("prompts/select.txt"),
+        "Query: {query}\nPick {max_select} of {candidates}\n",
+    )
+    .expect("write template");
+    std::fs::write(dir.join("bad.txt"), "{query} {page_url}").expect("write template");
+    let config_path = dir.join("llm-spider.toml");
+    std::fs::write(&config_path, "[prompts]\nselect = \"prompts/select.txt\"\n").expect("write");
+
+    let config = Config::load(&config_path).expect("load config");
+    let templates = config.prompts.templates().expect("templates");
+    assert_eq!(
+        templates.select_user.as_deref(),
+        Some("Query: {query}\nPick {max_select} of {candidates}\n")
+    );
+    assert_eq!(templates.search_user, None);
+
+    std::fs::write(&config_path, "[prompts]\nsearch = \"bad.txt\"\n").expect("write");
+    let err = Config::load(&config_path)
+        .expect("load config")
+        .prompts
+        .templates()
+        .expect_err("page_url is not a search placeholder");
+    assert!(format!("{err:#}").contains("{page_url}"), "{err:#}");
+}
+
+#[test]
+fn config_url_patterns_replace_one_builtin_list_at_a_time() {
+    let builtin = llm_spider::spider::UrlPatterns::default();
+    assert!(builtin.boost.contains(&"/docs/".to_owned()));
+    assert!(builtin.penalize.contains(&"/tag/".to_owned()));
+
+    let config = Config::parse("[url_patterns]\nboost = [\"/handbook/\"]\n").expect("parse");
+    assert_eq!(config.url_patterns.boost, vec!["/handbook/".to_owned()]);
+    assert_eq!(config.url_patterns.penalize, builtin.penalize);
+
+    assert_eq!(Config::parse("").expect("parse").url_patterns, builtin);
+    assert!(Config::parse("[url_patterns]\nboots = []\n").is_err());
+}
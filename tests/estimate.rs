@@ -0,0 +1,219 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use llm_spider::estimate::{ModelPricing, compose_estimate_markdown, estimate};
+use llm_spider::openai::{OpenAiModels, OutputTokenLimits};
+
+fn request() -> llm_spider::spider::UserRequest {
+    llm_spider::spider::UserRequest {
+        query: "q".to_owned(),
+        compare: Vec::new(),
+        intent: None,
+        classify_intent: false,
+        pinned_max_depth: false,
+        max_chars: 4000,
+        min_sources: 1,
+        search_limit: 10,
+        max_pages: 20,
+        max_depth: 1,
+        max_depth_high: None,
+        max_depth_medium: None,
+        max_depth_low: None,
+        max_elapsed: Duration::from_secs(30),
+        crawl_budget: None,
+        max_child_candidates: 20,
+        max_candidate_batches: 1,
+        max_children_per_page: 3,
+        max_children_per_hub: 0,
+        max_pagination: 0,
+        max_sources_per_host: 0,
+        max_frontier: 10_000,
+        max_visited: 100_000,
+        max_total_bytes: 0,
+        host_failure_limit: 3,
+        exclusion_statuses: vec![451],
+        honor_opt_outs: false,
+        dns_prefetch: 0,
+        cache_dir: None,
+        warc: None,
+        control_file: None,
+        challenge_retry: None,
+        schedule: None,
+        crawl_id: None,
+        audit_log: None,
+        selection_review: None,
+        plan_review: None,
+        cancellation: None,
+        host_policies: Default::default(),
+        url_patterns: Default::default(),
+        allow_local: false,
+        preferred_language: None,
+        seed_urls: Vec::new(),
+        on_search_failure: llm_spider::spider::SearchFailurePolicy::Abort,
+        on_selection_failure: llm_spider::spider::SelectionFailurePolicy::Heuristic,
+        paywall_policy: llm_spider::spider::PaywallPolicy::Flag,
+        frame_policy: llm_spider::spider::FramePolicy::Ignore,
+        robots_tags: llm_spider::spider::RobotsTagPolicy::Respect,
+        trust_merge: llm_spider::spider::TrustMergePolicy::ClassifierWins,
+        sources_format: llm_spider::spider::SourcesFormat::List,
+        findings_layout: llm_spider::spider::FindingsLayout::Flat,
+        follow_ups: false,
+        key_terms: false,
+        timeline: false,
+        grade_sources: false,
+        answer: false,
+        show_stats: false,
+        deterministic: false,
+        show_progress: false,
+    }
+}
+
+fn models() -> OpenAiModels {
+    OpenAiModels {
+        search: "search-model".to_owned(),
+        select: "select-model".to_owned(),
+    }
+}
+
+#[test]
+fn estimate_scales_selection_calls_with_max_pages() {
+    let mut req = request();
+    req.max_pages = 10;
+    let est = estimate(
+        &req,
+        &models(),
+        OutputTokenLimits::default(),
+        2,
+        &BTreeMap::new(),
+    );
+    assert_eq!(est.search.calls, 1);
+    assert_eq!(est.select.calls, 9);
+    assert_eq!(est.select.output_tokens, 9 * 256);
+    assert_eq!(est.cost_usd, None);
+
+    req.max_depth = 0;
+    let est = estimate(
+        &req,
+        &models(),
+        OutputTokenLimits::default(),
+        2,
+        &BTreeMap::new(),
+    );
+    assert_eq!(est.select.calls, 0);
+    assert_eq!(est.select.input_tokens, 0);
+}
+
+#[test]
+fn estimate_prices_tokens_and_search_calls() {
+    let mut req = request();
+    req.max_pages = 2;
+    let pricing = BTreeMap::from([
+        (
+            "search-model".to_owned(),
+            ModelPricing {
+                input_per_million: 1.0,
+                output_per_million: 10.0,
+                web_search_per_call: 0.01,
+            },
+        ),
+        (
+            "select-model".to_owned(),
+            ModelPricing {
+                input_per_million: 1.0,
+                output_per_million: 10.0,
+                web_search_per_call: 0.0,
+            },
+        ),
+    ]);
+    let est = estimate(&req, &models(), OutputTokenLimits::default(), 2, &pricing);
+
+    let tokens = est.search.input_tokens + est.select.input_tokens;
+    let output = est.search.output_tokens + est.select.output_tokens;
+    let expected = (tokens as f64 + output as f64 * 10.0) / 1_000_000.0 + 0.02;
+    let cost = est.cost_usd.expect("priced");
+    assert!((cost - expected).abs() < 1e-9, "{cost} != {expected}");
+
+    let md = compose_estimate_markdown(&req, &est);
+    assert!(md.contains("| select | select-model | 1 |"));
+    assert!(md.contains("Estimated cost: $"));
+}
+
+#[test]
+fn estimate_adds_an_analysis_call_for_subtopics() {
+    let mut req = request();
+    let est = estimate(
+        &req,
+        &models(),
+        OutputTokenLimits::default(),
+        2,
+        &BTreeMap::new(),
+    );
+    assert_eq!(est.analysis.calls, 0);
+    assert!(!compose_estimate_markdown(&req, &est).contains("| analysis |"));
+
+    req.findings_layout = llm_spider::spider::FindingsLayout::Subtopics;
+    let est = estimate(
+        &req,
+        &models(),
+        OutputTokenLimits::default(),
+        2,
+        &BTreeMap::new(),
+    );
+    assert_eq!(est.analysis.calls, 1);
+    assert_eq!(est.analysis.model, "select-model");
+    assert!(est.analysis.input_tokens > est.select.input_tokens / est.select.calls);
+    assert!(compose_estimate_markdown(&req, &est).contains("| analysis | select-model | 1 |"));
+}
+
+#[test]
+fn estimate_runs_one_search_per_compared_alternative() {
+    let mut req = request();
+    let single = estimate(
+        &req,
+        &models(),
+        OutputTokenLimits::default(),
+        2,
+        &BTreeMap::new(),
+    );
+    req.compare = vec!["a".to_owned(), "b".to_owned(), "c".to_owned()];
+    let est = estimate(
+        &req,
+        &models(),
+        OutputTokenLimits::default(),
+        2,
+        &BTreeMap::new(),
+    );
+    assert_eq!(est.search.calls, 3);
+    assert_eq!(est.search.input_tokens, single.search.input_tokens * 3);
+}
+
+#[test]
+fn estimate_uses_configured_output_token_limits() {
+    let req = request();
+    let limits = OutputTokenLimits {
+        search: 2048,
+        select: 1024,
+    };
+    let est = estimate(&req, &models(), limits, 2, &BTreeMap::new());
+    assert_eq!(est.search.output_tokens, 2048);
+    assert_eq!(est.select.output_tokens, 1024 * 19);
+}
+
+#[test]
+fn estimate_scales_search_cost_with_max_tool_calls() {
+    let mut req = request();
+    req.max_depth = 0;
+    let pricing = BTreeMap::from([(
+        "search-model".to_owned(),
+        ModelPricing {
+            input_per_million: 0.0,
+            output_per_million: 0.0,
+            web_search_per_call: 0.01,
+        },
+    )]);
+    let two = estimate(&req, &models(), OutputTokenLimits::default(), 2, &pricing);
+    let five = estimate(&req, &models(), OutputTokenLimits::default(), 5, &pricing);
+    assert!(five.search.input_tokens > two.search.input_tokens);
+    let cost = five.cost_usd.expect("priced");
+    assert!((cost - 0.05).abs() < 1e-9, "{cost}");
+}
@@ -0,0 +1,32 @@
+use std::time::Duration;
+
+use llm_spider::notify::CrawlNotifier;
+use llm_spider::spider::CrawlResult;
+
+#[test]
+fn notifier_summarizes_crawls_that_ran_past_the_threshold() {
+    let notifier = CrawlNotifier::start(Duration::ZERO);
+    let mut result = CrawlResult::default();
+    result.stats.pages_fetched = 4;
+    let done = notifier
+        .notification("rust async", Ok(std::slice::from_ref(&result)))
+        .expect("due");
+    assert_eq!(done.summary, "Crawl finished: rust async");
+    assert!(
+        done.body.starts_with("0 sources from 4 pages in "),
+        "{}",
+        done.body
+    );
+
+    let err = anyhow::anyhow!("timed out").context("crawl");
+    let failed = notifier.notification("rust async", Err(&err)).expect("due");
+    assert_eq!(failed.summary, "Crawl failed: rust async");
+    assert!(
+        failed.body.ends_with(": crawl: timed out"),
+        "{}",
+        failed.body
+    );
+
+    let patient = CrawlNotifier::start(Duration::from_secs(3600));
+    assert_eq!(patient.notification("rust async", Ok(&[])), None);
+}
@@ -0,0 +1,30 @@
+use llm_spider::output::ReportOutput;
+
+fn command(args: &[&str]) -> Vec<String> {
+    args.iter().map(|&arg| arg.to_owned()).collect()
+}
+
+#[test]
+fn copy_feeds_the_report_to_the_clipboard_command() {
+    let path = std::env::temp_dir().join(format!("llm-spider-clipboard-{}", std::process::id()));
+    let script = format!("cat > '{}'", path.display());
+    ReportOutput::new(true, false)
+        .with_clipboard_command(command(&["sh", "-c", &script]))
+        .emit("# Report\n")
+        .expect("emit");
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "# Report\n");
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn failed_copy_does_not_fail_the_run() {
+    ReportOutput::new(true, true)
+        .with_clipboard_command(command(&["llm-spider-no-such-clipboard"]))
+        .with_pager_command(command(&["llm-spider-no-such-pager"]))
+        .emit("# Report\n")
+        .expect("emit");
+    ReportOutput::new(true, false)
+        .with_clipboard_command(command(&["false"]))
+        .emit("# Report\n")
+        .expect("emit");
+}
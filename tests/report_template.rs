@@ -0,0 +1,85 @@
+use llm_spider::spider::ReportTemplate;
+use serde_json::json;
+
+#[test]
+fn report_template_renders_sections_loops_and_conditionals() {
+    let template = ReportTemplate::parse(
+        "---\n\
+         title: \"{{query}}\"\n\
+         ---\n\
+         {{! house style }}\n\
+         # {{query}}\n\
+         \n\
+         {{#each sources}}\n\
+         {{@index}}. [{{title}}]({{url}}) ({{trust_tier}}){{#if paywalled}} paywalled{{/if}}\n\
+         \x20  - query: {{../query}}, first: {{@first}}, headings: {{#each outline}}{{text}}{{#unless @last}} / {{/unless}}{{/each}}\n\
+         {{else}}\n\
+         No sources.\n\
+         {{/each}}\n\
+         {{#with stats}}\n\
+         Pages: {{pages_fetched}} for {{@root.sources.length}} sources\n\
+         {{/with}}\n\
+         Notes: {{notes}}\n",
+    )
+    .expect("parse");
+
+    let data = json!({
+        "query": "rust async",
+        "sources": [
+            {
+                "url": "https://a.test/",
+                "title": "A",
+                "trust_tier": "High",
+                "paywalled": false,
+                "outline": [{"text": "Intro"}, {"text": "Usage"}],
+            },
+            {
+                "url": "https://b.test/",
+                "title": "B",
+                "trust_tier": "Low",
+                "paywalled": true,
+                "outline": [],
+            },
+        ],
+        "stats": {"pages_fetched": 3},
+        "notes": ["one", "two"],
+    });
+    assert_eq!(
+        template.render(&data),
+        "---\n\
+         title: \"rust async\"\n\
+         ---\n\
+         # rust async\n\
+         \n\
+         0. [A](https://a.test/) (High)\n\
+         \x20  - query: rust async, first: true, headings: Intro / Usage\n\
+         1. [B](https://b.test/) (Low) paywalled\n\
+         \x20  - query: rust async, first: false, headings: \n\
+         Pages: 3 for 2 sources\n\
+         Notes: one, two\n"
+    );
+
+    let empty = json!({"query": "q", "sources": [], "notes": []});
+    assert_eq!(
+        template.render(&empty),
+        "---\ntitle: \"q\"\n---\n# q\n\nNo sources.\nNotes: \n"
+    );
+}
+
+#[test]
+fn report_template_trims_whitespace_around_tilde_tags() {
+    let template = ReportTemplate::parse("{{#each items}}  {{~this~}}  ,{{/each}}").unwrap();
+    assert_eq!(template.render(&json!({"items": ["a", "b"]})), "a,b,");
+}
+
+#[test]
+fn report_template_rejects_malformed_templates_with_line_numbers() {
+    let err = |text: &str| format!("{:#}", ReportTemplate::parse(text).unwrap_err());
+
+    assert!(err("a\n{{#each sources}}\n").contains("line 2: #each is never closed"));
+    assert!(err("{{#if a}}\n{{/each}}").contains("line 2: /each closes #if"));
+    assert!(err("{{#lookup a b}}{{/lookup}}").contains("unsupported helper: #lookup"));
+    assert!(err("{{> header}}").contains("partials are not supported"));
+    assert!(err("{{format url}}").contains("unsupported expression"));
+    assert!(err("text {{query").contains("line 1: unclosed tag"));
+}
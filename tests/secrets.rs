@@ -0,0 +1,26 @@
+use llm_spider::secrets::{read_api_key_file, resolve_openai_api_key};
+
+fn temp_file(name: &str, contents: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("llm-spider-secrets-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn api_key_file_is_trimmed_and_takes_precedence() {
+    let path = temp_file("key", "sk-test-123\n");
+    assert_eq!(read_api_key_file(&path).unwrap(), "sk-test-123");
+    assert_eq!(resolve_openai_api_key(Some(&path)).unwrap(), "sk-test-123");
+}
+
+#[test]
+fn empty_or_missing_api_key_file_is_an_error() {
+    let path = temp_file("empty", " \n");
+    let err = read_api_key_file(&path).unwrap_err().to_string();
+    assert!(err.contains("api key file is empty"), "{err}");
+
+    let missing = path.with_file_name("missing");
+    assert!(resolve_openai_api_key(Some(&missing)).is_err());
+}
@@ -1,25 +1,62 @@
 use std::collections::HashMap;
-use std::sync::Arc;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use llm_spider::openai::{OpenAiApi, SearchHit, SelectedLink};
-use llm_spider::spider::{FetchedPage, PageFetcher, TrustTier, crawl_with_fetcher};
+use llm_spider::openai::{
+    Answer, GlossaryEntry, OpenAiApi, QueryIntent, SearchHit, SelectedLink, SourceGrade, Subtopic,
+    TimelineEvent,
+};
+use llm_spider::spider::{
+    CacheValidators, CancellationToken, ConnectionStats, ContentChange, CrawlError, FetchedPage,
+    FrontMatter, PageFetcher, Revalidated, SchedulePolicy, TrustTier, compose_front_matter,
+    compose_notes, crawl_batch_with_fetcher, crawl_with_fetcher,
+};
 use url::Url;
 
 #[derive(Default)]
 struct FakeOpenAi {
     hits: Vec<Url>,
+    /// Hits for specific search queries; other queries get `hits`.
+    hits_by_query: HashMap<String, Vec<Url>>,
     selected_by_page: HashMap<String, Vec<Url>>,
+    tier_by_url: HashMap<String, TrustTier>,
+    title_by_url: HashMap<String, String>,
     select_calls: Arc<AtomicUsize>,
+    seen_candidates: Arc<Mutex<Vec<String>>>,
+    /// URLs of the collected sources each selection call was given.
+    seen_collected: Arc<Mutex<Vec<Vec<String>>>>,
+    failing_selection: Vec<String>,
+    subtopics: Vec<Subtopic>,
+    follow_ups: Vec<String>,
+    glossary: Vec<GlossaryEntry>,
+    timeline: Vec<TimelineEvent>,
+    grades: Vec<SourceGrade>,
+    intent: Option<QueryIntent>,
+    answer: Option<Answer>,
+    /// Stands in for the select model and prompts.
+    select_fingerprint: String,
 }
 
 impl FakeOpenAi {
+    fn with_failing_selection(mut self, page_url: &str) -> Self {
+        self.failing_selection.push(page_url.to_owned());
+        self
+    }
+
     fn with_hits(mut self, hits: Vec<&str>) -> Self {
         self.hits = hits.into_iter().map(|u| Url::parse(u).unwrap()).collect();
         self
     }
 
+    fn with_query_hits(mut self, query: &str, hits: Vec<&str>) -> Self {
+        self.hits_by_query.insert(
+            query.to_owned(),
+            hits.into_iter().map(|u| Url::parse(u).unwrap()).collect(),
+        );
+        self
+    }
+
     fn with_selected(mut self, page_url: &str, selected: Vec<&str>) -> Self {
         self.selected_by_page.insert(
             page_url.to_owned(),
@@ -30,19 +67,104 @@ impl FakeOpenAi {
         );
         self
     }
+
+    fn with_tier(mut self, url: &str, tier: TrustTier) -> Self {
+        self.tier_by_url.insert(url.to_owned(), tier);
+        self
+    }
+
+    fn with_subtopic(mut self, title: &str, sources: Vec<usize>) -> Self {
+        self.subtopics.push(Subtopic {
+            title: title.to_owned(),
+            sources,
+        });
+        self
+    }
+
+    fn with_follow_ups(mut self, queries: &[&str]) -> Self {
+        self.follow_ups = queries.iter().map(|q| (*q).to_owned()).collect();
+        self
+    }
+
+    fn with_glossary(mut self, entries: &[(&str, &str)]) -> Self {
+        self.glossary = entries
+            .iter()
+            .map(|(term, definition)| GlossaryEntry {
+                term: (*term).to_owned(),
+                definition: (*definition).to_owned(),
+            })
+            .collect();
+        self
+    }
+
+    fn with_timeline(mut self, events: &[(&str, &str, &[usize])]) -> Self {
+        self.timeline = events
+            .iter()
+            .map(|(date, event, sources)| TimelineEvent {
+                date: (*date).to_owned(),
+                event: (*event).to_owned(),
+                sources: sources.to_vec(),
+            })
+            .collect();
+        self
+    }
+
+    fn with_answer(mut self, paragraphs: &[&str], sources: &[usize]) -> Self {
+        self.answer = Some(Answer {
+            paragraphs: paragraphs.iter().map(|p| (*p).to_owned()).collect(),
+            sources: sources.to_vec(),
+        });
+        self
+    }
+
+    fn with_intent(mut self, intent: QueryIntent) -> Self {
+        self.intent = Some(intent);
+        self
+    }
+
+    fn with_grades(mut self, grades: &[(usize, bool, &str)]) -> Self {
+        self.grades = grades
+            .iter()
+            .map(|(source, keep, reason)| SourceGrade {
+                source: *source,
+                keep: *keep,
+                reason: (*reason).to_owned(),
+            })
+            .collect();
+        self
+    }
+
+    fn with_title(mut self, url: &str, title: &str) -> Self {
+        self.title_by_url.insert(url.to_owned(), title.to_owned());
+        self
+    }
+
+    fn tier_for(&self, url: &Url) -> TrustTier {
+        self.tier_by_url
+            .get(url.as_str())
+            .copied()
+            .unwrap_or(TrustTier::Medium)
+    }
 }
 
 impl OpenAiApi for FakeOpenAi {
-    fn web_search(&self, _query: &str, limit: usize) -> anyhow::Result<Vec<SearchHit>> {
+    fn classify_intent(&self, _query: &str) -> anyhow::Result<QueryIntent> {
+        self.intent
+            .ok_or_else(|| anyhow::anyhow!("no intent configured"))
+    }
+
+    fn web_search(&self, query: &str, limit: usize) -> anyhow::Result<Vec<SearchHit>> {
         Ok(self
-            .hits
+            .hits_by_query
+            .get(query)
+            .unwrap_or(&self.hits)
             .iter()
             .take(limit)
             .cloned()
             .map(|url| SearchHit {
+                trust_tier: self.tier_for(&url),
+                title: self.title_by_url.get(url.as_str()).cloned(),
                 url,
-                title: None,
-                trust_tier: TrustTier::Medium,
             })
             .collect())
     }
@@ -52,30 +174,134 @@ impl OpenAiApi for FakeOpenAi {
         _query: &str,
         page_url: &Url,
         _page_excerpt: &str,
-        _candidates: &[serde_json::Value],
+        candidates: &[serde_json::Value],
+        collected: &[serde_json::Value],
         max_select: usize,
     ) -> anyhow::Result<Vec<SelectedLink>> {
         self.select_calls.fetch_add(1, Ordering::Relaxed);
+        self.seen_collected.lock().unwrap().push(
+            collected
+                .iter()
+                .filter_map(|c| c.get("url").and_then(|u| u.as_str()))
+                .map(str::to_owned)
+                .collect(),
+        );
+        let candidate_urls = candidates
+            .iter()
+            .filter_map(|c| c.get("url").and_then(|u| u.as_str()))
+            .map(str::to_owned)
+            .collect::<Vec<_>>();
+        self.seen_candidates
+            .lock()
+            .unwrap()
+            .extend(candidate_urls.iter().cloned());
+        if self
+            .failing_selection
+            .iter()
+            .any(|u| u == page_url.as_str())
+        {
+            anyhow::bail!("selection schema mismatch");
+        }
         let selected = self
             .selected_by_page
             .get(page_url.as_str())
             .cloned()
             .unwrap_or_default();
+        // Like the real client, only offered candidates can be selected.
         Ok(selected
             .into_iter()
+            .filter(|url| candidate_urls.iter().any(|c| c == url.as_str()))
             .take(max_select)
             .map(|url| SelectedLink {
+                trust_tier: self.tier_for(&url),
                 url,
-                trust_tier: TrustTier::Medium,
             })
             .collect())
     }
+
+    fn cluster_sources(
+        &self,
+        _query: &str,
+        _sources: &[serde_json::Value],
+        _max_topics: usize,
+    ) -> anyhow::Result<Vec<Subtopic>> {
+        if self.subtopics.is_empty() {
+            anyhow::bail!("no subtopics configured");
+        }
+        Ok(self.subtopics.clone())
+    }
+
+    fn suggest_follow_ups(
+        &self,
+        _query: &str,
+        _sources: &[serde_json::Value],
+        max_queries: usize,
+    ) -> anyhow::Result<Vec<String>> {
+        if self.follow_ups.is_empty() {
+            anyhow::bail!("no follow-ups configured");
+        }
+        Ok(self.follow_ups.iter().take(max_queries).cloned().collect())
+    }
+
+    fn extract_key_terms(
+        &self,
+        _query: &str,
+        _sources: &[serde_json::Value],
+        max_terms: usize,
+    ) -> anyhow::Result<Vec<GlossaryEntry>> {
+        if self.glossary.is_empty() {
+            anyhow::bail!("no glossary configured");
+        }
+        Ok(self.glossary.iter().take(max_terms).cloned().collect())
+    }
+
+    fn extract_timeline(
+        &self,
+        _query: &str,
+        _sources: &[serde_json::Value],
+        max_events: usize,
+    ) -> anyhow::Result<Vec<TimelineEvent>> {
+        if self.timeline.is_empty() {
+            anyhow::bail!("no timeline configured");
+        }
+        Ok(self.timeline.iter().take(max_events).cloned().collect())
+    }
+
+    fn grade_sources(
+        &self,
+        _query: &str,
+        _sources: &[serde_json::Value],
+    ) -> anyhow::Result<Vec<SourceGrade>> {
+        if self.grades.is_empty() {
+            anyhow::bail!("no grades configured");
+        }
+        Ok(self.grades.clone())
+    }
+
+    fn answer_query(
+        &self,
+        _query: &str,
+        _sources: &[serde_json::Value],
+        _max_paragraphs: usize,
+    ) -> anyhow::Result<Answer> {
+        self.answer
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("no answer configured"))
+    }
+
+    fn select_fingerprint(&self) -> String {
+        self.select_fingerprint.clone()
+    }
 }
 
 #[derive(Default)]
 struct FakeFetcher {
     pages: HashMap<String, FetchedPage>,
     disallow: Vec<String>,
+    not_modified: Vec<String>,
+    revalidations: Arc<AtomicUsize>,
+    fetched: Arc<Mutex<Vec<String>>>,
+    prefetched: Arc<Mutex<Vec<Vec<String>>>>,
 }
 
 impl FakeFetcher {
@@ -88,12 +314,20 @@ impl FakeFetcher {
                     .into_iter()
                     .map(|u| Url::parse(u).unwrap())
                     .collect::<Vec<_>>(),
-                robots_delay: Duration::from_millis(0),
+                ..Default::default()
             },
         );
         self
     }
 
+    fn with_robots_tag(mut self, url: &str, value: &str) -> Self {
+        self.pages
+            .get_mut(url)
+            .expect("page added before its X-Robots-Tag")
+            .x_robots_tag = Some(value.to_owned());
+        self
+    }
+
     fn with_robots_disallow(mut self, url: &str) -> Self {
         self.disallow.push(url.to_owned());
         self
@@ -102,6 +336,7 @@ impl FakeFetcher {
 
 impl PageFetcher for FakeFetcher {
     fn fetch(&self, url: &Url) -> anyhow::Result<FetchedPage> {
+        self.fetched.lock().unwrap().push(url.to_string());
         if self.disallow.iter().any(|u| u == url.as_str()) {
             anyhow::bail!("blocked by robots.txt");
         }
@@ -110,20 +345,103 @@ impl PageFetcher for FakeFetcher {
             .cloned()
             .ok_or_else(|| anyhow::anyhow!("missing page fixture: {url}"))
     }
+
+    fn revalidate(&self, url: &Url, validators: &CacheValidators) -> anyhow::Result<Revalidated> {
+        self.revalidations.fetch_add(1, Ordering::Relaxed);
+        let current = self.pages.get(url.as_str()).and_then(|p| p.etag.clone());
+        if self.not_modified.iter().any(|u| u == url.as_str()) && current == validators.etag {
+            return Ok(Revalidated::NotModified {
+                robots_delay: Duration::ZERO,
+                robots_max_concurrent: None,
+            });
+        }
+        self.fetch(url)
+            .map(|page| Revalidated::Modified(Box::new(page)))
+    }
+
+    fn prefetch_dns(&self, urls: &[Url]) {
+        self.prefetched
+            .lock()
+            .unwrap()
+            .push(urls.iter().map(Url::to_string).collect());
+    }
+
+    fn connection_stats(&self) -> Option<ConnectionStats> {
+        let fetched = self.fetched.lock().unwrap();
+        let mut hosts = fetched
+            .iter()
+            .filter_map(|url| Url::parse(url).ok()?.host_str().map(str::to_owned))
+            .collect::<Vec<_>>();
+        hosts.sort();
+        hosts.dedup();
+        Some(ConnectionStats {
+            requests: fetched.len() as u64,
+            connections_opened: hosts.len() as u64,
+        })
+    }
 }
 
 fn request(query: &str) -> llm_spider::spider::UserRequest {
     llm_spider::spider::UserRequest {
         query: query.to_owned(),
+        compare: Vec::new(),
+        intent: None,
+        classify_intent: false,
+        pinned_max_depth: false,
         max_chars: 4000,
         min_sources: 1,
         search_limit: 10,
         max_pages: 20,
         max_depth: 1,
+        max_depth_high: None,
+        max_depth_medium: None,
+        max_depth_low: None,
         max_elapsed: Duration::from_secs(30),
+        crawl_budget: None,
         max_child_candidates: 20,
+        max_candidate_batches: 1,
         max_children_per_page: 3,
+        max_children_per_hub: 0,
+        max_pagination: 0,
+        max_sources_per_host: 0,
+        max_frontier: 10_000,
+        max_visited: 100_000,
+        max_total_bytes: 0,
+        host_failure_limit: 3,
+        exclusion_statuses: vec![451],
+        honor_opt_outs: false,
+        dns_prefetch: 0,
+        cache_dir: None,
+        warc: None,
+        control_file: None,
+        challenge_retry: None,
+        schedule: None,
+        crawl_id: None,
+        audit_log: None,
+        selection_review: None,
+        plan_review: None,
+        cancellation: None,
+        host_policies: Default::default(),
+        url_patterns: Default::default(),
         allow_local: false,
+        preferred_language: None,
+        seed_urls: Vec::new(),
+        on_search_failure: llm_spider::spider::SearchFailurePolicy::Abort,
+        on_selection_failure: llm_spider::spider::SelectionFailurePolicy::Heuristic,
+        paywall_policy: llm_spider::spider::PaywallPolicy::Flag,
+        frame_policy: llm_spider::spider::FramePolicy::Ignore,
+        robots_tags: llm_spider::spider::RobotsTagPolicy::Respect,
+        trust_merge: llm_spider::spider::TrustMergePolicy::ClassifierWins,
+        sources_format: llm_spider::spider::SourcesFormat::List,
+        findings_layout: llm_spider::spider::FindingsLayout::Flat,
+        follow_ups: false,
+        key_terms: false,
+        timeline: false,
+        grade_sources: false,
+        answer: false,
+        show_stats: false,
+        deterministic: false,
+        show_progress: false,
     }
 }
 
@@ -225,6 +543,7 @@ fn spider_max_chars_stops_at_page_boundary() {
         r.max_pages = 2;
         let single = llm_spider::spider::CrawlResult {
             sources: vec![result.sources[0].clone()],
+            ..Default::default()
         };
         llm_spider::spider::compose_markdown(&r, &single)
     };
@@ -322,3 +641,4417 @@ fn spider_respects_robots_txt_disallow() {
         TrustTier::High | TrustTier::Medium | TrustTier::Low
     )));
 }
+
+#[test]
+fn spider_sources_table_includes_title_date_and_depth() {
+    let start = "https://example.test/start";
+    let a = "https://example.test/a";
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![start])
+        .with_selected(start, vec![a]);
+
+    let fetcher = FakeFetcher::default()
+        .with_page(
+            start,
+            r#"<html><head><title>Start | Docs</title>
+<meta property="article:published_time" content="2025-03-14T09:00:00Z"></head>
+<body><main>start</main></body></html>"#,
+            vec![a],
+        )
+        .with_page(a, "<main>a</main>", vec![]);
+
+    let mut req = request("q");
+    req.max_pages = 2;
+    req.max_depth = 1;
+    req.max_chars = 0;
+    req.sources_format = llm_spider::spider::SourcesFormat::Table;
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    let markdown = llm_spider::spider::compose_markdown(&req, &result);
+
+    assert!(markdown.contains("| # | Tier | Title | URL | Date | License | Depth | Via |"));
+    assert!(
+        markdown.contains(
+            "| Medium | Start \\| Docs | https://example.test/start | 2025-03-14 |  | 0 |"
+        )
+    );
+    assert!(markdown.contains(
+        "| 2 | Medium |  | https://example.test/a |  |  | 1 | https://example.test/start |"
+    ));
+}
+
+#[test]
+fn spider_detects_declared_licenses() {
+    let pages = [
+        (
+            "https://example.test/rel",
+            r#"<html><head><link rel="license" href="https://creativecommons.org/licenses/by/4.0/"></head>
+<body><main>rel</main></body></html>"#,
+            Some("CC-BY-4.0"),
+        ),
+        (
+            "https://example.test/meta",
+            r#"<html><head><meta name="license" content="mit"></head><body><main>meta</main></body></html>"#,
+            Some("MIT"),
+        ),
+        (
+            "https://example.test/spdx",
+            "<main>code</main><footer>SPDX-License-Identifier: MIT OR Apache-2.0</footer>",
+            Some("MIT OR Apache-2.0"),
+        ),
+        (
+            "https://example.test/badge",
+            r#"<main>badge</main><img src="//i.creativecommons.org/l/by-sa/4.0/88x31.png">"#,
+            Some("CC-BY-SA-4.0"),
+        ),
+        (
+            "https://example.test/footer",
+            r#"<main>see <a href="https://www.gnu.org/licenses/gpl-3.0.html">GPL</a></main>
+<footer><a href="https://opensource.org/licenses/BSD-3-Clause">BSD</a></footer>"#,
+            Some("BSD-3-Clause"),
+        ),
+        (
+            "https://example.test/none",
+            r#"<main>All rights reserved. <a href="https://opensource.org/licenses/MIT">MIT</a></main>"#,
+            None,
+        ),
+    ];
+    let mut fetcher = FakeFetcher::default();
+    for (url, html, _) in pages {
+        fetcher = fetcher.with_page(url, html, vec![]);
+    }
+    let openai = FakeOpenAi::default().with_hits(pages.iter().map(|(url, _, _)| *url).collect());
+    let mut req = request("q");
+    req.max_depth = 0;
+    req.max_pages = pages.len();
+    req.sources_format = llm_spider::spider::SourcesFormat::Table;
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    let licenses = result
+        .sources
+        .iter()
+        .map(|s| (s.url.as_str(), s.license.as_deref()))
+        .collect::<Vec<_>>();
+    let expected = pages
+        .iter()
+        .map(|(url, _, license)| (*url, *license))
+        .collect::<Vec<_>>();
+    assert_eq!(licenses, expected);
+
+    let markdown = llm_spider::spider::compose_markdown(&req, &result);
+    assert!(
+        markdown.contains("| https://example.test/rel |  | CC-BY-4.0 | 0 |"),
+        "{markdown}"
+    );
+    let json = llm_spider::spider::compose_json(&req, &result);
+    let permissive = json["sources"]
+        .as_array()
+        .expect("sources")
+        .iter()
+        .map(|s| s["license_permissive"].as_bool().expect("bool"))
+        .collect::<Vec<_>>();
+    assert_eq!(permissive, [true, true, true, false, true, false]);
+}
+
+#[test]
+fn spdx_license_expressions_are_permissive_when_an_alternative_is() {
+    use llm_spider::spider::is_permissive;
+
+    assert!(is_permissive("MIT OR GPL-3.0-or-later"));
+    assert!(is_permissive("(Apache-2.0 WITH LLVM-exception)"));
+    assert!(!is_permissive("MIT AND GPL-2.0-or-later"));
+    assert!(!is_permissive("CC-BY-NC-4.0"));
+}
+
+#[test]
+fn spider_records_http_metadata_per_source() {
+    let start = "https://example.test/start";
+    let openai = FakeOpenAi::default().with_hits(vec![start]);
+    let mut fetcher = FakeFetcher::default().with_page(start, "<main>start</main>", vec![]);
+    let page = fetcher.pages.get_mut(start).expect("fixture");
+    page.status = Some(200);
+    page.final_url = Some(Url::parse("https://example.test/start/").unwrap());
+    page.content_type = Some("text/html; charset=utf-8".to_owned());
+    page.content_length = Some(512);
+    page.bytes = 2048;
+    page.server_timing = Some("db;dur=53, app;dur=47.2".to_owned());
+    let mut req = request("q");
+    req.max_depth = 0;
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    let http = &result.sources[0].http;
+    assert_eq!(http.status, Some(200));
+    assert_eq!(http.bytes, 2048);
+
+    let json = llm_spider::spider::compose_json(&req, &result);
+    let http = &json["sources"][0]["http"];
+    assert_eq!(http["status"], 200);
+    assert_eq!(http["final_url"], "https://example.test/start/");
+    assert_eq!(http["content_type"], "text/html; charset=utf-8");
+    assert_eq!(http["content_length"], 512);
+    assert_eq!(http["bytes"], 2048);
+    assert_eq!(http["server_timing"], "db;dur=53, app;dur=47.2");
+    assert!(http["fetch_ms"].is_u64(), "{http}");
+}
+
+const CHALLENGE_PAGE: &str = r#"<html><head><title>Just a moment...</title></head>
+<body><main>Checking if the site connection is secure. Enable JavaScript and cookies to continue.</main>
+<script>window._cf_chl_opt = {cType: 'managed'};</script></body></html>"#;
+
+#[test]
+fn spider_skips_bot_challenge_pages() {
+    let challenged = "https://example.test/challenged";
+    let ok = "https://example.test/ok";
+    let openai = FakeOpenAi::default().with_hits(vec![challenged, ok]);
+    let fetcher = FakeFetcher::default()
+        .with_page(challenged, CHALLENGE_PAGE, vec![])
+        .with_page(ok, "<main>ok</main>", vec![]);
+    let mut req = request("q");
+    req.max_depth = 0;
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    let urls = result
+        .sources
+        .iter()
+        .map(|s| s.url.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(urls, [ok]);
+    assert_eq!(result.failures.len(), 1);
+    assert_eq!(result.failures[0].message, "bot challenge (cloudflare)");
+    assert!(
+        llm_spider::spider::compose_url_list(&result)
+            .contains(&format!("{challenged}\tskipped:bot-challenge\n"))
+    );
+}
+
+/// Serves a challenge on the first fetch of each URL and the real page afterwards.
+struct ChallengeOnce {
+    inner: FakeFetcher,
+    challenged: Mutex<Vec<String>>,
+}
+
+impl PageFetcher for ChallengeOnce {
+    fn fetch(&self, url: &Url) -> anyhow::Result<FetchedPage> {
+        let mut challenged = self.challenged.lock().unwrap();
+        if !challenged.contains(&url.to_string()) {
+            challenged.push(url.to_string());
+            return Ok(FetchedPage {
+                html: CHALLENGE_PAGE.to_owned(),
+                ..Default::default()
+            });
+        }
+        self.inner.fetch(url)
+    }
+}
+
+#[test]
+fn spider_retries_a_challenged_page_once_after_a_delay() {
+    let start = "https://example.test/start";
+    let openai = FakeOpenAi::default().with_hits(vec![start]);
+    let fetcher = ChallengeOnce {
+        inner: FakeFetcher::default().with_page(start, "<main>real content</main>", vec![]),
+        challenged: Mutex::new(Vec::new()),
+    };
+    let mut req = request("q");
+    req.max_depth = 0;
+    req.challenge_retry = Some(Duration::from_millis(10));
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert_eq!(result.sources.len(), 1);
+    assert!(result.sources[0].content.contains("real content"));
+    assert!(result.failures.is_empty(), "{:?}", result.failures);
+}
+
+/// Serves a challenge on the first `challenges` fetches of every URL.
+struct ChallengeTimes {
+    inner: FakeFetcher,
+    challenges: usize,
+    fetches: Mutex<HashMap<String, usize>>,
+}
+
+impl PageFetcher for ChallengeTimes {
+    fn fetch(&self, url: &Url) -> anyhow::Result<FetchedPage> {
+        let mut fetches = self.fetches.lock().unwrap();
+        let count = fetches.entry(url.to_string()).or_default();
+        *count += 1;
+        if *count <= self.challenges {
+            return Ok(FetchedPage {
+                html: CHALLENGE_PAGE.to_owned(),
+                ..Default::default()
+            });
+        }
+        self.inner.fetch(url)
+    }
+}
+
+/// No pacing, and up to `retries` challenge retries; records the hosts it was asked about.
+#[derive(Debug, Default)]
+struct EagerSchedule {
+    retries: usize,
+    hosts: Mutex<Vec<String>>,
+}
+
+impl SchedulePolicy for EagerSchedule {
+    fn min_interval(&self, host: &str, _robots_delay: Duration) -> Duration {
+        self.hosts.lock().unwrap().push(host.to_owned());
+        Duration::ZERO
+    }
+
+    fn max_concurrent(&self, _host: &str, _robots_max_concurrent: Option<usize>) -> Option<usize> {
+        None
+    }
+
+    fn retry_delay(&self, _url: &Url, attempt: usize) -> Option<Duration> {
+        (attempt <= self.retries).then_some(Duration::ZERO)
+    }
+}
+
+#[test]
+fn spider_paces_and_retries_through_a_custom_schedule_policy() {
+    let start = "https://example.test/start";
+    let openai = FakeOpenAi::default().with_hits(vec![start]);
+    let fetcher = ChallengeTimes {
+        inner: FakeFetcher::default().with_page(start, "<main>real content</main>", vec![]),
+        challenges: 2,
+        fetches: Mutex::new(HashMap::new()),
+    };
+    let schedule = Arc::new(EagerSchedule {
+        retries: 2,
+        ..Default::default()
+    });
+    let mut req = request("q");
+    req.max_depth = 0;
+    req.schedule = Some(schedule.clone());
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert!(result.sources[0].content.contains("real content"));
+    assert_eq!(fetcher.fetches.lock().unwrap()[start], 3);
+    assert!(
+        schedule
+            .hosts
+            .lock()
+            .unwrap()
+            .contains(&"example.test".to_owned())
+    );
+    assert_eq!(result.sources[0].politeness_delay, Duration::ZERO);
+
+    // The default schedule retries once, so two challenges in a row give up.
+    let fetcher = ChallengeTimes {
+        inner: FakeFetcher::default().with_page(start, "<main>real content</main>", vec![]),
+        challenges: 2,
+        fetches: Mutex::new(HashMap::new()),
+    };
+    req.schedule = None;
+    req.challenge_retry = Some(Duration::from_millis(1));
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert!(result.sources.is_empty());
+    assert_eq!(fetcher.fetches.lock().unwrap()[start], 2);
+}
+
+/// Cancels `token` after its first fetch.
+struct CancelAfterFirstFetch {
+    inner: FakeFetcher,
+    token: CancellationToken,
+}
+
+impl PageFetcher for CancelAfterFirstFetch {
+    fn fetch(&self, url: &Url) -> anyhow::Result<FetchedPage> {
+        self.token.cancel();
+        self.inner.fetch(url)
+    }
+}
+
+#[test]
+fn spider_stops_when_the_cancellation_token_fires() {
+    let first = "https://a.example.test/first";
+    let second = "https://b.example.test/second";
+    let openai = FakeOpenAi::default().with_hits(vec![first, second]);
+    let token = CancellationToken::new();
+    let fetcher = CancelAfterFirstFetch {
+        inner: FakeFetcher::default()
+            .with_page(first, "<main>first</main>", vec![])
+            .with_page(second, "<main>second</main>", vec![]),
+        token: token.clone(),
+    };
+    let mut req = request("q");
+    req.max_depth = 0;
+    req.cancellation = Some(token);
+
+    let err = crawl_with_fetcher(&req, &openai, &fetcher).expect_err("cancelled");
+    assert!(matches!(err, CrawlError::Cancelled), "{err:?}");
+    assert_eq!(
+        *fetcher.inner.fetched.lock().unwrap(),
+        vec![first.to_owned()]
+    );
+}
+
+#[test]
+fn spider_cancellation_cuts_a_challenge_retry_wait_short() {
+    let start = "https://example.test/start";
+    let openai = FakeOpenAi::default().with_hits(vec![start]);
+    let fetcher = ChallengeOnce {
+        inner: FakeFetcher::default().with_page(start, "<main>real content</main>", vec![]),
+        challenged: Mutex::new(Vec::new()),
+    };
+    let flag = Arc::new(AtomicBool::new(false));
+    let mut req = request("q");
+    req.max_depth = 0;
+    req.challenge_retry = Some(Duration::from_secs(60));
+    req.cancellation = Some(CancellationToken::from(flag.clone()));
+
+    let started_at = Instant::now();
+    let canceller = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(100));
+        flag.store(true, Ordering::Relaxed);
+    });
+    let err = crawl_with_fetcher(&req, &openai, &fetcher).expect_err("cancelled");
+    canceller.join().unwrap();
+    assert!(matches!(err, CrawlError::Cancelled), "{err:?}");
+    assert!(started_at.elapsed() < Duration::from_secs(10));
+}
+
+const PAYWALLED_PAGE: &str = r#"<html><head><script type="application/ld+json">
+{"@context": "https://schema.org", "@type": "NewsArticle", "isAccessibleForFree": "False",
+ "hasPart": {"@type": "WebPageElement", "isAccessibleForFree": false, "cssSelector": ".locked"}}
+</script></head><body><main><p>Free lead paragraph.</p>
+<div class="locked">Subscriber body text.</div>
+<div class="paywall-prompt">Subscribe to keep reading.</div></main></body></html>"#;
+
+#[test]
+fn spider_flags_paywalled_sources() {
+    let paid = "https://example.test/paid";
+    let free = "https://example.test/free";
+    let openai = FakeOpenAi::default().with_hits(vec![paid, free]);
+    let fetcher = FakeFetcher::default()
+        .with_page(paid, PAYWALLED_PAGE, vec![])
+        .with_page(free, "<main>free</main>", vec![]);
+    let mut req = request("q");
+    req.max_depth = 0;
+    req.max_chars = 0;
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    let flags = result
+        .sources
+        .iter()
+        .map(|s| s.paywalled)
+        .collect::<Vec<_>>();
+    assert_eq!(flags, [true, false]);
+    assert_eq!(result.stats.paywalled_pages, 1);
+    assert!(result.sources[0].content.contains("Subscriber body text."));
+
+    let markdown = llm_spider::spider::compose_markdown(&req, &result);
+    assert!(
+        markdown.contains(&format!("### [Medium] {paid} (paywalled)")),
+        "{markdown}"
+    );
+    assert!(
+        markdown.contains(&format!("### [Medium] {free}\n")),
+        "{markdown}"
+    );
+    let json = llm_spider::spider::compose_json(&req, &result);
+    assert_eq!(json["sources"][0]["paywalled"], true);
+}
+
+#[test]
+fn spider_keeps_only_the_free_preview_of_paywalled_pages() {
+    let paid = "https://example.test/paid";
+    let openai = FakeOpenAi::default().with_hits(vec![paid]);
+    let fetcher = FakeFetcher::default().with_page(paid, PAYWALLED_PAGE, vec![]);
+    let mut req = request("q");
+    req.max_depth = 0;
+    req.paywall_policy = llm_spider::spider::PaywallPolicy::Preview;
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    let source = &result.sources[0];
+    assert!(source.paywalled);
+    assert!(
+        source.content.contains("Free lead paragraph."),
+        "{}",
+        source.content
+    );
+    assert!(
+        !source.content.contains("Subscriber body text."),
+        "{}",
+        source.content
+    );
+    assert!(
+        !source.content.contains("Subscribe to keep reading."),
+        "{}",
+        source.content
+    );
+    assert!(!source.excerpt.contains("Subscriber"), "{}", source.excerpt);
+}
+
+#[test]
+fn spider_skips_paywalled_pages_when_asked() {
+    let paid = "https://example.test/paid";
+    let free = "https://example.test/free";
+    let openai = FakeOpenAi::default().with_hits(vec![paid, free]);
+    let fetcher = FakeFetcher::default()
+        .with_page(
+            paid,
+            r#"<html><head><meta property="article:content_tier" content="locked"></head>
+<body><main>teaser</main></body></html>"#,
+            vec![],
+        )
+        .with_page(free, "<main>free</main>", vec![]);
+    let mut req = request("q");
+    req.max_depth = 0;
+    req.paywall_policy = llm_spider::spider::PaywallPolicy::Skip;
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    let urls = result
+        .sources
+        .iter()
+        .map(|s| s.url.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(urls, [free]);
+    assert_eq!(result.stats.paywalled_pages, 1);
+    assert!(
+        result.skipped.iter().any(|skip| skip.url.as_str() == paid
+            && skip.reason == llm_spider::spider::SkipReason::Paywalled),
+        "{:?}",
+        result.skipped
+    );
+    let list = llm_spider::spider::compose_url_list(&result);
+    assert!(
+        list.contains(&format!("{paid}\tskipped:paywalled\n")),
+        "{list}"
+    );
+    assert!(!list.contains(&format!("{paid}\tvisited")), "{list}");
+}
+
+#[test]
+fn spider_selection_is_told_which_sources_are_already_collected() {
+    let start = "https://example.test/start";
+    let child = "https://example.test/child";
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![start])
+        .with_selected(start, vec![child]);
+    let fetcher = FakeFetcher::default()
+        .with_page(start, "<main>start</main>", vec![child])
+        .with_page(
+            child,
+            "<main>child</main>",
+            vec!["https://example.test/grandchild"],
+        );
+    let mut req = request("q");
+    req.max_depth = 2;
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert_eq!(result.sources.len(), 2);
+    assert_eq!(
+        *openai.seen_collected.lock().unwrap(),
+        vec![
+            vec![start.to_owned()],
+            vec![start.to_owned(), child.to_owned()]
+        ]
+    );
+}
+
+#[test]
+fn spider_selection_cache_keys_on_the_collected_sources() {
+    let start = "https://example.test/start";
+    let child = "https://example.test/child";
+    let other = "https://example.test/other";
+    let cache_dir = std::env::temp_dir().join(format!(
+        "llm-spider-selection-collected-{}-{}",
+        std::process::id(),
+        line!()
+    ));
+    let _ = std::fs::remove_dir_all(&cache_dir);
+
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![start])
+        .with_selected(start, vec![child]);
+    let fetcher = FakeFetcher::default()
+        .with_page(start, "<main>start</main>", vec![child])
+        .with_page(child, "<main>child</main>", vec![])
+        .with_page(other, "<main>other</main>", vec![]);
+    let mut req = request("q");
+    req.cache_dir = Some(cache_dir.clone());
+
+    crawl_with_fetcher(&req, &openai, &fetcher).expect("first crawl");
+    crawl_with_fetcher(&req, &openai, &fetcher).expect("second crawl");
+    assert_eq!(openai.select_calls.load(Ordering::Relaxed), 1);
+
+    // `other` is collected before `start` now, so the earlier decision does not apply.
+    req.seed_urls = vec![other.parse().unwrap()];
+    let third = crawl_with_fetcher(&req, &openai, &fetcher).expect("third crawl");
+    assert_eq!(openai.select_calls.load(Ordering::Relaxed), 2);
+    assert_eq!(third.stats.selection_cache_hits, 0);
+
+    let _ = std::fs::remove_dir_all(&cache_dir);
+}
+
+#[test]
+fn spider_honors_x_robots_tag_and_robots_meta() {
+    let hidden = "https://example.test/hidden";
+    let leaf = "https://example.test/leaf";
+    let closed = "https://example.test/closed";
+    let meta = "https://example.test/meta";
+    let unreached = "https://example.test/unreached";
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![hidden, closed, meta])
+        .with_selected(hidden, vec![leaf])
+        .with_selected(closed, vec![unreached])
+        .with_selected(meta, vec![unreached]);
+    let fetcher = || {
+        FakeFetcher::default()
+            .with_page(hidden, "<main>hidden</main>", vec![leaf])
+            .with_robots_tag(hidden, "noindex")
+            .with_page(leaf, "<main>leaf</main>", vec![])
+            .with_page(closed, "<main>closed</main>", vec![unreached])
+            .with_robots_tag(closed, "googlebot: noindex, llm-spider: nofollow")
+            .with_page(
+                meta,
+                r#"<html><head><meta name="robots" content="nofollow"></head>
+<body><main>meta</main></body></html>"#,
+                vec![unreached],
+            )
+            .with_page(unreached, "<main>unreached</main>", vec![])
+    };
+    let mut req = request("q");
+    req.max_depth = 1;
+
+    let respected = fetcher();
+    let result = crawl_with_fetcher(&req, &openai, &respected).expect("crawl");
+    let mut urls = result
+        .sources
+        .iter()
+        .map(|s| s.url.as_str())
+        .collect::<Vec<_>>();
+    urls.sort_unstable();
+    assert_eq!(urls, [closed, leaf, meta]);
+    assert!(
+        !respected
+            .fetched
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|u| u == unreached)
+    );
+    assert_eq!(result.stats.noindex_pages, 1);
+    assert_eq!(result.stats.nofollow_pages, 2);
+    assert!(
+        llm_spider::spider::compose_url_list(&result)
+            .contains(&format!("{hidden}\tskipped:noindex\n"))
+    );
+
+    req.robots_tags = llm_spider::spider::RobotsTagPolicy::Ignore;
+    let result = crawl_with_fetcher(&req, &openai, &fetcher()).expect("crawl");
+    let urls = result
+        .sources
+        .iter()
+        .map(|s| s.url.as_str())
+        .collect::<Vec<_>>();
+    assert!(urls.contains(&hidden));
+    assert!(urls.contains(&unreached));
+    assert_eq!(result.stats.noindex_pages, 0);
+}
+
+#[test]
+fn robots_tag_header_rules_apply_to_every_agent_or_llm_spider() {
+    use llm_spider::spider::RobotsTags;
+
+    assert_eq!(
+        RobotsTags::parse_header("none"),
+        RobotsTags {
+            noindex: true,
+            nofollow: true
+        }
+    );
+    assert_eq!(
+        RobotsTags::parse_header("googlebot: noindex, LLM-Spider: nofollow"),
+        RobotsTags {
+            noindex: false,
+            nofollow: true
+        }
+    );
+    assert_eq!(
+        RobotsTags::parse_header("unavailable_after: 25 Jun 2010 15:00:00 PST, noindex"),
+        RobotsTags {
+            noindex: true,
+            nofollow: false
+        }
+    );
+}
+
+#[test]
+fn spider_output_includes_heading_outline() {
+    let start = "https://example.test/start";
+    let openai = FakeOpenAi::default().with_hits(vec![start]);
+    let fetcher = FakeFetcher::default().with_page(
+        start,
+        "<main><h1>Guide</h1><p>intro</p><h2>Install</h2><h3>From source</h3><h4>Skipped</h4><h2>Usage</h2></main>",
+        vec![],
+    );
+
+    let mut req = request("q");
+    req.max_depth = 0;
+    req.max_chars = 0;
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    let outline = &result.sources[0].outline;
+    assert_eq!(
+        outline.iter().map(|h| h.level).collect::<Vec<_>>(),
+        vec![1, 2, 3, 2]
+    );
+
+    let markdown = llm_spider::spider::compose_markdown(&req, &result);
+    assert!(markdown.contains("Outline:\n\n- Guide\n  - Install\n    - From source\n  - Usage\n"));
+}
+
+#[test]
+fn spider_content_renders_tables_as_markdown() {
+    let start = "https://example.test/start";
+    let openai = FakeOpenAi::default().with_hits(vec![start]);
+    let fetcher = FakeFetcher::default().with_page(
+        start,
+        "<main><p>Plans compared.</p><table>\
+         <thead><tr><th>Plan</th><th>Price</th></tr></thead>\
+         <tbody><tr><td>Free</td><td>$0</td></tr><tr><td>Pro | Team</td><td>$10</td></tr></tbody>\
+         </table><p>Footnote.</p></main>",
+        vec![],
+    );
+
+    let mut req = request("q");
+    req.max_depth = 0;
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    let source = &result.sources[0];
+    // Readability output and the fallback text render tables alike.
+    assert!(
+        source
+            .content
+            .contains("| Plan | Price |\n|---|---|\n| Free | $0 |\n| Pro \\| Team | $10 |"),
+        "{}",
+        source.content
+    );
+    assert!(
+        source.content.contains("Plans compared."),
+        "{}",
+        source.content
+    );
+    assert!(source.content.contains("Footnote."), "{}", source.content);
+    assert!(
+        source.excerpt.contains("| Plan | Price |") && source.excerpt.contains("| Free | $0 |"),
+        "{}",
+        source.excerpt
+    );
+}
+
+#[test]
+fn spider_excerpt_skips_script_style_and_embedded_nodes() {
+    let start = "https://example.test/start";
+    let openai = FakeOpenAi::default().with_hits(vec![start]);
+    let fetcher = FakeFetcher::default().with_page(
+        start,
+        r#"<html><head><style>.x { color: red }</style></head><body><main>
+<p>Visible text.</p>
+<script type="text/javascript">var x = "<noscript>not closed";</script>
+<noscript data-x="1"><p>Enable JS</p><noscript>nested</noscript></noscript>
+<template><p>Template text</p></template>
+<svg><text>Svg label</text></svg>
+<iframe src="https://example.test/frame">Frame fallback</iframe>
+<p>More visible text.</p>
+</main></body></html>"#,
+        vec![],
+    );
+
+    let mut req = request("q");
+    req.max_depth = 0;
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert_eq!(
+        result.sources[0].excerpt,
+        "Visible text. More visible text."
+    );
+}
+
+#[test]
+fn spider_excerpt_normalizes_unicode_and_stray_entities() {
+    let start = "https://example.test/start";
+    let openai = FakeOpenAi::default().with_hits(vec![start]);
+    let fetcher = FakeFetcher::default().with_page(
+        start,
+        "<main><p>Zero\u{200B}width\u{FEFF} ｆｕｌｌｗｉｄｔｈ &amp;#8217;quoted&amp;#x2019; &amp;#bogus;</p></main>",
+        vec![],
+    );
+
+    let mut req = request("q");
+    req.max_depth = 0;
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert_eq!(
+        result.sources[0].excerpt,
+        "Zerowidth fullwidth \u{2019}quoted\u{2019} &#bogus;"
+    );
+}
+
+#[test]
+fn spider_applies_per_tier_depth_limits_to_children() {
+    let start = "https://example.test/start";
+    let docs = "https://example.test/docs";
+    let docs_deep = "https://example.test/docs/deep";
+    let forum = "https://example.test/forum";
+    let forum_deep = "https://example.test/forum/deep";
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![start])
+        .with_tier(start, TrustTier::High)
+        .with_selected(start, vec![docs, forum])
+        .with_tier(docs, TrustTier::High)
+        .with_selected(docs, vec![docs_deep])
+        .with_tier(docs_deep, TrustTier::High)
+        .with_tier(forum, TrustTier::Low)
+        .with_selected(forum, vec![forum_deep])
+        .with_tier(forum_deep, TrustTier::Low);
+
+    let fetcher = FakeFetcher::default()
+        .with_page(start, "<main>start</main>", vec![docs, forum])
+        .with_page(docs, "<main>docs</main>", vec![docs_deep])
+        .with_page(docs_deep, "<main>docs deep</main>", vec![])
+        .with_page(forum, "<main>forum</main>", vec![forum_deep])
+        .with_page(forum_deep, "<main>forum deep</main>", vec![]);
+
+    let mut req = request("q");
+    req.max_depth = 1;
+    req.max_depth_high = Some(2);
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    let urls = result
+        .sources
+        .iter()
+        .map(|s| s.url.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(urls, vec![start, docs, docs_deep, forum]);
+}
+
+#[test]
+fn spider_bounded_frontier_evicts_lowest_tier_first() {
+    let start = "https://example.test/start";
+    let high = "https://example.test/high";
+    let high_2 = "https://example.test/high-2";
+    let low = "https://example.test/low";
+    let low_2 = "https://example.test/low-2";
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![start, low, low_2])
+        .with_tier(low, TrustTier::Low)
+        .with_tier(low_2, TrustTier::Low)
+        .with_selected(start, vec![high, high_2])
+        .with_tier(high, TrustTier::High)
+        .with_tier(high_2, TrustTier::High);
+
+    let fetcher = FakeFetcher::default()
+        .with_page(start, "<main>start</main>", vec![high, high_2])
+        .with_page(high, "<main>high</main>", vec![])
+        .with_page(high_2, "<main>high 2</main>", vec![])
+        .with_page(low, "<main>low</main>", vec![])
+        .with_page(low_2, "<main>low 2</main>", vec![]);
+
+    let mut req = request("q");
+    req.max_frontier = 2;
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    let urls = result
+        .sources
+        .iter()
+        .map(|s| s.url.as_str())
+        .collect::<Vec<_>>();
+    // `low-2` is dropped at seed time; `low` is evicted to make room for `high-2`.
+    assert_eq!(urls, vec![start, high, high_2]);
+    assert_eq!(result.stats.frontier_peak_len, 2);
+    assert_eq!(result.stats.frontier_evicted, 2);
+    assert_eq!(result.stats.visited_len, 3);
+    assert!(result.stats.visited_bytes > 0);
+    let frontier_full = result
+        .skipped
+        .iter()
+        .filter(|skip| skip.reason == llm_spider::spider::SkipReason::FrontierFull)
+        .map(|skip| skip.url.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(frontier_full, vec![low_2, low]);
+    let list = llm_spider::spider::compose_url_list(&result);
+    assert!(
+        list.contains(&format!("{low}\tskipped:frontier-full\n")),
+        "{list}"
+    );
+}
+
+#[test]
+fn spider_applies_host_policy_page_cap() {
+    let start = "https://docs.example.test/start";
+    let a = "https://docs.example.test/a";
+    let other = "https://other.test/b";
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![start, a, other])
+        .with_selected(start, vec![]);
+
+    let fetcher = FakeFetcher::default()
+        .with_page(start, "<main>start</main>", vec![])
+        .with_page(a, "<main>a</main>", vec![])
+        .with_page(other, "<main>other</main>", vec![]);
+
+    let mut req = request("q");
+    req.host_policies.insert(
+        "example.test".to_owned(),
+        llm_spider::spider::HostPolicy {
+            min_interval: Some(Duration::ZERO),
+            max_pages: Some(1),
+            ..Default::default()
+        },
+    );
+    assert!(req.host_policy("DOCS.example.test").is_some());
+    assert!(req.host_policy("other.test").is_none());
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    let urls = result
+        .sources
+        .iter()
+        .map(|s| s.url.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(urls, vec![start, other]);
+}
+
+#[test]
+fn spider_records_applied_politeness_delay_per_source() {
+    let start = "https://example.test/start";
+    let other = "https://other.test/page";
+    let openai = FakeOpenAi::default().with_hits(vec![start, other]);
+
+    let mut fetcher = FakeFetcher::default()
+        .with_page(start, "<main>start</main>", vec![])
+        .with_page(other, "<main>other</main>", vec![]);
+    fetcher.pages.get_mut(start).unwrap().robots_delay = Duration::from_millis(400);
+
+    let mut req = request("q");
+    req.max_depth = 0;
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert_eq!(
+        result.sources[0].politeness_delay,
+        Duration::from_millis(400)
+    );
+    assert_eq!(
+        result.sources[1].politeness_delay,
+        Duration::from_millis(150)
+    );
+}
+
+#[test]
+fn spider_page_cache_reuses_content_on_not_modified() {
+    let start = "https://example.test/start";
+    let cache_dir = std::env::temp_dir().join(format!(
+        "llm-spider-page-cache-{}-{}",
+        std::process::id(),
+        line!()
+    ));
+    let _ = std::fs::remove_dir_all(&cache_dir);
+
+    let openai = FakeOpenAi::default().with_hits(vec![start]);
+    let mut req = request("q");
+    req.max_depth = 0;
+    req.cache_dir = Some(cache_dir.clone());
+
+    let mut first = FakeFetcher::default().with_page(start, "<main>cached copy</main>", vec![]);
+    first.pages.get_mut(start).unwrap().etag = Some("\"v1\"".to_owned());
+    let result = crawl_with_fetcher(&req, &openai, &first).expect("first crawl");
+    assert_eq!(result.sources[0].excerpt, "cached copy");
+    assert_eq!(result.stats.cache_not_modified, 0);
+    assert_eq!(first.revalidations.load(Ordering::Relaxed), 0);
+
+    let mut second = FakeFetcher::default().with_page(start, "<main>fresh copy</main>", vec![]);
+    second.pages.get_mut(start).unwrap().etag = Some("\"v1\"".to_owned());
+    second.not_modified.push(start.to_owned());
+    let result = crawl_with_fetcher(&req, &openai, &second).expect("second crawl");
+    assert_eq!(result.sources[0].excerpt, "cached copy");
+    assert_eq!(result.stats.cache_not_modified, 1);
+    assert_eq!(second.revalidations.load(Ordering::Relaxed), 1);
+
+    let _ = std::fs::remove_dir_all(&cache_dir);
+}
+
+#[test]
+fn spider_writes_fetched_responses_to_a_gzipped_warc() {
+    use std::io::Read as _;
+
+    let start = "https://example.test/start";
+    let child = "https://example.test/child";
+    let warc = std::env::temp_dir().join(format!(
+        "llm-spider-warc-{}-{}.warc.gz",
+        std::process::id(),
+        line!()
+    ));
+
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![start])
+        .with_selected(start, vec![child]);
+    let fetcher = FakeFetcher::default()
+        .with_page(start, "<main>start page</main>", vec![child])
+        .with_page(child, "<main>child page</main>", vec![]);
+    let mut req = request("q");
+    req.warc = Some(warc.clone());
+    crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+
+    let mut text = String::new();
+    flate2::read::MultiGzDecoder::new(std::fs::File::open(&warc).expect("open warc"))
+        .read_to_string(&mut text)
+        .expect("gunzip warc");
+    let _ = std::fs::remove_file(&warc);
+
+    assert_eq!(text.matches("WARC/1.1\r\n").count(), 3);
+    assert!(text.starts_with("WARC/1.1\r\n"));
+    assert!(text.contains("WARC-Type: warcinfo\r\n"));
+    assert_eq!(text.matches("WARC-Type: response\r\n").count(), 2);
+    assert!(text.contains(&format!("WARC-Target-URI: {start}\r\n")));
+    assert!(text.contains(&format!("WARC-Target-URI: {child}\r\n")));
+    assert!(text.contains("HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\n"));
+    assert!(text.contains("<main>child page</main>"));
+}
+
+#[test]
+fn front_matter_lists_query_date_sources_tags_and_cost() {
+    let start = "https://example.test/start";
+    let openai = FakeOpenAi::default().with_hits(vec![start]);
+    let fetcher = FakeFetcher::default().with_page(start, "<main>start</main>", vec![]);
+    let mut req = request("rust \"async\" runtimes");
+    req.max_depth = 0;
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+
+    let front_matter = FrontMatter {
+        date: "2026-01-02".to_owned(),
+        tags: vec!["research".to_owned(), "rust: async".to_owned()],
+        cost_usd: Some(0.01234),
+    };
+    assert_eq!(
+        compose_front_matter(&req, &result, &front_matter),
+        "---\n\
+         query: \"rust \\\"async\\\" runtimes\"\n\
+         date: 2026-01-02\n\
+         sources: 1\n\
+         tags:\n  - \"research\"\n  - \"rust: async\"\n\
+         cost_usd: 0.0123\n\
+         ---\n\n"
+    );
+
+    let bare = FrontMatter {
+        tags: Vec::new(),
+        cost_usd: None,
+        ..front_matter
+    };
+    let yaml = compose_front_matter(&req, &result, &bare);
+    assert!(yaml.contains("tags: []\n"), "{yaml}");
+    assert!(!yaml.contains("cost_usd"), "{yaml}");
+}
+
+#[test]
+fn notes_export_one_note_per_source_and_a_wiki_linked_index() {
+    let a = "https://example.test/a";
+    let b = "https://example.test/b";
+    let c = "https://example.test/docs/c";
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![a, b, c])
+        .with_timeline(&[("2023-02-01", "Version 1.0 shipped", &[0])]);
+    let page = |title: &str, body: &str| {
+        format!("<html><head><title>{title}</title></head><body><main>{body}</main></body></html>")
+    };
+    let fetcher = FakeFetcher::default()
+        .with_page(
+            a,
+            &page("Async: A/B guide", "<h2>Setup</h2><p>alpha</p>"),
+            vec![],
+        )
+        .with_page(b, &page("async  a b GUIDE", "<p>beta</p>"), vec![])
+        .with_page(c, "<main>gamma</main>", vec![]);
+    let mut req = request("rust async?");
+    req.max_depth = 0;
+    req.timeline = true;
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    let notes = compose_notes(&req, &result);
+    let names = notes.iter().map(|n| n.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "rust async",
+            "Async A B guide",
+            "async a b GUIDE 2",
+            "example.test docs c"
+        ]
+    );
+
+    let index = &notes[0].text;
+    assert!(index.starts_with("---\nquery: \"rust async?\"\nsources: 3\n---\n\n# rust async?\n"));
+    assert!(
+        index.contains(&format!("- [[Async A B guide]] — [Medium] {a}\n")),
+        "{index}"
+    );
+    assert!(index.contains("- [[async a b GUIDE 2]]"), "{index}");
+
+    let first = &notes[1].text;
+    assert!(first.starts_with(&format!("---\nurl: \"{a}\"\ntrust_tier: Medium\n")));
+    assert!(first.contains(&format!(
+        "# Async: A/B guide\n\n<{a}>\n\nResearch: [[rust async]]\n"
+    )));
+    assert!(first.contains("## Excerpt\n\n"), "{first}");
+    assert!(
+        first.contains("## Highlights\n\n- 2023-02-01 — Version 1.0 shipped\n"),
+        "{first}"
+    );
+    assert!(first.contains("## Outline\n\n- Setup"), "{first}");
+    assert!(
+        !notes[2].text.contains("## Highlights"),
+        "{}",
+        notes[2].text
+    );
+}
+
+#[test]
+fn spider_url_list_records_visited_and_skipped_urls() {
+    let start = "https://example.test/start";
+    let picked = "https://example.test/picked";
+    let ignored = "https://example.test/ignored";
+    let archive = "https://example.test/archive.zip";
+    let blocked = "https://example.test/blocked";
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![start, blocked])
+        .with_selected(start, vec![picked]);
+    let fetcher = FakeFetcher::default()
+        .with_page(start, "<main>start</main>", vec![picked, ignored, archive])
+        .with_page(picked, "<main>picked</main>", vec![]);
+    let mut req = request("q");
+    req.max_depth = 1;
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    let list = llm_spider::spider::compose_url_list(&result);
+    let lines = list.lines().collect::<Vec<_>>();
+    assert!(lines.contains(&format!("{start}\tvisited").as_str()));
+    assert!(lines.contains(&format!("{picked}\tvisited").as_str()));
+    assert!(lines.contains(&format!("{ignored}\tskipped:not-selected").as_str()));
+    assert!(lines.contains(&format!("{archive}\tskipped:binary").as_str()));
+    assert!(lines.contains(&format!("{blocked}\tskipped:fetch-failed").as_str()));
+    assert_eq!(lines.len(), 5, "{list}");
+}
+
+#[test]
+fn spider_groups_findings_by_subtopic() {
+    let install = "https://example.test/install";
+    let runtime = "https://example.test/runtime";
+    let misc = "https://example.test/misc";
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![install, runtime, misc])
+        .with_subtopic("Runtime", vec![1, 1, 9])
+        .with_subtopic("Install", vec![0]);
+    let fetcher = FakeFetcher::default()
+        .with_page(install, "<main>install steps</main>", vec![])
+        .with_page(runtime, "<main>runtime notes</main>", vec![])
+        .with_page(misc, "<main>misc</main>", vec![]);
+    let mut req = request("q");
+    req.max_depth = 0;
+    req.findings_layout = llm_spider::spider::FindingsLayout::Subtopics;
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert_eq!(result.stats.llm_calls, 2);
+    let groups = result
+        .subtopics
+        .iter()
+        .map(|s| (s.title.as_str(), s.sources.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        groups,
+        [
+            ("Runtime", vec![1]),
+            ("Install", vec![0]),
+            ("Other", vec![2])
+        ]
+    );
+
+    let md = llm_spider::spider::compose_markdown(&req, &result);
+    let runtime_at = md.find("### Runtime\n\n#### [Medium] https://example.test/runtime");
+    let install_at = md.find("### Install\n\n#### [Medium] https://example.test/install");
+    let other_at = md.find("### Other\n\n#### [Medium] https://example.test/misc");
+    assert!(runtime_at < install_at && install_at < other_at, "{md}");
+    assert!(runtime_at.is_some(), "{md}");
+    let sources = &md[md.find("## Sources").unwrap()..];
+    assert!(sources.find(runtime) < sources.find(install), "{sources}");
+}
+
+#[test]
+fn spider_subtopic_failure_keeps_the_flat_layout() {
+    let start = "https://example.test/start";
+    let openai = FakeOpenAi::default().with_hits(vec![start]);
+    let fetcher = FakeFetcher::default().with_page(start, "<main>start</main>", vec![]);
+    let mut req = request("q");
+    req.findings_layout = llm_spider::spider::FindingsLayout::Subtopics;
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert!(result.subtopics.is_empty());
+    let md = llm_spider::spider::compose_markdown(&req, &result);
+    assert!(
+        md.contains("### [Medium] https://example.test/start"),
+        "{md}"
+    );
+    assert!(md.contains("サブトピックへの分類に失敗した"), "{md}");
+}
+
+#[test]
+fn spider_compare_searches_each_alternative_and_tags_sources() {
+    let tokio = "https://example.test/tokio";
+    let both = "https://example.test/runtimes";
+    let other = "https://example.test/other";
+    let openai = FakeOpenAi::default().with_hits(vec![tokio, both, other]);
+    let fetcher = FakeFetcher::default()
+        .with_page(tokio, "<main>Tokio is an async runtime.</main>", vec![])
+        .with_page(both, "<main>tokio and async-std compared</main>", vec![])
+        .with_page(other, "<main>tokiofoo is unrelated</main>", vec![]);
+    let mut req = request("async runtimes");
+    req.max_depth = 0;
+    req.compare = vec!["tokio".to_owned(), "async-std".to_owned()];
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert_eq!(result.stats.llm_calls, 2, "one search per alternative");
+    let coverage = result
+        .comparison
+        .iter()
+        .map(|c| (c.alternative.as_str(), c.sources.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(coverage, [("tokio", vec![0, 1]), ("async-std", vec![1])]);
+
+    let md = llm_spider::spider::compose_markdown(&req, &result);
+    assert!(md.contains("| tokio | 2 | 0 | 2 | 0 | 0 | 1 |"), "{md}");
+    assert!(md.contains("| async-std | 1 | 0 | 1 | 0 | 0 | 1 |"), "{md}");
+    let tokio_at = md.find("### tokio\n\n#### [Medium] https://example.test/tokio");
+    let both_at = md.find("### tokio + async-std\n\n#### [Medium] https://example.test/runtimes");
+    let general_at = md.find("### General\n\n#### [Medium] https://example.test/other");
+    assert!(
+        tokio_at.is_some() && tokio_at < both_at && both_at < general_at,
+        "{md}"
+    );
+
+    let json = llm_spider::spider::compose_json(&req, &result);
+    assert_eq!(json["comparison"][1]["alternative"], "async-std");
+    assert_eq!(json["comparison"][1]["sources"][0], both);
+}
+
+#[test]
+fn spider_suggests_follow_up_queries_under_next_steps() {
+    let start = "https://example.test/start";
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![start])
+        .with_follow_ups(&[
+            "tokio vs smol benchmarks",
+            "tokio [blocking] tasks",
+            "a",
+            "b",
+            "c",
+            "d",
+        ]);
+    let fetcher = FakeFetcher::default().with_page(start, "<main>start</main>", vec![]);
+    let mut req = request("q");
+    req.follow_ups = true;
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert_eq!(result.stats.llm_calls, 2);
+    assert_eq!(result.follow_ups.len(), 5);
+
+    let md = llm_spider::spider::compose_markdown(&req, &result);
+    assert!(
+        md.contains("## Next steps\n\n- tokio vs smol benchmarks\n- tokio \\[blocking\\] tasks\n"),
+        "{md}"
+    );
+    let json = llm_spider::spider::compose_json(&req, &result);
+    assert_eq!(json["follow_ups"][0], "tokio vs smol benchmarks");
+
+    req.follow_ups = false;
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert!(result.follow_ups.is_empty());
+    assert!(!llm_spider::spider::compose_markdown(&req, &result).contains("## Next steps"));
+}
+
+#[test]
+fn spider_counts_key_terms_shared_by_several_sources() {
+    let a = "https://example.test/a";
+    let b = "https://example.test/b";
+    let c = "https://example.test/c";
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![a, b, c])
+        .with_glossary(&[
+            ("Tokio", "An async runtime for Rust."),
+            ("epoll", "The Linux readiness API."),
+            ("io_uring", "A completion-based Linux I/O interface."),
+        ]);
+    let fetcher = FakeFetcher::default()
+        .with_page(
+            a,
+            "<main>Tokio uses epoll. tokio tasks are cheap.</main>",
+            vec![],
+        )
+        .with_page(b, "<main>Tokio on io_uring</main>", vec![])
+        .with_page(
+            c,
+            "<main>epoll and kqueue; tokio-util is separate.</main>",
+            vec![],
+        );
+    let mut req = request("q");
+    req.max_depth = 0;
+    req.key_terms = true;
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert_eq!(result.stats.llm_calls, 2);
+    let terms = result
+        .key_terms
+        .iter()
+        .map(|t| (t.term.as_str(), t.counts.clone()))
+        .collect::<Vec<_>>();
+    // io_uring is in one source only; "tokio-util" counts as a mention of Tokio.
+    assert_eq!(
+        terms,
+        [
+            ("Tokio", vec![(0, 2), (1, 1), (2, 1)]),
+            ("epoll", vec![(0, 1), (2, 1)])
+        ]
+    );
+
+    let md = llm_spider::spider::compose_markdown(&req, &result);
+    assert!(
+        md.contains("| Tokio | An async runtime for Rust. | #1 (2), #2 (1), #3 (1) |"),
+        "{md}"
+    );
+    assert!(md.find("## Key terms") < md.find("## Findings"), "{md}");
+    let json = llm_spider::spider::compose_json(&req, &result);
+    assert_eq!(json["key_terms"][1]["sources"][1]["url"], c);
+    assert_eq!(json["key_terms"][0]["sources"][0]["mentions"], 2);
+}
+
+#[test]
+fn spider_renders_timeline_with_citations() {
+    let a = "https://example.test/a";
+    let b = "https://example.test/b";
+    let openai = FakeOpenAi::default().with_hits(vec![a, b]).with_timeline(&[
+        ("2021-06", "Beta released", &[1]),
+        ("2023-02-01", "Version 1.0 shipped", &[0, 1, 7]),
+    ]);
+    let fetcher = FakeFetcher::default()
+        .with_page(a, "<main>1.0 shipped in February 2023</main>", vec![])
+        .with_page(b, "<main>beta in June 2021</main>", vec![]);
+    let mut req = request("q");
+    req.max_depth = 0;
+    req.timeline = true;
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert_eq!(result.stats.llm_calls, 2);
+    // Source ids beyond the collected sources are dropped.
+    assert_eq!(result.timeline[1].sources, vec![0, 1]);
+
+    let md = llm_spider::spider::compose_markdown(&req, &result);
+    assert!(
+        md.contains(&format!(
+            "- 2023-02-01 — Version 1.0 shipped ([#1]({a}), [#2]({b}))"
+        )),
+        "{md}"
+    );
+    assert!(md.find("## Timeline") < md.find("## Findings"), "{md}");
+    let json = llm_spider::spider::compose_json(&req, &result);
+    assert_eq!(json["timeline"][0]["date"], "2021-06");
+    assert_eq!(json["timeline"][0]["sources"][0], b);
+}
+
+#[test]
+fn spider_notes_failed_timeline_extraction() {
+    let start = "https://example.test/start";
+    let openai = FakeOpenAi::default().with_hits(vec![start]);
+    let fetcher = FakeFetcher::default().with_page(start, "<main>start</main>", vec![]);
+    let mut req = request("q");
+    req.max_depth = 0;
+    req.timeline = true;
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert!(result.timeline.is_empty());
+    assert!(
+        result.notes.iter().any(|n| n.contains("年表")),
+        "{:?}",
+        result.notes
+    );
+    assert!(!llm_spider::spider::compose_markdown(&req, &result).contains("## Timeline"));
+}
+
+#[test]
+fn spider_writes_a_sourced_answer_before_the_findings() {
+    let a = "https://example.test/a";
+    let b = "https://example.test/b";
+    let openai = FakeOpenAi::default().with_hits(vec![a, b]).with_answer(
+        &["Use `spawn_blocking` [for] CPU work.", "It runs on a pool."],
+        &[1],
+    );
+    let fetcher = FakeFetcher::default()
+        .with_page(a, "<main>a</main>", vec![])
+        .with_page(b, "<main>b</main>", vec![]);
+    let mut req = request("q");
+    req.max_depth = 0;
+    req.answer = true;
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert_eq!(result.stats.llm_calls, 2);
+    let md = llm_spider::spider::compose_markdown(&req, &result);
+    assert!(
+        md.contains(&format!(
+            "## Answer\n\nUse \\`spawn_blocking\\` \\[for\\] CPU work.\n\nIt runs on a pool.\n\nSources: [#2]({b})\n"
+        )),
+        "{md}"
+    );
+    assert!(md.find("## Answer") < md.find("## Findings"), "{md}");
+    let json = llm_spider::spider::compose_json(&req, &result);
+    assert_eq!(json["answer"]["paragraphs"][1], "It runs on a pool.");
+    assert_eq!(json["answer"]["sources"][0], b);
+
+    let openai = FakeOpenAi::default().with_hits(vec![a, b]);
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert!(result.answer.is_none());
+    assert!(
+        result.notes.iter().any(|n| n.contains("回答")),
+        "{:?}",
+        result.notes
+    );
+}
+
+#[test]
+fn spider_grades_sources_and_lists_dropped_ones_in_an_appendix() {
+    let a = "https://example.test/a";
+    let b = "https://example.test/b";
+    let c = "https://example.test/c";
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![a, b, c])
+        .with_title(b, "Tag archive")
+        .with_grades(&[
+            (0, true, "Answers the query"),
+            (1, false, "Only a list of [tags]"),
+            (9, false, "Not offered"),
+        ]);
+    let fetcher = FakeFetcher::default()
+        .with_page(a, "<main>answer</main>", vec![])
+        .with_page(b, "<main>tags</main>", vec![])
+        .with_page(c, "<main>details</main>", vec![]);
+    let mut req = request("q");
+    req.max_depth = 0;
+    req.grade_sources = true;
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert_eq!(result.stats.llm_calls, 2);
+    let kept = result
+        .sources
+        .iter()
+        .map(|source| source.url.as_str())
+        .collect::<Vec<_>>();
+    // Ungraded sources are kept.
+    assert_eq!(kept, [a, c]);
+    assert_eq!(result.dropped_sources.len(), 1);
+    assert_eq!(result.dropped_sources[0].source.url.as_str(), b);
+
+    let md = llm_spider::spider::compose_markdown(&req, &result);
+    let (findings, appendix) = md.split_once("## Dropped sources").expect("appendix");
+    assert!(!findings.contains(b), "{md}");
+    assert!(
+        appendix.contains(&format!("{b} — Tag archive: Only a list of \\[tags\\]")),
+        "{md}"
+    );
+    let json = llm_spider::spider::compose_json(&req, &result);
+    assert_eq!(json["dropped_sources"][0]["url"], b);
+    assert_eq!(
+        json["dropped_sources"][0]["reason"],
+        "Only a list of [tags]"
+    );
+}
+
+#[test]
+fn spider_keeps_every_source_when_grading_fails() {
+    let start = "https://example.test/start";
+    let openai = FakeOpenAi::default().with_hits(vec![start]);
+    let fetcher = FakeFetcher::default().with_page(start, "<main>start</main>", vec![]);
+    let mut req = request("q");
+    req.max_depth = 0;
+    req.grade_sources = true;
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert_eq!(result.sources.len(), 1);
+    assert!(result.dropped_sources.is_empty());
+    assert!(
+        result.notes.iter().any(|n| n.contains("有用性")),
+        "{:?}",
+        result.notes
+    );
+}
+
+#[test]
+fn spider_selection_cache_reuses_decisions() {
+    let start = "https://example.test/start";
+    let child = "https://example.test/child";
+    let cache_dir = std::env::temp_dir().join(format!(
+        "llm-spider-selection-cache-{}-{}",
+        std::process::id(),
+        line!()
+    ));
+    let _ = std::fs::remove_dir_all(&cache_dir);
+
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![start])
+        .with_selected(start, vec![child])
+        .with_tier(child, TrustTier::High);
+    let fetcher = FakeFetcher::default()
+        .with_page(start, "<main>start</main>", vec![child])
+        .with_page(child, "<main>child</main>", vec![]);
+    let mut req = request("q");
+    req.cache_dir = Some(cache_dir.clone());
+
+    let first = crawl_with_fetcher(&req, &openai, &fetcher).expect("first crawl");
+    assert_eq!(openai.select_calls.load(Ordering::Relaxed), 1);
+    assert_eq!(first.stats.selection_cache_hits, 0);
+
+    let second = crawl_with_fetcher(&req, &openai, &fetcher).expect("second crawl");
+    assert_eq!(openai.select_calls.load(Ordering::Relaxed), 1);
+    assert_eq!(second.stats.selection_cache_hits, 1);
+    assert_eq!(second.stats.llm_calls, 1);
+    assert_eq!(second.sources[1].url.as_str(), child);
+    assert_eq!(second.sources[1].trust_tier, TrustTier::High);
+
+    req.query = "another query".to_owned();
+    crawl_with_fetcher(&req, &openai, &fetcher).expect("third crawl");
+    assert_eq!(openai.select_calls.load(Ordering::Relaxed), 2);
+
+    // Another select model or prompt template asks again.
+    let openai = FakeOpenAi {
+        select_fingerprint: "other-model".to_owned(),
+        ..openai
+    };
+    crawl_with_fetcher(&req, &openai, &fetcher).expect("fourth crawl");
+    assert_eq!(openai.select_calls.load(Ordering::Relaxed), 3);
+
+    let _ = std::fs::remove_dir_all(&cache_dir);
+}
+
+#[test]
+fn spider_learns_host_tiers_across_runs() {
+    let pages = (1..=5)
+        .map(|i| format!("https://rated.example.com/{i}"))
+        .collect::<Vec<_>>();
+    let cache_dir = std::env::temp_dir().join(format!(
+        "llm-spider-reputation-{}-{}",
+        std::process::id(),
+        line!()
+    ));
+    let _ = std::fs::remove_dir_all(&cache_dir);
+
+    let mut fetcher = FakeFetcher::default();
+    let mut openai = FakeOpenAi::default().with_hits(pages.iter().map(String::as_str).collect());
+    for page in &pages {
+        fetcher = fetcher.with_page(page, "<main>page</main>", vec![]);
+        openai = openai.with_tier(page, TrustTier::Low);
+    }
+    let mut req = request("q");
+    req.max_depth = 0;
+    req.cache_dir = Some(cache_dir.clone());
+    crawl_with_fetcher(&req, &openai, &fetcher).expect("first crawl");
+
+    let store = llm_spider::cache::ReputationStore::open(&cache_dir).expect("store");
+    assert_eq!(store.counts("rated.example.com").map(|c| c.low), Some(5));
+    assert_eq!(
+        store.learned_tier("rated.example.com"),
+        Some(TrustTier::Low)
+    );
+
+    // The model now rates the host High, but the learned tier is the classifier.
+    for page in &pages {
+        openai = openai.with_tier(page, TrustTier::High);
+    }
+    let second = crawl_with_fetcher(&req, &openai, &fetcher).expect("second crawl");
+    assert!(
+        second
+            .sources
+            .iter()
+            .all(|s| s.trust_tier == TrustTier::Low)
+    );
+
+    req.trust_merge = llm_spider::spider::TrustMergePolicy::LlmWins;
+    let third = crawl_with_fetcher(&req, &openai, &fetcher).expect("third crawl");
+    assert!(
+        third
+            .sources
+            .iter()
+            .all(|s| s.trust_tier == TrustTier::High)
+    );
+
+    let _ = std::fs::remove_dir_all(&cache_dir);
+}
+
+#[test]
+fn spider_skips_non_textual_urls_before_fetching() {
+    let start = "https://example.test/start";
+    let archive = "https://example.test/release.tar.gz";
+    let page = "https://example.test/guide";
+    let image = "https://example.test/diagram.PNG";
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![archive, start])
+        .with_selected(start, vec![page]);
+
+    let fetcher = FakeFetcher::default()
+        .with_page(start, "<main>start</main>", vec![image, page])
+        .with_page(page, "<main>guide</main>", vec![]);
+
+    let req = request("q");
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+
+    assert_eq!(*fetcher.fetched.lock().unwrap(), vec![start, page]);
+    assert_eq!(*openai.seen_candidates.lock().unwrap(), vec![page]);
+    assert_eq!(result.sources.len(), 2);
+}
+
+#[test]
+fn spider_stops_fetching_when_download_budget_is_exhausted() {
+    let a = "https://a.example.test/1";
+    let b = "https://b.example.test/2";
+    let c = "https://a.example.test/3";
+    let openai = FakeOpenAi::default().with_hits(vec![a, b, c]);
+
+    let mut fetcher = FakeFetcher::default()
+        .with_page(a, "<main>a</main>", vec![])
+        .with_page(b, "<main>b</main>", vec![])
+        .with_page(c, "<main>c</main>", vec![]);
+    for page in fetcher.pages.values_mut() {
+        page.bytes = 80;
+    }
+
+    let mut req = request("q");
+    req.max_depth = 0;
+    req.max_total_bytes = 100;
+    req.show_stats = true;
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+
+    assert_eq!(*fetcher.fetched.lock().unwrap(), vec![a, b]);
+    assert_eq!(result.stats.pages_fetched, 2);
+    assert_eq!(result.stats.bytes_downloaded, 160);
+    assert!(result.stats.download_budget_exhausted);
+    assert_eq!(result.stats.bytes_by_host.get("a.example.test"), Some(&80));
+    assert_eq!(result.stats.bytes_by_host.get("b.example.test"), Some(&80));
+
+    let md = llm_spider::spider::compose_markdown(&req, &result);
+    assert!(md.contains("## Stats"));
+    assert!(md.contains("- Bytes downloaded: 160 (download budget exhausted)"));
+    assert!(md.contains("  - a.example.test: 80"));
+}
+
+#[test]
+fn spider_prefetches_dns_for_upcoming_frontier_urls() {
+    let a = "https://a.example.com/";
+    let b = "https://b.example.com/";
+    let c = "https://c.example.com/";
+    let openai = FakeOpenAi::default().with_hits(vec![a, b, c]);
+    let fetcher = FakeFetcher::default()
+        .with_page(a, "<main>a</main>", vec![])
+        .with_page(b, "<main>b</main>", vec![])
+        .with_page(c, "<main>c</main>", vec![]);
+
+    let mut req = request("q");
+    req.max_depth = 0;
+    req.dns_prefetch = 2;
+    crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert_eq!(
+        *fetcher.prefetched.lock().unwrap(),
+        vec![vec![b.to_owned(), c.to_owned()], vec![c.to_owned()]]
+    );
+
+    let fetcher = FakeFetcher {
+        prefetched: Arc::default(),
+        ..fetcher
+    };
+    req.dns_prefetch = 0;
+    crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert!(fetcher.prefetched.lock().unwrap().is_empty());
+}
+
+#[test]
+fn spider_drops_url_aliases_before_selection() {
+    let start = "https://example.com/start";
+    let other_seed = "https://example.com/queued";
+    let page = "https://example.com/docs";
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![start, other_seed])
+        .with_selected(start, vec![page]);
+    let fetcher = FakeFetcher::default()
+        .with_page(
+            start,
+            "<main>start</main>",
+            vec![
+                page,
+                "https://example.com/docs/",
+                "https://example.com/docs/index.html",
+                "https://example.com/docs?utm_source=feed",
+                "https://example.com/docs#intro",
+                "https://example.com/start/",
+                "https://example.com/queued?fbclid=abc",
+            ],
+        )
+        .with_page(other_seed, "<main>queued</main>", vec![])
+        .with_page(page, "<main>docs</main>", vec![]);
+
+    let mut req = request("q");
+    req.show_stats = true;
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+
+    assert_eq!(*openai.seen_candidates.lock().unwrap(), vec![page]);
+    assert_eq!(result.stats.duplicate_links_skipped, 6);
+    let urls = result
+        .sources
+        .iter()
+        .map(|s| s.url.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(urls, vec![start, other_seed, page]);
+}
+
+#[test]
+fn spider_keeps_most_relevant_candidates_when_over_the_cap() {
+    let start = "https://example.com/start";
+    let guide = "https://example.com/rust-async-guide";
+    let external = "https://docs.other.com/tokio";
+    let links = vec![
+        "https://example.com/a",
+        "https://example.com/b",
+        guide,
+        "https://example.com/c",
+        external,
+    ];
+    let html = format!(
+        "<main>{}</main>",
+        links
+            .iter()
+            .map(|link| {
+                let text = if *link == external {
+                    "Async Rust"
+                } else {
+                    "More"
+                };
+                format!("<a href=\"{link}\">{text}</a>")
+            })
+            .collect::<String>()
+    );
+    let openai = FakeOpenAi::default().with_hits(vec![start]);
+    let fetcher = FakeFetcher::default().with_page(start, &html, links);
+
+    let mut req = request("how to use async in Rust");
+    req.max_child_candidates = 2;
+    crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+
+    assert_eq!(
+        *openai.seen_candidates.lock().unwrap(),
+        vec![external, guide]
+    );
+}
+
+#[test]
+fn spider_classified_intent_adjusts_unpinned_defaults() {
+    let start = "https://example.test/start";
+    let child = "https://example.test/child";
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![start])
+        .with_selected(start, vec![child])
+        .with_intent(QueryIntent::Troubleshooting);
+    let fetcher = FakeFetcher::default()
+        .with_page(start, "<main>error E0277</main>", vec![child])
+        .with_page(child, "<main>fix</main>", vec![]);
+    let mut req = request("error E0277 after upgrade");
+    req.max_depth = 0;
+    req.classify_intent = true;
+
+    // Troubleshooting crawls one level deeper.
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert_eq!(result.intent, Some(QueryIntent::Troubleshooting));
+    assert_eq!(result.sources.len(), 2);
+    assert_eq!(result.stats.llm_calls, 3);
+    let md = llm_spider::spider::compose_markdown(&req, &result);
+    assert!(md.contains("- Intent: troubleshooting\n"), "{md}");
+    let json = llm_spider::spider::compose_json(&req, &result);
+    assert_eq!(json["intent"], "troubleshooting");
+
+    req.pinned_max_depth = true;
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert_eq!(result.sources.len(), 1);
+
+    // A given intent skips the call.
+    req.pinned_max_depth = false;
+    req.intent = Some(QueryIntent::HowTo);
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert_eq!(result.intent, Some(QueryIntent::HowTo));
+    assert_eq!(result.sources.len(), 1);
+    assert_eq!(result.stats.llm_calls, 1);
+}
+
+#[test]
+fn spider_api_reference_intent_ranks_docs_hosts_first() {
+    let start = "https://example.test/start";
+    let blog = "https://blog.example.org/tips";
+    let docs = "https://docs.example.org/attributes";
+    let html = format!("<main><a href=\"{blog}\">Tips</a><a href=\"{docs}\">Attributes</a></main>");
+    let fetcher = FakeFetcher::default().with_page(start, &html, vec![blog, docs]);
+    let mut req = request("serde rename");
+    req.max_child_candidates = 1;
+    req.max_candidate_batches = 1;
+
+    let openai = FakeOpenAi::default().with_hits(vec![start]);
+    crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert_eq!(*openai.seen_candidates.lock().unwrap(), vec![blog]);
+
+    req.intent = Some(QueryIntent::ApiReference);
+    let openai = FakeOpenAi::default().with_hits(vec![start]);
+    crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert_eq!(*openai.seen_candidates.lock().unwrap(), vec![docs]);
+}
+
+#[test]
+fn spider_notes_failed_intent_classification_and_keeps_defaults() {
+    let start = "https://example.test/start";
+    let openai = FakeOpenAi::default().with_hits(vec![start]);
+    let fetcher = FakeFetcher::default().with_page(start, "<main>start</main>", vec![]);
+    let mut req = request("q");
+    req.classify_intent = true;
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert_eq!(result.intent, None);
+    assert_eq!(result.sources.len(), 1);
+    assert!(
+        result.notes.iter().any(|n| n.contains("意図")),
+        "{:?}",
+        result.notes
+    );
+}
+
+#[test]
+fn spider_stats_count_llm_calls() {
+    let start = "https://example.test/start";
+    let child = "https://example.test/child";
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![start])
+        .with_selected(start, vec![child]);
+    let fetcher = FakeFetcher::default()
+        .with_page(start, "<main>start</main>", vec![child])
+        .with_page(child, "<main>child</main>", vec![]);
+
+    let mut req = request("q");
+    req.show_stats = true;
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+
+    assert_eq!(result.stats.llm_calls, 2);
+    let md = llm_spider::spider::compose_markdown(&req, &result);
+    assert!(md.contains("- LLM calls: 2"));
+    assert!(md.contains("- Connections opened: 1 for 2 requests (1 reused)"));
+}
+
+#[test]
+fn spider_plan_orders_seeds_by_tier_without_fetching() {
+    let low = "https://blog.example.test/post";
+    let high = "https://docs.example.test/guide";
+    let archive = "https://docs.example.test/release.zip";
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![low, high, archive, high])
+        .with_tier(low, TrustTier::Low)
+        .with_tier(high, TrustTier::High)
+        .with_tier(archive, TrustTier::High);
+
+    let req = request("q");
+    let plan = llm_spider::spider::plan_crawl(&req, &openai).expect("plan");
+
+    let seeds = plan
+        .seeds
+        .iter()
+        .map(|s| (s.url.as_str(), s.skip_reason))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        seeds,
+        vec![
+            (high, None),
+            (archive, Some("non-textual resource")),
+            (low, None),
+        ]
+    );
+    assert_eq!(openai.select_calls.load(Ordering::Relaxed), 0);
+
+    let md = llm_spider::spider::compose_plan_markdown(&req, &plan);
+    assert!(md.contains("## Frontier"));
+    assert!(md.contains(&format!("- [High] {high}\n")));
+    assert!(md.contains("- max_pages: "));
+}
+
+#[test]
+fn spider_merges_duplicate_seeds_and_keeps_search_titles() {
+    let page = "https://example.com/guide";
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![page])
+        .with_tier(page, TrustTier::Low)
+        .with_title(page, "Search Title");
+    let fetcher = FakeFetcher::default().with_page(page, "<main>untitled</main>", vec![]);
+
+    let mut req = request("q");
+    req.seed_urls = vec![page.parse().unwrap()];
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+
+    assert_eq!(result.sources.len(), 1);
+    assert_eq!(result.sources[0].trust_tier, TrustTier::Low);
+    assert_eq!(result.sources[0].title.as_deref(), Some("Search Title"));
+    assert_eq!(fetcher.fetched.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn spider_merges_host_rule_tiers_with_model_tiers() {
+    use llm_spider::spider::{HostPolicy, TrustMergePolicy};
+
+    let docs = "https://docs.example.com/guide";
+    let blog = "https://blog.example.net/post";
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![docs, blog])
+        .with_tier(docs, TrustTier::Low)
+        .with_tier(blog, TrustTier::Medium);
+    let fetcher = FakeFetcher::default()
+        .with_page(docs, "<main>docs</main>", vec![])
+        .with_page(blog, "<main>blog</main>", vec![]);
+
+    let mut req = request("q");
+    req.host_policies.insert(
+        "example.com".to_owned(),
+        HostPolicy {
+            trust_tier: Some(TrustTier::High),
+            ..HostPolicy::default()
+        },
+    );
+    let cases = [
+        (TrustMergePolicy::ClassifierWins, TrustTier::High),
+        (TrustMergePolicy::LlmWins, TrustTier::Low),
+        (TrustMergePolicy::Min, TrustTier::Low),
+        (TrustMergePolicy::Max, TrustTier::High),
+    ];
+    for (policy, expected) in cases {
+        req.trust_merge = policy;
+        let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+        let tiers = result
+            .sources
+            .iter()
+            .map(|s| (s.url.as_str(), s.trust_tier))
+            .collect::<Vec<_>>();
+        assert!(tiers.contains(&(docs, expected)), "{policy:?}: {tiers:?}");
+        // Hosts without a rule keep the model's tier.
+        assert!(tiers.contains(&(blog, TrustTier::Medium)), "{policy:?}");
+    }
+}
+
+#[test]
+fn spider_reports_the_pacing_applied_to_each_host_and_where_it_came_from() {
+    use llm_spider::spider::{HostPolicy, HostPoliteness, PolitenessSource};
+
+    let plain = "https://plain.test/a";
+    let configured = "https://configured.test/a";
+    let robots = ["https://robots.test/a", "https://robots.test/b"];
+    let openai = FakeOpenAi::default().with_hits(vec![plain, configured, robots[0], robots[1]]);
+    let mut fetcher = FakeFetcher::default()
+        .with_page(plain, "<main>plain</main>", vec![])
+        .with_page(configured, "<main>configured</main>", vec![])
+        .with_page(robots[0], "<main>robots a</main>", vec![])
+        .with_page(robots[1], "<main>robots b</main>", vec![]);
+    for url in robots {
+        let page = fetcher.pages.get_mut(url).unwrap();
+        page.robots_delay = Duration::from_millis(300);
+        page.robots_max_concurrent = Some(1);
+    }
+    let audit_path = std::env::temp_dir().join(format!(
+        "llm-spider-politeness-audit-{}-{}.jsonl",
+        std::process::id(),
+        line!()
+    ));
+    let _ = std::fs::remove_file(&audit_path);
+
+    let mut req = request("q");
+    req.max_depth = 0;
+    req.show_stats = true;
+    req.host_policies.insert(
+        "configured.test".to_owned(),
+        HostPolicy {
+            min_interval: Some(Duration::from_millis(20)),
+            max_concurrent: Some(2),
+            ..HostPolicy::default()
+        },
+    );
+    req.audit_log = Some(Arc::new(
+        llm_spider::audit::AuditLog::open(&audit_path).expect("audit log"),
+    ));
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert_eq!(
+        result.stats.host_politeness,
+        [
+            HostPoliteness {
+                host: "configured.test".to_owned(),
+                requests: 1,
+                unpaced_requests: 0,
+                min_interval: Duration::from_millis(20),
+                interval_source: PolitenessSource::Config,
+                max_concurrent: Some(2),
+                concurrency_source: Some(PolitenessSource::Config),
+            },
+            HostPoliteness {
+                host: "plain.test".to_owned(),
+                requests: 1,
+                unpaced_requests: 0,
+                min_interval: Duration::from_millis(150),
+                interval_source: PolitenessSource::Default,
+                max_concurrent: None,
+                concurrency_source: None,
+            },
+            HostPoliteness {
+                host: "robots.test".to_owned(),
+                requests: 2,
+                unpaced_requests: 0,
+                min_interval: Duration::from_millis(300),
+                interval_source: PolitenessSource::Robots,
+                max_concurrent: Some(1),
+                concurrency_source: Some(PolitenessSource::Robots),
+            },
+        ]
+    );
+    assert!(
+        llm_spider::spider::compose_markdown(&req, &result).contains(
+            "  - robots.test: 300ms between requests (robots.txt), at most 1 at a time (robots.txt), 2 requests\n"
+        )
+    );
+
+    let audit = std::fs::read_to_string(&audit_path).expect("read audit log");
+    let mut events = audit
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).expect("json line"))
+        .filter(|event| event["kind"] == "politeness")
+        .map(|event| {
+            format!(
+                "{} {} {}",
+                event["host"].as_str().unwrap_or_default(),
+                event["min_interval_ms"],
+                event["interval_source"].as_str().unwrap_or_default()
+            )
+        })
+        .collect::<Vec<_>>();
+    events.sort();
+    // The robots.txt delay is learned from the first response, so the second request
+    // to the host logs the change.
+    assert_eq!(
+        events,
+        [
+            "configured.test 20 config",
+            "plain.test 150 default",
+            "robots.test 150 default",
+            "robots.test 300 robots",
+        ]
+    );
+
+    let _ = std::fs::remove_file(&audit_path);
+}
+
+#[derive(Default)]
+struct FailingSearch {
+    rate_limited: bool,
+}
+
+impl OpenAiApi for FailingSearch {
+    fn classify_intent(&self, _query: &str) -> anyhow::Result<QueryIntent> {
+        Ok(QueryIntent::HowTo)
+    }
+
+    fn web_search(&self, _query: &str, _limit: usize) -> anyhow::Result<Vec<SearchHit>> {
+        if self.rate_limited {
+            return Err(CrawlError::LlmRateLimited {
+                retry_after: Some(Duration::from_secs(7)),
+            }
+            .into());
+        }
+        anyhow::bail!("connection reset")
+    }
+
+    fn select_child_links(
+        &self,
+        _query: &str,
+        _page_url: &Url,
+        _page_excerpt: &str,
+        _candidates: &[serde_json::Value],
+        _collected: &[serde_json::Value],
+        _max_select: usize,
+    ) -> anyhow::Result<Vec<SelectedLink>> {
+        Ok(Vec::new())
+    }
+
+    fn cluster_sources(
+        &self,
+        _query: &str,
+        _sources: &[serde_json::Value],
+        _max_topics: usize,
+    ) -> anyhow::Result<Vec<Subtopic>> {
+        Ok(Vec::new())
+    }
+
+    fn suggest_follow_ups(
+        &self,
+        _query: &str,
+        _sources: &[serde_json::Value],
+        _max_queries: usize,
+    ) -> anyhow::Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    fn extract_key_terms(
+        &self,
+        _query: &str,
+        _sources: &[serde_json::Value],
+        _max_terms: usize,
+    ) -> anyhow::Result<Vec<GlossaryEntry>> {
+        Ok(Vec::new())
+    }
+
+    fn extract_timeline(
+        &self,
+        _query: &str,
+        _sources: &[serde_json::Value],
+        _max_events: usize,
+    ) -> anyhow::Result<Vec<TimelineEvent>> {
+        Ok(Vec::new())
+    }
+
+    fn grade_sources(
+        &self,
+        _query: &str,
+        _sources: &[serde_json::Value],
+    ) -> anyhow::Result<Vec<SourceGrade>> {
+        Ok(Vec::new())
+    }
+
+    fn answer_query(
+        &self,
+        _query: &str,
+        _sources: &[serde_json::Value],
+        _max_paragraphs: usize,
+    ) -> anyhow::Result<Answer> {
+        anyhow::bail!("no answer")
+    }
+}
+
+#[test]
+fn spider_surfaces_typed_errors_for_search_failures() {
+    let fetcher = FakeFetcher::default();
+    let req = request("q");
+
+    let err = crawl_with_fetcher(&req, &FailingSearch::default(), &fetcher).unwrap_err();
+    assert!(matches!(err, CrawlError::SearchFailed(_)), "{err:?}");
+    assert!(format!("{:#}", anyhow::Error::from(err)).contains("connection reset"));
+
+    let limited = FailingSearch { rate_limited: true };
+    let err = crawl_with_fetcher(&req, &limited, &fetcher).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            CrawlError::LlmRateLimited {
+                retry_after: Some(after)
+            } if after == Duration::from_secs(7)
+        ),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn spider_continues_with_seed_urls_when_search_fails() {
+    let seed = "https://docs.example.test/root";
+    let fetcher = FakeFetcher::default().with_page(seed, "<main>root docs</main>", vec![]);
+
+    let mut req = request("q");
+    req.seed_urls = vec![seed.parse().unwrap()];
+    req.on_search_failure = llm_spider::spider::SearchFailurePolicy::Continue;
+    let result = crawl_with_fetcher(&req, &FailingSearch::default(), &fetcher).expect("crawl");
+
+    assert_eq!(result.sources.len(), 1);
+    assert_eq!(result.sources[0].trust_tier, TrustTier::High);
+    let md = llm_spider::spider::compose_markdown(&req, &result);
+    assert!(md.contains("web search が失敗した"), "{md}");
+    assert!(md.contains("connection reset"), "{md}");
+}
+
+#[test]
+fn spider_reports_no_seeds_instead_of_failing() {
+    let fetcher = FakeFetcher::default();
+    let mut req = request("q");
+    req.on_search_failure = llm_spider::spider::SearchFailurePolicy::Continue;
+
+    let result = crawl_with_fetcher(&req, &FailingSearch::default(), &fetcher).expect("crawl");
+    assert!(result.sources.is_empty());
+    let md = llm_spider::spider::compose_markdown(&req, &result);
+    assert!(md.contains("- No sources collected."));
+    assert!(md.contains("no seeds"), "{md}");
+
+    let empty = FakeOpenAi::default();
+    let result = crawl_with_fetcher(&req, &empty, &fetcher).expect("crawl");
+    assert!(result.notes.iter().any(|n| n.contains("0 件")));
+    assert!(result.notes.iter().any(|n| n.starts_with("no seeds")));
+}
+
+#[test]
+fn spider_selection_failure_degrades_to_heuristic_children() {
+    let start = "https://example.test/start";
+    let same_host = "https://example.test/guide";
+    let other_host = "https://other.test/page";
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![start])
+        .with_failing_selection(start);
+    let fetcher = FakeFetcher::default()
+        .with_page(
+            start,
+            r#"<main><a href="https://other.test/page">Other</a><a href="/guide">Guide</a></main>"#,
+            vec![other_host, same_host],
+        )
+        .with_page(same_host, "<main>guide</main>", vec![])
+        .with_page(other_host, "<main>other</main>", vec![]);
+
+    let mut req = request("q");
+    req.max_children_per_page = 1;
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+
+    let urls = result
+        .sources
+        .iter()
+        .map(|s| s.url.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(urls, vec![start, same_host]);
+    assert_eq!(result.sources[1].trust_tier, TrustTier::Unverified);
+    assert_eq!(result.failures.len(), 1);
+    assert_eq!(
+        result.failures[0].stage,
+        llm_spider::spider::FailureStage::Selection
+    );
+
+    let md = llm_spider::spider::compose_markdown(&req, &result);
+    assert!(md.contains("## Failures"), "{md}");
+    assert!(md.contains("selection schema mismatch"), "{md}");
+}
+
+#[test]
+fn spider_selection_failure_policy_skip_and_abort() {
+    let start = "https://example.test/start";
+    let child = "https://example.test/child";
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![start])
+        .with_failing_selection(start);
+    let fetcher = FakeFetcher::default()
+        .with_page(start, "<main>start</main>", vec![child])
+        .with_page(child, "<main>child</main>", vec![]);
+
+    let mut req = request("q");
+    req.on_selection_failure = llm_spider::spider::SelectionFailurePolicy::Skip;
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert_eq!(result.sources.len(), 1);
+    assert_eq!(result.failures.len(), 1);
+
+    req.on_selection_failure = llm_spider::spider::SelectionFailurePolicy::Abort;
+    let err = crawl_with_fetcher(&req, &openai, &fetcher).unwrap_err();
+    assert!(matches!(err, CrawlError::SelectionFailed { .. }), "{err:?}");
+}
+
+#[test]
+fn spider_records_fetch_failures_in_report() {
+    let start = "https://example.test/start";
+    let missing = "https://example.test/missing";
+    let openai = FakeOpenAi::default().with_hits(vec![missing, start]);
+    let fetcher = FakeFetcher::default().with_page(start, "<main>start</main>", vec![]);
+
+    let result = crawl_with_fetcher(&request("q"), &openai, &fetcher).expect("crawl");
+    assert_eq!(result.sources.len(), 1);
+    assert_eq!(result.failures.len(), 1);
+    assert_eq!(result.failures[0].url.as_str(), missing);
+    assert_eq!(
+        result.failures[0].stage,
+        llm_spider::spider::FailureStage::Fetch
+    );
+}
+
+#[test]
+fn spider_deterministic_mode_sorts_links_and_ignores_elapsed_budget() {
+    let start = "https://example.test/start";
+    let a = "https://example.test/a";
+    let b = "https://example.test/b";
+    let c = "https://example.test/c";
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![start])
+        .with_selected(start, vec![a, b, c]);
+    let fetcher = FakeFetcher::default()
+        .with_page(start, "<main>start</main>", vec![c, b, a])
+        .with_page(a, "<main>a</main>", vec![])
+        .with_page(b, "<main>b</main>", vec![])
+        .with_page(c, "<main>c</main>", vec![]);
+
+    let mut req = request("q");
+    req.max_elapsed = Duration::ZERO;
+    req.max_child_candidates = 2;
+    req.deterministic = true;
+
+    let first = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    let second = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+
+    assert_eq!(*openai.seen_candidates.lock().unwrap(), vec![a, b, a, b]);
+    let urls = first
+        .sources
+        .iter()
+        .map(|s| s.url.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(urls, vec![start, a, b]);
+    assert_eq!(
+        llm_spider::spider::compose_markdown(&req, &first),
+        llm_spider::spider::compose_markdown(&req, &second)
+    );
+}
+
+struct SlowSearch {
+    inner: FakeOpenAi,
+    delay: Duration,
+}
+
+impl OpenAiApi for SlowSearch {
+    fn classify_intent(&self, query: &str) -> anyhow::Result<QueryIntent> {
+        self.inner.classify_intent(query)
+    }
+
+    fn web_search(&self, query: &str, limit: usize) -> anyhow::Result<Vec<SearchHit>> {
+        std::thread::sleep(self.delay);
+        self.inner.web_search(query, limit)
+    }
+
+    fn select_child_links(
+        &self,
+        query: &str,
+        page_url: &Url,
+        page_excerpt: &str,
+        candidates: &[serde_json::Value],
+        collected: &[serde_json::Value],
+        max_select: usize,
+    ) -> anyhow::Result<Vec<SelectedLink>> {
+        self.inner.select_child_links(
+            query,
+            page_url,
+            page_excerpt,
+            candidates,
+            collected,
+            max_select,
+        )
+    }
+
+    fn cluster_sources(
+        &self,
+        query: &str,
+        sources: &[serde_json::Value],
+        max_topics: usize,
+    ) -> anyhow::Result<Vec<Subtopic>> {
+        self.inner.cluster_sources(query, sources, max_topics)
+    }
+
+    fn suggest_follow_ups(
+        &self,
+        query: &str,
+        sources: &[serde_json::Value],
+        max_queries: usize,
+    ) -> anyhow::Result<Vec<String>> {
+        self.inner.suggest_follow_ups(query, sources, max_queries)
+    }
+
+    fn extract_key_terms(
+        &self,
+        query: &str,
+        sources: &[serde_json::Value],
+        max_terms: usize,
+    ) -> anyhow::Result<Vec<GlossaryEntry>> {
+        self.inner.extract_key_terms(query, sources, max_terms)
+    }
+
+    fn extract_timeline(
+        &self,
+        query: &str,
+        sources: &[serde_json::Value],
+        max_events: usize,
+    ) -> anyhow::Result<Vec<TimelineEvent>> {
+        self.inner.extract_timeline(query, sources, max_events)
+    }
+
+    fn grade_sources(
+        &self,
+        query: &str,
+        sources: &[serde_json::Value],
+    ) -> anyhow::Result<Vec<SourceGrade>> {
+        self.inner.grade_sources(query, sources)
+    }
+
+    fn answer_query(
+        &self,
+        query: &str,
+        sources: &[serde_json::Value],
+        max_paragraphs: usize,
+    ) -> anyhow::Result<Answer> {
+        self.inner.answer_query(query, sources, max_paragraphs)
+    }
+}
+
+#[test]
+fn spider_crawl_budget_starts_after_web_search() {
+    let start = "https://example.test/start";
+    let openai = SlowSearch {
+        inner: FakeOpenAi::default().with_hits(vec![start]),
+        delay: Duration::from_millis(50),
+    };
+    let fetcher = FakeFetcher::default().with_page(start, "<main>start</main>", vec![]);
+
+    let mut req = request("q");
+    req.max_elapsed = Duration::from_millis(10);
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert!(result.sources.is_empty());
+
+    req.crawl_budget = Some(Duration::from_secs(30));
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert_eq!(result.sources.len(), 1);
+}
+
+#[test]
+fn spider_batches_candidates_and_merges_by_tier() {
+    let start = "https://example.com/";
+    let links = [
+        "https://example.com/1",
+        "https://example.com/2",
+        "https://example.com/3",
+        "https://example.com/4",
+        "https://example.com/5",
+        "https://example.com/6",
+        "https://example.com/7",
+    ];
+    // The best link sits at the bottom of the page, past the first batch.
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![start])
+        .with_selected(start, vec![links[0], links[2], links[4]])
+        .with_tier(links[0], TrustTier::Low)
+        .with_tier(links[4], TrustTier::High);
+    let fetcher = links.iter().fold(
+        FakeFetcher::default().with_page(start, "<main>start</main>", links.to_vec()),
+        |fetcher, link| fetcher.with_page(link, "<main>child</main>", vec![]),
+    );
+
+    let mut req = request("q");
+    req.max_child_candidates = 2;
+    req.max_candidate_batches = 3;
+    req.max_children_per_page = 2;
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+
+    assert_eq!(openai.select_calls.load(Ordering::Relaxed), 3);
+    let seen = openai.seen_candidates.lock().unwrap().clone();
+    assert_eq!(seen, links[..6]);
+    let urls = result
+        .sources
+        .iter()
+        .map(|s| s.url.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(urls, vec![start, links[4], links[2]]);
+    assert_eq!(result.stats.llm_calls, 4);
+}
+
+#[test]
+fn spider_expands_hub_pages_without_counting_them() {
+    let hub = "https://example.com/docs/";
+    let children = (0..20)
+        .map(|i| format!("https://example.com/docs/page-{i}"))
+        .collect::<Vec<_>>();
+    let child_refs = children.iter().map(String::as_str).collect::<Vec<_>>();
+    let html = format!(
+        "<main><h1>Index</h1><ul>{}</ul></main>",
+        child_refs
+            .iter()
+            .map(|url| format!("<li><a href=\"{url}\">Guide {url}</a></li>"))
+            .collect::<String>()
+    );
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![hub])
+        .with_selected(hub, child_refs[..6].to_vec());
+    let fetcher = child_refs.iter().fold(
+        FakeFetcher::default().with_page(hub, &html, child_refs.clone()),
+        |fetcher, child| {
+            fetcher.with_page(
+                child,
+                "<main><p>A long article body that is mostly prose.</p></main>",
+                vec![],
+            )
+        },
+    );
+
+    let mut req = request("q");
+    req.max_children_per_page = 2;
+    req.max_children_per_hub = 5;
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+
+    assert_eq!(result.stats.hub_pages, 1);
+    let urls = result
+        .sources
+        .iter()
+        .map(|s| s.url.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(urls.len(), 5);
+    assert!(!urls.contains(&hub));
+
+    req.max_children_per_hub = 0;
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert_eq!(result.stats.hub_pages, 0);
+    assert_eq!(result.sources[0].url.as_str(), hub);
+    assert_eq!(result.sources.len(), 3);
+}
+
+#[test]
+fn spider_follows_pagination_into_one_source() {
+    let page1 = "https://example.com/changelog";
+    let page2 = "https://example.com/changelog?page=2";
+    let page3 = "https://example.com/changelog?page=3";
+    let openai = FakeOpenAi::default().with_hits(vec![page1]);
+    let fetcher = FakeFetcher::default()
+        .with_page(
+            page1,
+            r#"<html><head><link rel="next" href="/changelog?page=2"></head>
+               <body><main><p>Release 3.0</p></main></body></html>"#,
+            vec![],
+        )
+        .with_page(
+            page2,
+            r#"<main><p>Release 2.0</p><a href="/changelog?page=3">Next page</a></main>"#,
+            vec![],
+        )
+        .with_page(page3, "<main><p>Release 1.0</p></main>", vec![]);
+
+    let mut req = request("q");
+    req.max_depth = 0;
+    req.max_pagination = 5;
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+
+    assert_eq!(result.sources.len(), 1);
+    let source = &result.sources[0];
+    let continued = source
+        .continuation_urls
+        .iter()
+        .map(Url::as_str)
+        .collect::<Vec<_>>();
+    assert_eq!(continued, [page2, page3]);
+    for release in ["Release 3.0", "Release 2.0", "Release 1.0"] {
+        assert!(source.content.contains(release), "{}", source.content);
+    }
+    assert_eq!(result.stats.pages_fetched, 3);
+    assert_eq!(result.stats.pagination_pages, 2);
+
+    req.max_pagination = 1;
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert_eq!(result.sources[0].continuation_urls.len(), 1);
+    assert!(!result.sources[0].content.contains("Release 1.0"));
+}
+
+#[test]
+fn spider_guards_pagination_like_any_other_fetch() {
+    let challenged = "https://example.com/changelog";
+    let capped = "https://docs.example.org/notes";
+    let openai = FakeOpenAi::default().with_hits(vec![challenged, capped]);
+    let fetcher = FakeFetcher::default()
+        .with_page(
+            challenged,
+            r#"<html><head><link rel="next" href="/changelog?page=2"></head>
+               <body><main><p>Release 3.0</p></main></body></html>"#,
+            vec![],
+        )
+        .with_page(
+            "https://example.com/changelog?page=2",
+            CHALLENGE_PAGE,
+            vec![],
+        )
+        .with_page(
+            capped,
+            r#"<html><head><link rel="next" href="/notes?page=2"></head>
+               <body><main><p>Notes</p></main></body></html>"#,
+            vec![],
+        )
+        .with_page(
+            "https://docs.example.org/notes?page=2",
+            "<main>more notes</main>",
+            vec![],
+        );
+
+    let mut req = request("q");
+    req.max_depth = 0;
+    req.max_pagination = 5;
+    req.host_policies.insert(
+        "docs.example.org".to_owned(),
+        llm_spider::spider::HostPolicy {
+            max_pages: Some(1),
+            ..Default::default()
+        },
+    );
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+
+    assert_eq!(result.sources.len(), 2);
+    for source in &result.sources {
+        assert!(source.continuation_urls.is_empty(), "{source:?}");
+        assert!(!source.content.contains("more notes"), "{}", source.content);
+    }
+    assert_eq!(result.stats.pagination_pages, 0);
+    let reasons = result
+        .skipped
+        .iter()
+        .map(|skip| (skip.url.as_str(), skip.reason))
+        .collect::<Vec<_>>();
+    assert!(
+        reasons.contains(&(
+            "https://example.com/changelog?page=2",
+            llm_spider::spider::SkipReason::BotChallenge
+        )),
+        "{reasons:?}"
+    );
+    assert!(
+        reasons.contains(&(
+            "https://docs.example.org/notes?page=2",
+            llm_spider::spider::SkipReason::HostCap
+        )),
+        "{reasons:?}"
+    );
+}
+
+#[test]
+fn spider_lists_sources_over_the_per_host_limit_separately() {
+    let blog = [
+        "https://blog.example/a",
+        "https://blog.example/b",
+        "https://blog.example/c",
+    ];
+    let docs = "https://docs.example/guide";
+    let openai = FakeOpenAi::default().with_hits(vec![blog[0], blog[1], blog[2], docs]);
+    let fetcher = FakeFetcher::default()
+        .with_page(blog[0], "<main>post a</main>", vec![])
+        .with_page(blog[1], "<main>post b</main>", vec![])
+        .with_page(blog[2], "<main>post c</main>", vec![])
+        .with_page(docs, "<main>guide</main>", vec![]);
+
+    let mut req = request("q");
+    req.max_depth = 0;
+    req.max_sources_per_host = 2;
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+
+    let kept = result
+        .sources
+        .iter()
+        .map(|source| source.url.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(kept, [blog[0], blog[1], docs]);
+    let overflow = result
+        .overflow_sources
+        .iter()
+        .map(|source| source.url.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(overflow, [blog[2]]);
+
+    let markdown = llm_spider::spider::compose_markdown(&req, &result);
+    let (findings, more) = markdown
+        .split_once("## More from the same hosts")
+        .expect("overflow section");
+    assert!(!findings.contains(blog[2]), "{markdown}");
+    assert!(more.contains(blog[2]), "{markdown}");
+
+    req.max_sources_per_host = 0;
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert_eq!(result.sources.len(), 4);
+    assert!(result.overflow_sources.is_empty());
+}
+
+#[test]
+fn spider_overflow_sources_skip_max_pages_but_inform_the_answer() {
+    let blog = ["https://blog.example/a", "https://blog.example/b"];
+    let docs = "https://docs.example/guide";
+    // Answer ids cover the sources, then the overflow.
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![blog[0], blog[1], docs])
+        .with_answer(&["From both hosts."], &[0, 2]);
+    let fetcher = FakeFetcher::default()
+        .with_page(blog[0], "<main>post a</main>", vec![])
+        .with_page(blog[1], "<main>post b</main>", vec![])
+        .with_page(docs, "<main>guide</main>", vec![]);
+
+    let mut req = request("q");
+    req.max_depth = 0;
+    req.max_pages = 2;
+    req.max_sources_per_host = 1;
+    req.answer = true;
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+
+    let kept = result
+        .sources
+        .iter()
+        .map(|source| source.url.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(kept, [blog[0], docs]);
+    assert_eq!(result.overflow_sources[0].url.as_str(), blog[1]);
+    let cited = result
+        .answer
+        .as_ref()
+        .expect("answer")
+        .sources
+        .iter()
+        .map(|&id| result.analysis_sources()[id].url.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(cited, [blog[0], blog[1]]);
+
+    let markdown = llm_spider::spider::compose_markdown(&req, &result);
+    let (answer, _) = markdown.split_once("## Findings").expect("findings");
+    assert!(answer.contains(&format!("]({})", blog[1])), "{markdown}");
+    let json = llm_spider::spider::compose_json(&req, &result);
+    assert_eq!(json["answer"]["sources"][1], blog[1]);
+}
+
+#[test]
+fn spider_collapses_hreflang_variants_to_preferred_language() {
+    let start = "https://example.com/";
+    let en = "https://example.com/en/guide";
+    let ja = "https://example.com/ja/guide";
+    let de = "https://example.com/de/guide";
+    let alternates = format!(
+        r#"<link rel="alternate" hreflang="en" href="{en}">
+           <link rel="alternate" hreflang="ja-JP" href="{ja}">
+           <link rel="alternate" hreflang="de" href="{de}">"#
+    );
+    let variant = |body: &str| {
+        format!("<html><head>{alternates}</head><body><main>{body}</main></body></html>")
+    };
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![start])
+        .with_selected(start, vec![de, en, ja]);
+    let fetcher = FakeFetcher::default()
+        .with_page(start, "<main>start</main>", vec![de, en, ja])
+        .with_page(en, &variant("guide"), vec![])
+        .with_page(ja, &variant("ガイド"), vec![])
+        .with_page(de, &variant("Anleitung"), vec![]);
+
+    let mut req = request("q");
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert_eq!(result.sources.len(), 2);
+    assert_eq!(result.stats.language_variants_skipped, 2);
+
+    req.preferred_language = Some("ja".to_owned());
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    let urls = result
+        .sources
+        .iter()
+        .map(|s| s.url.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(urls, [start, ja]);
+}
+
+#[test]
+fn spider_records_crawl_path_on_sources() {
+    let start = "https://example.com/";
+    let a = "https://example.com/a";
+    let deep = "https://example.com/a/deep";
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![start])
+        .with_selected(start, vec![a])
+        .with_selected(a, vec![deep]);
+    let fetcher = FakeFetcher::default()
+        .with_page(start, "<main>start</main>", vec![a])
+        .with_page(a, "<main>a</main>", vec![deep])
+        .with_page(deep, "<main>deep</main>", vec![]);
+
+    let mut req = request("q");
+    req.max_depth = 2;
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+
+    let path = result
+        .sources
+        .iter()
+        .map(|s| {
+            (
+                s.url.as_str(),
+                s.parent.as_ref().map(Url::as_str),
+                s.fetch_order,
+                s.depth,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        path,
+        [
+            (start, None, 1, 0),
+            (a, Some(start), 2, 1),
+            (deep, Some(a), 3, 2),
+        ]
+    );
+
+    let md = llm_spider::spider::compose_markdown(&req, &result);
+    assert!(
+        md.contains(&format!("- [Medium] {start} (#1, seed)\n")),
+        "{md}"
+    );
+    assert!(
+        md.contains(&format!("- [Medium] {deep} (#3, depth 2 via {a})\n")),
+        "{md}"
+    );
+
+    let json = llm_spider::spider::compose_json(&req, &result);
+    assert_eq!(json["sources"][2]["parent"], a);
+    assert_eq!(json["sources"][2]["fetch_order"], 3);
+    assert_eq!(json["sources"][0]["parent"], serde_json::Value::Null);
+    assert_eq!(json["stats"]["pages_fetched"], 3);
+}
+
+fn seeded(query: &str, seeds: &[&str]) -> llm_spider::spider::UserRequest {
+    let mut req = request(query);
+    req.seed_urls = seeds.iter().map(|seed| seed.parse().unwrap()).collect();
+    req
+}
+
+#[test]
+fn batch_fetches_a_page_shared_by_two_queries_once() {
+    let shared = "https://example.com/shared";
+    let a = "https://example.com/a";
+    let b = "https://example.com/b";
+    let fetcher = FakeFetcher::default()
+        .with_page(shared, "<title>Shared</title><main>both</main>", vec![])
+        .with_page(a, "<main>a</main>", vec![])
+        .with_page(b, "<main>b</main>", vec![]);
+    let requests = vec![seeded("qa", &[shared, a]), seeded("qb", &[shared, b])];
+
+    let results =
+        crawl_batch_with_fetcher(&requests, &FakeOpenAi::default(), &fetcher).expect("crawl");
+
+    let urls = |index: usize| {
+        results[index]
+            .sources
+            .iter()
+            .map(|source| source.url.to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(urls(0), vec![shared, a]);
+    assert_eq!(urls(1), vec![shared, b]);
+    assert_eq!(results[1].sources[0].title.as_deref(), Some("Shared"));
+    assert_eq!(results[0].stats.shared_fetches, 0);
+    assert_eq!(results[1].stats.shared_fetches, 1);
+    assert_eq!(results[1].stats.pages_fetched, 1);
+    let fetched = fetcher.fetched.lock().unwrap();
+    assert_eq!(fetched.iter().filter(|url| *url == shared).count(), 1);
+}
+
+#[test]
+fn batch_splits_the_page_budget_evenly_between_queries() {
+    let pages =
+        ["a1", "a2", "a3", "b1", "b2", "b3"].map(|name| format!("https://example.com/{name}"));
+    let fetcher = pages.iter().fold(FakeFetcher::default(), |fetcher, url| {
+        fetcher.with_page(url, "<main>text</main>", vec![])
+    });
+    let mut requests = vec![
+        seeded("qa", &[&pages[0], &pages[1], &pages[2]]),
+        seeded("qb", &[&pages[3], &pages[4], &pages[5]]),
+    ];
+    for req in &mut requests {
+        req.max_pages = 4;
+    }
+
+    let results =
+        crawl_batch_with_fetcher(&requests, &FakeOpenAi::default(), &fetcher).expect("crawl");
+
+    assert_eq!(results[0].sources.len(), 2);
+    assert_eq!(results[1].sources.len(), 2);
+}
+
+#[test]
+fn batch_gives_the_share_of_a_query_without_links_to_the_others() {
+    let pages = ["a1", "a2", "a3", "b1"].map(|name| format!("https://example.com/{name}"));
+    let fetcher = pages.iter().fold(FakeFetcher::default(), |fetcher, url| {
+        fetcher.with_page(url, "<main>text</main>", vec![])
+    });
+    let mut requests = vec![
+        seeded("qa", &[&pages[0], &pages[1], &pages[2]]),
+        seeded("qb", &[&pages[3]]),
+    ];
+    for req in &mut requests {
+        req.max_pages = 4;
+    }
+
+    let results =
+        crawl_batch_with_fetcher(&requests, &FakeOpenAi::default(), &fetcher).expect("crawl");
+
+    assert_eq!(results[0].sources.len(), 3);
+    assert_eq!(results[1].sources.len(), 1);
+}
+
+#[test]
+fn spider_marks_sources_new_changed_or_unchanged_since_the_last_run() {
+    let stable = "https://example.test/stable";
+    let edited = "https://example.test/edited";
+    let added = "https://example.test/added";
+    let cache_dir = std::env::temp_dir().join(format!(
+        "llm-spider-content-store-{}-{}",
+        std::process::id(),
+        line!()
+    ));
+    let _ = std::fs::remove_dir_all(&cache_dir);
+
+    let mut req = request("q");
+    req.max_depth = 0;
+    req.cache_dir = Some(cache_dir.clone());
+    let first = FakeFetcher::default()
+        .with_page(stable, "<main>same text</main>", vec![])
+        .with_page(edited, "<main>first draft</main>", vec![]);
+    let openai = FakeOpenAi::default().with_hits(vec![stable, edited]);
+    let result = crawl_with_fetcher(&req, &openai, &first).expect("first crawl");
+    assert!(
+        result
+            .sources
+            .iter()
+            .all(|source| source.change == Some(ContentChange::New))
+    );
+
+    let second = FakeFetcher::default()
+        .with_page(stable, "<main>same text</main>", vec![])
+        .with_page(edited, "<main>second draft</main>", vec![])
+        .with_page(added, "<main>added later</main>", vec![]);
+    let openai = FakeOpenAi::default().with_hits(vec![stable, edited, added]);
+    let result = crawl_with_fetcher(&req, &openai, &second).expect("second crawl");
+    let changes = result
+        .sources
+        .iter()
+        .map(|source| source.change)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        changes,
+        vec![
+            Some(ContentChange::Unchanged),
+            Some(ContentChange::Changed),
+            Some(ContentChange::New),
+        ]
+    );
+    let markdown = llm_spider::spider::compose_markdown(&req, &result);
+    assert!(
+        markdown.contains(&format!("{edited} (changed)")),
+        "{markdown}"
+    );
+    let json = llm_spider::spider::compose_json(&req, &result);
+    assert_eq!(json["sources"][0]["change"], "unchanged");
+
+    let _ = std::fs::remove_dir_all(&cache_dir);
+}
+
+#[test]
+fn spider_leaves_change_unset_without_a_cache_dir() {
+    let page = "https://example.test/page";
+    let openai = FakeOpenAi::default().with_hits(vec![page]);
+    let fetcher = FakeFetcher::default().with_page(page, "<main>text</main>", vec![]);
+
+    let result = crawl_with_fetcher(&request("q"), &openai, &fetcher).expect("crawl");
+
+    assert_eq!(result.sources[0].change, None);
+}
+
+#[test]
+fn spider_reuses_robots_txt_from_the_cache_dir_across_runs() {
+    use std::io::{BufRead as _, BufReader, Write as _};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    let base = format!("http://{}", listener.local_addr().expect("addr"));
+    let requests = Arc::new(Mutex::new(Vec::<String>::new()));
+    let seen = requests.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            let _ = reader.read_line(&mut request_line);
+            let mut header = String::new();
+            while reader.read_line(&mut header).unwrap_or(0) > 2 {
+                header.clear();
+            }
+            let mut parts = request_line.split_whitespace();
+            let method = parts.next().unwrap_or_default().to_owned();
+            let path = parts.next().unwrap_or_default().to_owned();
+            seen.lock().unwrap().push(format!("{method} {path}"));
+            let (content_type, body) = if path == "/robots.txt" {
+                ("text/plain", "User-agent: *\nDisallow: /private\n")
+            } else {
+                ("text/html", "<html><main>page text</main></html>")
+            };
+            let mut stream = reader.into_inner();
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\ncontent-type: {content_type}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                body.len()
+            );
+            if method != "HEAD" {
+                let _ = stream.write_all(body.as_bytes());
+            }
+        }
+    });
+    let cache_dir = std::env::temp_dir().join(format!(
+        "llm-spider-robots-cache-{}-{}",
+        std::process::id(),
+        line!()
+    ));
+    let _ = std::fs::remove_dir_all(&cache_dir);
+
+    let mut req = request("q");
+    req.allow_local = true;
+    req.max_depth = 0;
+    req.cache_dir = Some(cache_dir.clone());
+    req.seed_urls = vec![
+        format!("{base}/page").parse().unwrap(),
+        format!("{base}/private").parse().unwrap(),
+    ];
+    let robots_fetches = || {
+        requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|line| *line == "GET /robots.txt")
+            .count()
+    };
+
+    for run in 0..2 {
+        let result = llm_spider::spider::crawl(&req, &FakeOpenAi::default()).expect("crawl");
+        assert_eq!(result.sources.len(), 1, "run {run}");
+        assert!(result.sources[0].url.path().ends_with("/page"));
+        assert!(
+            result
+                .skipped
+                .iter()
+                .any(|skip| skip.url.path() == "/private"
+                    && skip.reason == llm_spider::spider::SkipReason::RobotsBlocked),
+            "run {run}: {:?}",
+            result.skipped
+        );
+        assert_eq!(robots_fetches(), 1, "run {run}");
+    }
+    assert!(
+        !requests
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|line| line.ends_with("/private"))
+    );
+
+    let _ = std::fs::remove_dir_all(&cache_dir);
+}
+
+#[test]
+fn spider_checks_robots_txt_for_the_redirect_target() {
+    use std::io::{BufRead as _, BufReader, Write as _};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    let base = format!("http://{}", listener.local_addr().expect("addr"));
+    let paths = Arc::new(Mutex::new(Vec::<String>::new()));
+    let seen = paths.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            let _ = reader.read_line(&mut request_line);
+            let mut header = String::new();
+            while reader.read_line(&mut header).unwrap_or(0) > 2 {
+                header.clear();
+            }
+            let path = request_line
+                .split_whitespace()
+                .nth(1)
+                .unwrap_or_default()
+                .to_owned();
+            seen.lock().unwrap().push(path.clone());
+            let (status, extra, body) = match path.as_str() {
+                "/robots.txt" => ("200 OK", "", "User-agent: *\nDisallow: /private\n"),
+                "/old" => ("302 Found", "location: /private/page\r\n", ""),
+                _ => ("200 OK", "", "<html><main>private text</main></html>"),
+            };
+            let mut stream = reader.into_inner();
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {status}\r\ncontent-type: text/html\r\n{extra}content-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+        }
+    });
+
+    let mut req = request("q");
+    req.allow_local = true;
+    req.max_depth = 0;
+    req.seed_urls = vec![format!("{base}/old").parse().unwrap()];
+
+    let result = llm_spider::spider::crawl(&req, &FakeOpenAi::default()).expect("crawl");
+    assert!(result.sources.is_empty(), "{:?}", result.sources);
+    assert!(
+        result
+            .skipped
+            .iter()
+            .any(|skip| skip.reason == llm_spider::spider::SkipReason::RobotsBlocked),
+        "{:?}",
+        result.skipped
+    );
+    // The target is checked before it is requested, not after.
+    let paths = paths.lock().unwrap();
+    assert!(
+        !paths.iter().any(|path| path == "/private/page"),
+        "{paths:?}"
+    );
+}
+
+#[test]
+fn spider_resolves_links_against_the_redirect_target() {
+    use std::io::{BufRead as _, BufReader, Write as _};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    let base = format!("http://{}", listener.local_addr().expect("addr"));
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            let _ = reader.read_line(&mut request_line);
+            let mut header = String::new();
+            while reader.read_line(&mut header).unwrap_or(0) > 2 {
+                header.clear();
+            }
+            let path = request_line
+                .split_whitespace()
+                .nth(1)
+                .unwrap_or_default()
+                .to_owned();
+            let (status, extra, body) = match path.as_str() {
+                "/docs" => ("301 Moved Permanently", "location: /docs/\r\n", ""),
+                "/docs/" => (
+                    "200 OK",
+                    "",
+                    "<html><main>docs index</main><a href=\"guide\">guide</a></html>",
+                ),
+                "/docs/guide" => ("200 OK", "", "<html><main>the guide</main></html>"),
+                _ => ("404 Not Found", "", ""),
+            };
+            let mut stream = reader.into_inner();
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {status}\r\ncontent-type: text/html\r\n{extra}content-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+        }
+    });
+
+    let docs = format!("{base}/docs");
+    let guide = format!("{base}/docs/guide");
+    let openai = FakeOpenAi::default()
+        .with_selected(&docs, vec![guide.as_str()])
+        .with_selected(&format!("{docs}/"), vec![guide.as_str()]);
+    let mut req = request("q");
+    req.allow_local = true;
+    req.max_depth = 1;
+    req.seed_urls = vec![docs.parse().unwrap()];
+
+    let result = llm_spider::spider::crawl(&req, &openai).expect("crawl");
+    let urls = result
+        .sources
+        .iter()
+        .map(|s| s.url.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(urls, [docs.as_str(), guide.as_str()]);
+    assert_eq!(
+        result.sources[0].http.final_url.as_ref().map(Url::as_str),
+        Some(format!("{docs}/").as_str())
+    );
+}
+
+#[test]
+fn spider_sends_no_head_request_to_a_path_robots_txt_disallows() {
+    use std::io::{BufRead as _, BufReader, Write as _};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    let base = format!("http://{}", listener.local_addr().expect("addr"));
+    let requests = Arc::new(Mutex::new(Vec::<String>::new()));
+    let seen = requests.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            let _ = reader.read_line(&mut request_line);
+            let mut header = String::new();
+            while reader.read_line(&mut header).unwrap_or(0) > 2 {
+                header.clear();
+            }
+            seen.lock().unwrap().push(request_line.trim().to_owned());
+            let body = if request_line.contains("/robots.txt") {
+                "User-agent: *\nDisallow: /private\n"
+            } else {
+                "<html><main>private text</main></html>"
+            };
+            let mut stream = reader.into_inner();
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\ncontent-type: text/plain\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+        }
+    });
+
+    let mut req = request("q");
+    req.allow_local = true;
+    req.max_depth = 0;
+    // An unrecognized extension would get a `HEAD` to learn its `Content-Type`.
+    req.seed_urls = vec![format!("{base}/private/v1.2").parse().unwrap()];
+
+    let result = llm_spider::spider::crawl(&req, &FakeOpenAi::default()).expect("crawl");
+    assert!(result.sources.is_empty(), "{:?}", result.sources);
+    assert!(
+        result
+            .skipped
+            .iter()
+            .any(|skip| skip.reason == llm_spider::spider::SkipReason::RobotsBlocked),
+        "{:?}",
+        result.skipped
+    );
+    let requests = requests.lock().unwrap();
+    assert!(
+        requests.iter().all(|line| !line.contains("/private")),
+        "{requests:?}"
+    );
+}
+
+#[test]
+fn spider_counts_head_and_robots_txt_requests_apart_from_paced_pages() {
+    use std::io::{BufRead as _, BufReader, Write as _};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    let base = format!("http://{}", listener.local_addr().expect("addr"));
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            let _ = reader.read_line(&mut request_line);
+            let mut header = String::new();
+            while reader.read_line(&mut header).unwrap_or(0) > 2 {
+                header.clear();
+            }
+            let (content_type, body) = if request_line.contains("/robots.txt") {
+                ("text/plain", "User-agent: *\nAllow: /\n")
+            } else {
+                ("text/html", "<html><main>release notes</main></html>")
+            };
+            let mut stream = reader.into_inner();
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\ncontent-type: {content_type}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+        }
+    });
+
+    let mut req = request("q");
+    req.allow_local = true;
+    req.max_depth = 0;
+    req.show_stats = true;
+    // An unrecognized extension gets a `HEAD` before its `GET`.
+    req.seed_urls = vec![format!("{base}/notes/v1.2").parse().unwrap()];
+
+    let result = llm_spider::spider::crawl(&req, &FakeOpenAi::default()).expect("crawl");
+    assert_eq!(result.sources.len(), 1, "{:?}", result.skipped);
+    let [host] = result.stats.host_politeness.as_slice() else {
+        panic!("{:?}", result.stats.host_politeness);
+    };
+    assert_eq!((host.requests, host.unpaced_requests), (1, 2));
+    assert!(
+        llm_spider::spider::compose_markdown(&req, &result)
+            .contains(", 1 requests (+2 HEAD, robots.txt, or ai.txt)\n")
+    );
+}
+
+#[test]
+fn spider_sends_the_crawl_id_and_echoes_it_in_the_artifacts() {
+    use std::io::{BufRead as _, BufReader, Write as _};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    let base = format!("http://{}", listener.local_addr().expect("addr"));
+    let headers = Arc::new(Mutex::new(Vec::<String>::new()));
+    let seen = headers.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            let _ = reader.read_line(&mut request_line);
+            let mut header = String::new();
+            while reader.read_line(&mut header).unwrap_or(0) > 2 {
+                seen.lock()
+                    .unwrap()
+                    .push(header.trim().to_ascii_lowercase());
+                header.clear();
+            }
+            let body = "<html><main>page text</main></html>";
+            let mut stream = reader.into_inner();
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\ncontent-type: text/html\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                body.len()
+            );
+            let _ = stream.write_all(body.as_bytes());
+        }
+    });
+    let warc = std::env::temp_dir().join(format!(
+        "llm-spider-crawl-id-{}-{}.warc",
+        std::process::id(),
+        line!()
+    ));
+
+    let mut req = request("q");
+    req.allow_local = true;
+    req.max_depth = 0;
+    req.crawl_id = Some("run-42".to_owned());
+    req.warc = Some(warc.clone());
+    req.seed_urls = vec![format!("{base}/page.html").parse().unwrap()];
+
+    let result = llm_spider::spider::crawl(&req, &FakeOpenAi::default()).expect("crawl");
+    assert_eq!(result.sources.len(), 1);
+    let headers = headers.lock().unwrap();
+    assert!(
+        headers
+            .iter()
+            .any(|h| h.starts_with("user-agent: llm-spider/") && h.ends_with("crawl-id: run-42)")),
+        "{headers:?}"
+    );
+    assert!(
+        headers.iter().any(|h| h == "x-crawl-id: run-42"),
+        "{headers:?}"
+    );
+
+    assert!(llm_spider::spider::compose_markdown(&req, &result).contains("- Crawl ID: `run-42`\n"));
+    assert_eq!(
+        llm_spider::spider::compose_json(&req, &result)["crawl_id"],
+        "run-42"
+    );
+    let archive = std::fs::read_to_string(&warc).expect("read WARC");
+    assert!(archive.contains("crawl-id: run-42\r\n"));
+
+    let _ = std::fs::remove_file(&warc);
+}
+
+#[test]
+fn spider_honors_ai_txt_and_tdm_reservation_opt_outs_when_asked() {
+    use std::io::{BufRead as _, BufReader, Write as _};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    let base = format!("http://{}", listener.local_addr().expect("addr"));
+    let requests = Arc::new(Mutex::new(Vec::<String>::new()));
+    let seen = requests.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            let _ = reader.read_line(&mut request_line);
+            let mut header = String::new();
+            while reader.read_line(&mut header).unwrap_or(0) > 2 {
+                header.clear();
+            }
+            let mut parts = request_line.split_whitespace();
+            let method = parts.next().unwrap_or_default().to_owned();
+            let path = parts.next().unwrap_or_default().to_owned();
+            seen.lock().unwrap().push(format!("{method} {path}"));
+            let (status, content_type, extra, body) = match path.as_str() {
+                "/robots.txt" => ("404 Not Found", "text/plain", "", ""),
+                "/ai.txt" => (
+                    "200 OK",
+                    "text/plain",
+                    "",
+                    "User-Agent: *\nDisallow: /private\n",
+                ),
+                "/reserved" => (
+                    "200 OK",
+                    "text/html",
+                    "tdm-reservation: 1\r\n",
+                    "<html><main>reserved text</main></html>",
+                ),
+                _ => (
+                    "200 OK",
+                    "text/html",
+                    "",
+                    "<html><main>page text</main></html>",
+                ),
+            };
+            let mut stream = reader.into_inner();
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {status}\r\ncontent-type: {content_type}\r\n{extra}content-length: {}\r\nconnection: close\r\n\r\n",
+                body.len()
+            );
+            if method != "HEAD" {
+                let _ = stream.write_all(body.as_bytes());
+            }
+        }
+    });
+    let audit_path = std::env::temp_dir().join(format!(
+        "llm-spider-opt-out-audit-{}-{}.jsonl",
+        std::process::id(),
+        line!()
+    ));
+    let _ = std::fs::remove_file(&audit_path);
+
+    let mut req = request("q");
+    req.allow_local = true;
+    req.max_depth = 0;
+    req.seed_urls = ["page", "private", "reserved"]
+        .map(|path| format!("{base}/{path}").parse().unwrap())
+        .to_vec();
+
+    let result = llm_spider::spider::crawl(&req, &FakeOpenAi::default()).expect("crawl");
+    assert_eq!(result.sources.len(), 3);
+    assert!(
+        !requests
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|line| line == "GET /ai.txt")
+    );
+
+    req.honor_opt_outs = true;
+    req.audit_log = Some(Arc::new(
+        llm_spider::audit::AuditLog::open(&audit_path).expect("audit log"),
+    ));
+    let result = llm_spider::spider::crawl(&req, &FakeOpenAi::default()).expect("crawl");
+    let paths = result
+        .sources
+        .iter()
+        .map(|s| s.url.path())
+        .collect::<Vec<_>>();
+    assert_eq!(paths, ["/page"]);
+    let opted_out = result
+        .skipped
+        .iter()
+        .filter(|skip| skip.reason == llm_spider::spider::SkipReason::OptedOut)
+        .map(|skip| skip.url.path())
+        .collect::<Vec<_>>();
+    assert_eq!(opted_out, ["/private", "/reserved"]);
+    let requests = requests.lock().unwrap();
+    assert_eq!(
+        requests
+            .iter()
+            .filter(|line| *line == "GET /ai.txt")
+            .count(),
+        1
+    );
+    assert_eq!(
+        requests
+            .iter()
+            .filter(|line| *line == "GET /private")
+            .count(),
+        1,
+        "only the first run fetches /private: {requests:?}"
+    );
+
+    let audit = std::fs::read_to_string(&audit_path).expect("read audit log");
+    let events = audit
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).expect("json line"))
+        .filter(|event| event["kind"] == "opt_out")
+        .map(|event| {
+            (
+                event["url"].as_str().unwrap_or_default().to_owned(),
+                event["signal"].as_str().unwrap_or_default().to_owned(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        events,
+        [
+            (format!("{base}/private"), "ai.txt".to_owned()),
+            (format!("{base}/reserved"), "tdm-reservation".to_owned()),
+        ]
+    );
+
+    let _ = std::fs::remove_file(&audit_path);
+}
+
+#[test]
+fn spider_stops_fetching_from_a_host_after_consecutive_failures() {
+    let down = [1, 2, 3, 4].map(|n| format!("https://down.example/{n}"));
+    let up = "https://up.example/page";
+    let fetcher = FakeFetcher::default().with_page(up, "<main>up</main>", vec![]);
+    let mut req = request("q");
+    req.seed_urls = down
+        .iter()
+        .map(String::as_str)
+        .chain([up])
+        .map(|url| url.parse().unwrap())
+        .collect();
+
+    let result = crawl_with_fetcher(&req, &FakeOpenAi::default(), &fetcher).expect("crawl");
+
+    let fetched = fetcher.fetched.lock().unwrap().clone();
+    assert_eq!(fetched, vec![&down[0], &down[1], &down[2], up]);
+    assert_eq!(result.sources.len(), 1);
+    assert!(
+        result
+            .skipped
+            .iter()
+            .any(|skip| skip.url.as_str() == down[3]
+                && skip.reason == llm_spider::spider::SkipReason::HostDown)
+    );
+    assert_eq!(
+        result
+            .notes
+            .iter()
+            .filter(|note| note.contains("down.example"))
+            .count(),
+        1,
+        "{:?}",
+        result.notes
+    );
+}
+
+/// Answers every fetch with the given HTTP status.
+struct StatusFetcher {
+    status: u16,
+    fetched: Mutex<Vec<String>>,
+}
+
+impl PageFetcher for StatusFetcher {
+    fn fetch(&self, url: &Url) -> anyhow::Result<FetchedPage> {
+        self.fetched.lock().unwrap().push(url.to_string());
+        Err(CrawlError::FetchFailed {
+            url: url.clone(),
+            status: Some(self.status),
+        }
+        .into())
+    }
+}
+
+#[test]
+fn spider_host_breaker_counts_server_errors_but_not_missing_pages() {
+    let urls = [1, 2, 3, 4].map(|n| format!("https://example.test/{n}"));
+    let mut req = request("q");
+    req.seed_urls = urls.iter().map(|url| url.parse().unwrap()).collect();
+
+    for (status, fetches) in [(503, 3), (404, 4)] {
+        let fetcher = StatusFetcher {
+            status,
+            fetched: Mutex::new(Vec::new()),
+        };
+        crawl_with_fetcher(&req, &FakeOpenAi::default(), &fetcher).expect("crawl");
+        assert_eq!(fetcher.fetched.lock().unwrap().len(), fetches, "{status}");
+    }
+
+    req.host_failure_limit = 0;
+    let fetcher = StatusFetcher {
+        status: 503,
+        fetched: Mutex::new(Vec::new()),
+    };
+    crawl_with_fetcher(&req, &FakeOpenAi::default(), &fetcher).expect("crawl");
+    assert_eq!(fetcher.fetched.lock().unwrap().len(), 4);
+}
+
+#[test]
+fn spider_backs_off_hosts_that_keep_refusing_and_suggests_a_host_rule() {
+    let urls = [1, 2, 3, 4].map(|n| format!("https://strict.example/{n}"));
+    let mut req = request("q");
+    req.seed_urls = urls.iter().map(|url| url.parse().unwrap()).collect();
+
+    for status in [403, 429] {
+        let fetcher = StatusFetcher {
+            status,
+            fetched: Mutex::new(Vec::new()),
+        };
+        let result = crawl_with_fetcher(&req, &FakeOpenAi::default(), &fetcher).expect("crawl");
+
+        assert_eq!(fetcher.fetched.lock().unwrap().len(), 3, "{status}");
+        assert!(
+            result
+                .skipped
+                .iter()
+                .any(|skip| skip.url.as_str() == urls[3]
+                    && skip.reason == llm_spider::spider::SkipReason::HostRefused)
+        );
+        assert_eq!(
+            result.refused_hosts,
+            vec![llm_spider::spider::RefusedHost {
+                host: "strict.example".to_string(),
+                status,
+                refusals: 3,
+            }]
+        );
+        assert!(
+            !result
+                .notes
+                .iter()
+                .any(|note| note.contains("strict.example")),
+            "{:?}",
+            result.notes
+        );
+        let markdown = llm_spider::spider::compose_markdown(&req, &result);
+        assert!(
+            markdown.contains(&format!(
+                "- strict.example: answered {status} to 3 requests in a row."
+            )),
+            "{markdown}"
+        );
+        assert!(
+            markdown.contains("`[hosts.\"strict.example\"]` with `max_pages = 0`"),
+            "{markdown}"
+        );
+    }
+}
+
+#[test]
+fn spider_excludes_a_host_on_its_first_takedown_status() {
+    let urls = [1, 2, 3].map(|n| format!("https://legal.example/{n}"));
+    let mut req = request("q");
+    req.seed_urls = urls.iter().map(|url| url.parse().unwrap()).collect();
+    // Even with the failure breaker off.
+    req.host_failure_limit = 0;
+    req.exclusion_statuses = vec![451, 410];
+
+    for status in [451, 410] {
+        let fetcher = StatusFetcher {
+            status,
+            fetched: Mutex::new(Vec::new()),
+        };
+        let result = crawl_with_fetcher(&req, &FakeOpenAi::default(), &fetcher).expect("crawl");
+
+        assert_eq!(fetcher.fetched.lock().unwrap().len(), 1, "{status}");
+        let excluded = &result.excluded_hosts;
+        assert_eq!(excluded.len(), 1, "{excluded:?}");
+        assert_eq!(
+            (excluded[0].host.as_str(), excluded[0].status),
+            ("legal.example", status)
+        );
+        assert_eq!(
+            result
+                .skipped
+                .iter()
+                .filter(|skip| skip.reason == llm_spider::spider::SkipReason::HostExcluded)
+                .count(),
+            2
+        );
+        let markdown = llm_spider::spider::compose_markdown(&req, &result);
+        assert!(
+            markdown.contains(&format!(
+                "after a takedown signal:\n\n- legal.example: answered {status} at {}\n",
+                excluded[0].url
+            )),
+            "{markdown}"
+        );
+        let json = llm_spider::spider::compose_json(&req, &result);
+        assert_eq!(json["excluded_hosts"][0]["status"], status);
+    }
+
+    req.exclusion_statuses.clear();
+    let fetcher = StatusFetcher {
+        status: 451,
+        fetched: Mutex::new(Vec::new()),
+    };
+    let result = crawl_with_fetcher(&req, &FakeOpenAi::default(), &fetcher).expect("crawl");
+    assert_eq!(fetcher.fetched.lock().unwrap().len(), 3);
+    assert!(result.excluded_hosts.is_empty());
+}
+
+#[test]
+fn spider_applies_seed_depth_and_children_overrides_to_pages_below_the_seed() {
+    let docs = "https://docs.example/";
+    let [guide, api, deep] = ["guide", "api", "guide/deep"].map(|p| format!("{docs}{p}"));
+    let hit = "https://hit.example/";
+    let hit_child = "https://hit.example/child";
+    let fetcher = FakeFetcher::default()
+        .with_page(docs, "<main>docs root</main>", vec![&guide, &api])
+        .with_page(&guide, "<main>guide</main>", vec![&deep])
+        .with_page(&api, "<main>api</main>", vec![])
+        .with_page(&deep, "<main>deep</main>", vec![])
+        .with_page(hit, "<main>hit</main>", vec![hit_child])
+        .with_page(hit_child, "<main>hit child</main>", vec![]);
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![hit])
+        .with_selected(docs, vec![&guide, &api])
+        .with_selected(&guide, vec![&deep])
+        .with_selected(hit, vec![hit_child]);
+    let mut req = request("q");
+    req.max_depth = 0;
+    req.seed_urls = vec![format!("{docs} depth=2 children=1").parse().unwrap()];
+
+    crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+
+    let mut fetched = fetcher.fetched.lock().unwrap().clone();
+    fetched.sort();
+    let mut expected = vec![docs.to_owned(), guide, deep, hit.to_owned()];
+    expected.sort();
+    assert_eq!(fetched, expected);
+}
+
+#[test]
+fn seed_urls_parse_optional_limits_and_reject_unknown_options() {
+    use llm_spider::spider::{SeedLimits, SeedUrl, parse_seed_file};
+
+    let seeds = parse_seed_file(
+        "# docs roots\nhttps://docs.example/ depth=3 children=5\n\nhttps://blog.example/\n",
+    )
+    .expect("parse");
+    assert_eq!(
+        seeds,
+        vec![
+            SeedUrl {
+                url: Url::parse("https://docs.example/").unwrap(),
+                limits: SeedLimits {
+                    max_depth: Some(3),
+                    max_children: Some(5),
+                },
+            },
+            Url::parse("https://blog.example/").unwrap().into(),
+        ]
+    );
+
+    let err = parse_seed_file("https://docs.example/\nhttps://a.example/ depht=2\n")
+        .expect_err("unknown option");
+    assert!(format!("{err:#}").contains("line 2"), "{err:#}");
+    assert!("https://a.example/ depth=x".parse::<SeedUrl>().is_err());
+    assert!("not a url".parse::<SeedUrl>().is_err());
+}
+
+#[test]
+fn spider_crawls_seeds_of_one_tier_in_search_rank_order() {
+    let [seed, a1, a2, b1, b2] =
+        ["seed", "a1", "a2", "b1", "b2"].map(|p| format!("https://example.test/{p}"));
+    let openai = FakeOpenAi::default()
+        .with_tier(&seed, TrustTier::Medium)
+        .with_query_hits("alpha: q", vec![&a1, &a2])
+        .with_query_hits("beta: q", vec![&b1, &b2, &a2, &seed]);
+    let fetcher = [&seed, &a1, &a2, &b1, &b2]
+        .into_iter()
+        .fold(FakeFetcher::default(), |fetcher, url| {
+            fetcher.with_page(url, "<main>page</main>", vec![])
+        });
+    let mut req = request("q");
+    req.max_depth = 0;
+    req.compare = vec!["alpha".to_owned(), "beta".to_owned()];
+    req.seed_urls = vec![seed.parse().unwrap()];
+
+    crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+
+    // The `--seed-url` seed first even though search ranked it last, then each
+    // search's top hit before any second hit.
+    let fetched = fetcher.fetched.lock().unwrap().clone();
+    assert_eq!(fetched, [seed, a1, b1, a2, b2]);
+}
+
+#[test]
+fn spider_follows_meta_refresh_interstitials_instead_of_keeping_them() {
+    let moved = "https://example.test/moved";
+    let target = "https://example.test/docs/current";
+    let local = "https://example.test/local";
+    let fetcher = FakeFetcher::default()
+        .with_page(
+            moved,
+            r#"<html><head><meta http-equiv="Refresh" content="0; URL='/docs/current'"></head><body>Redirecting…</body></html>"#,
+            vec![],
+        )
+        .with_page(target, "<main>The current documentation.</main>", vec![])
+        .with_page(
+            local,
+            r#"<meta http-equiv="refresh" content="1;url=http://127.0.0.1/admin"><main>Local redirect.</main>"#,
+            vec![],
+        );
+    let mut req = request("q");
+    req.seed_urls = vec![moved.parse().unwrap(), local.parse().unwrap()];
+
+    let result = crawl_with_fetcher(&req, &FakeOpenAi::default(), &fetcher).expect("crawl");
+
+    let fetched = fetcher.fetched.lock().unwrap().clone();
+    assert_eq!(fetched, [moved, target, local]);
+    assert_eq!(result.sources.len(), 2);
+    assert_eq!(result.sources[0].url.as_str(), moved);
+    assert!(
+        result.sources[0]
+            .content
+            .contains("The current documentation.")
+    );
+    assert_eq!(
+        result.sources[0].http.final_url.as_ref().map(Url::as_str),
+        Some(target)
+    );
+    assert!(result.sources[1].content.contains("Local redirect."));
+    assert!(
+        result
+            .skipped
+            .iter()
+            .any(|skip| skip.url.as_str() == "http://127.0.0.1/admin"
+                && skip.reason == llm_spider::spider::SkipReason::NotAllowed)
+    );
+}
+
+#[test]
+fn spider_guards_meta_refresh_targets_and_keeps_timed_refreshes() {
+    let article = "https://example.test/article";
+    let moved = "https://docs.example.org/moved";
+    let report = "https://example.test/report";
+    let fetcher = FakeFetcher::default()
+        .with_page(
+            article,
+            r#"<html><head><meta http-equiv="refresh" content="600; url=/"></head>
+               <body><main>A long article that returns home after ten minutes.</main></body></html>"#,
+            vec![],
+        )
+        .with_page(
+            moved,
+            r#"<meta http-equiv="refresh" content="0; url=/current"><p>Moved.</p>"#,
+            vec![],
+        )
+        .with_page(
+            "https://docs.example.org/current",
+            "<main>current docs</main>",
+            vec![],
+        )
+        .with_page(
+            report,
+            r#"<meta http-equiv="refresh" content="0; url=/report.pdf"><p>Downloading…</p>"#,
+            vec![],
+        );
+    let mut req = request("q");
+    req.max_depth = 0;
+    req.seed_urls = vec![
+        article.parse().unwrap(),
+        moved.parse().unwrap(),
+        report.parse().unwrap(),
+    ];
+    req.host_policies.insert(
+        "docs.example.org".to_owned(),
+        llm_spider::spider::HostPolicy {
+            max_pages: Some(1),
+            ..Default::default()
+        },
+    );
+
+    let result = crawl_with_fetcher(&req, &FakeOpenAi::default(), &fetcher).expect("crawl");
+
+    let fetched = fetcher.fetched.lock().unwrap().clone();
+    assert_eq!(fetched, [article, moved, report]);
+    assert!(result.sources[0].content.contains("A long article"));
+    assert!(result.sources[0].http.final_url.is_none());
+    let reasons = result
+        .skipped
+        .iter()
+        .map(|skip| (skip.url.as_str(), skip.reason))
+        .collect::<Vec<_>>();
+    assert!(
+        reasons.contains(&(
+            "https://docs.example.org/current",
+            llm_spider::spider::SkipReason::HostCap
+        )),
+        "{reasons:?}"
+    );
+    assert!(
+        reasons.contains(&(
+            "https://example.test/report.pdf",
+            llm_spider::spider::SkipReason::Binary
+        )),
+        "{reasons:?}"
+    );
+}
+
+#[test]
+fn spider_follows_same_origin_frames_of_empty_pages_only_when_asked() {
+    let viewer = "https://docs.example.test/viewer";
+    let topic = "https://docs.example.test/topics/install.html";
+    let topic_text = "Install the toolkit with the bundled installer, then restart the service.";
+    let fetcher = FakeFetcher::default()
+        .with_page(
+            viewer,
+            r#"<html><body><iframe src="https://ads.example.com/banner"></iframe><iframe src="topics/install.html"></iframe></body></html>"#,
+            vec![],
+        )
+        .with_page(topic, &format!("<main>{topic_text}</main>"), vec![]);
+    let mut req = request("q");
+    req.seed_urls = vec![viewer.parse().unwrap()];
+
+    let result = crawl_with_fetcher(&req, &FakeOpenAi::default(), &fetcher).expect("crawl");
+    assert_eq!(fetcher.fetched.lock().unwrap().clone(), [viewer]);
+    assert!(
+        !result
+            .sources
+            .iter()
+            .any(|s| s.content.contains(topic_text))
+    );
+
+    req.frame_policy = llm_spider::spider::FramePolicy::Follow;
+    let fetcher = FakeFetcher {
+        fetched: Default::default(),
+        ..fetcher
+    };
+    let result = crawl_with_fetcher(&req, &FakeOpenAi::default(), &fetcher).expect("crawl");
+    assert_eq!(fetcher.fetched.lock().unwrap().clone(), [viewer, topic]);
+    assert_eq!(result.sources.len(), 1);
+    assert_eq!(result.sources[0].url.as_str(), viewer);
+    assert!(result.sources[0].content.contains(topic_text));
+    assert_eq!(
+        result.sources[0].http.final_url.as_ref().map(Url::as_str),
+        Some(topic)
+    );
+}
+
+#[test]
+fn spider_url_patterns_rank_candidates_and_heuristic_picks() {
+    let start = "https://example.test/start";
+    let [tag, docs, about] =
+        ["tag/rust", "docs/intro", "about"].map(|path| format!("https://example.test/{path}"));
+    let fetcher = FakeFetcher::default()
+        .with_page(
+            start,
+            r#"<main><a href="/tag/rust">Rust</a><a href="/about">About</a><a href="/docs/intro">Intro</a></main>"#,
+            vec![&tag, &about, &docs],
+        )
+        .with_page(&tag, "<main>tag</main>", vec![])
+        .with_page(&docs, "<main>docs</main>", vec![])
+        .with_page(&about, "<main>about</main>", vec![]);
+
+    // Only the best candidate reaches the model.
+    let openai = FakeOpenAi::default().with_hits(vec![start]);
+    let mut req = request("q");
+    req.max_child_candidates = 1;
+    crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert_eq!(
+        *openai.seen_candidates.lock().unwrap(),
+        std::slice::from_ref(&docs)
+    );
+
+    // Heuristic picks put penalized paths last, even on the same host with anchor text.
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![start])
+        .with_failing_selection(start);
+    let mut req = request("q");
+    req.max_children_per_page = 2;
+    req.url_patterns.boost.clear();
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    let mut urls = result
+        .sources
+        .iter()
+        .map(|s| s.url.as_str())
+        .collect::<Vec<_>>();
+    urls.sort();
+    assert_eq!(urls, [&about, &docs, start]);
+}
+
+#[test]
+fn spider_pins_urls_and_bans_hosts_from_the_control_file() {
+    let [a, b, c, extra] = ["a", "b", "c", "extra"].map(|p| format!("https://example.test/{p}"));
+    let banned = "https://docs.other.test/page";
+    let control = std::env::temp_dir().join(format!(
+        "llm-spider-control-{}-{}.txt",
+        std::process::id(),
+        line!()
+    ));
+    std::fs::write(
+        &control,
+        format!("# steer the crawl\nban other.test\npin {extra}\npin {c}\nfrobnicate\n"),
+    )
+    .expect("write control file");
+
+    let openai = FakeOpenAi::default().with_hits(vec![&a, banned, &b, &c]);
+    let fetcher = [&a, &b, &c, &extra, banned]
+        .into_iter()
+        .fold(FakeFetcher::default(), |fetcher, url| {
+            fetcher.with_page(url, "<main>page</main>", vec![])
+        });
+    let mut req = request("q");
+    req.max_depth = 0;
+    req.control_file = Some(control.clone());
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    let _ = std::fs::remove_file(&control);
+
+    // Pins in file order ahead of the search hits; the banned host's subdomain never
+    // reaches the fetcher.
+    let fetched = fetcher.fetched.lock().unwrap().clone();
+    assert_eq!(fetched, [extra, c, a, b]);
+    let list = llm_spider::spider::compose_url_list(&result);
+    assert!(
+        list.contains(&format!("{banned}\tskipped:banned\n")),
+        "{list}"
+    );
+}
+
+#[test]
+fn spider_interactive_review_replaces_the_selected_links() {
+    use llm_spider::spider::SelectionReview;
+
+    let [start, a, b, c] = ["start", "a", "b", "c"].map(|p| format!("https://example.test/{p}"));
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![&start])
+        .with_selected(&start, vec![&a]);
+    let fetcher = FakeFetcher::default()
+        .with_page(&start, "<main>start</main>", vec![&a, &b, &c])
+        .with_page(&a, "<main>a</main>", vec![])
+        .with_page(&b, "<main>b</main>", vec![])
+        .with_page(&c, "<main>c</main>", vec![]);
+    let mut req = request("q");
+    req.max_depth = 1;
+    // An out-of-range answer is asked again; then the two unselected candidates.
+    req.selection_review = Some(Arc::new(SelectionReview::new(
+        std::io::Cursor::new("9\n2 3\n"),
+        std::io::sink(),
+    )));
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+
+    let mut fetched = fetcher.fetched.lock().unwrap().clone();
+    fetched.sort();
+    assert_eq!(fetched, [b.clone(), c.clone(), start.clone()]);
+    let list = llm_spider::spider::compose_url_list(&result);
+    assert!(
+        list.contains(&format!("{a}\tskipped:not-selected\n")),
+        "{list}"
+    );
+
+    // Closed input keeps the model's picks.
+    let fetcher = FakeFetcher::default()
+        .with_page(&start, "<main>start</main>", vec![&a, &b, &c])
+        .with_page(&a, "<main>a</main>", vec![]);
+    req.selection_review = Some(Arc::new(SelectionReview::new(
+        std::io::empty(),
+        std::io::sink(),
+    )));
+    crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert_eq!(*fetcher.fetched.lock().unwrap(), [start, a]);
+}
+
+#[test]
+fn spider_plan_review_shows_the_plan_and_asks_above_the_cost_threshold() {
+    use llm_spider::estimate::{CallEstimate, Estimate};
+    use llm_spider::spider::PlanConfirmation;
+
+    #[derive(Clone, Default)]
+    struct Output(Arc<Mutex<Vec<u8>>>);
+    impl std::io::Write for Output {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let start = "https://example.test/start";
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![start])
+        .with_title(start, "Start");
+    let estimate = |_: &llm_spider::spider::UserRequest| {
+        let call = CallEstimate {
+            model: "m".to_owned(),
+            calls: 1,
+            input_tokens: 10,
+            output_tokens: 10,
+        };
+        Estimate {
+            search: call.clone(),
+            select: call.clone(),
+            analysis: call,
+            cost_usd: Some(1.0),
+        }
+    };
+    let mut req = request("q");
+    req.max_depth = 0;
+
+    let output = Output::default();
+    req.plan_review = Some(Arc::new(
+        PlanConfirmation::new(estimate, std::io::Cursor::new("n\n"), output.clone())
+            .with_confirm_above(0.5),
+    ));
+    let fetcher = FakeFetcher::default().with_page(start, "<main>start</main>", vec![]);
+    let err = crawl_with_fetcher(&req, &openai, &fetcher).unwrap_err();
+    assert!(matches!(err, CrawlError::PlanDeclined), "{err}");
+    assert!(fetcher.fetched.lock().unwrap().is_empty());
+    let shown = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+    assert!(
+        shown.starts_with("# Spider Plan\n\n## Query\n\n- q\n"),
+        "{shown}"
+    );
+    assert!(
+        shown.contains(&format!("- [Medium] {start} — Start\n")),
+        "{shown}"
+    );
+    assert!(shown.contains("## Estimate\n\n"), "{shown}");
+    assert!(
+        shown.ends_with("Estimated cost $1.0000 is above $0.5000. Crawl anyway? [y/N] "),
+        "{shown}"
+    );
+
+    req.plan_review = Some(Arc::new(
+        PlanConfirmation::new(estimate, std::io::Cursor::new("y\n"), std::io::sink())
+            .with_confirm_above(0.5),
+    ));
+    crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    assert_eq!(*fetcher.fetched.lock().unwrap(), [start]);
+
+    // Under the threshold, and with the answer given in advance, nothing is read.
+    for review in [
+        PlanConfirmation::new(estimate, std::io::empty(), std::io::sink()).with_confirm_above(2.0),
+        PlanConfirmation::new(estimate, std::io::empty(), std::io::sink())
+            .with_confirm_above(0.5)
+            .with_assume_yes(true),
+    ] {
+        req.plan_review = Some(Arc::new(review));
+        let fetcher = FakeFetcher::default().with_page(start, "<main>start</main>", vec![]);
+        crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    }
+}
+
+#[test]
+fn spider_selection_log_records_candidates_picks_and_sources() {
+    let [start, good, dead, ignored] =
+        ["start", "good", "dead", "ignored"].map(|p| format!("https://example.test/{p}"));
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![&start])
+        .with_selected(&start, vec![&good, &dead]);
+    let fetcher = FakeFetcher::default()
+        .with_page(&start, "<main>start</main>", vec![&good, &dead, &ignored])
+        .with_page(&good, "<main>good</main>", vec![]);
+    let mut req = request("q");
+    req.max_depth = 1;
+
+    let result = crawl_with_fetcher(&req, &openai, &fetcher).expect("crawl");
+    let log = llm_spider::spider::compose_selection_log(&req, &result);
+
+    let lines = log.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 1, "{log}");
+    let record: serde_json::Value = serde_json::from_str(lines[0]).expect("json line");
+    assert_eq!(record["query"], "q");
+    assert_eq!(record["page"], start.as_str());
+    assert_eq!(record["depth"], 0);
+    assert_eq!(record["selection_failed"], false);
+    let candidate = |url: &str| {
+        record["candidates"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|candidate| candidate["url"] == url)
+            .cloned()
+            .unwrap_or_else(|| panic!("{url} missing from {record}"))
+    };
+    assert_eq!(candidate(&good)["selected"], true);
+    assert_eq!(candidate(&good)["followed"], true);
+    assert_eq!(candidate(&good)["became_source"], true);
+    assert_eq!(candidate(&dead)["selected"], true);
+    assert_eq!(candidate(&dead)["became_source"], false);
+    assert_eq!(candidate(&ignored)["selected"], false);
+    assert_eq!(candidate(&ignored)["trust_tier"], serde_json::Value::Null);
+}
+
+#[test]
+fn spider_replays_a_recorded_warc_and_scores_it_as_an_eval_case() {
+    use llm_spider::eval::{EvalCases, compose_eval_markdown, score};
+    use llm_spider::spider::WarcReplay;
+
+    let start = "https://example.test/start";
+    let child = "https://example.test/child";
+    let warc = std::env::temp_dir().join(format!(
+        "llm-spider-eval-{}-{}.warc.gz",
+        std::process::id(),
+        line!()
+    ));
+    let openai = FakeOpenAi::default()
+        .with_hits(vec![start])
+        .with_selected(start, vec![child]);
+    let fetcher = FakeFetcher::default()
+        .with_page(
+            start,
+            &format!("<main>start page <a href=\"{child}\">child</a></main>"),
+            vec![child],
+        )
+        .with_page(child, "<main>child page</main>", vec![]);
+    let mut req = request("q");
+    req.warc = Some(warc.clone());
+    crawl_with_fetcher(&req, &openai, &fetcher).expect("record");
+
+    let replay = WarcReplay::open(&warc).expect("open warc");
+    let _ = std::fs::remove_file(&warc);
+    assert_eq!(replay.len(), 2);
+    req.warc = None;
+    let result = crawl_with_fetcher(&req, &openai, &replay).expect("replay");
+    let urls = result
+        .sources
+        .iter()
+        .map(|source| source.url.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(urls, [start, child]);
+    assert!(result.sources[1].content.contains("child page"));
+
+    let cases = EvalCases::parse(&format!(
+        r#"
+[[case]]
+name = "recorded"
+query = "q"
+fixture = "recorded.warc.gz"
+expected_urls = ["{child}"]
+max_pages = 2
+max_cost_usd = 0.05
+
+[[case]]
+name = "too strict"
+query = "q"
+fixture = "recorded.warc.gz"
+expected_urls = ["{child}", "https://example.test/missing"]
+max_llm_calls = 1
+
+[[case]]
+name = "over budget"
+query = "q"
+fixture = "recorded.warc.gz"
+expected_urls = ["{child}"]
+max_cost_usd = 0.01
+"#
+    ))
+    .expect("parse cases");
+    let outcomes = cases
+        .cases
+        .iter()
+        .map(|case| score(case, &result, Some(0.0125)))
+        .collect::<Vec<_>>();
+    assert!(outcomes[0].passed, "{outcomes:?}");
+    assert!(!outcomes[1].passed);
+    assert!(!outcomes[2].passed);
+    // A cost that cannot be priced does not meet a bound.
+    assert!(!score(&cases.cases[0], &result, None).passed);
+    let table = compose_eval_markdown(&cases, &outcomes);
+    assert!(
+        table.contains("| recorded | pass | 1/1 | 2/2 | 2 | $0.0125/$0.0500 |"),
+        "{table}"
+    );
+    assert!(
+        table.contains("| too strict | FAIL | 1/2 | 2 | 2/1 | $0.0125 |"),
+        "{table}"
+    );
+    assert!(
+        table.contains("| over budget | FAIL | 1/1 | 2 | 2 | $0.0125/$0.0100 |"),
+        "{table}"
+    );
+    assert!(table.contains("1 of 3 cases passed."));
+    assert!(table.contains("- missing: https://example.test/missing"));
+}
+
+#[test]
+fn eval_cases_reject_duplicate_names_and_invalid_urls() {
+    use llm_spider::eval::EvalCases;
+
+    let case = |name: &str, url: &str| {
+        format!(
+            "[[case]]\nname = \"{name}\"\nquery = \"q\"\nfixture = \"f.warc\"\nexpected_urls = [\"{url}\"]\n"
+        )
+    };
+    let ok = "https://example.test/";
+    assert!(EvalCases::parse(&case("a", ok)).is_ok());
+    assert!(EvalCases::parse(&format!("{}{}", case("a", ok), case("a", ok))).is_err());
+    assert!(EvalCases::parse(&case("a", "not a url")).is_err());
+    assert!(EvalCases::parse("").is_err());
+}
+
+#[test]
+fn bench_fetches_every_url_once_and_reports_phase_latencies() {
+    use llm_spider::spider::{bench_with_fetcher, compose_bench_markdown, parse_url_list};
+
+    let urls = parse_url_list(
+        "# pages\nhttps://example.test/a\n\nhttps://example.test/b\nhttps://example.test/missing\n",
+    )
+    .expect("parse");
+    assert_eq!(urls.len(), 3);
+    assert!(parse_url_list("https://example.test/\nnot a url\n").is_err());
+    let fetcher = FakeFetcher::default()
+        .with_page("https://example.test/a", "<main>page a</main>", vec![])
+        .with_page("https://example.test/b", "<main>page b</main>", vec![]);
+
+    let report = bench_with_fetcher(&urls, 8, &fetcher);
+
+    let mut fetched = fetcher.fetched.lock().unwrap().clone();
+    fetched.sort();
+    assert_eq!(
+        fetched,
+        [
+            "https://example.test/a",
+            "https://example.test/b",
+            "https://example.test/missing"
+        ]
+    );
+    assert_eq!(report.concurrency, 3, "capped at the URL count");
+    assert_eq!((report.fetched, report.fetch_failed), (2, 1));
+    assert_eq!(report.extract_failed, 0);
+    assert!(report.fetch.p50 <= report.fetch.p90 && report.fetch.p90 <= report.fetch.max);
+    let markdown = compose_bench_markdown(&report);
+    assert!(markdown.contains("3 URLs at concurrency 3"), "{markdown}");
+    assert!(markdown.contains("| fetch |"));
+    assert!(markdown.contains("| extract |"));
+    assert!(markdown.contains("Peak memory (RSS):"));
+}
+
+fn site_request(root: &str) -> llm_spider::spider::SiteRequest {
+    llm_spider::spider::SiteRequest {
+        query: "setup".to_owned(),
+        root: Url::parse(root).unwrap(),
+        max_pages: 50,
+        max_depth: 5,
+        max_elapsed: Duration::from_secs(30),
+        allow_local: false,
+        host_policies: Default::default(),
+        challenge_retry: None,
+        honor_opt_outs: false,
+        crawl_id: None,
+        audit_log: None,
+    }
+}
+
+#[test]
+fn site_audit_crawls_under_the_root_breadth_first_and_reports_coverage() {
+    let root = "https://docs.example.test/docs/";
+    let intro = "https://docs.example.test/docs/guide/intro";
+    let client = "https://docs.example.test/docs/api/client";
+    let advanced = "https://docs.example.test/docs/guide/advanced";
+    let openai = FakeOpenAi::default().with_grades(&[
+        (0, false, "Index"),
+        (1, true, "Covers setup"),
+        (2, false, "API only"),
+        (3, true, "Advanced [setup]"),
+    ]);
+    let fetcher = FakeFetcher::default()
+        .with_page(
+            root,
+            "<title>Docs</title><main>index</main>",
+            vec![
+                intro,
+                client,
+                "https://other.test/setup",
+                "https://docs.example.test/blog/post",
+                "https://docs.example.test/docs/logo.png",
+            ],
+        )
+        .with_page(
+            intro,
+            "<title>Intro</title><main>intro</main>",
+            vec![advanced, root],
+        )
+        .with_page(client, "<main>client</main>", vec![])
+        .with_page(advanced, "<main>advanced</main>", vec![]);
+
+    let req = site_request(root);
+    let audit = llm_spider::spider::audit_site_with_fetcher(&req, &openai, &fetcher);
+    assert_eq!(
+        *fetcher.fetched.lock().unwrap(),
+        [root, intro, client, advanced]
+    );
+    assert_eq!(audit.stats.llm_calls, 1);
+    assert_eq!(audit.stats.out_of_scope_links, 2);
+    assert_eq!(audit.stats.unvisited, 0);
+    let coverage = audit
+        .coverage()
+        .into_iter()
+        .map(|section| (section.section, section.pages, section.relevant))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        coverage,
+        [
+            ("/docs/".to_owned(), 1, 0),
+            ("/docs/api/".to_owned(), 1, 0),
+            ("/docs/guide/".to_owned(), 2, 2),
+        ]
+    );
+
+    let md = llm_spider::spider::compose_site_markdown(&req, &audit);
+    assert!(md.contains("| /docs/guide/ | 2 | 2 |"), "{md}");
+    let (_, relevant) = md.split_once("## Relevant pages").expect("relevant pages");
+    assert!(relevant.contains("### /docs/guide/"), "{md}");
+    assert!(
+        relevant.contains(&format!("- {intro} — Intro: Covers setup")),
+        "{md}"
+    );
+    assert!(relevant.contains("Advanced \\[setup\\]"), "{md}");
+    assert!(!relevant.contains(client), "{md}");
+    let json = llm_spider::spider::compose_site_json(&req, &audit);
+    assert_eq!(json["coverage"][2]["section"], "/docs/guide/");
+    assert_eq!(json["pages"][2]["relevant"], false);
+}
+
+#[test]
+fn site_audit_stops_at_max_pages_and_notes_failed_grading() {
+    let root = "https://docs.example.test/docs/index.html";
+    let a = "https://docs.example.test/docs/a";
+    let b = "https://docs.example.test/docs/b";
+    let openai = FakeOpenAi::default();
+    let fetcher = FakeFetcher::default()
+        .with_page(root, "<main>index</main>", vec![a, b])
+        .with_page(a, "<main>a</main>", vec![])
+        .with_page(b, "<main>b</main>", vec![]);
+    let mut req = site_request(root);
+    req.max_pages = 2;
+
+    let audit = llm_spider::spider::audit_site_with_fetcher(&req, &openai, &fetcher);
+    assert_eq!(*fetcher.fetched.lock().unwrap(), [root, a]);
+    assert_eq!(audit.stats.unvisited, 1);
+    assert!(audit.pages.iter().all(|page| page.relevant.is_none()));
+    assert_eq!(audit.notes.len(), 1);
+    let md = llm_spider::spider::compose_site_markdown(&req, &audit);
+    assert!(md.contains("- Not reached: 1 queued pages"), "{md}");
+    assert!(!md.contains("## Relevant pages"), "{md}");
+}
+
+#[test]
+fn site_audit_honors_opt_outs_and_sends_the_crawl_id() {
+    use std::io::{BufRead as _, BufReader, Write as _};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    let base = format!("http://{}", listener.local_addr().expect("addr"));
+    let headers = Arc::new(Mutex::new(Vec::<String>::new()));
+    let seen = headers.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            let _ = reader.read_line(&mut request_line);
+            let mut header = String::new();
+            while reader.read_line(&mut header).unwrap_or(0) > 2 {
+                seen.lock()
+                    .unwrap()
+                    .push(header.trim().to_ascii_lowercase());
+                header.clear();
+            }
+            let path = request_line
+                .split_whitespace()
+                .nth(1)
+                .unwrap_or_default()
+                .to_owned();
+            let (status, body) = match path.as_str() {
+                "/robots.txt" => ("404 Not Found", ""),
+                "/ai.txt" => ("200 OK", "User-Agent: *\nDisallow: /docs/private\n"),
+                "/docs/" => (
+                    "200 OK",
+                    r#"<main><a href="/docs/guide">Guide</a> <a href="/docs/private">Private</a></main>"#,
+                ),
+                _ => ("200 OK", "<main>page text</main>"),
+            };
+            let mut stream = reader.into_inner();
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {status}\r\ncontent-type: text/html\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+        }
+    });
+
+    let mut req = site_request(&format!("{base}/docs/"));
+    req.allow_local = true;
+    req.honor_opt_outs = true;
+    req.crawl_id = Some("audit-7".to_owned());
+    let audit = llm_spider::spider::audit_site(&req, &FakeOpenAi::default()).expect("audit");
+
+    let pages = audit
+        .pages
+        .iter()
+        .map(|page| page.url.path())
+        .collect::<Vec<_>>();
+    assert_eq!(pages, ["/docs/", "/docs/guide"]);
+    assert_eq!(audit.failures.len(), 1, "{:?}", audit.failures);
+    assert_eq!(audit.failures[0].url.path(), "/docs/private");
+    assert!(
+        headers
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|header| header == "x-crawl-id: audit-7")
+    );
+    let md = llm_spider::spider::compose_site_markdown(&req, &audit);
+    assert!(md.contains("- Crawl ID: `audit-7`"), "{md}");
+}
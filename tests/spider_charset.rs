@@ -0,0 +1,25 @@
+use llm_spider::spider::decode_html;
+
+#[test]
+fn decode_html_uses_content_type_charset() {
+    let (bytes, _, _) = encoding_rs::SHIFT_JIS.encode("<p>日本語のページ</p>");
+    let html = decode_html(&bytes, Some("text/html; charset=Shift_JIS"));
+    assert_eq!(html, "<p>日本語のページ</p>");
+}
+
+#[test]
+fn decode_html_falls_back_to_meta_charset() {
+    let source = "<html><head><meta http-equiv=\"Content-Type\" content=\"text/html; charset=euc-kr\"></head><body>한국어</body></html>";
+    let (bytes, _, _) = encoding_rs::EUC_KR.encode(source);
+    assert_eq!(decode_html(&bytes, Some("text/html")), source);
+
+    let source = "<meta charset='windows-1252'><p>café</p>";
+    let (bytes, _, _) = encoding_rs::WINDOWS_1252.encode(source);
+    assert_eq!(decode_html(&bytes, None), source);
+}
+
+#[test]
+fn decode_html_defaults_to_utf8() {
+    let source = "<p>日本語</p>";
+    assert_eq!(decode_html(source.as_bytes(), None), source);
+}
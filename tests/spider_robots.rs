@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+use llm_spider::spider::{RobotsDirectives, is_allowed_by_robots, parse_robots_directives};
+
+const UA: &str = "llm-spider/0.1 (respectful; contact: unknown)";
+
+#[test]
+fn robots_directives_prefer_matching_agent_group() {
+    let robots = "\
+User-agent: *
+Crawl-delay: 1
+
+User-agent: Googlebot
+User-agent: LLM-Spider
+Disallow: /private
+Crawl-delay: 4
+Request-rate: 1/10s # one page every ten seconds
+";
+    let directives = parse_robots_directives(robots, UA);
+    assert_eq!(
+        directives,
+        RobotsDirectives {
+            crawl_delay: Some(Duration::from_secs(4)),
+            request_rate: Some((1, Duration::from_secs(10))),
+        }
+    );
+    assert_eq!(directives.min_interval(), Duration::from_secs(10));
+    assert_eq!(directives.max_concurrent(), Some(1));
+}
+
+#[test]
+fn robots_directives_fall_back_to_wildcard_group() {
+    let robots = "\
+User-agent: otherbot
+Crawl-delay: 30
+
+User-agent: *
+Request-rate: 6/1m 0600-0845
+";
+    let directives = parse_robots_directives(robots, UA);
+    assert_eq!(directives.crawl_delay, None);
+    assert_eq!(directives.min_interval(), Duration::from_secs(10));
+}
+
+#[test]
+fn robots_directives_default_to_no_pacing() {
+    let directives = parse_robots_directives("User-agent: *\nDisallow:\n", UA);
+    assert_eq!(directives, RobotsDirectives::default());
+    assert_eq!(directives.min_interval(), Duration::ZERO);
+    assert_eq!(directives.max_concurrent(), None);
+}
+
+#[test]
+fn robots_rules_pick_the_longest_match_with_allow_on_ties() {
+    let robots = "\
+User-agent: *
+Disallow: /docs/
+Allow: /docs/public/
+Disallow: /tmp
+Allow: /tmp
+Disallow:
+";
+    assert!(!is_allowed_by_robots(robots, UA, "/docs/guide"));
+    assert!(is_allowed_by_robots(robots, UA, "/docs/public/intro"));
+    assert!(is_allowed_by_robots(robots, UA, "/tmp/file"));
+    assert!(is_allowed_by_robots(robots, UA, "/about"));
+    assert!(is_allowed_by_robots(robots, UA, "/robots.txt"));
+}
+
+#[test]
+fn robots_rules_support_wildcards_and_end_anchors() {
+    let robots = "\
+User-agent: *
+Disallow: /*.pdf$
+Disallow: /search*q=
+";
+    assert!(!is_allowed_by_robots(robots, UA, "/files/report.pdf"));
+    assert!(is_allowed_by_robots(robots, UA, "/files/report.pdf.html"));
+    assert!(!is_allowed_by_robots(robots, UA, "/search?lang=en&q=rust"));
+    assert!(is_allowed_by_robots(robots, UA, "/search?lang=en"));
+}
+
+#[test]
+fn robots_rules_follow_the_matching_agent_group_only() {
+    let robots = "\
+User-agent: *
+Disallow: /
+
+User-agent: llm-spider
+Disallow: /private
+";
+    assert!(is_allowed_by_robots(robots, UA, "/public"));
+    assert!(!is_allowed_by_robots(robots, UA, "/private/page"));
+    assert!(!is_allowed_by_robots(robots, "otherbot/1.0", "/public"));
+}